@@ -0,0 +1,174 @@
+//! Compares the probing strategies of `HashTable` against `std::collections::HashMap`.
+//!
+//! Run with `cargo bench --bench hash_table`. Each workload inserts, looks up (half hits,
+//! half misses) and removes the same random keys, and reports the best of several rounds.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use the_algorithms_rust::data_structures::{HashTable, Probing};
+
+const ROUNDS: usize = 5;
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+/// The operations every benchmarked map supports.
+trait Map {
+    fn create() -> Self;
+    fn insert(&mut self, key: u64, value: u64);
+    fn get(&self, key: u64) -> Option<u64>;
+    fn remove(&mut self, key: u64) -> Option<u64>;
+}
+
+impl Map for HashMap<u64, u64> {
+    fn create() -> Self {
+        HashMap::new()
+    }
+
+    fn insert(&mut self, key: u64, value: u64) {
+        HashMap::insert(self, key, value);
+    }
+
+    fn get(&self, key: u64) -> Option<u64> {
+        HashMap::get(self, &key).copied()
+    }
+
+    fn remove(&mut self, key: u64) -> Option<u64> {
+        HashMap::remove(self, &key)
+    }
+}
+
+/// Picks the probing strategy of a benchmarked `HashTable`.
+trait Strategy {
+    const PROBING: Probing;
+}
+
+struct Linear;
+struct Quadratic;
+struct RobinHood;
+
+impl Strategy for Linear {
+    const PROBING: Probing = Probing::Linear;
+}
+
+impl Strategy for Quadratic {
+    const PROBING: Probing = Probing::Quadratic;
+}
+
+impl Strategy for RobinHood {
+    const PROBING: Probing = Probing::RobinHood;
+}
+
+struct Table<P>(HashTable<u64, u64>, std::marker::PhantomData<P>);
+
+impl<P: Strategy> Map for Table<P> {
+    fn create() -> Self {
+        Table(
+            HashTable::with_probing(P::PROBING),
+            std::marker::PhantomData,
+        )
+    }
+
+    fn insert(&mut self, key: u64, value: u64) {
+        self.0.insert(key, value);
+    }
+
+    fn get(&self, key: u64) -> Option<u64> {
+        self.0.get(&key).copied()
+    }
+
+    fn remove(&mut self, key: u64) -> Option<u64> {
+        self.0.remove(&key)
+    }
+}
+
+struct Timings {
+    insert: Duration,
+    lookup: Duration,
+    remove: Duration,
+}
+
+fn run<M: Map>(keys: &[u64], missing: &[u64]) -> Timings {
+    let mut best = Timings {
+        insert: Duration::MAX,
+        lookup: Duration::MAX,
+        remove: Duration::MAX,
+    };
+    for _ in 0..ROUNDS {
+        let mut map = M::create();
+
+        let start = Instant::now();
+        for &key in keys {
+            map.insert(key, key);
+        }
+        best.insert = best.insert.min(start.elapsed());
+
+        let start = Instant::now();
+        for (&hit, &miss) in keys.iter().zip(missing) {
+            black_box(map.get(hit));
+            black_box(map.get(miss));
+        }
+        best.lookup = best.lookup.min(start.elapsed());
+
+        let start = Instant::now();
+        for &key in keys {
+            black_box(map.remove(key));
+        }
+        best.remove = best.remove.min(start.elapsed());
+    }
+    best
+}
+
+fn report(out: &mut impl Write, name: &str, size: usize, timings: &Timings) -> io::Result<()> {
+    let per_op = |total: Duration, ops: usize| total.as_nanos() as f64 / ops as f64;
+    writeln!(
+        out,
+        "{name:<24}{size:>10}{:>12.1}{:>12.1}{:>12.1}",
+        per_op(timings.insert, size),
+        per_op(timings.lookup, 2 * size),
+        per_op(timings.remove, size),
+    )
+}
+
+fn main() -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    writeln!(
+        out,
+        "{:<24}{:>10}{:>12}{:>12}{:>12}",
+        "map", "size", "insert ns", "lookup ns", "remove ns"
+    )?;
+    let mut rng = ChaCha8Rng::seed_from_u64(37);
+    for size in SIZES {
+        // Even keys are inserted and odd keys are looked up as misses.
+        let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>() & !1).collect();
+        let missing: Vec<u64> = (0..size).map(|_| rng.gen::<u64>() | 1).collect();
+        report(
+            &mut out,
+            "std HashMap",
+            size,
+            &run::<HashMap<u64, u64>>(&keys, &missing),
+        )?;
+        report(
+            &mut out,
+            "HashTable (linear)",
+            size,
+            &run::<Table<Linear>>(&keys, &missing),
+        )?;
+        report(
+            &mut out,
+            "HashTable (quadratic)",
+            size,
+            &run::<Table<Quadratic>>(&keys, &missing),
+        )?;
+        report(
+            &mut out,
+            "HashTable (Robin Hood)",
+            size,
+            &run::<Table<RobinHood>>(&keys, &missing),
+        )?;
+    }
+    Ok(())
+}
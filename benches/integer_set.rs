@@ -0,0 +1,159 @@
+//! Compares the `IntegerSet` implementations against each other and `std::collections::BTreeSet`.
+//!
+//! Run with `cargo bench --bench integer_set`. Each workload inserts random values filling a
+//! sixteenth of the universe, looks up random values, queries their successors and
+//! predecessors, and removes the inserted values, and reports the best of several rounds.
+
+use std::collections::BTreeSet;
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use the_algorithms_rust::data_structures::{BitSet, IntegerSet, VebTree, YFastTrie};
+
+const ROUNDS: usize = 5;
+const UNIVERSES: [u32; 3] = [1 << 12, 1 << 16, 1 << 20];
+
+/// The baseline, which does not depend on the universe.
+struct Sorted(BTreeSet<u32>);
+
+impl IntegerSet for Sorted {
+    fn new(_universe: u32) -> Self {
+        Sorted(BTreeSet::new())
+    }
+
+    fn insert(&mut self, value: u32) -> bool {
+        self.0.insert(value)
+    }
+
+    fn remove(&mut self, value: u32) -> bool {
+        self.0.remove(&value)
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.0.contains(&value)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn min(&self) -> Option<u32> {
+        self.0.first().copied()
+    }
+
+    fn max(&self) -> Option<u32> {
+        self.0.last().copied()
+    }
+
+    fn succ(&self, value: u32) -> Option<u32> {
+        self.0.range(value.checked_add(1)?..).next().copied()
+    }
+
+    fn pred(&self, value: u32) -> Option<u32> {
+        self.0.range(..value).next_back().copied()
+    }
+}
+
+struct Timings {
+    insert: Duration,
+    contains: Duration,
+    neighbors: Duration,
+    remove: Duration,
+}
+
+fn run<S: IntegerSet>(universe: u32, values: &[u32], queries: &[u32]) -> Timings {
+    let mut best = Timings {
+        insert: Duration::MAX,
+        contains: Duration::MAX,
+        neighbors: Duration::MAX,
+        remove: Duration::MAX,
+    };
+    for _ in 0..ROUNDS {
+        let mut set = S::new(universe);
+
+        let start = Instant::now();
+        for &value in values {
+            black_box(set.insert(value));
+        }
+        best.insert = best.insert.min(start.elapsed());
+
+        let start = Instant::now();
+        for &query in queries {
+            black_box(set.contains(query));
+        }
+        best.contains = best.contains.min(start.elapsed());
+
+        let start = Instant::now();
+        for &query in queries {
+            black_box(set.succ(query));
+            black_box(set.pred(query));
+        }
+        best.neighbors = best.neighbors.min(start.elapsed());
+
+        let start = Instant::now();
+        for &value in values {
+            black_box(set.remove(value));
+        }
+        best.remove = best.remove.min(start.elapsed());
+    }
+    best
+}
+
+fn report(out: &mut impl Write, name: &str, universe: u32, timings: &Timings) -> io::Result<()> {
+    let ops = (universe / 16) as usize;
+    let per_op = |total: Duration, ops: usize| total.as_nanos() as f64 / ops as f64;
+    writeln!(
+        out,
+        "{name:<14}{universe:>10}{:>12.1}{:>12.1}{:>12.1}{:>12.1}",
+        per_op(timings.insert, ops),
+        per_op(timings.contains, ops),
+        per_op(timings.neighbors, 2 * ops),
+        per_op(timings.remove, ops),
+    )
+}
+
+fn main() -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    writeln!(
+        out,
+        "{:<14}{:>10}{:>12}{:>12}{:>12}{:>12}",
+        "set", "universe", "insert ns", "lookup ns", "succ/pred ns", "remove ns"
+    )?;
+    let mut rng = ChaCha8Rng::seed_from_u64(37);
+    for universe in UNIVERSES {
+        let values: Vec<u32> = (0..universe / 16)
+            .map(|_| rng.gen_range(0..universe))
+            .collect();
+        let queries: Vec<u32> = (0..universe / 16)
+            .map(|_| rng.gen_range(0..universe))
+            .collect();
+        report(
+            &mut out,
+            "BTreeSet",
+            universe,
+            &run::<Sorted>(universe, &values, &queries),
+        )?;
+        report(
+            &mut out,
+            "VebTree",
+            universe,
+            &run::<VebTree>(universe, &values, &queries),
+        )?;
+        report(
+            &mut out,
+            "YFastTrie",
+            universe,
+            &run::<YFastTrie>(universe, &values, &queries),
+        )?;
+        report(
+            &mut out,
+            "BitSet",
+            universe,
+            &run::<BitSet>(universe, &values, &queries),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,381 @@
+//! Bit-level run-length encoding for bitmaps.
+//!
+//! The input is read as a stream of bits, most significant bit first. Its runs are
+//! stored as alternating lengths, always starting with a run of `0` bits (which may be empty),
+//! each length written as an LEB128 varint. This suits sparse masks and monochrome images
+//! where long stretches of equal pixels dominate.
+//!
+//! Noisy bitmaps produce many short runs, so the first output byte selects the mode:
+//! `0` stores the input unchanged and `1` stores the run lengths. The encoder picks the
+//! smaller one, which bounds the output to `len + 1` bytes.
+//!
+//! https://en.wikipedia.org/wiki/Run-length_encoding
+
+use std::convert::Infallible;
+
+use super::streaming::{Compressor, Decompressor};
+
+const MODE_STORED: u8 = 0;
+const MODE_RUNS: u8 = 1;
+
+/// Errors that can occur while decoding a bit-RLE stream.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BitRunLengthError {
+    /// The mode byte is neither stored nor runs.
+    UnknownMode(u8),
+    /// The input ends in the middle of a varint.
+    TruncatedVarint,
+    /// A varint does not fit into a `usize`.
+    VarintOverflow,
+    /// The decoded run lengths do not add up to a whole number of bytes.
+    UnalignedBitCount,
+    /// The decoded data would exceed the size limit given to the decoder.
+    OutputTooLarge,
+}
+
+/// Returns the upper bound on the length of `bit_run_length_encode(bitmap)` for an input of `len` bytes.
+pub fn bit_run_length_max_encoded_len(len: usize) -> usize {
+    len + 1
+}
+
+/// Compresses `bitmap`, falling back to storing it verbatim when that is shorter.
+pub fn bit_run_length_encode(bitmap: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut encoder = BitRunLengthEncoder::new();
+    // Feed small chunks so that noisy input gives up on runs early.
+    for chunk in bitmap.chunks(64) {
+        let Ok(()) = encoder.compress_chunk(chunk, &mut encoded);
+        if encoded.len() > bitmap.len() {
+            break;
+        }
+    }
+    let Ok(()) = encoder.finish(&mut encoded);
+
+    if encoded.len() > bit_run_length_max_encoded_len(bitmap.len()) {
+        encoded.clear();
+        encoded.push(MODE_STORED);
+        encoded.extend_from_slice(bitmap);
+    }
+
+    encoded
+}
+
+/// Decompresses a stream produced by `bit_run_length_encode`.
+///
+/// `max_len` caps the decompressed size: a single varint can describe a run of
+/// billions of bits, so untrusted input must not be expanded without a bound.
+pub fn bit_run_length_decode(encoded: &[u8], max_len: usize) -> Result<Vec<u8>, BitRunLengthError> {
+    let mut decoded = Vec::new();
+    let mut decoder = BitRunLengthDecoder::new(max_len);
+    decoder.decompress_chunk(encoded, &mut decoded)?;
+    decoder.finish(&mut decoded)?;
+    Ok(decoded)
+}
+
+/// Incremental bit-RLE compressor.
+///
+/// It always writes run lengths: falling back to the stored mode would require
+/// seeing the whole input first.
+pub struct BitRunLengthEncoder {
+    header_written: bool,
+    current: bool,
+    run: usize,
+}
+
+impl Default for BitRunLengthEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BitRunLengthEncoder {
+    pub fn new() -> Self {
+        BitRunLengthEncoder {
+            header_written: false,
+            current: false,
+            run: 0,
+        }
+    }
+
+    fn write_header(&mut self, output: &mut Vec<u8>) {
+        if !self.header_written {
+            output.push(MODE_RUNS);
+            self.header_written = true;
+        }
+    }
+}
+
+impl Compressor for BitRunLengthEncoder {
+    type Error = Infallible;
+
+    fn compress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Infallible> {
+        self.write_header(output);
+        for bit in bits(input) {
+            if bit == self.current {
+                self.run += 1;
+            } else {
+                write_varint(output, self.run);
+                self.current = bit;
+                self.run = 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Infallible> {
+        self.write_header(output);
+        write_varint(output, self.run);
+        Ok(())
+    }
+}
+
+enum DecoderState {
+    Mode,
+    Stored,
+    Runs,
+}
+
+/// Incremental bit-RLE decompressor, accepting both the stored and the runs mode.
+pub struct BitRunLengthDecoder {
+    state: DecoderState,
+    /// Value and bit shift of the varint being read.
+    varint: usize,
+    shift: u32,
+    /// Value of the bits in the current run.
+    bit: bool,
+    /// Bits of the output byte being assembled, and how many of them are set.
+    pending: u8,
+    pending_bits: usize,
+    /// Number of bytes that may still be written before `OutputTooLarge` is reported.
+    remaining: usize,
+}
+
+impl BitRunLengthDecoder {
+    /// Creates a decoder that produces at most `max_len` bytes in total.
+    pub fn new(max_len: usize) -> Self {
+        BitRunLengthDecoder {
+            state: DecoderState::Mode,
+            varint: 0,
+            shift: 0,
+            bit: false,
+            pending: 0,
+            pending_bits: 0,
+            remaining: max_len,
+        }
+    }
+
+    fn push_varint_byte(
+        &mut self,
+        byte: u8,
+        output: &mut Vec<u8>,
+    ) -> Result<(), BitRunLengthError> {
+        let chunk = (byte & 0x7f) as usize;
+        if self.shift >= usize::BITS || (chunk << self.shift) >> self.shift != chunk {
+            return Err(BitRunLengthError::VarintOverflow);
+        }
+        self.varint |= chunk << self.shift;
+        self.shift += 7;
+
+        if byte & 0x80 == 0 {
+            self.push_run(self.varint, output)?;
+            self.varint = 0;
+            self.shift = 0;
+        }
+        Ok(())
+    }
+
+    fn push_run(&mut self, run: usize, output: &mut Vec<u8>) -> Result<(), BitRunLengthError> {
+        let capacity = self.remaining.saturating_mul(8) - self.pending_bits;
+        if run > capacity {
+            return Err(BitRunLengthError::OutputTooLarge);
+        }
+        self.remaining -= (self.pending_bits + run) / 8;
+
+        let mut remaining = run;
+        // Top up a partially filled byte one bit at a time.
+        while remaining > 0 && self.pending_bits > 0 {
+            self.push_bit();
+            remaining -= 1;
+            if self.pending_bits == 8 {
+                output.push(self.pending);
+                self.pending = 0;
+                self.pending_bits = 0;
+            }
+        }
+        // Whole bytes can be emitted directly.
+        let fill = if self.bit { u8::MAX } else { 0 };
+        output.extend(std::iter::repeat_n(fill, remaining / 8));
+        for _ in 0..remaining % 8 {
+            self.push_bit();
+        }
+
+        self.bit = !self.bit;
+        Ok(())
+    }
+
+    fn push_bit(&mut self) {
+        self.pending = (self.pending << 1) | self.bit as u8;
+        self.pending_bits += 1;
+    }
+}
+
+impl Decompressor for BitRunLengthDecoder {
+    type Error = BitRunLengthError;
+
+    fn decompress_chunk(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), BitRunLengthError> {
+        let payload = match self.state {
+            DecoderState::Mode => {
+                let Some((&mode, rest)) = input.split_first() else {
+                    return Ok(());
+                };
+                self.state = match mode {
+                    MODE_STORED => DecoderState::Stored,
+                    MODE_RUNS => DecoderState::Runs,
+                    _ => return Err(BitRunLengthError::UnknownMode(mode)),
+                };
+                rest
+            }
+            DecoderState::Stored | DecoderState::Runs => input,
+        };
+
+        match self.state {
+            DecoderState::Mode => unreachable!("the mode byte was consumed above"),
+            DecoderState::Stored => {
+                if payload.len() > self.remaining {
+                    return Err(BitRunLengthError::OutputTooLarge);
+                }
+                self.remaining -= payload.len();
+                output.extend_from_slice(payload);
+            }
+            DecoderState::Runs => {
+                for &byte in payload {
+                    self.push_varint_byte(byte, output)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _output: &mut Vec<u8>) -> Result<(), BitRunLengthError> {
+        if self.shift != 0 {
+            return Err(BitRunLengthError::TruncatedVarint);
+        }
+        if self.pending_bits != 0 {
+            return Err(BitRunLengthError::UnalignedBitCount);
+        }
+        Ok(())
+    }
+}
+
+/// Iterates over the bits of `bytes`, most significant bit first.
+fn bits(bytes: &[u8]) -> impl Iterator<Item = bool> + '_ {
+    bytes
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |shift| (byte >> shift) & 1 == 1))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    macro_rules! test_bit_run_length {
+        ($($name:ident: $test_case:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (raw, encoded): (&[u8], &[u8]) = $test_case;
+                    assert_eq!(bit_run_length_encode(raw), encoded);
+                    assert_eq!(bit_run_length_decode(encoded, usize::MAX).unwrap(), raw);
+                }
+            )*
+        };
+    }
+
+    test_bit_run_length! {
+        empty: (b"", b"\x00"),
+        all_zeros: (&[0u8; 4], b"\x01\x20"),
+        leading_ones: (&[0xff, 0xff, 0x00, 0x00], b"\x01\x00\x10\x10"),
+        mixed_runs: (&[0x0f, 0xff, 0xff, 0xf0], b"\x01\x04\x18\x04"),
+        noisy_bitmap_is_stored: (&[0x55, 0xaa], b"\x00\x55\xaa"),
+        digits_are_plain_bytes: (b"11", b"\x00\x31\x31"),
+    }
+
+    #[test]
+    fn long_runs_use_multi_byte_varints() {
+        let mut bitmap = vec![0u8; 100];
+        bitmap.extend([0xffu8; 100]);
+        let encoded = bit_run_length_encode(&bitmap);
+        assert_eq!(encoded, [0x01, 0xa0, 0x06, 0xa0, 0x06]);
+        assert_eq!(
+            bit_run_length_decode(&encoded, bitmap.len()).unwrap(),
+            bitmap
+        );
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(bit_run_length_decode(b"", 0), Ok(vec![]));
+        assert_eq!(
+            bit_run_length_decode(b"\x07", usize::MAX),
+            Err(BitRunLengthError::UnknownMode(7))
+        );
+        assert_eq!(
+            bit_run_length_decode(b"\x01\x80", usize::MAX),
+            Err(BitRunLengthError::TruncatedVarint)
+        );
+        assert_eq!(
+            bit_run_length_decode(b"\x01\x03", usize::MAX),
+            Err(BitRunLengthError::UnalignedBitCount)
+        );
+        assert_eq!(
+            bit_run_length_decode(
+                &[1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f],
+                usize::MAX
+            ),
+            Err(BitRunLengthError::VarintOverflow)
+        );
+    }
+
+    #[test]
+    fn output_is_limited() {
+        let huge_run = [1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(
+            bit_run_length_decode(&huge_run, 1 << 20),
+            Err(BitRunLengthError::OutputTooLarge)
+        );
+
+        let encoded = bit_run_length_encode(&[0u8; 4]);
+        assert_eq!(bit_run_length_decode(&encoded, 4), Ok(vec![0; 4]));
+        assert_eq!(
+            bit_run_length_decode(&encoded, 3),
+            Err(BitRunLengthError::OutputTooLarge)
+        );
+        assert_eq!(
+            bit_run_length_decode(b"\x00\x55\xaa", 1),
+            Err(BitRunLengthError::OutputTooLarge)
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(bitmap: Vec<u8>) -> bool {
+        bit_run_length_decode(&bit_run_length_encode(&bitmap), bitmap.len()).unwrap() == bitmap
+    }
+
+    #[quickcheck]
+    fn expansion_is_bounded(bitmap: Vec<u8>) -> bool {
+        bit_run_length_encode(&bitmap).len() <= bit_run_length_max_encoded_len(bitmap.len())
+    }
+}
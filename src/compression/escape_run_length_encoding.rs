@@ -0,0 +1,281 @@
+//! Escape-byte run-length encoding.
+//!
+//! The encoder picks the least frequent byte of the input as the escape marker and
+//! stores it as the first byte of the output. The remaining stream is made of:
+//!
+//! * `escape count byte` with `count >= 1`: `byte` repeated `count` times;
+//! * `escape 0`: a literal occurrence of the escape byte;
+//! * any other byte: copied as is.
+//!
+//! Since the least frequent of 256 byte values occurs at most `len / 256` times,
+//! the output is never longer than `1 + len + len / 256` bytes.
+//!
+//! https://en.wikipedia.org/wiki/Run-length_encoding
+
+use std::convert::Infallible;
+
+use super::streaming::{Compressor, Decompressor};
+
+/// Runs shorter than this are cheaper to emit literally than as `escape count byte`.
+const MIN_RUN_LEN: usize = 4;
+
+/// Longest run that fits into the single count byte.
+const MAX_RUN_LEN: usize = u8::MAX as usize;
+
+/// Errors that can occur while decoding an escape-RLE stream.
+#[derive(Debug, PartialEq, Eq)]
+pub enum EscapeRunLengthError {
+    /// The input ends in the middle of an escape sequence.
+    TruncatedEscapeSequence,
+}
+
+/// Returns the upper bound on the length of `escape_run_length_encode(data)` for an input of `len` bytes.
+pub fn escape_run_length_max_encoded_len(len: usize) -> usize {
+    if len == 0 {
+        0
+    } else {
+        1 + len + len / 256
+    }
+}
+
+/// Compresses `data`, choosing the rarest byte value as the escape marker.
+pub fn escape_run_length_encode(data: &[u8]) -> Vec<u8> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let mut encoded = Vec::with_capacity(escape_run_length_max_encoded_len(data.len()));
+    let mut encoder = EscapeRunLengthEncoder::new(least_frequent_byte(data));
+    let Ok(()) = encoder.compress_chunk(data, &mut encoded);
+    let Ok(()) = encoder.finish(&mut encoded);
+    encoded
+}
+
+/// Decompresses a stream produced by `escape_run_length_encode`.
+pub fn escape_run_length_decode(encoded: &[u8]) -> Result<Vec<u8>, EscapeRunLengthError> {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut decoder = EscapeRunLengthDecoder::new();
+    decoder.decompress_chunk(encoded, &mut decoded)?;
+    decoder.finish(&mut decoded)?;
+    Ok(decoded)
+}
+
+/// Incremental escape-RLE compressor.
+///
+/// A stream cannot be scanned in advance, so the escape byte is chosen by the caller.
+/// The output is decoded by `escape_run_length_decode`, but the expansion bound only
+/// holds when `escape` is rare in the input.
+pub struct EscapeRunLengthEncoder {
+    escape: u8,
+    header_written: bool,
+    run_byte: u8,
+    run_len: usize,
+}
+
+impl EscapeRunLengthEncoder {
+    pub fn new(escape: u8) -> Self {
+        EscapeRunLengthEncoder {
+            escape,
+            header_written: false,
+            run_byte: 0,
+            run_len: 0,
+        }
+    }
+
+    fn write_header(&mut self, output: &mut Vec<u8>) {
+        if !self.header_written {
+            output.push(self.escape);
+            self.header_written = true;
+        }
+    }
+
+    fn end_run(&mut self, output: &mut Vec<u8>) {
+        let (escape, byte, run) = (self.escape, self.run_byte, self.run_len);
+        if run == 0 {
+            return;
+        }
+
+        if byte == escape && run == 1 {
+            output.extend_from_slice(&[escape, 0]);
+        } else if byte == escape || run >= MIN_RUN_LEN {
+            output.extend_from_slice(&[escape, run as u8, byte]);
+        } else {
+            output.extend(std::iter::repeat_n(byte, run));
+        }
+        self.run_len = 0;
+    }
+}
+
+impl Compressor for EscapeRunLengthEncoder {
+    type Error = Infallible;
+
+    fn compress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Infallible> {
+        self.write_header(output);
+        for &byte in input {
+            if self.run_len > 0 && (byte != self.run_byte || self.run_len == MAX_RUN_LEN) {
+                self.end_run(output);
+            }
+            self.run_byte = byte;
+            self.run_len += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Infallible> {
+        self.write_header(output);
+        self.end_run(output);
+        Ok(())
+    }
+}
+
+enum DecoderState {
+    /// Waiting for the escape byte that starts the stream.
+    Header,
+    Plain,
+    /// Right after an escape byte.
+    Escaped,
+    /// After `escape count`, waiting for the byte to repeat.
+    Count(u8),
+}
+
+/// Incremental escape-RLE decompressor.
+pub struct EscapeRunLengthDecoder {
+    escape: u8,
+    state: DecoderState,
+}
+
+impl Default for EscapeRunLengthDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EscapeRunLengthDecoder {
+    pub fn new() -> Self {
+        EscapeRunLengthDecoder {
+            escape: 0,
+            state: DecoderState::Header,
+        }
+    }
+}
+
+impl Decompressor for EscapeRunLengthDecoder {
+    type Error = EscapeRunLengthError;
+
+    fn decompress_chunk(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), EscapeRunLengthError> {
+        for &byte in input {
+            self.state = match self.state {
+                DecoderState::Header => {
+                    self.escape = byte;
+                    DecoderState::Plain
+                }
+                DecoderState::Plain if byte == self.escape => DecoderState::Escaped,
+                DecoderState::Plain => {
+                    output.push(byte);
+                    DecoderState::Plain
+                }
+                DecoderState::Escaped if byte == 0 => {
+                    output.push(self.escape);
+                    DecoderState::Plain
+                }
+                DecoderState::Escaped => DecoderState::Count(byte),
+                DecoderState::Count(count) => {
+                    output.extend(std::iter::repeat_n(byte, count as usize));
+                    DecoderState::Plain
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _output: &mut Vec<u8>) -> Result<(), EscapeRunLengthError> {
+        match self.state {
+            DecoderState::Header | DecoderState::Plain => Ok(()),
+            DecoderState::Escaped | DecoderState::Count(_) => {
+                Err(EscapeRunLengthError::TruncatedEscapeSequence)
+            }
+        }
+    }
+}
+
+/// Returns the byte value that occurs least often in `data`, preferring the smallest value on ties.
+fn least_frequent_byte(data: &[u8]) -> u8 {
+    let mut histogram = [0usize; 256];
+    for &byte in data {
+        histogram[byte as usize] += 1;
+    }
+
+    (0..=u8::MAX)
+        .min_by_key(|&byte| histogram[byte as usize])
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    macro_rules! test_escape_run_length {
+        ($($name:ident: $test_case:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (raw, encoded): (&[u8], &[u8]) = $test_case;
+                    assert_eq!(escape_run_length_encode(raw), encoded);
+                    assert_eq!(escape_run_length_decode(encoded).unwrap(), raw);
+                }
+            )*
+        };
+    }
+
+    test_escape_run_length! {
+        empty: (b"", b""),
+        short_runs_stay_literal: (b"AAAB", b"\x00AAAB"),
+        long_run: (b"AAAAAAB", b"\x00\x00\x06AB"),
+        digits_are_plain_bytes: (b"1111222223", b"\x00\x00\x041\x00\x0523"),
+        zero_bytes_are_plain: (b"\x00x\x00\x00", b"\x01\x00x\x00\x00"),
+    }
+
+    #[test]
+    fn escape_byte_is_escaped_when_every_value_occurs() {
+        let data: Vec<u8> = (0..=u8::MAX).chain([0, 0, 0, 7]).collect();
+        let encoded = escape_run_length_encode(&data);
+        assert_eq!(encoded[0], 1);
+        assert_eq!(&encoded[2..4], &[1, 0]);
+        assert_eq!(escape_run_length_decode(&encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn long_runs_are_split() {
+        let data = vec![b'z'; 600];
+        let encoded = escape_run_length_encode(&data);
+        assert_eq!(encoded, b"\x00\x00\xffz\x00\xffz\x00\x5az");
+        assert_eq!(escape_run_length_decode(&encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(
+            escape_run_length_decode(b"\x00A\x00"),
+            Err(EscapeRunLengthError::TruncatedEscapeSequence)
+        );
+        assert_eq!(
+            escape_run_length_decode(b"\x00A\x00\x05"),
+            Err(EscapeRunLengthError::TruncatedEscapeSequence)
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(data: Vec<u8>) -> bool {
+        escape_run_length_decode(&escape_run_length_encode(&data)).unwrap() == data
+    }
+
+    #[quickcheck]
+    fn expansion_is_bounded(data: Vec<u8>) -> bool {
+        escape_run_length_encode(&data).len() <= escape_run_length_max_encoded_len(data.len())
+    }
+}
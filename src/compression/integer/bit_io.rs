@@ -0,0 +1,174 @@
+//! Bit-granular writer and reader shared by the integer codes.
+//!
+//! Bits are stored most significant bit first within each byte, so a code reads left to
+//! right in a hex dump. The last byte is padded with zero bits.
+
+use super::IntegerCodecError;
+
+/// Appends bits to a growing byte buffer.
+#[derive(Debug, Default, Clone)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    bit_len: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of bits written so far.
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn write_bit(&mut self, bit: bool) {
+        let offset = self.bit_len % 8;
+        if offset == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> offset;
+        }
+        self.bit_len += 1;
+    }
+
+    /// Writes the `count` lowest bits of `value`, most significant first.
+    pub fn write_bits(&mut self, value: u64, count: u32) {
+        assert!(count <= 64, "cannot write more than 64 bits at once");
+        for shift in (0..count).rev() {
+            self.write_bit((value >> shift) & 1 == 1);
+        }
+    }
+
+    /// Writes `n` in unary: `n` zero bits followed by a one bit.
+    pub fn write_unary(&mut self, n: u64) {
+        for _ in 0..n {
+            self.write_bit(false);
+        }
+        self.write_bit(true);
+    }
+
+    /// Pads the last byte with zero bits and returns the buffer.
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads bits from a byte slice written by `BitWriter`.
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, position: 0 }
+    }
+
+    /// Returns the number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the number of bits left, including the padding of the last byte.
+    pub fn remaining_bits(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, IntegerCodecError> {
+        let byte = self
+            .bytes
+            .get(self.position / 8)
+            .ok_or(IntegerCodecError::UnexpectedEnd)?;
+        let bit = byte & (0x80 >> (self.position % 8)) != 0;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    /// Reads `count` bits as an unsigned integer, most significant first.
+    pub fn read_bits(&mut self, count: u32) -> Result<u64, IntegerCodecError> {
+        debug_assert!(count <= 64, "cannot read more than 64 bits at once");
+        if self.remaining_bits() < count as usize {
+            return Err(IntegerCodecError::UnexpectedEnd);
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value)
+    }
+
+    /// Reads a unary number, rejecting codes with more than `max` leading zeros.
+    pub fn read_unary(&mut self, max: u64) -> Result<u64, IntegerCodecError> {
+        let mut n = 0;
+        while !self.read_bit()? {
+            n += 1;
+            if n > max {
+                return Err(IntegerCodecError::InvalidCode);
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn layout_is_msb_first() {
+        let mut writer = BitWriter::new();
+        writer.write_bit(true);
+        writer.write_bits(0b0110, 4);
+        writer.write_unary(2);
+        assert_eq!(writer.bit_len(), 8);
+        writer.write_bits(0b11, 2);
+        assert_eq!(writer.finish(), [0b1011_0001, 0b1100_0000]);
+    }
+
+    #[test]
+    fn reader_reports_errors() {
+        let mut reader = BitReader::new(&[0b0000_0001]);
+        assert_eq!(reader.read_unary(6), Err(IntegerCodecError::InvalidCode));
+
+        let mut reader = BitReader::new(&[0b0000_0001]);
+        assert_eq!(reader.read_unary(7), Ok(7));
+        assert_eq!(reader.remaining_bits(), 0);
+        assert_eq!(reader.read_bit(), Err(IntegerCodecError::UnexpectedEnd));
+
+        let mut reader = BitReader::new(&[0xff]);
+        assert_eq!(reader.read_bits(9), Err(IntegerCodecError::UnexpectedEnd));
+        assert_eq!(reader.position(), 0);
+    }
+
+    #[quickcheck]
+    fn round_trip(fields: Vec<(u64, u8)>) -> bool {
+        let fields: Vec<(u64, u32)> = fields
+            .into_iter()
+            .map(|(value, width)| {
+                let width = (width % 65) as u32;
+                let mask = if width == 64 {
+                    u64::MAX
+                } else {
+                    (1 << width) - 1
+                };
+                (value & mask, width)
+            })
+            .collect();
+
+        let mut writer = BitWriter::new();
+        for &(value, width) in &fields {
+            writer.write_bits(value, width);
+        }
+        let bytes = writer.finish();
+
+        let mut reader = BitReader::new(&bytes);
+        fields
+            .iter()
+            .all(|&(value, width)| reader.read_bits(width) == Ok(value))
+            && reader.remaining_bits() < 8
+    }
+}
@@ -0,0 +1,120 @@
+//! Bit packing with frame of reference.
+//!
+//! Values are split into blocks of `BLOCK_LEN`. Each block stores its minimum and then
+//! every value as its offset from that minimum, all with the bit width of the largest
+//! offset. Blocks of nearly equal values, as found in sorted or clustered columns, pack
+//! into a few bits per value, and a block of equal values takes no bits beyond its header.
+//!
+//! Layout: the value count as LEB128, then for each block its minimum as LEB128, the bit
+//! width in 7 bits and the offsets. Signed values can be packed after `zigzag_encode`.
+
+use super::{BitReader, BitWriter, IntegerCode, IntegerCodecError, Leb128};
+
+/// Number of values sharing a minimum and a bit width.
+const BLOCK_LEN: usize = 128;
+/// Bits used to store the width of a block.
+const WIDTH_BITS: u32 = 7;
+
+/// Packs `values` block by block.
+pub fn bit_pack_encode(values: &[u64]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    Leb128.write(&mut writer, values.len() as u64);
+    for block in values.chunks(BLOCK_LEN) {
+        let min = *block.iter().min().unwrap();
+        let max = *block.iter().max().unwrap();
+        let width = 64 - (max - min).leading_zeros();
+        Leb128.write(&mut writer, min);
+        writer.write_bits(width as u64, WIDTH_BITS);
+        for &value in block {
+            writer.write_bits(value - min, width);
+        }
+    }
+    writer.finish()
+}
+
+/// Unpacks values packed by `bit_pack_encode`.
+pub fn bit_pack_decode(bytes: &[u8]) -> Result<Vec<u64>, IntegerCodecError> {
+    let mut reader = BitReader::new(bytes);
+    let count = Leb128.read(&mut reader)?;
+    let count = usize::try_from(count).map_err(|_| IntegerCodecError::Overflow)?;
+    let mut values = Vec::with_capacity(count.min(bytes.len() * 8));
+    while values.len() < count {
+        let min = Leb128.read(&mut reader)?;
+        let width = reader.read_bits(WIDTH_BITS)? as u32;
+        if width > 64 {
+            return Err(IntegerCodecError::InvalidCode);
+        }
+        for _ in 0..BLOCK_LEN.min(count - values.len()) {
+            let offset = reader.read_bits(width)?;
+            values.push(min.checked_add(offset).ok_or(IntegerCodecError::Overflow)?);
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn layout() {
+        // Count 3, minimum 10, width 2, offsets 0, 3 and 1.
+        assert_eq!(
+            bit_pack_encode(&[10, 13, 11]),
+            [0x03, 0x0a, 0b0000_0100, 0b0110_1000]
+        );
+        assert_eq!(bit_pack_encode(&[]), [0x00]);
+    }
+
+    #[test]
+    fn equal_values_take_no_bits() {
+        let values = vec![1_000_000; 1000];
+        let bytes = bit_pack_encode(&values);
+        assert!(bytes.len() < 40);
+        assert_eq!(bit_pack_decode(&bytes), Ok(values));
+    }
+
+    #[test]
+    fn clustered_values_pack_tightly() {
+        let values: Vec<u64> = (0..1000).map(|i| (1 << 40) + i % 16).collect();
+        let bytes = bit_pack_encode(&values);
+        assert!(bytes.len() < 1000 / 2 + 100);
+        assert_eq!(bit_pack_decode(&bytes), Ok(values));
+    }
+
+    #[test]
+    fn full_width_block() {
+        let values = [0, u64::MAX, 5];
+        assert_eq!(
+            bit_pack_decode(&bit_pack_encode(&values)),
+            Ok(values.to_vec())
+        );
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(bit_pack_decode(&[]), Err(IntegerCodecError::UnexpectedEnd));
+        assert_eq!(
+            bit_pack_decode(&[0x01, 0x00, 0xfe]),
+            Err(IntegerCodecError::InvalidCode)
+        );
+        assert_eq!(
+            bit_pack_decode(&[0x03, 0x0a, 0b0000_0100]),
+            Err(IntegerCodecError::UnexpectedEnd)
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(values: Vec<u64>) -> bool {
+        bit_pack_decode(&bit_pack_encode(&values)) == Ok(values)
+    }
+
+    #[quickcheck]
+    fn small_values_round_trip(values: Vec<u8>) -> bool {
+        let values: Vec<u64> = values.into_iter().map(u64::from).collect();
+        let bytes = bit_pack_encode(&values);
+        bytes.len() <= values.len() + 3 * values.len().div_ceil(BLOCK_LEN) + 10
+            && bit_pack_decode(&bytes) == Ok(values)
+    }
+}
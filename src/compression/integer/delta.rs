@@ -0,0 +1,105 @@
+//! Delta and delta-of-delta transforms.
+//!
+//! Sorted identifiers turn into small gaps under delta encoding, and regularly sampled
+//! timestamps turn into runs of zeros under delta-of-delta encoding. Both outputs are
+//! meant to be mapped with `zigzag_encode` and stored with a variable-length code.
+//!
+//! All arithmetic wraps, so every `i64` sequence round-trips.
+//!
+//! https://en.wikipedia.org/wiki/Delta_encoding
+
+/// Replaces every value but the first by its difference to the previous one.
+pub fn delta_encode(values: &[i64]) -> Vec<i64> {
+    let mut previous = 0i64;
+    values
+        .iter()
+        .map(|&value| {
+            let delta = value.wrapping_sub(previous);
+            previous = value;
+            delta
+        })
+        .collect()
+}
+
+/// Inverts `delta_encode` by taking prefix sums.
+pub fn delta_decode(deltas: &[i64]) -> Vec<i64> {
+    let mut current = 0i64;
+    deltas
+        .iter()
+        .map(|&delta| {
+            current = current.wrapping_add(delta);
+            current
+        })
+        .collect()
+}
+
+/// Applies `delta_encode` twice: the output holds the first value, the first
+/// difference, and then the changes between consecutive differences.
+pub fn delta_of_delta_encode(values: &[i64]) -> Vec<i64> {
+    let deltas = delta_encode(values);
+    let mut encoded = delta_encode(&deltas);
+    // Keep the first difference as is rather than relative to the first value.
+    if let Some(first_delta) = encoded.get_mut(1) {
+        *first_delta = deltas[1];
+    }
+    encoded
+}
+
+/// Inverts `delta_of_delta_encode`.
+pub fn delta_of_delta_decode(encoded: &[i64]) -> Vec<i64> {
+    let mut deltas = Vec::with_capacity(encoded.len());
+    let mut delta = 0i64;
+    for (i, &value) in encoded.iter().enumerate() {
+        delta = match i {
+            0 | 1 => value,
+            _ => delta.wrapping_add(value),
+        };
+        deltas.push(delta);
+    }
+    delta_decode(&deltas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn sorted_ids() {
+        let ids = [1000, 1003, 1004, 1010, 1010];
+        assert_eq!(delta_encode(&ids), [1000, 3, 1, 6, 0]);
+        assert_eq!(delta_decode(&[1000, 3, 1, 6, 0]), ids);
+    }
+
+    #[test]
+    fn regular_timestamps() {
+        let timestamps = [1_700_000_000, 1_700_000_060, 1_700_000_120, 1_700_000_181];
+        let encoded = delta_of_delta_encode(&timestamps);
+        assert_eq!(encoded, [1_700_000_000, 60, 0, 1]);
+        assert_eq!(delta_of_delta_decode(&encoded), timestamps);
+    }
+
+    #[test]
+    fn short_inputs() {
+        assert_eq!(delta_of_delta_encode(&[]), []);
+        assert_eq!(delta_of_delta_encode(&[5]), [5]);
+        assert_eq!(delta_of_delta_encode(&[5, 2]), [5, -3]);
+        assert_eq!(delta_of_delta_decode(&[5, -3]), [5, 2]);
+    }
+
+    #[test]
+    fn extreme_values_wrap() {
+        let values = [i64::MIN, i64::MAX, 0, i64::MIN];
+        assert_eq!(delta_decode(&delta_encode(&values)), values);
+        assert_eq!(
+            delta_of_delta_decode(&delta_of_delta_encode(&values)),
+            values
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(values: Vec<i64>) -> bool {
+        delta_decode(&delta_encode(&values)) == values
+            && delta_of_delta_decode(&delta_of_delta_encode(&values)) == values
+    }
+}
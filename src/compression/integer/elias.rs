@@ -0,0 +1,172 @@
+//! Elias gamma and delta codes, universal codes for positive integers.
+//!
+//! Gamma writes `floor(log2(n))` zeros followed by `n` in binary, which costs
+//! `2 * floor(log2(n)) + 1` bits. Delta writes the length of `n` with the gamma code
+//! instead, which is shorter for large values.
+//!
+//! Both codes start at one, so values are shifted up by one before being written:
+//! zero becomes the one-bit code `1`, and `u64::MAX` is still representable.
+//!
+//! https://en.wikipedia.org/wiki/Elias_gamma_coding
+//! https://en.wikipedia.org/wiki/Elias_delta_coding
+
+use super::{BitReader, BitWriter, IntegerCode, IntegerCodecError};
+
+/// Number of bits in a `u64` plus one, the longest binary length of `value + 1`.
+const MAX_LEN: u64 = 65;
+
+/// The Elias gamma code, shifted to start at zero.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EliasGamma;
+
+/// The Elias delta code, shifted to start at zero.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EliasDelta;
+
+/// Writes `n >= 1` as the unary length of its binary form, followed by the bits below its leading one.
+fn write_gamma(writer: &mut BitWriter, n: u128) {
+    let log = n.ilog2();
+    writer.write_unary(log as u64);
+    writer.write_bits(n as u64, log);
+}
+
+fn read_gamma(reader: &mut BitReader, max_log: u64) -> Result<u128, IntegerCodecError> {
+    let log = reader.read_unary(max_log)? as u32;
+    Ok((1 << log) | reader.read_bits(log)? as u128)
+}
+
+/// Undoes the shift by one applied before writing.
+fn unshift(n: u128) -> Result<u64, IntegerCodecError> {
+    u64::try_from(n - 1).map_err(|_| IntegerCodecError::Overflow)
+}
+
+impl IntegerCode for EliasGamma {
+    fn write(&self, writer: &mut BitWriter, value: u64) {
+        write_gamma(writer, value as u128 + 1);
+    }
+
+    fn read(&self, reader: &mut BitReader) -> Result<u64, IntegerCodecError> {
+        unshift(read_gamma(reader, MAX_LEN - 1)?)
+    }
+}
+
+impl IntegerCode for EliasDelta {
+    fn write(&self, writer: &mut BitWriter, value: u64) {
+        let n = value as u128 + 1;
+        let log = n.ilog2();
+        write_gamma(writer, log as u128 + 1);
+        writer.write_bits(n as u64, log);
+    }
+
+    fn read(&self, reader: &mut BitReader) -> Result<u64, IntegerCodecError> {
+        let len = read_gamma(reader, MAX_LEN.ilog2() as u64)?;
+        if len > MAX_LEN as u128 {
+            return Err(IntegerCodecError::Overflow);
+        }
+        let log = len as u32 - 1;
+        unshift((1 << log) | reader.read_bits(log)? as u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{decode_all, encode_all};
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    /// Renders the code of `value` as a string of bits.
+    fn bits_of<C: IntegerCode>(code: &C, value: u64) -> String {
+        let mut writer = BitWriter::new();
+        code.write(&mut writer, value);
+        let len = writer.bit_len();
+        let bytes = writer.finish();
+        let mut reader = BitReader::new(&bytes);
+        std::iter::repeat_with(|| if reader.read_bit().unwrap() { '1' } else { '0' })
+            .take(len)
+            .collect()
+    }
+
+    #[test]
+    fn gamma_codes() {
+        // Codes of the positive integers 1 to 5.
+        let expected = ["1", "010", "011", "00100", "00101"];
+        for (value, code) in expected.iter().enumerate() {
+            assert_eq!(bits_of(&EliasGamma, value as u64), *code);
+        }
+        assert_eq!(bits_of(&EliasGamma, 16), "000010001");
+        assert_eq!(bits_of(&EliasGamma, u64::MAX).len(), 129);
+    }
+
+    #[test]
+    fn delta_codes() {
+        // Codes of the positive integers 1 to 4.
+        let expected = ["1", "0100", "0101", "01100"];
+        for (value, code) in expected.iter().enumerate() {
+            assert_eq!(bits_of(&EliasDelta, value as u64), *code);
+        }
+        assert_eq!(bits_of(&EliasDelta, 9), "00100010");
+        assert_eq!(bits_of(&EliasDelta, 16), "001010001");
+    }
+
+    #[test]
+    fn extremes() {
+        let values = [0, 1, u64::MAX, u64::MAX - 1, 1 << 63];
+        for code in [&EliasGamma as &dyn IntegerCode, &EliasDelta] {
+            let mut writer = BitWriter::new();
+            for &value in &values {
+                code.write(&mut writer, value);
+            }
+            let bytes = writer.finish();
+            let mut reader = BitReader::new(&bytes);
+            for &value in &values {
+                assert_eq!(code.read(&mut reader), Ok(value));
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(
+            decode_all(&EliasGamma, &[0u8; 9], 1),
+            Err(IntegerCodecError::InvalidCode)
+        );
+        assert_eq!(
+            decode_all(&EliasGamma, &[0b0000_0001], 1),
+            Err(IntegerCodecError::UnexpectedEnd)
+        );
+        // A length of 127 does not fit into 64 bits.
+        assert_eq!(
+            decode_all(&EliasDelta, &[0b0000_0011, 0b1111_1100, 0, 0], 1),
+            Err(IntegerCodecError::Overflow)
+        );
+        // 65 bits long, but above `u64::MAX + 1`.
+        let mut writer = BitWriter::new();
+        writer.write_unary(64);
+        writer.write_bits(1, 64);
+        assert_eq!(
+            EliasGamma.read(&mut BitReader::new(&writer.finish())),
+            Err(IntegerCodecError::Overflow)
+        );
+        let mut writer = BitWriter::new();
+        write_gamma(&mut writer, 65);
+        writer.write_bits(1, 64);
+        assert_eq!(
+            EliasDelta.read(&mut BitReader::new(&writer.finish())),
+            Err(IntegerCodecError::Overflow)
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(values: Vec<u64>) -> bool {
+        decode_all(&EliasGamma, &encode_all(&EliasGamma, &values), values.len())
+            == Ok(values.clone())
+            && decode_all(&EliasDelta, &encode_all(&EliasDelta, &values), values.len())
+                == Ok(values)
+    }
+
+    #[quickcheck]
+    fn small_values_take_few_bits(value: u8) -> bool {
+        let log = (value as u64 + 1).ilog2() as usize;
+        bits_of(&EliasGamma, value as u64).len() == 2 * log + 1
+    }
+}
@@ -0,0 +1,122 @@
+//! Golomb-Rice codes split a value into a quotient `value >> k`, written in unary, and the
+//! `k` low bits, written as they are. They are optimal for geometrically distributed values
+//! such as the gaps between sorted identifiers, given a parameter `k` close to
+//! `log2` of the mean.
+//!
+//! A quotient of `ESCAPE_QUOTIENT` or more would make the unary part grow without bound, so
+//! such values are written as the escape quotient followed by all 64 bits of the value.
+//!
+//! https://en.wikipedia.org/wiki/Golomb_coding#Rice_coding
+
+use super::{BitReader, BitWriter, IntegerCode, IntegerCodecError};
+
+/// Quotients from this one on are escaped.
+const ESCAPE_QUOTIENT: u64 = 32;
+
+/// The Rice code with divisor `2^k`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GolombRice {
+    k: u32,
+}
+
+impl GolombRice {
+    /// Creates the code with divisor `2^k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not below 64.
+    pub fn new(k: u32) -> Self {
+        assert!(k < 64, "the Rice parameter must be below 64");
+        GolombRice { k }
+    }
+
+    /// Creates the code whose parameter suits the mean of `values`.
+    pub fn for_values(values: &[u64]) -> Self {
+        let sum: u128 = values.iter().map(|&value| value as u128).sum();
+        let mean = sum / values.len().max(1) as u128;
+        GolombRice::new(mean.checked_ilog2().unwrap_or(0))
+    }
+
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+}
+
+impl IntegerCode for GolombRice {
+    fn write(&self, writer: &mut BitWriter, value: u64) {
+        let quotient = value >> self.k;
+        if quotient < ESCAPE_QUOTIENT {
+            writer.write_unary(quotient);
+            writer.write_bits(value, self.k);
+        } else {
+            writer.write_unary(ESCAPE_QUOTIENT);
+            writer.write_bits(value, 64);
+        }
+    }
+
+    fn read(&self, reader: &mut BitReader) -> Result<u64, IntegerCodecError> {
+        let quotient = reader.read_unary(ESCAPE_QUOTIENT)?;
+        if quotient == ESCAPE_QUOTIENT {
+            reader.read_bits(64)
+        } else {
+            Ok((quotient << self.k) | reader.read_bits(self.k)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{decode_all, encode_all};
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn layout() {
+        let code = GolombRice::new(2);
+        // 9 = 2 * 4 + 1: quotient "001", remainder "01".
+        assert_eq!(encode_all(&code, &[9]), [0b0010_1000]);
+        assert_eq!(encode_all(&code, &[0, 3]), [0b1001_1100]);
+    }
+
+    #[test]
+    fn large_values_are_escaped() {
+        let code = GolombRice::new(0);
+        let bytes = encode_all(&code, &[u64::MAX, 31]);
+        assert_eq!(bytes.len(), (33 + 64 + 32usize).div_ceil(8));
+        assert_eq!(decode_all(&code, &bytes, 2), Ok(vec![u64::MAX, 31]));
+    }
+
+    #[test]
+    fn parameter_follows_the_mean() {
+        assert_eq!(GolombRice::for_values(&[]).k(), 0);
+        assert_eq!(GolombRice::for_values(&[0, 1]).k(), 0);
+        assert_eq!(GolombRice::for_values(&[100, 200, 300]).k(), 7);
+        assert_eq!(GolombRice::for_values(&[u64::MAX; 3]).k(), 63);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let code = GolombRice::new(4);
+        assert_eq!(
+            decode_all(&code, &[0u8; 5], 1),
+            Err(IntegerCodecError::InvalidCode)
+        );
+        assert_eq!(
+            decode_all(&code, &[0b0001_0000], 2),
+            Err(IntegerCodecError::UnexpectedEnd)
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(values: Vec<u64>, k: u8) -> bool {
+        let code = GolombRice::new(k as u32 % 64);
+        decode_all(&code, &encode_all(&code, &values), values.len()) == Ok(values)
+    }
+
+    #[quickcheck]
+    fn small_gaps_are_compact(gaps: Vec<u8>) -> bool {
+        let values: Vec<u64> = gaps.into_iter().map(u64::from).collect();
+        let code = GolombRice::for_values(&values);
+        encode_all(&code, &values).len() <= values.len() * 2 + 1
+    }
+}
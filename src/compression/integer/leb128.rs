@@ -0,0 +1,96 @@
+//! LEB128 stores an unsigned integer in groups of seven bits, least significant group
+//! first, with the high bit of every byte telling whether another group follows.
+//! Values below 128 take a single byte and a `u64` takes at most ten.
+//!
+//! https://en.wikipedia.org/wiki/LEB128
+
+use super::{BitReader, BitWriter, IntegerCode, IntegerCodecError};
+
+/// Maximum number of groups needed for a `u64`.
+const MAX_GROUPS: u32 = 10;
+
+/// The unsigned LEB128 varint, written as whole bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Leb128;
+
+impl IntegerCode for Leb128 {
+    fn write(&self, writer: &mut BitWriter, mut value: u64) {
+        loop {
+            let group = value & 0x7f;
+            value >>= 7;
+            if value == 0 {
+                writer.write_bits(group, 8);
+                return;
+            }
+            writer.write_bits(group | 0x80, 8);
+        }
+    }
+
+    fn read(&self, reader: &mut BitReader) -> Result<u64, IntegerCodecError> {
+        let mut value = 0u64;
+        for i in 0..MAX_GROUPS {
+            let byte = reader.read_bits(8)?;
+            let group = byte & 0x7f;
+            let shift = 7 * i;
+            if (group << shift) >> shift != group {
+                return Err(IntegerCodecError::Overflow);
+            }
+            value |= group << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(IntegerCodecError::Overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{decode_all, encode_all};
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    macro_rules! test_leb128 {
+        ($($name:ident: $test_case:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (value, bytes): (u64, &[u8]) = $test_case;
+                    assert_eq!(encode_all(&Leb128, &[value]), bytes);
+                    assert_eq!(decode_all(&Leb128, bytes, 1), Ok(vec![value]));
+                }
+            )*
+        };
+    }
+
+    test_leb128! {
+        zero: (0, &[0x00]),
+        one_byte: (127, &[0x7f]),
+        two_bytes: (128, &[0x80, 0x01]),
+        wikipedia_example: (624_485, &[0xe5, 0x8e, 0x26]),
+        max: (u64::MAX, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(
+            decode_all(&Leb128, &[0x80], 1),
+            Err(IntegerCodecError::UnexpectedEnd)
+        );
+        // The tenth group of a `u64` only has room for a single bit.
+        let too_large = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02];
+        assert_eq!(
+            decode_all(&Leb128, &too_large, 1),
+            Err(IntegerCodecError::Overflow)
+        );
+        assert_eq!(
+            decode_all(&Leb128, &[0x80; 11], 1),
+            Err(IntegerCodecError::Overflow)
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(values: Vec<u64>) -> bool {
+        decode_all(&Leb128, &encode_all(&Leb128, &values), values.len()) == Ok(values)
+    }
+}
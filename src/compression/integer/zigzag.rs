@@ -0,0 +1,34 @@
+//! ZigZag encoding maps signed integers to unsigned ones so that values close to zero
+//! stay small: `0, -1, 1, -2, 2, ...` become `0, 1, 2, 3, 4, ...`. This lets
+//! variable-length codes, which favour small values, also handle negative numbers.
+//!
+//! https://protobuf.dev/programming-guides/encoding/#signed-ints
+
+pub fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+pub fn zigzag_decode(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn small_magnitudes_map_to_small_values() {
+        let signed = [0, -1, 1, -2, 2, i64::MAX, i64::MIN];
+        let unsigned = [0, 1, 2, 3, 4, u64::MAX - 1, u64::MAX];
+        for (&s, &u) in signed.iter().zip(unsigned.iter()) {
+            assert_eq!(zigzag_encode(s), u);
+            assert_eq!(zigzag_decode(u), s);
+        }
+    }
+
+    #[quickcheck]
+    fn round_trip(value: i64) -> bool {
+        zigzag_decode(zigzag_encode(value)) == value
+    }
+}
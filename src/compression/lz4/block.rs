@@ -0,0 +1,292 @@
+//! The LZ4 block format.
+//!
+//! A block is a series of sequences. Each sequence starts with a token whose high nibble is the
+//! literal length and whose low nibble is the match length minus `MIN_MATCH`; a nibble of `15`
+//! means that extra length bytes follow, each adding up to 255. Then come the literals, a 2-byte
+//! little-endian offset back into the already decoded data, and the extra match length bytes.
+//! The last sequence carries literals only.
+//!
+//! The compressor finds matches with a single-entry hash table indexed by the next four bytes,
+//! which trades ratio for speed in the same way as the reference `LZ4_compress_fast`.
+//!
+//! https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md
+
+use super::Lz4Error;
+
+/// Shortest match that the format can describe.
+const MIN_MATCH: usize = 4;
+/// The last match must start at least this many bytes before the end of the block.
+const MF_LIMIT: usize = 12;
+/// The last bytes of a block are always emitted as literals.
+const LAST_LITERALS: usize = 5;
+/// Matches may reach back at most this far.
+const MAX_DISTANCE: usize = u16::MAX as usize;
+const HASH_LOG: u32 = 12;
+
+/// Returns the upper bound on the length of `lz4_compress_block(data)` for an input of `len` bytes.
+pub fn lz4_max_compressed_len(len: usize) -> usize {
+    len + len / 255 + 16
+}
+
+/// Compresses `data` into a single LZ4 block.
+///
+/// The block does not record the uncompressed length, so callers must keep it alongside
+/// (the frame format does this for them).
+pub fn lz4_compress_block(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(lz4_max_compressed_len(data.len()));
+    compress_into(data, &mut out);
+    out
+}
+
+/// Decompresses a single LZ4 block.
+///
+/// `max_len` caps the decompressed size, protecting callers from blocks that expand
+/// without bound. Malformed input is reported as an error and never read or written
+/// out of bounds.
+pub fn lz4_decompress_block(block: &[u8], max_len: usize) -> Result<Vec<u8>, Lz4Error> {
+    let mut out = Vec::new();
+    decompress_into(block, &mut out, 0, max_len)?;
+    Ok(out)
+}
+
+pub(super) fn compress_into(data: &[u8], out: &mut Vec<u8>) {
+    let mut table = vec![0usize; 1 << HASH_LOG];
+    let mut anchor = 0;
+    let mut i = 0;
+
+    if data.len() > MF_LIMIT {
+        let match_limit = data.len() - MF_LIMIT;
+        // Table entries hold `position + 1`, so that `0` means empty.
+        while i < match_limit {
+            let sequence = read_u32(data, i);
+            let slot = hash(sequence);
+            let candidate = table[slot];
+            table[slot] = i + 1;
+
+            let is_match = candidate != 0
+                && i - (candidate - 1) <= MAX_DISTANCE
+                && read_u32(data, candidate - 1) == sequence;
+            if !is_match {
+                i += 1;
+                continue;
+            }
+
+            let mut start = i;
+            let mut reference = candidate - 1;
+            // Extend the match backwards over pending literals.
+            while start > anchor && reference > 0 && data[start - 1] == data[reference - 1] {
+                start -= 1;
+                reference -= 1;
+            }
+            let end_limit = data.len() - LAST_LITERALS;
+            let mut end = i + MIN_MATCH;
+            while end < end_limit && data[end] == data[reference + (end - start)] {
+                end += 1;
+            }
+
+            write_sequence(
+                out,
+                &data[anchor..start],
+                Some(((start - reference) as u16, end - start)),
+            );
+            i = end;
+            anchor = end;
+        }
+    }
+
+    write_sequence(out, &data[anchor..], None);
+}
+
+/// Decompresses `block` by appending to `out`.
+///
+/// Matches may reach back into `out` down to `window_start`, which lets the frame decoder
+/// resolve references into previous blocks when they are linked.
+pub(super) fn decompress_into(
+    block: &[u8],
+    out: &mut Vec<u8>,
+    window_start: usize,
+    max_len: usize,
+) -> Result<(), Lz4Error> {
+    let limit = out.len().saturating_add(max_len);
+    let mut i = 0;
+
+    loop {
+        let token = *block.get(i).ok_or(Lz4Error::TruncatedInput)?;
+        i += 1;
+
+        let literal_len = read_length(block, &mut i, (token >> 4) as usize)?;
+        let literals = block
+            .get(i..i.saturating_add(literal_len))
+            .ok_or(Lz4Error::TruncatedInput)?;
+        if out.len() + literal_len > limit {
+            return Err(Lz4Error::OutputTooLarge);
+        }
+        out.extend_from_slice(literals);
+        i += literal_len;
+
+        if i == block.len() {
+            return Ok(());
+        }
+
+        let offset = block
+            .get(i..i + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+            .ok_or(Lz4Error::TruncatedInput)?;
+        i += 2;
+        if offset == 0 || offset > out.len() - window_start {
+            return Err(Lz4Error::InvalidOffset);
+        }
+
+        let match_len = read_length(block, &mut i, (token & 0x0f) as usize)?
+            .checked_add(MIN_MATCH)
+            .ok_or(Lz4Error::OutputTooLarge)?;
+        if out.len() + match_len > limit {
+            return Err(Lz4Error::OutputTooLarge);
+        }
+        // Matches may overlap the bytes they produce, so copy one byte at a time.
+        let start = out.len() - offset;
+        for k in 0..match_len {
+            out.push(out[start + k]);
+        }
+    }
+}
+
+/// Reads the nibble length `base` and its optional continuation bytes.
+fn read_length(block: &[u8], i: &mut usize, base: usize) -> Result<usize, Lz4Error> {
+    let mut len = base;
+    if base == 15 {
+        loop {
+            let byte = *block.get(*i).ok_or(Lz4Error::TruncatedInput)?;
+            *i += 1;
+            len = len
+                .checked_add(byte as usize)
+                .ok_or(Lz4Error::OutputTooLarge)?;
+            if byte != 255 {
+                break;
+            }
+        }
+    }
+    Ok(len)
+}
+
+fn write_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(u16, usize)>) {
+    let match_nibble = matched.map_or(0, |(_, len)| (len - MIN_MATCH).min(15));
+    out.push(((literals.len().min(15) as u8) << 4) | match_nibble as u8);
+    write_length(out, literals.len());
+    out.extend_from_slice(literals);
+
+    if let Some((offset, len)) = matched {
+        out.extend_from_slice(&offset.to_le_bytes());
+        write_length(out, len - MIN_MATCH);
+    }
+}
+
+/// Writes the continuation bytes of a length whose nibble saturated at 15.
+fn write_length(out: &mut Vec<u8>, len: usize) {
+    if len < 15 {
+        return;
+    }
+    let mut rest = len - 15;
+    while rest >= 255 {
+        out.push(255);
+        rest -= 255;
+    }
+    out.push(rest as u8);
+}
+
+fn hash(sequence: u32) -> usize {
+    (sequence.wrapping_mul(2_654_435_761) >> (32 - HASH_LOG)) as usize
+}
+
+fn read_u32(data: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    fn round_trip_of(data: &[u8]) -> Vec<u8> {
+        let block = lz4_compress_block(data);
+        assert!(block.len() <= lz4_max_compressed_len(data.len()));
+        lz4_decompress_block(&block, data.len()).unwrap()
+    }
+
+    #[test]
+    fn short_input_is_all_literals() {
+        assert_eq!(lz4_compress_block(b""), [0x00]);
+        assert_eq!(lz4_compress_block(b"abc"), b"\x30abc");
+    }
+
+    #[test]
+    fn repetitive_input_compresses() {
+        let data = b"abcdabcdabcdabcdabcdabcdabcdabcd".repeat(32);
+        let block = lz4_compress_block(&data);
+        assert!(block.len() < data.len() / 10);
+        assert_eq!(lz4_decompress_block(&block, data.len()).unwrap(), data);
+    }
+
+    #[test]
+    fn decodes_overlapping_match() {
+        // Literal "a", then a 9-byte match at offset 1, then the literals "bcdef".
+        let block = [0x15, b'a', 0x01, 0x00, 0x50, b'b', b'c', b'd', b'e', b'f'];
+        assert_eq!(
+            lz4_decompress_block(&block, 64).unwrap(),
+            b"aaaaaaaaaabcdef"
+        );
+    }
+
+    #[test]
+    fn decodes_long_lengths() {
+        let data: Vec<u8> = (0..300u32).map(|x| (x * 7 % 251) as u8).collect();
+        assert_eq!(round_trip_of(&data), data);
+        let zeros = vec![0u8; 100_000];
+        assert_eq!(round_trip_of(&zeros), zeros);
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(lz4_decompress_block(&[], 16), Err(Lz4Error::TruncatedInput));
+        assert_eq!(
+            lz4_decompress_block(&[0x50, b'a'], 16),
+            Err(Lz4Error::TruncatedInput)
+        );
+        assert_eq!(
+            lz4_decompress_block(&[0x10, b'a', 0x00, 0x00], 16),
+            Err(Lz4Error::InvalidOffset)
+        );
+        assert_eq!(
+            lz4_decompress_block(&[0x10, b'a', 0x02, 0x00], 16),
+            Err(Lz4Error::InvalidOffset)
+        );
+        assert_eq!(
+            lz4_decompress_block(&[0x10, b'a', 0x01], 16),
+            Err(Lz4Error::TruncatedInput)
+        );
+        assert_eq!(
+            lz4_decompress_block(&[0x1f, b'a', 0x01, 0x00, 0xff], 16),
+            Err(Lz4Error::TruncatedInput)
+        );
+        assert_eq!(
+            lz4_decompress_block(&[0x1f, b'a', 0x01, 0x00, 0xff, 0x00, 0x00], 16),
+            Err(Lz4Error::OutputTooLarge)
+        );
+    }
+
+    #[quickcheck]
+    fn round_trip(data: Vec<u8>) -> bool {
+        round_trip_of(&data) == data
+    }
+
+    #[quickcheck]
+    fn round_trip_low_entropy(data: Vec<bool>) -> bool {
+        let data: Vec<u8> = data.into_iter().map(u8::from).collect();
+        round_trip_of(&data) == data
+    }
+
+    #[quickcheck]
+    fn arbitrary_input_respects_output_limit(block: Vec<u8>) -> bool {
+        lz4_decompress_block(&block, 1 << 10).map_or(true, |out| out.len() <= 1 << 10)
+    }
+}
@@ -0,0 +1,644 @@
+//! The LZ4 frame format, which wraps LZ4 blocks with the metadata needed to decode them on
+//! their own: a magic number, a frame descriptor protected by a header checksum, a sequence of
+//! size-prefixed blocks and an optional xxHash32 checksum of the whole content.
+//!
+//! Frames produced here can be read by the reference `lz4` command line tool and vice versa.
+//!
+//! https://github.com/lz4/lz4/blob/dev/doc/lz4_Frame_format.md
+
+use std::convert::Infallible;
+
+use super::block::{compress_into, decompress_into};
+use super::xxhash32::{xxhash32, XxHash32};
+use super::Lz4Error;
+use crate::compression::streaming::{Compressor, Decompressor};
+
+const MAGIC_NUMBER: u32 = 0x184d_2204;
+/// Skippable frames use the magic numbers `0x184d2a50..=0x184d2a5f`.
+const SKIPPABLE_MAGIC_MASK: u32 = 0xffff_fff0;
+const SKIPPABLE_MAGIC_NUMBER: u32 = 0x184d_2a50;
+
+const VERSION: u8 = 0b01;
+const FLAG_BLOCK_INDEPENDENCE: u8 = 1 << 5;
+const FLAG_BLOCK_CHECKSUM: u8 = 1 << 4;
+const FLAG_CONTENT_SIZE: u8 = 1 << 3;
+const FLAG_CONTENT_CHECKSUM: u8 = 1 << 2;
+const FLAG_RESERVED: u8 = 1 << 1;
+const FLAG_DICTIONARY_ID: u8 = 1;
+const BLOCK_SIZE_RESERVED: u8 = 0b1000_1111;
+/// The high bit of a block size marks a block stored without compression.
+const UNCOMPRESSED_BLOCK: u32 = 1 << 31;
+
+/// Maximum size of the blocks in a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lz4BlockSize {
+    #[default]
+    Max64KiB,
+    Max256KiB,
+    Max1MiB,
+    Max4MiB,
+}
+
+impl Lz4BlockSize {
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            4 => Some(Lz4BlockSize::Max64KiB),
+            5 => Some(Lz4BlockSize::Max256KiB),
+            6 => Some(Lz4BlockSize::Max1MiB),
+            7 => Some(Lz4BlockSize::Max4MiB),
+            _ => None,
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Lz4BlockSize::Max64KiB => 4,
+            Lz4BlockSize::Max256KiB => 5,
+            Lz4BlockSize::Max1MiB => 6,
+            Lz4BlockSize::Max4MiB => 7,
+        }
+    }
+
+    /// Returns the block size in bytes.
+    pub fn bytes(self) -> usize {
+        1 << (8 + 2 * self.id())
+    }
+}
+
+/// Settings used when writing a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lz4FrameOptions {
+    pub block_size: Lz4BlockSize,
+    /// Append an xxHash32 of every block after it.
+    pub block_checksum: bool,
+    /// Append an xxHash32 of the whole uncompressed content after the last block.
+    pub content_checksum: bool,
+}
+
+impl Default for Lz4FrameOptions {
+    fn default() -> Self {
+        Lz4FrameOptions {
+            block_size: Lz4BlockSize::default(),
+            block_checksum: false,
+            content_checksum: true,
+        }
+    }
+}
+
+/// Compresses `data` into an LZ4 frame with the default options.
+pub fn lz4_frame_compress(data: &[u8]) -> Vec<u8> {
+    lz4_frame_compress_with(data, &Lz4FrameOptions::default())
+}
+
+/// Compresses `data` into an LZ4 frame made of independent blocks.
+///
+/// The content size is recorded in the frame descriptor. Blocks that do not shrink
+/// are stored uncompressed.
+pub fn lz4_frame_compress_with(data: &[u8], options: &Lz4FrameOptions) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2 + 32);
+    let mut encoder = Lz4FrameEncoder::new(*options);
+    encoder.content_size = Some(data.len() as u64);
+    let Ok(()) = encoder.compress_chunk(data, &mut out);
+    let Ok(()) = encoder.finish(&mut out);
+    out
+}
+
+/// Decompresses one or more concatenated LZ4 frames, skipping skippable frames.
+///
+/// Every checksum present in the input is verified, and linked (dependent) blocks are supported.
+/// Frames that require an external dictionary are rejected.
+pub fn lz4_frame_decompress(input: &[u8]) -> Result<Vec<u8>, Lz4Error> {
+    let mut out = Vec::new();
+    let mut decoder = Lz4FrameDecoder::new();
+    decoder.decompress_chunk(input, &mut out)?;
+    decoder.finish(&mut out)?;
+    Ok(out)
+}
+
+/// Incremental LZ4 frame compressor, buffering at most one block of input.
+///
+/// The content size is unknown up front, so it is left out of the frame descriptor.
+pub struct Lz4FrameEncoder {
+    options: Lz4FrameOptions,
+    content_size: Option<u64>,
+    header_written: bool,
+    /// Input that does not fill a whole block yet.
+    buffer: Vec<u8>,
+    /// Scratch space for compressed blocks.
+    block: Vec<u8>,
+    hasher: XxHash32,
+}
+
+impl Lz4FrameEncoder {
+    pub fn new(options: Lz4FrameOptions) -> Self {
+        Lz4FrameEncoder {
+            options,
+            content_size: None,
+            header_written: false,
+            buffer: Vec::new(),
+            block: Vec::new(),
+            hasher: XxHash32::new(0),
+        }
+    }
+
+    fn write_header(&mut self, output: &mut Vec<u8>) {
+        if self.header_written {
+            return;
+        }
+        self.header_written = true;
+
+        output.extend_from_slice(&MAGIC_NUMBER.to_le_bytes());
+        let descriptor_start = output.len();
+        let mut flags = (VERSION << 6) | FLAG_BLOCK_INDEPENDENCE;
+        if self.options.block_checksum {
+            flags |= FLAG_BLOCK_CHECKSUM;
+        }
+        if self.content_size.is_some() {
+            flags |= FLAG_CONTENT_SIZE;
+        }
+        if self.options.content_checksum {
+            flags |= FLAG_CONTENT_CHECKSUM;
+        }
+        output.push(flags);
+        output.push(self.options.block_size.id() << 4);
+        if let Some(size) = self.content_size {
+            output.extend_from_slice(&size.to_le_bytes());
+        }
+        output.push(header_checksum(&output[descriptor_start..]));
+    }
+}
+
+/// Compresses `chunk` as one block, storing it verbatim if that is not larger.
+fn write_block(chunk: &[u8], scratch: &mut Vec<u8>, block_checksum: bool, output: &mut Vec<u8>) {
+    scratch.clear();
+    compress_into(chunk, scratch);
+    let (stored, size) = if scratch.len() < chunk.len() {
+        (&scratch[..], scratch.len() as u32)
+    } else {
+        (chunk, chunk.len() as u32 | UNCOMPRESSED_BLOCK)
+    };
+    output.extend_from_slice(&size.to_le_bytes());
+    output.extend_from_slice(stored);
+    if block_checksum {
+        output.extend_from_slice(&xxhash32(stored, 0).to_le_bytes());
+    }
+}
+
+impl Compressor for Lz4FrameEncoder {
+    type Error = Infallible;
+
+    fn compress_chunk(&mut self, mut input: &[u8], output: &mut Vec<u8>) -> Result<(), Infallible> {
+        self.write_header(output);
+        self.hasher.update(input);
+
+        let block_size = self.options.block_size.bytes();
+        while !input.is_empty() {
+            if self.buffer.is_empty() && input.len() >= block_size {
+                let (chunk, rest) = input.split_at(block_size);
+                write_block(chunk, &mut self.block, self.options.block_checksum, output);
+                input = rest;
+                continue;
+            }
+
+            let len = (block_size - self.buffer.len()).min(input.len());
+            self.buffer.extend_from_slice(&input[..len]);
+            input = &input[len..];
+            if self.buffer.len() == block_size {
+                write_block(
+                    &self.buffer,
+                    &mut self.block,
+                    self.options.block_checksum,
+                    output,
+                );
+                self.buffer.clear();
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Infallible> {
+        self.write_header(output);
+        if !self.buffer.is_empty() {
+            write_block(
+                &self.buffer,
+                &mut self.block,
+                self.options.block_checksum,
+                output,
+            );
+            self.buffer.clear();
+        }
+        output.extend_from_slice(&0u32.to_le_bytes());
+        if self.options.content_checksum {
+            output.extend_from_slice(&self.hasher.finish().to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+enum DecoderState {
+    Magic,
+    SkippableSize,
+    /// Inside a skippable frame, with this many bytes left to skip.
+    Skipping(usize),
+    Descriptor,
+    BlockSize,
+    Block {
+        len: usize,
+        compressed: bool,
+    },
+    ContentChecksum,
+}
+
+/// Incremental LZ4 frame decompressor.
+///
+/// It buffers at most one block of input and, for linked blocks, the last 64 KiB of output.
+pub struct Lz4FrameDecoder {
+    state: DecoderState,
+    /// Input that does not hold a complete header, block or checksum yet.
+    pending: Vec<u8>,
+    started: bool,
+    flags: u8,
+    block_size: usize,
+    content_size: Option<u64>,
+    content_len: u64,
+    hasher: XxHash32,
+    /// The decoded block, preceded by the history that linked blocks may refer to.
+    window: Vec<u8>,
+}
+
+impl Default for Lz4FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lz4FrameDecoder {
+    pub fn new() -> Self {
+        Lz4FrameDecoder {
+            state: DecoderState::Magic,
+            pending: Vec::new(),
+            started: false,
+            flags: 0,
+            block_size: 0,
+            content_size: None,
+            content_len: 0,
+            hasher: XxHash32::new(0),
+            window: Vec::new(),
+        }
+    }
+
+    /// Consumes the next structure from the front of `input`.
+    ///
+    /// Returns the number of bytes consumed, or `None` if `input` does not hold the whole structure.
+    fn step(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<Option<usize>, Lz4Error> {
+        let consumed = match self.state {
+            DecoderState::Magic | DecoderState::SkippableSize | DecoderState::BlockSize
+                if input.len() < 4 =>
+            {
+                return Ok(None);
+            }
+            DecoderState::Magic => {
+                let magic = read_u32(input);
+                self.state = if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC_NUMBER {
+                    DecoderState::SkippableSize
+                } else if magic == MAGIC_NUMBER {
+                    DecoderState::Descriptor
+                } else {
+                    return Err(Lz4Error::BadMagicNumber);
+                };
+                4
+            }
+            DecoderState::SkippableSize => {
+                self.state = DecoderState::Skipping(read_u32(input) as usize);
+                4
+            }
+            DecoderState::Skipping(0) => {
+                self.state = DecoderState::Magic;
+                0
+            }
+            DecoderState::Skipping(remaining) => {
+                if input.is_empty() {
+                    return Ok(None);
+                }
+                let len = remaining.min(input.len());
+                self.state = DecoderState::Skipping(remaining - len);
+                len
+            }
+            DecoderState::Descriptor => return self.read_descriptor(input),
+            DecoderState::BlockSize => {
+                let size = read_u32(input);
+                self.state = if size == 0 {
+                    self.end_blocks()?
+                } else {
+                    let len = (size & !UNCOMPRESSED_BLOCK) as usize;
+                    if len > self.block_size {
+                        return Err(Lz4Error::BlockTooLarge);
+                    }
+                    DecoderState::Block {
+                        len,
+                        compressed: size & UNCOMPRESSED_BLOCK == 0,
+                    }
+                };
+                4
+            }
+            DecoderState::Block { len, compressed } => {
+                let checksum_len = if self.flags & FLAG_BLOCK_CHECKSUM != 0 {
+                    4
+                } else {
+                    0
+                };
+                if input.len() < len + checksum_len {
+                    return Ok(None);
+                }
+                let block = &input[..len];
+                if checksum_len != 0 && read_u32(&input[len..]) != xxhash32(block, 0) {
+                    return Err(Lz4Error::BlockChecksumMismatch);
+                }
+                self.decode_block(block, compressed, output)?;
+                self.state = DecoderState::BlockSize;
+                len + checksum_len
+            }
+            DecoderState::ContentChecksum => {
+                if input.len() < 4 {
+                    return Ok(None);
+                }
+                if read_u32(input) != self.hasher.finish() {
+                    return Err(Lz4Error::ContentChecksumMismatch);
+                }
+                self.state = DecoderState::Magic;
+                4
+            }
+        };
+        Ok(Some(consumed))
+    }
+
+    fn read_descriptor(&mut self, input: &[u8]) -> Result<Option<usize>, Lz4Error> {
+        let [flags, block_descriptor, ..] = *input else {
+            return Ok(None);
+        };
+
+        if flags >> 6 != VERSION {
+            return Err(Lz4Error::UnsupportedVersion);
+        }
+        if flags & FLAG_RESERVED != 0 || block_descriptor & BLOCK_SIZE_RESERVED != 0 {
+            return Err(Lz4Error::ReservedBitsSet);
+        }
+        let block_size = Lz4BlockSize::from_id(block_descriptor >> 4)
+            .ok_or(Lz4Error::ReservedBitsSet)?
+            .bytes();
+        if flags & FLAG_DICTIONARY_ID != 0 {
+            return Err(Lz4Error::UnsupportedDictionary);
+        }
+
+        let content_size_len = if flags & FLAG_CONTENT_SIZE != 0 { 8 } else { 0 };
+        let descriptor_len = 2 + content_size_len;
+        if input.len() <= descriptor_len {
+            return Ok(None);
+        }
+        if input[descriptor_len] != header_checksum(&input[..descriptor_len]) {
+            return Err(Lz4Error::HeaderChecksumMismatch);
+        }
+
+        self.flags = flags;
+        self.block_size = block_size;
+        self.content_size =
+            (content_size_len != 0).then(|| u64::from_le_bytes(input[2..10].try_into().unwrap()));
+        self.content_len = 0;
+        self.hasher = XxHash32::new(0);
+        self.window.clear();
+        self.state = DecoderState::BlockSize;
+        Ok(Some(descriptor_len + 1))
+    }
+
+    fn decode_block(
+        &mut self,
+        block: &[u8],
+        compressed: bool,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Lz4Error> {
+        let independent = self.flags & FLAG_BLOCK_INDEPENDENCE != 0;
+        if independent {
+            self.window.clear();
+        }
+
+        let start = self.window.len();
+        if compressed {
+            decompress_into(block, &mut self.window, 0, self.block_size)?;
+        } else {
+            self.window.extend_from_slice(block);
+        }
+
+        let decoded = &self.window[start..];
+        self.hasher.update(decoded);
+        self.content_len += decoded.len() as u64;
+        output.extend_from_slice(decoded);
+
+        // Linked blocks may refer back at most 64 KiB.
+        let history = Lz4BlockSize::Max64KiB.bytes();
+        if !independent && self.window.len() > history {
+            self.window.drain(..self.window.len() - history);
+        }
+        Ok(())
+    }
+
+    /// Checks the content size once the end mark is reached, returning the next state.
+    fn end_blocks(&self) -> Result<DecoderState, Lz4Error> {
+        if self
+            .content_size
+            .is_some_and(|size| size != self.content_len)
+        {
+            return Err(Lz4Error::ContentSizeMismatch);
+        }
+        Ok(if self.flags & FLAG_CONTENT_CHECKSUM != 0 {
+            DecoderState::ContentChecksum
+        } else {
+            DecoderState::Magic
+        })
+    }
+}
+
+impl Decompressor for Lz4FrameDecoder {
+    type Error = Lz4Error;
+
+    fn decompress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Lz4Error> {
+        self.started |= !input.is_empty();
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.extend_from_slice(input);
+
+        let mut consumed = 0;
+        let result = loop {
+            match self.step(&pending[consumed..], output) {
+                Ok(Some(len)) => consumed += len,
+                Ok(None) => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+
+        pending.drain(..consumed);
+        self.pending = pending;
+        result
+    }
+
+    fn finish(&mut self, _output: &mut Vec<u8>) -> Result<(), Lz4Error> {
+        if !self.started || !self.pending.is_empty() {
+            return Err(Lz4Error::TruncatedInput);
+        }
+        match self.state {
+            DecoderState::Magic => Ok(()),
+            _ => Err(Lz4Error::TruncatedInput),
+        }
+    }
+}
+
+/// The header checksum is the second byte of the xxHash32 of the frame descriptor.
+fn header_checksum(descriptor: &[u8]) -> u8 {
+    (xxhash32(descriptor, 0) >> 8) as u8
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    /// `printf 'hello hello hello hello hello hello' | lz4 -c`
+    const REFERENCE_FRAME: [u8; 35] = [
+        0x04, 0x22, 0x4d, 0x18, 0x64, 0x40, 0xa7, 0x10, 0x00, 0x00, 0x00, 0x6f, 0x68, 0x65, 0x6c,
+        0x6c, 0x6f, 0x20, 0x06, 0x00, 0x05, 0x50, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x00, 0x00, 0x00,
+        0x00, 0xbd, 0x9c, 0xd6, 0x74,
+    ];
+
+    /// `printf 'hello hello hello hello hello hello' | lz4 -c -BD -BX`
+    const REFERENCE_LINKED_FRAME: [u8; 39] = [
+        0x04, 0x22, 0x4d, 0x18, 0x74, 0x40, 0xbd, 0x10, 0x00, 0x00, 0x00, 0x6f, 0x68, 0x65, 0x6c,
+        0x6c, 0x6f, 0x20, 0x06, 0x00, 0x05, 0x50, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x72, 0x20, 0xb5,
+        0x28, 0x00, 0x00, 0x00, 0x00, 0xbd, 0x9c, 0xd6, 0x74,
+    ];
+
+    const HELLO: &[u8] = b"hello hello hello hello hello hello";
+
+    #[test]
+    fn decodes_reference_frames() {
+        assert_eq!(lz4_frame_decompress(&REFERENCE_FRAME).unwrap(), HELLO);
+        assert_eq!(
+            lz4_frame_decompress(&REFERENCE_LINKED_FRAME).unwrap(),
+            HELLO
+        );
+    }
+
+    #[test]
+    fn encodes_reference_layout() {
+        let frame = lz4_frame_compress(HELLO);
+        assert_eq!(&frame[..4], &REFERENCE_FRAME[..4]);
+        assert_eq!(frame[4], 0x6c);
+        assert_eq!(&frame[6..14], &(HELLO.len() as u64).to_le_bytes());
+        assert_eq!(&frame[frame.len() - 4..], &REFERENCE_FRAME[31..]);
+        assert_eq!(lz4_frame_decompress(&frame).unwrap(), HELLO);
+    }
+
+    #[test]
+    fn empty_content() {
+        let frame = lz4_frame_compress(b"");
+        assert_eq!(lz4_frame_decompress(&frame).unwrap(), b"");
+    }
+
+    #[test]
+    fn multiple_blocks_and_checksums() {
+        let data: Vec<u8> = (0..200_000u32)
+            .map(|x| (x % 97) as u8 ^ (x >> 12) as u8)
+            .collect();
+        let options = Lz4FrameOptions {
+            block_size: Lz4BlockSize::Max64KiB,
+            block_checksum: true,
+            content_checksum: true,
+        };
+        let frame = lz4_frame_compress_with(&data, &options);
+        assert!(frame.len() < data.len());
+        assert_eq!(lz4_frame_decompress(&frame).unwrap(), data);
+    }
+
+    #[test]
+    fn incompressible_blocks_are_stored() {
+        let data: Vec<u8> = (0..=255).collect();
+        let frame = lz4_frame_compress(&data);
+        assert_eq!(&frame[15..19], &(256 | UNCOMPRESSED_BLOCK).to_le_bytes());
+        assert_eq!(lz4_frame_decompress(&frame).unwrap(), data);
+    }
+
+    #[test]
+    fn concatenated_and_skippable_frames() {
+        let mut input = lz4_frame_compress(b"first ");
+        input.extend_from_slice(&0x184d_2a53u32.to_le_bytes());
+        input.extend_from_slice(&3u32.to_le_bytes());
+        input.extend_from_slice(b"???");
+        input.extend_from_slice(&REFERENCE_FRAME);
+        assert_eq!(
+            lz4_frame_decompress(&input).unwrap(),
+            [&b"first "[..], HELLO].concat()
+        );
+    }
+
+    #[test]
+    fn malformed_frames() {
+        let mut frame = REFERENCE_FRAME;
+        frame[0] = 0x05;
+        assert_eq!(lz4_frame_decompress(&frame), Err(Lz4Error::BadMagicNumber));
+
+        let mut frame = REFERENCE_FRAME;
+        frame[6] ^= 1;
+        assert_eq!(
+            lz4_frame_decompress(&frame),
+            Err(Lz4Error::HeaderChecksumMismatch)
+        );
+
+        let mut frame = REFERENCE_FRAME;
+        frame[4] = 0x24;
+        assert_eq!(
+            lz4_frame_decompress(&frame),
+            Err(Lz4Error::UnsupportedVersion)
+        );
+
+        let mut frame = REFERENCE_FRAME;
+        frame[20] = b'X';
+        assert_eq!(
+            lz4_frame_decompress(&frame),
+            Err(Lz4Error::ContentChecksumMismatch)
+        );
+
+        let mut frame = REFERENCE_LINKED_FRAME;
+        frame[20] = b'X';
+        assert_eq!(
+            lz4_frame_decompress(&frame),
+            Err(Lz4Error::BlockChecksumMismatch)
+        );
+
+        let mut frame = lz4_frame_compress(HELLO);
+        frame[6] = 0;
+        frame[14] = header_checksum(&frame[4..14]);
+        assert_eq!(
+            lz4_frame_decompress(&frame),
+            Err(Lz4Error::ContentSizeMismatch)
+        );
+
+        assert_eq!(
+            lz4_frame_decompress(&REFERENCE_FRAME[..30]),
+            Err(Lz4Error::TruncatedInput)
+        );
+        assert_eq!(lz4_frame_decompress(&[]), Err(Lz4Error::TruncatedInput));
+    }
+
+    #[quickcheck]
+    fn round_trip(data: Vec<u8>) -> bool {
+        lz4_frame_decompress(&lz4_frame_compress(&data)).unwrap() == data
+    }
+
+    #[quickcheck]
+    fn arbitrary_blocks_are_rejected_without_panicking(tail: Vec<u8>) -> bool {
+        let mut input = REFERENCE_FRAME[..7].to_vec();
+        input.extend(tail);
+        lz4_frame_decompress(&input).map_or(true, |out| out.len() <= input.len() * 255)
+    }
+}
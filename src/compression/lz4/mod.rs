@@ -0,0 +1,39 @@
+mod block;
+mod frame;
+mod xxhash32;
+
+pub use self::block::{lz4_compress_block, lz4_decompress_block, lz4_max_compressed_len};
+pub use self::frame::{
+    lz4_frame_compress, lz4_frame_compress_with, lz4_frame_decompress, Lz4BlockSize,
+    Lz4FrameDecoder, Lz4FrameEncoder, Lz4FrameOptions,
+};
+pub use self::xxhash32::{xxhash32, XxHash32};
+
+/// Errors that can occur while decoding LZ4 blocks and frames.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lz4Error {
+    /// The input ends before the structure it describes.
+    TruncatedInput,
+    /// A match refers to data before the start of the decoded output, or has offset zero.
+    InvalidOffset,
+    /// The decoded data would exceed the allowed size.
+    OutputTooLarge,
+    /// The input does not start with an LZ4 or skippable frame magic number.
+    BadMagicNumber,
+    /// The frame descriptor announces a format version other than 1.
+    UnsupportedVersion,
+    /// The frame descriptor uses reserved bits or an unknown block size.
+    ReservedBitsSet,
+    /// The frame needs a dictionary that was not provided.
+    UnsupportedDictionary,
+    /// The frame descriptor does not match its checksum.
+    HeaderChecksumMismatch,
+    /// A block is larger than the maximum block size declared by its frame.
+    BlockTooLarge,
+    /// A block does not match its checksum.
+    BlockChecksumMismatch,
+    /// The decoded content does not match the frame's content checksum.
+    ContentChecksumMismatch,
+    /// The decoded content does not have the size declared by the frame.
+    ContentSizeMismatch,
+}
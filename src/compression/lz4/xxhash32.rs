@@ -0,0 +1,157 @@
+//! xxHash32, the non-cryptographic checksum used by the LZ4 frame format.
+//!
+//! https://github.com/Cyan4973/xxHash/blob/dev/doc/xxhash_spec.md
+
+const PRIME_1: u32 = 0x9e37_79b1;
+const PRIME_2: u32 = 0x85eb_ca77;
+const PRIME_3: u32 = 0xc2b2_ae3d;
+const PRIME_4: u32 = 0x27d4_eb2f;
+const PRIME_5: u32 = 0x1656_67b1;
+
+/// Computes the 32-bit xxHash of `data` with the given `seed`.
+pub fn xxhash32(data: &[u8], seed: u32) -> u32 {
+    let mut hasher = XxHash32::new(seed);
+    hasher.update(data);
+    hasher.finish()
+}
+
+/// Incremental xxHash32, for data that arrives in pieces.
+#[derive(Debug, Clone)]
+pub struct XxHash32 {
+    seed: u32,
+    acc: [u32; 4],
+    /// Bytes of an incomplete 16-byte stripe.
+    buffer: [u8; 16],
+    buffered: usize,
+    total_len: u64,
+}
+
+impl XxHash32 {
+    pub fn new(seed: u32) -> Self {
+        XxHash32 {
+            seed,
+            acc: [
+                seed.wrapping_add(PRIME_1).wrapping_add(PRIME_2),
+                seed.wrapping_add(PRIME_2),
+                seed,
+                seed.wrapping_sub(PRIME_1),
+            ],
+            buffer: [0; 16],
+            buffered: 0,
+            total_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+
+        if self.buffered > 0 {
+            let len = (16 - self.buffered).min(data.len());
+            self.buffer[self.buffered..self.buffered + len].copy_from_slice(&data[..len]);
+            self.buffered += len;
+            data = &data[len..];
+            if self.buffered < 16 {
+                return;
+            }
+            let stripe = self.buffer;
+            self.consume(&stripe);
+            self.buffered = 0;
+        }
+
+        let mut stripes = data.chunks_exact(16);
+        for stripe in stripes.by_ref() {
+            self.consume(stripe);
+        }
+        let rest = stripes.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    /// Returns the hash of everything passed to `update` so far.
+    pub fn finish(&self) -> u32 {
+        let mut hash = if self.total_len >= 16 {
+            self.acc[0]
+                .rotate_left(1)
+                .wrapping_add(self.acc[1].rotate_left(7))
+                .wrapping_add(self.acc[2].rotate_left(12))
+                .wrapping_add(self.acc[3].rotate_left(18))
+        } else {
+            self.seed.wrapping_add(PRIME_5)
+        };
+
+        hash = hash.wrapping_add(self.total_len as u32);
+
+        let mut words = self.buffer[..self.buffered].chunks_exact(4);
+        for word in words.by_ref() {
+            hash = hash
+                .wrapping_add(read_u32(word).wrapping_mul(PRIME_3))
+                .rotate_left(17)
+                .wrapping_mul(PRIME_4);
+        }
+        for &byte in words.remainder() {
+            hash = hash
+                .wrapping_add((byte as u32).wrapping_mul(PRIME_5))
+                .rotate_left(11)
+                .wrapping_mul(PRIME_1);
+        }
+
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(PRIME_2);
+        hash ^= hash >> 13;
+        hash = hash.wrapping_mul(PRIME_3);
+        hash ^ (hash >> 16)
+    }
+
+    fn consume(&mut self, stripe: &[u8]) {
+        for (lane, word) in self.acc.iter_mut().zip(stripe.chunks_exact(4)) {
+            *lane = round(*lane, read_u32(word));
+        }
+    }
+}
+
+fn round(acc: u32, input: u32) -> u32 {
+    acc.wrapping_add(input.wrapping_mul(PRIME_2))
+        .rotate_left(13)
+        .wrapping_mul(PRIME_1)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_xxhash32 {
+        ($($name:ident: $test_case:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (data, seed, expected): (&[u8], u32, u32) = $test_case;
+                    assert_eq!(xxhash32(data, seed), expected);
+                }
+            )*
+        };
+    }
+
+    test_xxhash32! {
+        empty: (b"", 0, 0x02cc_5d05),
+        empty_with_seed: (b"", 1, 0x0b2c_b792),
+        short: (b"abc", 0, 0x32d1_53ff),
+        long: (b"Nobody inspects the spammish repetition", 0, 0xe229_3b2f),
+    }
+
+    #[test]
+    fn incremental_matches_one_shot() {
+        let data: Vec<u8> = (0..1000u32).map(|x| (x * 31 % 256) as u8).collect();
+        for split in [0, 1, 15, 16, 17, 100, 999, 1000] {
+            let mut hasher = XxHash32::new(7);
+            for piece in data[..split].chunks(3) {
+                hasher.update(piece);
+            }
+            hasher.update(&data[split..]);
+            assert_eq!(hasher.finish(), xxhash32(&data, 7));
+        }
+    }
+}
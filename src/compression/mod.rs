@@ -1,3 +1,35 @@
+mod bit_run_length_encoding;
+mod escape_run_length_encoding;
+mod integer;
+mod lz4;
+mod packbits;
 mod run_length_encoding;
+mod streaming;
 
+pub use self::bit_run_length_encoding::{
+    bit_run_length_decode, bit_run_length_encode, bit_run_length_max_encoded_len,
+    BitRunLengthDecoder, BitRunLengthEncoder, BitRunLengthError,
+};
+pub use self::escape_run_length_encoding::{
+    escape_run_length_decode, escape_run_length_encode, escape_run_length_max_encoded_len,
+    EscapeRunLengthDecoder, EscapeRunLengthEncoder, EscapeRunLengthError,
+};
+pub use self::integer::{
+    bit_pack_decode, bit_pack_encode, decode_all, delta_decode, delta_encode,
+    delta_of_delta_decode, delta_of_delta_encode, encode_all, zigzag_decode, zigzag_encode,
+    BitReader, BitWriter, EliasDelta, EliasGamma, GolombRice, IntegerCode, IntegerCodecError,
+    Leb128,
+};
+pub use self::lz4::{
+    lz4_compress_block, lz4_decompress_block, lz4_frame_compress, lz4_frame_compress_with,
+    lz4_frame_decompress, lz4_max_compressed_len, xxhash32, Lz4BlockSize, Lz4Error,
+    Lz4FrameDecoder, Lz4FrameEncoder, Lz4FrameOptions, XxHash32,
+};
+pub use self::packbits::{
+    packbits_decode, packbits_encode, packbits_max_encoded_len, PackBitsDecoder, PackBitsEncoder,
+    PackBitsError,
+};
 pub use self::run_length_encoding::{run_length_decode, run_length_encode};
+pub use self::streaming::{
+    CompressReader, CompressWriter, Compressor, DecompressReader, DecompressWriter, Decompressor,
+};
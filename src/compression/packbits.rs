@@ -0,0 +1,281 @@
+//! PackBits is the byte-oriented run-length scheme used by TIFF and Apple's MacPaint.
+//!
+//! The encoded stream is a sequence of packets, each starting with a signed header byte `n`:
+//!
+//! * `0..=127`: the next `n + 1` bytes are copied literally;
+//! * `-127..=-1`: the next byte is repeated `1 - n` times;
+//! * `-128`: no-op, skipped by the decoder.
+//!
+//! Literal packets cap at 128 bytes, so the encoded output is never longer than
+//! `len + ceil(len / 128)` bytes.
+//!
+//! https://en.wikipedia.org/wiki/PackBits
+
+use std::convert::Infallible;
+
+use super::streaming::{Compressor, Decompressor};
+
+/// Longest run or literal that a single PackBits packet can describe.
+const MAX_PACKET_LEN: usize = 128;
+
+/// Header byte that the decoder must skip, as mandated by the TIFF specification.
+const NO_OP_HEADER: u8 = 0x80;
+
+/// Errors that can occur while decoding a PackBits stream.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PackBitsError {
+    /// A literal packet announces more bytes than the input still contains.
+    TruncatedLiteral,
+    /// A run packet header is not followed by the byte to repeat.
+    MissingRunByte,
+}
+
+/// Returns the upper bound on the length of `packbits_encode(data)` for an input of `len` bytes.
+pub fn packbits_max_encoded_len(len: usize) -> usize {
+    len + len.div_ceil(MAX_PACKET_LEN)
+}
+
+/// Compresses `data` with PackBits.
+///
+/// Runs of three or more equal bytes become run packets; everything else is
+/// gathered into literal packets of at most 128 bytes.
+pub fn packbits_encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(packbits_max_encoded_len(data.len()));
+    let mut encoder = PackBitsEncoder::new();
+    let Ok(()) = encoder.compress_chunk(data, &mut encoded);
+    let Ok(()) = encoder.finish(&mut encoded);
+    encoded
+}
+
+/// Decompresses a PackBits stream produced by `packbits_encode` or any TIFF-compliant encoder.
+pub fn packbits_decode(encoded: &[u8]) -> Result<Vec<u8>, PackBitsError> {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut decoder = PackBitsDecoder::new();
+    decoder.decompress_chunk(encoded, &mut decoded)?;
+    decoder.finish(&mut decoded)?;
+    Ok(decoded)
+}
+
+/// Incremental PackBits compressor, producing the same output as `packbits_encode`.
+///
+/// It holds at most one pending literal packet and one run.
+pub struct PackBitsEncoder {
+    literal: Vec<u8>,
+    run_byte: u8,
+    run_len: usize,
+}
+
+impl Default for PackBitsEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackBitsEncoder {
+    pub fn new() -> Self {
+        PackBitsEncoder {
+            literal: Vec::with_capacity(MAX_PACKET_LEN),
+            run_byte: 0,
+            run_len: 0,
+        }
+    }
+
+    /// Emits the current run as a run packet, or moves it into the pending literal if it is too short.
+    fn end_run(&mut self, output: &mut Vec<u8>) {
+        if self.run_len >= 3 {
+            flush_literal(output, &self.literal);
+            self.literal.clear();
+            output.push((1 - self.run_len as i16) as u8);
+            output.push(self.run_byte);
+        } else {
+            for _ in 0..self.run_len {
+                self.literal.push(self.run_byte);
+                if self.literal.len() == MAX_PACKET_LEN {
+                    flush_literal(output, &self.literal);
+                    self.literal.clear();
+                }
+            }
+        }
+        self.run_len = 0;
+    }
+}
+
+impl Compressor for PackBitsEncoder {
+    type Error = Infallible;
+
+    fn compress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Infallible> {
+        for &byte in input {
+            if self.run_len > 0 && (byte != self.run_byte || self.run_len == MAX_PACKET_LEN) {
+                self.end_run(output);
+            }
+            self.run_byte = byte;
+            self.run_len += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Infallible> {
+        self.end_run(output);
+        flush_literal(output, &self.literal);
+        self.literal.clear();
+        Ok(())
+    }
+}
+
+enum DecoderState {
+    Header,
+    /// Inside a literal packet, with this many bytes left to copy.
+    Literal(usize),
+    /// After a run header, waiting for the byte to repeat this many times.
+    Run(usize),
+}
+
+/// Incremental PackBits decompressor.
+pub struct PackBitsDecoder {
+    state: DecoderState,
+}
+
+impl Default for PackBitsDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackBitsDecoder {
+    pub fn new() -> Self {
+        PackBitsDecoder {
+            state: DecoderState::Header,
+        }
+    }
+}
+
+impl Decompressor for PackBitsDecoder {
+    type Error = PackBitsError;
+
+    fn decompress_chunk(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), PackBitsError> {
+        let mut i = 0;
+        while i < input.len() {
+            self.state = match self.state {
+                DecoderState::Header => {
+                    let header = input[i];
+                    i += 1;
+                    match header {
+                        NO_OP_HEADER => DecoderState::Header,
+                        0..=127 => DecoderState::Literal(header as usize + 1),
+                        _ => DecoderState::Run((1 - header as i8 as isize) as usize),
+                    }
+                }
+                DecoderState::Literal(remaining) => {
+                    let len = remaining.min(input.len() - i);
+                    output.extend_from_slice(&input[i..i + len]);
+                    i += len;
+                    if len == remaining {
+                        DecoderState::Header
+                    } else {
+                        DecoderState::Literal(remaining - len)
+                    }
+                }
+                DecoderState::Run(count) => {
+                    output.extend(std::iter::repeat_n(input[i], count));
+                    i += 1;
+                    DecoderState::Header
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _output: &mut Vec<u8>) -> Result<(), PackBitsError> {
+        match self.state {
+            DecoderState::Header => Ok(()),
+            DecoderState::Literal(_) => Err(PackBitsError::TruncatedLiteral),
+            DecoderState::Run(_) => Err(PackBitsError::MissingRunByte),
+        }
+    }
+}
+
+/// Emits `literal` as one or more literal packets.
+fn flush_literal(encoded: &mut Vec<u8>, literal: &[u8]) {
+    for chunk in literal.chunks(MAX_PACKET_LEN) {
+        encoded.push((chunk.len() - 1) as u8);
+        encoded.extend_from_slice(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    macro_rules! test_packbits {
+        ($($name:ident: $test_case:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (raw, encoded): (&[u8], &[u8]) = $test_case;
+                    assert_eq!(packbits_encode(raw), encoded);
+                    assert_eq!(packbits_decode(encoded).unwrap(), raw);
+                }
+            )*
+        };
+    }
+
+    test_packbits! {
+        empty: (b"", b""),
+        single_byte: (b"A", b"\x00A"),
+        short_pair_stays_literal: (b"AA", b"\x01AA"),
+        short_run: (b"AAA", b"\xfeA"),
+        digits_are_plain_bytes: (b"1112333", b"\xfe1\x002\xfe3"),
+        binary_run: (&[0u8; 5], b"\xfc\x00"),
+        mixed: (b"ab\x00\x00\x00\x00cd", b"\x01ab\xfd\x00\x01cd"),
+    }
+
+    #[test]
+    fn tiff_specification_example() {
+        let encoded = [
+            0xfe, 0xaa, 0x02, 0x80, 0x00, 0x2a, 0xfd, 0xaa, 0x03, 0x80, 0x00, 0x2a, 0x22, 0xf7,
+            0xaa,
+        ];
+        let raw = [
+            0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0xaa, 0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0x22,
+            0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+        ];
+        assert_eq!(packbits_decode(&encoded).unwrap(), raw);
+    }
+
+    #[test]
+    fn long_runs_are_split_into_packets() {
+        let raw = vec![7u8; 300];
+        let encoded = packbits_encode(&raw);
+        assert_eq!(encoded, [0x81, 7, 0x81, 7, 0xd5, 7]);
+        assert_eq!(packbits_decode(&encoded).unwrap(), raw);
+    }
+
+    #[test]
+    fn no_op_header_is_skipped() {
+        assert_eq!(packbits_decode(b"\x80\x00A\x80").unwrap(), b"A");
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(
+            packbits_decode(b"\x03AB"),
+            Err(PackBitsError::TruncatedLiteral)
+        );
+        assert_eq!(packbits_decode(b"\xfe"), Err(PackBitsError::MissingRunByte));
+    }
+
+    #[quickcheck]
+    fn round_trip(data: Vec<u8>) -> bool {
+        packbits_decode(&packbits_encode(&data)).unwrap() == data
+    }
+
+    #[quickcheck]
+    fn expansion_is_bounded(data: Vec<u8>) -> bool {
+        packbits_encode(&data).len() <= packbits_max_encoded_len(data.len())
+    }
+}
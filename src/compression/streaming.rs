@@ -0,0 +1,400 @@
+//! Incremental compression interfaces and `std::io` adapters.
+//!
+//! A `Compressor` or `Decompressor` is fed its input in chunks of any size and appends whatever
+//! output is ready to a caller-provided buffer, so a codec only ever holds the state it needs
+//! (a pending run, a block, a match window) instead of the whole input. `finish` flushes the
+//! remaining state and terminates the stream; a codec must not be fed after that.
+//!
+//! The adapters plug any codec into `Read`/`Write` pipelines:
+//!
+//! * `CompressReader` and `DecompressReader` transform the bytes read from an inner reader;
+//! * `CompressWriter` and `DecompressWriter` transform the bytes written to an inner writer.
+
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+
+/// Size of the chunks that the readers pull from their inner reader.
+const READ_CHUNK_LEN: usize = 8 * 1024;
+
+/// A codec that compresses a byte stream incrementally.
+///
+/// Codecs that accept any input use `std::convert::Infallible` as their error type.
+pub trait Compressor {
+    type Error: Debug;
+
+    /// Compresses `input`, appending the output that is ready to `output`.
+    fn compress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Self::Error>;
+
+    /// Flushes any buffered state and terminates the stream.
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
+
+/// A codec that decompresses a byte stream incrementally.
+pub trait Decompressor {
+    type Error: Debug;
+
+    /// Decompresses `input`, appending the output that is ready to `output`.
+    fn decompress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Self::Error>;
+
+    /// Checks that the stream ended cleanly and flushes any buffered output.
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
+
+fn invalid_data<E: Debug>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{error:?}"))
+}
+
+fn invalid_input<E: Debug>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("{error:?}"))
+}
+
+/// Pulls chunks from an inner reader through a codec and serves the transformed bytes.
+struct Pump<R> {
+    inner: R,
+    chunk: Vec<u8>,
+    pending: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> Pump<R> {
+    fn new(inner: R) -> Self {
+        Pump {
+            inner,
+            chunk: vec![0; READ_CHUNK_LEN],
+            pending: Vec::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// Fills `buf` from the pending output, calling `transform` with fresh input (or `None` at
+    /// the end of the inner reader) whenever the pending output runs dry.
+    fn read<F>(&mut self, buf: &mut [u8], mut transform: F) -> io::Result<usize>
+    where
+        F: FnMut(Option<&[u8]>, &mut Vec<u8>) -> io::Result<()>,
+    {
+        while self.position == self.pending.len() && !self.finished {
+            self.pending.clear();
+            self.position = 0;
+            let read = self.inner.read(&mut self.chunk)?;
+            if read == 0 {
+                self.finished = true;
+                transform(None, &mut self.pending)?;
+            } else {
+                transform(Some(&self.chunk[..read]), &mut self.pending)?;
+            }
+        }
+
+        let available = &self.pending[self.position..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+/// Reads the compressed form of the bytes produced by an inner reader.
+///
+/// Input the codec cannot compress is reported as an `io::ErrorKind::InvalidInput` error.
+pub struct CompressReader<R, C> {
+    pump: Pump<R>,
+    codec: C,
+}
+
+impl<R: Read, C: Compressor> CompressReader<R, C> {
+    pub fn new(inner: R, codec: C) -> Self {
+        CompressReader {
+            pump: Pump::new(inner),
+            codec,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.pump.inner
+    }
+}
+
+impl<R: Read, C: Compressor> Read for CompressReader<R, C> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let codec = &mut self.codec;
+        self.pump.read(buf, |input, output| {
+            match input {
+                Some(input) => codec.compress_chunk(input, output),
+                None => codec.finish(output),
+            }
+            .map_err(invalid_input)
+        })
+    }
+}
+
+/// Reads the decompressed form of the bytes produced by an inner reader.
+///
+/// Malformed input is reported as an `io::ErrorKind::InvalidData` error.
+pub struct DecompressReader<R, D> {
+    pump: Pump<R>,
+    codec: D,
+}
+
+impl<R: Read, D: Decompressor> DecompressReader<R, D> {
+    pub fn new(inner: R, codec: D) -> Self {
+        DecompressReader {
+            pump: Pump::new(inner),
+            codec,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.pump.inner
+    }
+}
+
+impl<R: Read, D: Decompressor> Read for DecompressReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let codec = &mut self.codec;
+        self.pump.read(buf, |input, output| {
+            match input {
+                Some(input) => codec.decompress_chunk(input, output),
+                None => codec.finish(output),
+            }
+            .map_err(invalid_data)
+        })
+    }
+}
+
+/// Compresses everything written to it into an inner writer.
+///
+/// The stream is only complete once `finish` has been called; dropping the writer
+/// without it leaves the inner writer with a truncated stream. Input the codec cannot
+/// compress is reported as an `io::ErrorKind::InvalidInput` error.
+pub struct CompressWriter<W, C> {
+    inner: W,
+    codec: C,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write, C: Compressor> CompressWriter<W, C> {
+    pub fn new(inner: W, codec: C) -> Self {
+        CompressWriter {
+            inner,
+            codec,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Terminates the compressed stream and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.buffer.clear();
+        self.codec.finish(&mut self.buffer).map_err(invalid_input)?;
+        self.inner.write_all(&self.buffer)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write, C: Compressor> Write for CompressWriter<W, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.clear();
+        self.codec
+            .compress_chunk(buf, &mut self.buffer)
+            .map_err(invalid_input)?;
+        self.inner.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    /// Flushes the inner writer. Output the codec still buffers internally is not forced out.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decompresses everything written to it into an inner writer.
+///
+/// Malformed input is reported as an `io::ErrorKind::InvalidData` error.
+pub struct DecompressWriter<W, D> {
+    inner: W,
+    codec: D,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write, D: Decompressor> DecompressWriter<W, D> {
+    pub fn new(inner: W, codec: D) -> Self {
+        DecompressWriter {
+            inner,
+            codec,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Checks that the compressed stream ended cleanly and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.buffer.clear();
+        self.codec.finish(&mut self.buffer).map_err(invalid_data)?;
+        self.inner.write_all(&self.buffer)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write, D: Decompressor> Write for DecompressWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.clear();
+        self.codec
+            .decompress_chunk(buf, &mut self.buffer)
+            .map_err(invalid_data)?;
+        self.inner.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::{
+        bit_run_length_decode, escape_run_length_decode, lz4_frame_decompress, packbits_decode,
+        BitRunLengthDecoder, BitRunLengthEncoder, EscapeRunLengthDecoder, EscapeRunLengthEncoder,
+        Lz4FrameDecoder, Lz4FrameEncoder, Lz4FrameOptions, PackBitsDecoder, PackBitsEncoder,
+    };
+    use std::convert::Infallible;
+    use std::io::Cursor;
+
+    /// A reader that hands out at most `step` bytes per call, to exercise chunk boundaries.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.step.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut data = b"0000000011223344 stream me, stream me! ".repeat(300);
+        data.extend((0..5000u32).map(|x| (x * x % 251) as u8));
+        data.extend([0u8; 3000]);
+        data
+    }
+
+    type OneShotDecode<E> = fn(&[u8]) -> Result<Vec<u8>, E>;
+
+    fn check_codec<C, D>(
+        compressor: fn() -> C,
+        decompressor: fn() -> D,
+        decode: OneShotDecode<D::Error>,
+    ) where
+        C: Compressor,
+        D: Decompressor,
+        D::Error: PartialEq,
+    {
+        let data = sample();
+        for step in [1, 7, 4096, usize::MAX] {
+            let mut compressed = Vec::new();
+            CompressReader::new(Trickle { data: &data, step }, compressor())
+                .read_to_end(&mut compressed)
+                .unwrap();
+
+            let mut writer = CompressWriter::new(Vec::new(), compressor());
+            for chunk in data.chunks(step.min(data.len())) {
+                writer.write_all(chunk).unwrap();
+            }
+            assert_eq!(writer.finish().unwrap(), compressed);
+
+            let mut decompressed = Vec::new();
+            DecompressReader::new(
+                Trickle {
+                    data: &compressed,
+                    step,
+                },
+                decompressor(),
+            )
+            .read_to_end(&mut decompressed)
+            .unwrap();
+            assert_eq!(decompressed, data);
+
+            let mut writer = DecompressWriter::new(Vec::new(), decompressor());
+            for chunk in compressed.chunks(step.min(compressed.len())) {
+                writer.write_all(chunk).unwrap();
+            }
+            assert_eq!(writer.finish().unwrap(), data);
+
+            assert_eq!(decode(&compressed), Ok(data.clone()));
+        }
+    }
+
+    #[test]
+    fn packbits() {
+        check_codec(PackBitsEncoder::new, PackBitsDecoder::new, packbits_decode);
+    }
+
+    #[test]
+    fn escape_run_length() {
+        check_codec(
+            || EscapeRunLengthEncoder::new(0xfe),
+            EscapeRunLengthDecoder::new,
+            escape_run_length_decode,
+        );
+    }
+
+    #[test]
+    fn bit_run_length() {
+        check_codec(
+            BitRunLengthEncoder::new,
+            || BitRunLengthDecoder::new(usize::MAX),
+            |encoded| bit_run_length_decode(encoded, usize::MAX),
+        );
+    }
+
+    #[test]
+    fn lz4_frame() {
+        check_codec(
+            || Lz4FrameEncoder::new(Lz4FrameOptions::default()),
+            Lz4FrameDecoder::new,
+            lz4_frame_decompress,
+        );
+    }
+
+    type IsValid = fn(&[u8]) -> bool;
+
+    #[test]
+    fn codecs_are_interchangeable() {
+        let data = sample();
+        let codecs: Vec<(Box<dyn Compressor<Error = Infallible>>, IsValid)> = vec![
+            (Box::new(PackBitsEncoder::new()), |c| {
+                packbits_decode(c).is_ok()
+            }),
+            (
+                Box::new(Lz4FrameEncoder::new(Lz4FrameOptions::default())),
+                |c| lz4_frame_decompress(c).is_ok(),
+            ),
+        ];
+        for (mut codec, is_valid) in codecs {
+            let mut compressed = Vec::new();
+            for chunk in data.chunks(1000) {
+                let Ok(()) = codec.compress_chunk(chunk, &mut compressed);
+            }
+            let Ok(()) = codec.finish(&mut compressed);
+            assert!(compressed.len() < data.len());
+            assert!(is_valid(&compressed));
+        }
+    }
+
+    #[test]
+    fn malformed_input_is_invalid_data() {
+        let mut reader = DecompressReader::new(Cursor::new(b"\x05AB"), PackBitsDecoder::new());
+        let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut writer = DecompressWriter::new(Vec::new(), Lz4FrameDecoder::new());
+        let error = writer.write_all(b"not an lz4 frame").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -2,17 +2,22 @@ use std::{
     cmp::{max, Ordering},
     iter::FromIterator,
     mem,
-    ops::Not,
+    ops::{Bound, Not, RangeBounds},
 };
 
 /// An internal node of an `AVLTree`.
 struct AVLNode<T: Ord> {
     value: T,
     height: usize,
+    /// Number of nodes in the subtree rooted at this node.
+    size: usize,
     left: Option<Box<AVLNode<T>>>,
     right: Option<Box<AVLNode<T>>>,
 }
 
+/// An optional subtree of an `AVLTree`.
+type Subtree<T> = Option<Box<AVLNode<T>>>;
+
 /// A set based on an AVL Tree.
 ///
 /// An AVL Tree is a self-balancing binary search tree. It tracks the height of each node
@@ -105,6 +110,206 @@ impl<T: Ord> AVLTree<T> {
             node_iter: self.node_iter(),
         }
     }
+
+    /// Returns the `index`-th smallest value, counting from zero.
+    pub fn select(&self, mut index: usize) -> Option<&T> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let left_size = node.size(Side::Left);
+            current = match index.cmp(&left_size) {
+                Ordering::Less => &node.left,
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    &node.right
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the number of values smaller than `value`.
+    pub fn rank(&self, value: &T) -> usize {
+        self.count_before(|v| v < value)
+    }
+
+    /// Counts the values for which `is_before` holds, given that it holds for a prefix of them.
+    fn count_before<F: Fn(&T) -> bool>(&self, is_before: F) -> usize {
+        let mut count = 0;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if is_before(&node.value) {
+                count += node.size(Side::Left) + 1;
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        count
+    }
+
+    /// Returns the smallest value in the tree.
+    pub fn first(&self) -> Option<&T> {
+        self.select(0)
+    }
+
+    /// Returns the largest value in the tree.
+    pub fn last(&self) -> Option<&T> {
+        self.select(self.length.checked_sub(1)?)
+    }
+
+    /// Returns the largest value smaller than `value`.
+    pub fn predecessor(&self, value: &T) -> Option<&T> {
+        self.select(self.rank(value).checked_sub(1)?)
+    }
+
+    /// Returns the smallest value larger than `value`.
+    pub fn successor(&self, value: &T) -> Option<&T> {
+        self.select(self.count_before(|v| v <= value))
+    }
+
+    /// Returns an iterator over the values in `range`, usable from either end.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T> {
+        let start = match range.start_bound() {
+            Bound::Included(value) => self.rank(value),
+            Bound::Excluded(value) => self.count_before(|v| v <= value),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(value) => self.count_before(|v| v <= value),
+            Bound::Excluded(value) => self.rank(value),
+            Bound::Unbounded => self.length,
+        };
+        let mut range = Range {
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining: end.saturating_sub(start),
+        };
+        if range.remaining > 0 {
+            range.front = self.path_to(start, Side::Left);
+            range.back = self.path_to(end - 1, Side::Right);
+        }
+        range
+    }
+
+    /// Returns the stack of an in-order traversal towards `side` that is about to visit
+    /// the `index`-th smallest node: the node itself on top of every ancestor that the
+    /// traversal visits after it.
+    fn path_to(&self, mut index: usize, side: Side) -> Vec<&AVLNode<T>> {
+        let mut stack = Vec::new();
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let left_size = node.size(Side::Left);
+            let ordering = index.cmp(&left_size);
+            let is_visited_later = match side {
+                Side::Left => ordering != Ordering::Greater,
+                Side::Right => ordering != Ordering::Less,
+            };
+            if is_visited_later {
+                stack.push(node.as_ref());
+            }
+            current = match ordering {
+                Ordering::Less => &node.left,
+                Ordering::Equal => break,
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    &node.right
+                }
+            }
+        }
+        stack
+    }
+
+    /// Moves the values greater than or equal to `value` into a new tree.
+    ///
+    /// The tree is cut along the search path of `value` and the pieces are joined back
+    /// together, which takes `O(log(n))` time.
+    pub fn split_off(&mut self, value: &T) -> AVLTree<T> {
+        let (left, right) = split(self.root.take(), value);
+        self.root = left;
+        self.length = self.root.as_ref().map_or(0, |n| n.size);
+        let length = right.as_ref().map_or(0, |n| n.size);
+        AVLTree {
+            root: right,
+            length,
+        }
+    }
+
+    /// Moves all values of `other` into this tree, leaving `other` empty.
+    ///
+    /// If all values of one tree are smaller than those of the other, the trees are joined
+    /// in `O(log(n))` time; otherwise the values of `other` are inserted one by one.
+    pub fn append(&mut self, other: &mut AVLTree<T>) {
+        let (low, high) = match (self.last(), other.first(), other.last(), self.first()) {
+            (Some(last), Some(first), _, _) if last < first => {
+                (self.root.take(), other.root.take())
+            }
+            (_, _, Some(last), Some(first)) if last < first => {
+                (other.root.take(), self.root.take())
+            }
+            _ => {
+                while let Some(node) = take_min(&mut other.root) {
+                    self.insert(node.value);
+                }
+                other.length = 0;
+                return;
+            }
+        };
+        self.root = join_trees(low, high);
+        self.length += mem::take(&mut other.length);
+    }
+}
+
+/// Joins two trees whose values are all smaller in `left` than in `right`.
+fn join_trees<T: Ord>(left: Subtree<T>, mut right: Subtree<T>) -> Subtree<T> {
+    match take_min(&mut right) {
+        Some(middle) => Some(join(left, middle, right)),
+        None => left,
+    }
+}
+
+/// Joins `left`, `middle` and `right`, whose values are in ascending order, into one tree.
+///
+/// The middle node is attached where the spine of the taller tree meets the height of
+/// the shorter one, and the nodes above it are rebalanced on the way back up.
+fn join<T: Ord>(
+    left: Subtree<T>,
+    mut middle: Box<AVLNode<T>>,
+    right: Subtree<T>,
+) -> Box<AVLNode<T>> {
+    let left_height = left.as_ref().map_or(0, |n| n.height);
+    let right_height = right.as_ref().map_or(0, |n| n.height);
+    if left_height > right_height + 1 {
+        let mut root = left.unwrap();
+        root.right = Some(join(root.right.take(), middle, right));
+        root.rebalance();
+        root
+    } else if right_height > left_height + 1 {
+        let mut root = right.unwrap();
+        root.left = Some(join(left, middle, root.left.take()));
+        root.rebalance();
+        root
+    } else {
+        middle.left = left;
+        middle.right = right;
+        middle.update();
+        middle
+    }
+}
+
+/// Splits a tree into the values smaller than `value` and the others.
+fn split<T: Ord>(tree: Subtree<T>, value: &T) -> (Subtree<T>, Subtree<T>) {
+    let Some(mut node) = tree else {
+        return (None, None);
+    };
+    let (left, right) = (node.left.take(), node.right.take());
+    if node.value < *value {
+        let (smaller, larger) = split(right, value);
+        (Some(join(left, node, smaller)), larger)
+    } else {
+        let (smaller, larger) = split(left, value);
+        (smaller, Some(join(larger, node, right)))
+    }
 }
 
 /// Recursive helper function for `AVLTree` insertion.
@@ -123,6 +328,7 @@ fn insert<T: Ord>(tree: &mut Option<Box<AVLNode<T>>>, value: T) -> bool {
         *tree = Some(Box::new(AVLNode {
             value,
             height: 1,
+            size: 1,
             left: None,
             right: None,
         }));
@@ -216,26 +422,32 @@ impl<T: Ord> AVLNode<T> {
         }
     }
 
-    /// Recomputes the `height` field.
-    fn update_height(&mut self) {
+    /// Returns the number of nodes in the left or right subtree.
+    fn size(&self, side: Side) -> usize {
+        self.child(side).as_ref().map_or(0, |n| n.size)
+    }
+
+    /// Recomputes the `height` and `size` fields.
+    fn update(&mut self) {
         self.height = 1 + max(self.height(Side::Left), self.height(Side::Right));
+        self.size = 1 + self.size(Side::Left) + self.size(Side::Right);
     }
 
     /// Performs a left or right rotation.
     fn rotate(&mut self, side: Side) {
         let mut subtree = self.child_mut(!side).take().unwrap();
         *self.child_mut(!side) = subtree.child_mut(side).take();
-        self.update_height();
+        self.update();
         // Swap root and child nodes in memory
         mem::swap(self, subtree.as_mut());
         // Set old root (subtree) as child of new root (self)
         *self.child_mut(side) = Some(subtree);
-        self.update_height();
+        self.update();
     }
 
     /// Performs left or right tree rotations to balance this node.
     fn rebalance(&mut self) {
-        self.update_height();
+        self.update();
         let side = match self.balance_factor() {
             -2 => Side::Left,
             2 => Side::Right,
@@ -321,9 +533,62 @@ impl<'a, T: Ord> Iterator for Iter<'a, T> {
     }
 }
 
+/// A double-ended iterator over a range of values of an `AVLTree`.
+///
+/// This struct is created by the `range` method of `AVLTree`. Each end keeps the stack
+/// of an in-order traversal in its direction, and the number of values left tells when
+/// the two ends meet.
+pub struct Range<'a, T: Ord> {
+    front: Vec<&'a AVLNode<T>>,
+    back: Vec<&'a AVLNode<T>>,
+    remaining: usize,
+}
+
+impl<'a, T: Ord> Iterator for Range<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.front.pop()?;
+        // Push left path of right subtree to stack
+        let mut child = &node.right;
+        while let Some(subtree) = child {
+            self.front.push(subtree.as_ref());
+            child = &subtree.left;
+        }
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Ord> DoubleEndedIterator for Range<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.back.pop()?;
+        // Push right path of left subtree to stack
+        let mut child = &node.left;
+        while let Some(subtree) = child {
+            self.back.push(subtree.as_ref());
+            child = &subtree.right;
+        }
+        Some(&node.value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::AVLTree;
+    use super::{AVLNode, AVLTree};
+    use quickcheck_macros::quickcheck;
+    use std::ops::Bound;
 
     /// Returns `true` if all nodes in the tree are balanced.
     fn is_balanced<T: Ord>(tree: &AVLTree<T>) -> bool {
@@ -331,6 +596,40 @@ mod tests {
             .all(|n| (-1..=1).contains(&n.balance_factor()))
     }
 
+    /// Checks the heights, sizes and ordering of a subtree.
+    fn check_node<T: Ord>(node: &Option<Box<AVLNode<T>>>) -> (usize, usize) {
+        let Some(node) = node else {
+            return (0, 0);
+        };
+        assert!(node.left.as_ref().is_none_or(|n| n.value < node.value));
+        assert!(node.right.as_ref().is_none_or(|n| n.value > node.value));
+        let (left_height, left_size) = check_node(&node.left);
+        let (right_height, right_size) = check_node(&node.right);
+        assert!(left_height.abs_diff(right_height) <= 1);
+        assert_eq!(node.height, 1 + left_height.max(right_height));
+        assert_eq!(node.size, 1 + left_size + right_size);
+        (node.height, node.size)
+    }
+
+    fn check_invariants<T: Ord>(tree: &AVLTree<T>) {
+        assert_eq!(check_node(&tree.root).1, tree.len());
+        assert!(tree.iter().zip(tree.iter().skip(1)).all(|(a, b)| a < b));
+    }
+
+    /// Builds a tree with `values` and the sorted, deduplicated model of its values.
+    fn with_model(values: &[u8], removed: &[u8]) -> (AVLTree<u8>, Vec<u8>) {
+        let mut tree: AVLTree<_> = values.iter().copied().collect();
+        let mut model = values.to_vec();
+        model.sort_unstable();
+        model.dedup();
+        for value in removed {
+            tree.remove(value);
+            model.retain(|v| v != value);
+        }
+        check_invariants(&tree);
+        (tree, model)
+    }
+
     #[test]
     fn len() {
         let tree: AVLTree<_> = (1..4).collect();
@@ -377,4 +676,100 @@ mod tests {
             assert!(is_balanced(&tree));
         }
     }
+
+    #[test]
+    fn order_statistics() {
+        let tree: AVLTree<_> = [50, 20, 80, 10, 30, 70, 90, 60].into_iter().collect();
+        assert_eq!(tree.select(0), Some(&10));
+        assert_eq!(tree.select(4), Some(&60));
+        assert_eq!(tree.select(8), None);
+        assert_eq!(tree.rank(&55), 4);
+        assert_eq!(tree.rank(&10), 0);
+        assert_eq!(tree.first(), Some(&10));
+        assert_eq!(tree.last(), Some(&90));
+        assert_eq!(tree.predecessor(&50), Some(&30));
+        assert_eq!(tree.successor(&50), Some(&60));
+        assert_eq!(tree.successor(&90), None);
+        assert!(tree.range(25..=70).copied().eq([30, 50, 60, 70]));
+        assert!(tree.range(..50).rev().copied().eq([30, 20, 10]));
+        assert_eq!(tree.range(71..80).next(), None);
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        let mut tree: AVLTree<_> = (0..100).collect();
+        let mut right = tree.split_off(&60);
+        check_invariants(&tree);
+        check_invariants(&right);
+        assert!((0..60).eq(tree.iter().copied()));
+        assert!((60..100).eq(right.iter().copied()));
+
+        tree.append(&mut right);
+        check_invariants(&tree);
+        assert!(right.is_empty());
+        assert!((0..100).eq(tree.iter().copied()));
+    }
+
+    #[quickcheck]
+    fn order_statistics_match_model(values: Vec<u8>, removed: Vec<u8>, probe: u8) -> bool {
+        let (tree, model) = with_model(&values, &removed);
+        let rank = model.partition_point(|&v| v < probe);
+        let after = model.partition_point(|&v| v <= probe);
+        (0..=model.len()).all(|i| tree.select(i) == model.get(i))
+            && tree.rank(&probe) == rank
+            && tree.predecessor(&probe) == rank.checked_sub(1).map(|i| &model[i])
+            && tree.successor(&probe) == model.get(after)
+            && tree.first() == model.first()
+            && tree.last() == model.last()
+    }
+
+    #[quickcheck]
+    fn ranges_match_model(values: Vec<u8>, start: u8, end: u8, steps: Vec<bool>) -> bool {
+        let (tree, model) = with_model(&values, &[]);
+        let ranges = [
+            (Bound::Included(start), Bound::Excluded(end)),
+            (Bound::Excluded(start), Bound::Included(end)),
+            (Bound::Unbounded, Bound::Included(end)),
+            (Bound::Included(start), Bound::Unbounded),
+        ];
+        ranges.iter().all(|&range| {
+            let mut expected = model
+                .iter()
+                .filter(|v| std::ops::RangeBounds::contains(&range, *v));
+            let mut actual = tree.range(range);
+            steps.iter().all(|&forward| {
+                if forward {
+                    actual.next() == expected.next()
+                } else {
+                    actual.next_back() == expected.next_back()
+                }
+            }) && actual.eq(expected)
+        })
+    }
+
+    #[quickcheck]
+    fn split_off_and_append_match_model(values: Vec<u8>, others: Vec<u8>, at: u8) -> bool {
+        let (mut tree, model) = with_model(&values, &[]);
+        let right = tree.split_off(&at);
+        check_invariants(&tree);
+        check_invariants(&right);
+        let split_ok = tree.iter().eq(model.iter().filter(|&&v| v < at))
+            && right.iter().eq(model.iter().filter(|&&v| v >= at));
+
+        // Appending a split-off part takes the join path, anything else the insert path.
+        let mut left = tree;
+        let mut right = right;
+        right.append(&mut left);
+        check_invariants(&right);
+        let rejoin_ok = left.is_empty() && right.iter().eq(model.iter());
+
+        let (mut tree, mut model) = with_model(&values, &[]);
+        let mut other: AVLTree<_> = others.iter().copied().collect();
+        tree.append(&mut other);
+        check_invariants(&tree);
+        model.extend(others);
+        model.sort_unstable();
+        model.dedup();
+        split_ok && rejoin_ok && other.is_empty() && tree.iter().eq(model.iter())
+    }
 }
@@ -1,15 +1,23 @@
-use std::convert::TryFrom;
+use std::cmp::Ordering;
 use std::fmt::Debug;
 use std::mem;
+use std::ops::{Bound, RangeBounds};
 
-struct Node<T> {
-    keys: Vec<T>,
-    children: Vec<Node<T>>,
+struct Node<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+    children: Vec<Node<K, V>>,
 }
 
-pub struct BTree<T> {
-    root: Node<T>,
+/// An ordered map stored in a B-Tree.
+///
+/// Every node but the root holds between `branch_factor - 1` and `2 * branch_factor - 1`
+/// keys, and all leaves are at the same depth, so lookups, insertions and removals take
+/// `O(branch_factor * log(n))` comparisons.
+pub struct BTree<K, V> {
+    root: Node<K, V>,
     props: BTreeProps,
+    len: usize,
 }
 
 // Why to need a different Struct for props...
@@ -17,29 +25,60 @@ pub struct BTree<T> {
 struct BTreeProps {
     degree: usize,
     max_keys: usize,
+    min_keys: usize,
     mid_key_index: usize,
 }
 
-impl<T> Node<T>
-where
-    T: Ord,
-{
-    fn new(degree: usize, _keys: Option<Vec<T>>, _children: Option<Vec<Node<T>>>) -> Self {
+impl<K, V> Node<K, V> {
+    fn new(degree: usize) -> Self {
         Node {
-            keys: match _keys {
-                Some(_keys) => _keys,
-                None => Vec::with_capacity(degree - 1),
-            },
-            children: match _children {
-                Some(_children) => _children,
-                None => Vec::with_capacity(degree),
-            },
+            keys: Vec::with_capacity(degree - 1),
+            values: Vec::with_capacity(degree - 1),
+            children: Vec::new(),
         }
     }
 
     fn is_leaf(&self) -> bool {
         self.children.is_empty()
     }
+
+    fn push_front(&mut self, key: K, value: V, child: Option<Node<K, V>>) {
+        self.keys.insert(0, key);
+        self.values.insert(0, value);
+        if let Some(child) = child {
+            self.children.insert(0, child);
+        }
+    }
+
+    fn push_back(&mut self, key: K, value: V, child: Option<Node<K, V>>) {
+        self.keys.push(key);
+        self.values.push(value);
+        if let Some(child) = child {
+            self.children.push(child);
+        }
+    }
+
+    fn pop_front(&mut self) -> (K, V, Option<Node<K, V>>) {
+        let child = (!self.is_leaf()).then(|| self.children.remove(0));
+        (self.keys.remove(0), self.values.remove(0), child)
+    }
+
+    fn pop_back(&mut self) -> (K, V, Option<Node<K, V>>) {
+        let child = if self.is_leaf() {
+            None
+        } else {
+            self.children.pop()
+        };
+        (self.keys.pop().unwrap(), self.values.pop().unwrap(), child)
+    }
+
+    /// Swaps the separator at `index` with the given entry and returns the old separator.
+    fn replace_entry(&mut self, index: usize, key: K, value: V) -> (K, V) {
+        (
+            mem::replace(&mut self.keys[index], key),
+            mem::replace(&mut self.values[index], value),
+        )
+    }
 }
 
 impl BTreeProps {
@@ -47,60 +86,123 @@ impl BTreeProps {
         BTreeProps {
             degree,
             max_keys: degree - 1,
+            min_keys: degree / 2 - 1,
             mid_key_index: (degree - 1) / 2,
         }
     }
 
-    fn is_maxed_out<T: Ord + Copy>(&self, node: &Node<T>) -> bool {
+    fn is_maxed_out<K, V>(&self, node: &Node<K, V>) -> bool {
         node.keys.len() == self.max_keys
     }
 
     // Split Child expects the Child Node to be full
     /// Move the middle_key to parent node and split the child_node's
     /// keys/chilren_nodes into half
-    fn split_child<T: Ord + Copy + Default>(&self, parent: &mut Node<T>, child_index: usize) {
+    fn split_child<K, V>(&self, parent: &mut Node<K, V>, child_index: usize) {
         let child = &mut parent.children[child_index];
-        let middle_key = child.keys[self.mid_key_index];
-        let right_keys = match child.keys.split_off(self.mid_key_index).split_first() {
-            Some((_first, _others)) => {
-                // We don't need _first, as it will move to parent node.
-                _others.to_vec()
-            }
-            None => Vec::with_capacity(self.max_keys),
-        };
-        let right_children = if !child.is_leaf() {
-            Some(child.children.split_off(self.mid_key_index + 1))
-        } else {
-            None
-        };
-        let new_child_node: Node<T> = Node::new(self.degree, Some(right_keys), right_children);
+        let mut right = Node::new(self.degree);
+        right.keys = child.keys.split_off(self.mid_key_index + 1);
+        right.values = child.values.split_off(self.mid_key_index + 1);
+        if !child.is_leaf() {
+            right.children = child.children.split_off(self.mid_key_index + 1);
+        }
+        let middle_key = child.keys.pop().unwrap();
+        let middle_value = child.values.pop().unwrap();
 
         parent.keys.insert(child_index, middle_key);
-        parent.children.insert(child_index + 1, new_child_node);
+        parent.values.insert(child_index, middle_value);
+        parent.children.insert(child_index + 1, right);
     }
 
-    fn insert_non_full<T: Ord + Copy + Default>(&mut self, node: &mut Node<T>, key: T) {
-        let mut index: isize = isize::try_from(node.keys.len()).ok().unwrap() - 1;
-        while index >= 0 && node.keys[index as usize] >= key {
-            index -= 1;
-        }
-
-        let mut u_index: usize = usize::try_from(index + 1).ok().unwrap();
+    /// Inserts a key that is not in the tree yet and returns its value.
+    fn insert_non_full<'a, K: Ord, V>(
+        &self,
+        node: &'a mut Node<K, V>,
+        key: K,
+        value: V,
+    ) -> &'a mut V {
+        let mut index = node.keys.partition_point(|k| k < &key);
         if node.is_leaf() {
             // Just insert it, as we know this method will be called only when node is not full
-            node.keys.insert(u_index, key);
-        } else {
-            if self.is_maxed_out(&node.children[u_index]) {
-                self.split_child(node, u_index);
-                if node.keys[u_index] < key {
-                    u_index += 1;
-                }
+            node.keys.insert(index, key);
+            node.values.insert(index, value);
+            return &mut node.values[index];
+        }
+        if self.is_maxed_out(&node.children[index]) {
+            self.split_child(node, index);
+            if node.keys[index] < key {
+                index += 1;
+            }
+        }
+        self.insert_non_full(&mut node.children[index], key, value)
+    }
+
+    fn remove_from<K: Ord, V>(&self, node: &mut Node<K, V>, key: &K) -> Option<(K, V)> {
+        match node.keys.binary_search(key) {
+            Ok(index) if node.is_leaf() => {
+                Some((node.keys.remove(index), node.values.remove(index)))
             }
+            Ok(index) => {
+                // Replace the entry with its predecessor, the last entry of the left subtree.
+                let (key, value) = self.remove_last(&mut node.children[index]);
+                let removed = node.replace_entry(index, key, value);
+                self.fix_child(node, index);
+                Some(removed)
+            }
+            Err(_) if node.is_leaf() => None,
+            Err(index) => {
+                let removed = self.remove_from(&mut node.children[index], key)?;
+                self.fix_child(node, index);
+                Some(removed)
+            }
+        }
+    }
+
+    fn remove_last<K, V>(&self, node: &mut Node<K, V>) -> (K, V) {
+        if node.is_leaf() {
+            return (node.keys.pop().unwrap(), node.values.pop().unwrap());
+        }
+        let index = node.children.len() - 1;
+        let removed = self.remove_last(&mut node.children[index]);
+        self.fix_child(node, index);
+        removed
+    }
 
-            self.insert_non_full(&mut node.children[u_index], key);
+    /// Restores the minimum number of keys in the child at `index` after a removal,
+    /// by borrowing a key from a sibling or merging with it.
+    fn fix_child<K, V>(&self, node: &mut Node<K, V>, index: usize) {
+        if node.children[index].keys.len() >= self.min_keys {
+            return;
+        }
+        if index > 0 && node.children[index - 1].keys.len() > self.min_keys {
+            let (key, value, grandchild) = node.children[index - 1].pop_back();
+            let (key, value) = node.replace_entry(index - 1, key, value);
+            node.children[index].push_front(key, value, grandchild);
+        } else if index + 1 < node.children.len()
+            && node.children[index + 1].keys.len() > self.min_keys
+        {
+            let (key, value, grandchild) = node.children[index + 1].pop_front();
+            let (key, value) = node.replace_entry(index, key, value);
+            node.children[index].push_back(key, value, grandchild);
+        } else {
+            self.merge_children(node, index.min(node.children.len() - 2));
         }
     }
-    fn traverse_node<T: Ord + Debug>(node: &Node<T>, depth: usize) {
+
+    /// Merges the child at `index`, the separator at `index` and the next child.
+    fn merge_children<K, V>(&self, node: &mut Node<K, V>, index: usize) {
+        let right = node.children.remove(index + 1);
+        let key = node.keys.remove(index);
+        let value = node.values.remove(index);
+        let left = &mut node.children[index];
+        left.keys.push(key);
+        left.values.push(value);
+        left.keys.extend(right.keys);
+        left.values.extend(right.values);
+        left.children.extend(right.children);
+    }
+
+    fn traverse_node<K: Debug, V>(node: &Node<K, V>, depth: usize) {
         if node.is_leaf() {
             print!(" {0:{<1$}{2:?}{0:}<1$} ", "", depth, node.keys);
         } else {
@@ -116,54 +218,340 @@ impl BTreeProps {
     }
 }
 
-impl<T> BTree<T>
-where
-    T: Ord + Copy + Debug + Default,
-{
+impl<K: Ord, V> BTree<K, V> {
+    /// Creates an empty tree whose nodes hold up to `2 * branch_factor - 1` keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `branch_factor` is below 2.
     pub fn new(branch_factor: usize) -> Self {
+        assert!(branch_factor >= 2, "the branch factor must be at least 2");
         let degree = 2 * branch_factor;
         BTree {
-            root: Node::new(degree, None, None),
+            root: Node::new(degree),
             props: BTreeProps::new(degree),
+            len: 0,
         }
     }
 
-    pub fn insert(&mut self, key: T) {
-        if self.props.is_maxed_out(&self.root) {
-            // Create an empty root and split the old root...
-            let mut new_root = Node::new(self.props.degree, None, None);
-            mem::swap(&mut new_root, &mut self.root);
-            self.root.children.insert(0, new_root);
-            self.props.split_child(&mut self.root, 0);
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key if it was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Removes a key from the tree, returning its value if it was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (_, value) = self.props.remove_from(&mut self.root, key)?;
+        if self.root.keys.is_empty() && !self.root.is_leaf() {
+            self.root = self.root.children.pop().unwrap();
         }
-        self.props.insert_non_full(&mut self.root, key);
+        self.len -= 1;
+        Some(value)
     }
 
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = &self.root;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(index) => return Some(&node.values[index]),
+                Err(_) if node.is_leaf() => return None,
+                Err(index) => node = &node.children[index],
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(index) => return Some(&mut node.values[index]),
+                Err(_) if node.is_leaf() => return None,
+                Err(index) => node = &mut node.children[index],
+            }
+        }
+    }
+
+    pub fn search(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Gets the entry of `key`, for in-place updates.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        if self.search(&key) {
+            Entry::Occupied(OccupiedEntry { tree: self, key })
+        } else {
+            Entry::Vacant(VacantEntry { tree: self, key })
+        }
+    }
+
+    /// Iterates over the entries in ascending key order.
+    pub fn iter(&self) -> Range<'_, K, V> {
+        self.range(..)
+    }
+
+    /// Iterates over the entries whose keys lie in `range`, from either end.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        let mut front = Vec::new();
+        let mut node = &self.root;
+        loop {
+            let index = match range.start_bound() {
+                Bound::Included(start) => node.keys.partition_point(|k| k < start),
+                Bound::Excluded(start) => node.keys.partition_point(|k| k <= start),
+                Bound::Unbounded => 0,
+            };
+            front.push((node, index));
+            if node.is_leaf() {
+                break;
+            }
+            node = &node.children[index];
+        }
+
+        let mut back = Vec::new();
+        let mut node = &self.root;
+        loop {
+            let index = match range.end_bound() {
+                Bound::Included(end) => node.keys.partition_point(|k| k <= end),
+                Bound::Excluded(end) => node.keys.partition_point(|k| k < end),
+                Bound::Unbounded => node.keys.len(),
+            };
+            back.push((node, index));
+            if node.is_leaf() {
+                break;
+            }
+            node = &node.children[index];
+        }
+
+        let mut range = Range { front, back };
+        range.settle_front();
+        range.settle_back();
+        range
+    }
+}
+
+impl<K: Debug, V> BTree<K, V> {
     pub fn traverse(&self) {
         BTreeProps::traverse_node(&self.root, 0);
         println!();
     }
+}
 
-    pub fn search(&self, key: T) -> bool {
-        let mut current_node = &self.root;
-        loop {
-            match current_node.keys.binary_search(&key) {
-                Ok(_) => return true,
-                Err(index) => {
-                    if current_node.is_leaf() {
-                        return false;
-                    } else {
-                        current_node = &current_node.children[index];
-                    }
+/// A view into a single entry of a `BTree`, which is either vacant or occupied.
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    tree: &'a mut BTree<K, V>,
+    key: K,
+}
+
+pub struct VacantEntry<'a, K, V> {
+    tree: &'a mut BTree<K, V>,
+    key: K,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant and returns the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Applies `f` to the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> &V {
+        self.tree.get(&self.key).unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.tree.get_mut(&self.key).unwrap()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.tree.get_mut(&self.key).unwrap()
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.tree.remove(&self.key).unwrap()
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let tree = self.tree;
+        if tree.props.is_maxed_out(&tree.root) {
+            // Create an empty root and split the old root...
+            let mut new_root = Node::new(tree.props.degree);
+            mem::swap(&mut new_root, &mut tree.root);
+            tree.root.children.push(new_root);
+            tree.props.split_child(&mut tree.root, 0);
+        }
+        tree.len += 1;
+        tree.props.insert_non_full(&mut tree.root, self.key, value)
+    }
+}
+
+/// A double-ended iterator over a key range of a `BTree`.
+///
+/// Each end keeps the path from the root to its next entry. An entry `(node, index)` on
+/// top of the front path points at `node.keys[index]`, and on top of the back path at
+/// `node.keys[index - 1]`. The iterator is exhausted once the two ends cross.
+pub struct Range<'a, K, V> {
+    front: Vec<(&'a Node<K, V>, usize)>,
+    back: Vec<(&'a Node<K, V>, usize)>,
+}
+
+impl<'a, K: Ord, V> Range<'a, K, V> {
+    /// Pops finished nodes so that the front path points at an entry, if any is left.
+    fn settle_front(&mut self) {
+        while let Some(&(node, index)) = self.front.last() {
+            if index < node.keys.len() {
+                return;
+            }
+            self.front.pop();
+        }
+    }
+
+    fn settle_back(&mut self) {
+        while let Some(&(_, index)) = self.back.last() {
+            if index > 0 {
+                return;
+            }
+            self.back.pop();
+        }
+    }
+
+    fn peek_front(&self) -> Option<(&'a K, &'a V)> {
+        let &(node, index) = self.front.last()?;
+        Some((&node.keys[index], &node.values[index]))
+    }
+
+    fn peek_back(&self) -> Option<(&'a K, &'a V)> {
+        let &(node, index) = self.back.last()?;
+        Some((&node.keys[index - 1], &node.values[index - 1]))
+    }
+
+    fn is_crossed(&self) -> bool {
+        match (self.peek_front(), self.peek_back()) {
+            (Some((first, _)), Some((last, _))) => first.cmp(last) == Ordering::Greater,
+            _ => true,
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_crossed() {
+            return None;
+        }
+        let entry = self.peek_front();
+        let (mut node, index) = self.front.pop().unwrap();
+        self.front.push((node, index + 1));
+        // The next entry is the first one of the subtree right of this entry.
+        if !node.is_leaf() {
+            node = &node.children[index + 1];
+            loop {
+                self.front.push((node, 0));
+                if node.is_leaf() {
+                    break;
                 }
+                node = &node.children[0];
             }
         }
+        self.settle_front();
+        entry
+    }
+}
+
+impl<K: Ord, V> DoubleEndedIterator for Range<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.is_crossed() {
+            return None;
+        }
+        let entry = self.peek_back();
+        let (mut node, index) = self.back.pop().unwrap();
+        self.back.push((node, index - 1));
+        // The next entry is the last one of the subtree left of this entry.
+        if !node.is_leaf() {
+            node = &node.children[index - 1];
+            loop {
+                self.back.push((node, node.keys.len()));
+                if node.is_leaf() {
+                    break;
+                }
+                node = node.children.last().unwrap();
+            }
+        }
+        self.settle_back();
+        entry
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::BTree;
+    use super::{BTree, Entry, Node};
+    use quickcheck::{Arbitrary, Gen, TestResult};
+    use quickcheck_macros::quickcheck;
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
 
     macro_rules! test_search {
         ($($name:ident: $number_of_children:expr,)*) => {
@@ -171,28 +559,28 @@ mod test {
             #[test]
             fn $name() {
                 let mut tree = BTree::new($number_of_children);
-                tree.insert(10);
-                tree.insert(20);
-                tree.insert(30);
-                tree.insert(5);
-                tree.insert(6);
-                tree.insert(7);
-                tree.insert(11);
-                tree.insert(12);
-                tree.insert(15);
-                assert!(!tree.search(4));
-                assert!(tree.search(5));
-                assert!(tree.search(6));
-                assert!(tree.search(7));
-                assert!(!tree.search(8));
-                assert!(!tree.search(9));
-                assert!(tree.search(10));
-                assert!(tree.search(11));
-                assert!(tree.search(12));
-                assert!(!tree.search(13));
-                assert!(!tree.search(14));
-                assert!(tree.search(15));
-                assert!(!tree.search(16));
+                tree.insert(10, ());
+                tree.insert(20, ());
+                tree.insert(30, ());
+                tree.insert(5, ());
+                tree.insert(6, ());
+                tree.insert(7, ());
+                tree.insert(11, ());
+                tree.insert(12, ());
+                tree.insert(15, ());
+                assert!(!tree.search(&4));
+                assert!(tree.search(&5));
+                assert!(tree.search(&6));
+                assert!(tree.search(&7));
+                assert!(!tree.search(&8));
+                assert!(!tree.search(&9));
+                assert!(tree.search(&10));
+                assert!(tree.search(&11));
+                assert!(tree.search(&12));
+                assert!(!tree.search(&13));
+                assert!(!tree.search(&14));
+                assert!(tree.search(&15));
+                assert!(!tree.search(&16));
             }
         )*
         }
@@ -207,4 +595,194 @@ mod test {
         children_60: 60,
         children_101: 101,
     }
+
+    /// Checks the B-Tree invariants and returns the height of `node`.
+    fn check_node<K: Ord, V>(tree: &BTree<K, V>, node: &Node<K, V>, is_root: bool) -> usize {
+        assert!(node.keys.len() <= tree.props.max_keys);
+        assert!(is_root || node.keys.len() >= tree.props.min_keys);
+        assert_eq!(node.keys.len(), node.values.len());
+        assert!(node.keys.windows(2).all(|pair| pair[0] < pair[1]));
+        if node.is_leaf() {
+            return 0;
+        }
+        assert_eq!(node.children.len(), node.keys.len() + 1);
+        for (i, key) in node.keys.iter().enumerate() {
+            assert!(node.children[i].keys.iter().all(|k| k < key));
+            assert!(node.children[i + 1].keys.iter().all(|k| k > key));
+        }
+        let height = check_node(tree, &node.children[0], false);
+        for child in &node.children[1..] {
+            assert_eq!(check_node(tree, child, false), height);
+        }
+        height + 1
+    }
+
+    fn check_invariants<K: Ord, V>(tree: &BTree<K, V>) {
+        check_node(tree, &tree.root, true);
+        assert_eq!(tree.iter().count(), tree.len());
+    }
+
+    #[test]
+    fn remove_rebalances() {
+        for branch_factor in [2, 3, 5] {
+            let mut tree = BTree::new(branch_factor);
+            for key in 0..200 {
+                assert_eq!(tree.insert(key, key * 10), None);
+            }
+            check_invariants(&tree);
+            for key in (0..200).step_by(3) {
+                assert_eq!(tree.remove(&key), Some(key * 10));
+                check_invariants(&tree);
+            }
+            for key in (0..200).rev() {
+                let expected = (key % 3 != 0).then_some(key * 10);
+                assert_eq!(tree.remove(&key), expected);
+                check_invariants(&tree);
+            }
+            assert!(tree.is_empty());
+            assert_eq!(tree.remove(&7), None);
+        }
+    }
+
+    #[test]
+    fn values_are_updated() {
+        let mut tree = BTree::new(2);
+        assert_eq!(tree.insert("b", 1), None);
+        assert_eq!(tree.insert("a", 2), None);
+        assert_eq!(tree.insert("b", 3), Some(1));
+        *tree.get_mut(&"a").unwrap() += 10;
+        assert_eq!(tree.get(&"a"), Some(&12));
+        assert_eq!(tree.get(&"b"), Some(&3));
+        assert_eq!(tree.get(&"c"), None);
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn entry_api() {
+        let mut tree = BTree::new(2);
+        for word in "the quick fox jumps over the lazy dog the end".split(' ') {
+            *tree.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(tree.get(&"the"), Some(&3));
+        assert_eq!(tree.get(&"fox"), Some(&1));
+
+        tree.entry("fox")
+            .and_modify(|count| *count += 5)
+            .or_default();
+        tree.entry("cat")
+            .and_modify(|count| *count += 5)
+            .or_default();
+        assert_eq!(tree.get(&"fox"), Some(&6));
+        assert_eq!(tree.get(&"cat"), Some(&0));
+
+        match tree.entry("the") {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 3),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match tree.entry("the") {
+            Entry::Occupied(_) => unreachable!(),
+            Entry::Vacant(entry) => assert_eq!(entry.key(), &"the"),
+        }
+        assert!(!tree.search(&"the"));
+        check_invariants(&tree);
+    }
+
+    fn keys<'a>(entries: impl Iterator<Item = (&'a i32, &'a ())>) -> Vec<i32> {
+        entries.map(|(key, ())| *key).collect()
+    }
+
+    #[test]
+    fn range_in_both_directions() {
+        let mut tree = BTree::new(2);
+        for key in (0..100).step_by(2) {
+            tree.insert(key, ());
+        }
+        assert_eq!(keys(tree.range(10..17)), [10, 12, 14, 16]);
+        assert_eq!(keys(tree.range(11..=16)), [12, 14, 16]);
+        assert_eq!(keys(tree.range(95..)), [96, 98]);
+        assert_eq!(keys(tree.range(..4)), [0, 2]);
+        assert_eq!(keys(tree.range(41..42)), []);
+        assert_eq!(
+            keys(tree.range((Bound::Excluded(10), Bound::Excluded(16))).rev()),
+            [14, 12]
+        );
+
+        let mut range = tree.range(20..=30);
+        assert_eq!(range.next_back(), Some((&30, &())));
+        assert_eq!(range.next(), Some((&20, &())));
+        assert_eq!(keys(range), [22, 24, 26, 28]);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(u8, u16),
+        Remove(u8),
+        Get(u8),
+        Entry(u8, u16),
+    }
+
+    impl Arbitrary for Op {
+        fn arbitrary(g: &mut Gen) -> Self {
+            match u8::arbitrary(g) % 4 {
+                0 => Op::Insert(u8::arbitrary(g), u16::arbitrary(g)),
+                1 => Op::Remove(u8::arbitrary(g)),
+                2 => Op::Get(u8::arbitrary(g)),
+                _ => Op::Entry(u8::arbitrary(g), u16::arbitrary(g)),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn matches_std_btree_map(ops: Vec<Op>, branch_factor: u8) -> TestResult {
+        let mut tree = BTree::new(branch_factor as usize % 4 + 2);
+        let mut expected = BTreeMap::new();
+        for op in ops {
+            let same = match op {
+                Op::Insert(key, value) => tree.insert(key, value) == expected.insert(key, value),
+                Op::Remove(key) => tree.remove(&key) == expected.remove(&key),
+                Op::Get(key) => tree.get(&key) == expected.get(&key),
+                Op::Entry(key, value) => {
+                    let got = *tree.entry(key).and_modify(|v| *v ^= value).or_insert(value);
+                    let want = *expected
+                        .entry(key)
+                        .and_modify(|v| *v ^= value)
+                        .or_insert(value);
+                    got == want
+                }
+            };
+            if !same {
+                return TestResult::failed();
+            }
+        }
+        check_invariants(&tree);
+        TestResult::from_bool(tree.len() == expected.len() && tree.iter().eq(expected.iter()))
+    }
+
+    #[quickcheck]
+    fn ranges_match_std_btree_map(keys: Vec<u8>, bounds: (u8, u8), steps: Vec<bool>) -> bool {
+        let mut tree = BTree::new(2);
+        let mut expected = BTreeMap::new();
+        for key in keys {
+            tree.insert(key, u32::from(key) * 3);
+            expected.insert(key, u32::from(key) * 3);
+        }
+        let (start, end) = (bounds.0.min(bounds.1), bounds.0.max(bounds.1));
+        let ranges = [
+            (Bound::Included(start), Bound::Included(end)),
+            (Bound::Included(start), Bound::Excluded(end)),
+            (Bound::Excluded(start), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Excluded(end)),
+        ];
+        ranges.iter().all(|&range| {
+            let mut ours = tree.range(range);
+            let mut theirs = expected.range(range);
+            steps.iter().all(|&forward| {
+                if forward {
+                    ours.next() == theirs.next()
+                } else {
+                    ours.next_back() == theirs.next_back()
+                }
+            }) && ours.eq(theirs)
+        })
+    }
 }
@@ -0,0 +1,142 @@
+use super::IntegerSet;
+
+const WORD_BITS: u32 = u64::BITS;
+
+/// A set of integers of `0..universe` stored as one bit per integer, in words of 64 bits.
+///
+/// Insertion, removal and lookup flip or read a single bit in `O(1)`. The successor and
+/// predecessor queries scan the words between the value and the answer, which takes
+/// `O(U / 64)` in the worst case but only a few instructions per word, thanks to
+/// `trailing_zeros` and `leading_zeros`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    universe: u32,
+    len: usize,
+}
+
+impl BitSet {
+    /// Creates an empty set that can hold the integers of `0..universe`.
+    pub fn new(universe: u32) -> Self {
+        BitSet {
+            words: vec![0; universe.div_ceil(WORD_BITS) as usize],
+            universe,
+            len: 0,
+        }
+    }
+
+    pub fn universe(&self) -> u32 {
+        self.universe
+    }
+
+    /// Returns the index of the word of a value and the mask of its bit.
+    fn position(value: u32) -> (usize, u64) {
+        ((value / WORD_BITS) as usize, 1 << (value % WORD_BITS))
+    }
+
+    /// Returns the smallest value of the set at least `value`.
+    fn next_from(&self, value: u32) -> Option<u32> {
+        let mut index = (value / WORD_BITS) as usize;
+        let mut word = self.words.get(index)? & (u64::MAX << (value % WORD_BITS));
+        while word == 0 {
+            index += 1;
+            word = *self.words.get(index)?;
+        }
+        Some(index as u32 * WORD_BITS + word.trailing_zeros())
+    }
+
+    /// Returns the largest value of the set at most `value`.
+    fn prev_until(&self, value: u32) -> Option<u32> {
+        let value = value.min(self.universe.checked_sub(1)?);
+        let mut index = (value / WORD_BITS) as usize;
+        let mut word = self.words[index] & (u64::MAX >> (WORD_BITS - 1 - value % WORD_BITS));
+        while word == 0 {
+            index = index.checked_sub(1)?;
+            word = self.words[index];
+        }
+        Some(index as u32 * WORD_BITS + WORD_BITS - 1 - word.leading_zeros())
+    }
+
+    /// Iterates over the values of the set in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        std::iter::successors(self.next_from(0), |&value| self.succ(value))
+    }
+}
+
+impl IntegerSet for BitSet {
+    fn new(universe: u32) -> Self {
+        BitSet::new(universe)
+    }
+
+    fn insert(&mut self, value: u32) -> bool {
+        assert!(value < self.universe, "value outside the universe");
+        let (index, mask) = Self::position(value);
+        let inserted = self.words[index] & mask == 0;
+        self.words[index] |= mask;
+        self.len += usize::from(inserted);
+        inserted
+    }
+
+    fn remove(&mut self, value: u32) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+        let (index, mask) = Self::position(value);
+        self.words[index] &= !mask;
+        self.len -= 1;
+        true
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        let (index, mask) = Self::position(value);
+        self.words.get(index).is_some_and(|word| word & mask != 0)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn min(&self) -> Option<u32> {
+        self.next_from(0)
+    }
+
+    fn max(&self) -> Option<u32> {
+        self.prev_until(u32::MAX)
+    }
+
+    fn succ(&self, value: u32) -> Option<u32> {
+        self.next_from(value.checked_add(1)?)
+    }
+
+    fn pred(&self, value: u32) -> Option<u32> {
+        self.prev_until(value.checked_sub(1)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::IntegerSet;
+    use super::BitSet;
+
+    #[test]
+    fn test_queries_across_words() {
+        let mut set = BitSet::new(200);
+        for value in [0, 63, 64, 130, 199] {
+            assert!(set.insert(value));
+        }
+        assert!(!set.insert(64));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 63, 64, 130, 199]);
+        assert_eq!(set.succ(64), Some(130));
+        assert_eq!(set.pred(130), Some(64));
+        assert_eq!(set.pred(u32::MAX), Some(199));
+        assert_eq!(set.succ(199), None);
+        assert_eq!(set.pred(0), None);
+
+        assert!(set.remove(199));
+        assert!(!set.remove(500));
+        assert_eq!(set.max(), Some(130));
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.universe(), 200);
+        assert!(BitSet::new(0).max().is_none());
+    }
+}
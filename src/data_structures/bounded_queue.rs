@@ -0,0 +1,300 @@
+//! A bounded multi-producer, multi-consumer blocking queue.
+
+use super::Deque;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Enum representing the reasons a value could not be pushed; the value is handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushError<T> {
+    /// The queue stayed full, for a non-blocking or timed push.
+    Full(T),
+    /// The queue is closed.
+    Closed(T),
+}
+
+impl<T> PushError<T> {
+    /// Takes back the value that was not pushed.
+    pub fn into_inner(self) -> T {
+        match self {
+            PushError::Full(value) | PushError::Closed(value) => value,
+        }
+    }
+}
+
+/// Enum representing the reasons no value could be popped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopError {
+    /// The queue stayed empty, for a non-blocking or timed pop.
+    Empty,
+    /// The queue is closed and empty.
+    Closed,
+}
+
+struct State<T> {
+    items: Deque<T>,
+    closed: bool,
+}
+
+/// A FIFO queue holding at most `capacity` values, shared between threads (e.g. in an `Arc`
+/// or a scope).
+///
+/// `push` waits while the queue is full and `pop` while it is empty, each on a `Condvar`
+/// signalled by the other side. The `try_` variants return at once and the `_timeout` ones
+/// give up after a while. Closing the queue wakes everyone up: pushes fail from then on, while
+/// pops drain the remaining values before failing, which lets consumers stop once producers
+/// are done.
+pub struct BoundedQueue<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+}
+
+impl<T> BoundedQueue<T> {
+    /// Creates a queue holding at most `capacity` values.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        BoundedQueue {
+            state: Mutex::new(State {
+                items: Deque::with_capacity(capacity),
+                closed: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Closes the queue, failing the pending and future pushes, and the pops once it is empty.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Pushes a value at the back, waiting while the queue is full.
+    pub fn push(&self, value: T) -> Result<(), PushError<T>> {
+        self.push_until(value, None)
+    }
+
+    /// Pushes a value at the back if the queue is not full.
+    pub fn try_push(&self, value: T) -> Result<(), PushError<T>> {
+        self.push_until(value, Some(Instant::now()))
+    }
+
+    /// Pushes a value at the back, waiting at most `timeout` while the queue is full.
+    /// A timeout too large to be represented as an `Instant` waits forever.
+    pub fn push_timeout(&self, value: T, timeout: Duration) -> Result<(), PushError<T>> {
+        self.push_until(value, Instant::now().checked_add(timeout))
+    }
+
+    /// Pops the value at the front, waiting while the queue is empty.
+    pub fn pop(&self) -> Result<T, PopError> {
+        self.pop_until(None)
+    }
+
+    /// Pops the value at the front if the queue is not empty.
+    pub fn try_pop(&self) -> Result<T, PopError> {
+        self.pop_until(Some(Instant::now()))
+    }
+
+    /// Pops the value at the front, waiting at most `timeout` while the queue is empty.
+    /// A timeout too large to be represented as an `Instant` waits forever.
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<T, PopError> {
+        self.pop_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Takes back the values left in the queue.
+    pub fn into_inner(self) -> Deque<T> {
+        self.state
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .items
+    }
+
+    /// Locks the state. The state is valid between any two operations, so a panic of another
+    /// thread holding the lock does not prevent using it.
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits on `condvar` until `deadline`, or forever without one. Returns `None` once the
+    /// deadline has passed.
+    fn wait<'a>(
+        condvar: &Condvar,
+        state: MutexGuard<'a, State<T>>,
+        deadline: Option<Instant>,
+    ) -> Option<MutexGuard<'a, State<T>>> {
+        let Some(deadline) = deadline else {
+            return Some(condvar.wait(state).unwrap_or_else(PoisonError::into_inner));
+        };
+        let timeout = deadline
+            .checked_duration_since(Instant::now())
+            .filter(|timeout| !timeout.is_zero())?;
+        let (state, _) = condvar
+            .wait_timeout(state, timeout)
+            .unwrap_or_else(PoisonError::into_inner);
+        Some(state)
+    }
+
+    fn push_until(&self, value: T, deadline: Option<Instant>) -> Result<(), PushError<T>> {
+        let mut state = self.lock();
+        loop {
+            if state.closed {
+                return Err(PushError::Closed(value));
+            }
+            if state.items.len() < self.capacity {
+                state.items.push_back(value);
+                drop(state);
+                self.not_empty.notify_one();
+                return Ok(());
+            }
+            match Self::wait(&self.not_full, state, deadline) {
+                Some(next) => state = next,
+                None => return Err(PushError::Full(value)),
+            }
+        }
+    }
+
+    fn pop_until(&self, deadline: Option<Instant>) -> Result<T, PopError> {
+        let mut state = self.lock();
+        loop {
+            if let Some(value) = state.items.pop_front() {
+                drop(state);
+                self.not_full.notify_one();
+                return Ok(value);
+            }
+            if state.closed {
+                return Err(PopError::Closed);
+            }
+            match Self::wait(&self.not_empty, state, deadline) {
+                Some(next) => state = next,
+                None => return Err(PopError::Empty),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundedQueue, PopError, PushError};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_non_blocking_operations() {
+        let queue = BoundedQueue::new(2);
+        assert_eq!(queue.try_pop(), Err(PopError::Empty));
+        assert_eq!(queue.try_push(1), Ok(()));
+        assert_eq!(queue.push(2), Ok(()));
+        assert_eq!(queue.try_push(3), Err(PushError::Full(3)));
+        assert_eq!(
+            queue.push_timeout(3, Duration::from_millis(10)),
+            Err(PushError::Full(3))
+        );
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.try_pop(), Ok(1));
+        assert_eq!(queue.try_push(3), Ok(()));
+
+        // A closed queue refuses new values but still hands out the remaining ones
+        queue.close();
+        assert!(queue.is_closed());
+        assert_eq!(queue.try_push(4).map_err(PushError::into_inner), Err(4));
+        assert_eq!(queue.pop(), Ok(2));
+        assert_eq!(queue.pop_timeout(Duration::from_secs(1)), Ok(3));
+        assert_eq!(queue.pop_timeout(Duration::MAX), Err(PopError::Closed));
+        assert_eq!(queue.pop(), Err(PopError::Closed));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_pop_timeout_waits_for_a_producer() {
+        let queue = BoundedQueue::new(1);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                queue.push("late").unwrap();
+            });
+            assert_eq!(queue.pop_timeout(Duration::from_secs(10)), Ok("late"));
+        });
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                queue.pop().unwrap();
+            });
+            queue.push("first").unwrap();
+            assert_eq!(queue.push_timeout("second", Duration::MAX), Ok(()));
+        });
+        assert_eq!(queue.try_pop(), Ok("second"));
+        assert_eq!(
+            queue.pop_timeout(Duration::from_millis(10)),
+            Err(PopError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_producers_and_consumers() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 1000;
+        let queue = BoundedQueue::new(8);
+        let mut received: Vec<usize> = thread::scope(|scope| {
+            let producers: Vec<_> = (0..PRODUCERS)
+                .map(|p| {
+                    let queue = &queue;
+                    scope.spawn(move || {
+                        for i in 0..PER_PRODUCER {
+                            queue.push(p * PER_PRODUCER + i).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            let consumers: Vec<_> = (0..3)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut received = Vec::new();
+                        let mut last = [None; PRODUCERS];
+                        while let Ok(value) = queue.pop() {
+                            // Each producer's values come out in order
+                            let producer = value / PER_PRODUCER;
+                            assert!(last[producer] < Some(value));
+                            last[producer] = Some(value);
+                            received.push(value);
+                        }
+                        received
+                    })
+                })
+                .collect();
+            for producer in producers {
+                producer.join().unwrap();
+            }
+            queue.close();
+            consumers
+                .into_iter()
+                .flat_map(|consumer| consumer.join().unwrap())
+                .collect()
+        });
+        received.sort_unstable();
+        assert_eq!(received, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+        assert!(queue.into_inner().is_empty());
+    }
+}
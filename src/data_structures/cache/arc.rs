@@ -0,0 +1,318 @@
+use super::list::{List, Slab};
+use super::{Cache, CacheStats, Limits};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The four lists of an `ArcCache`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Location {
+    /// The entries used once since they entered the cache.
+    T1,
+    /// The entries used at least twice since they entered the cache.
+    T2,
+    /// The keys recently evicted from `T1`, without their value.
+    B1,
+    /// The keys recently evicted from `T2`, without their value.
+    B2,
+}
+
+impl Location {
+    fn is_resident(self) -> bool {
+        matches!(self, Location::T1 | Location::T2)
+    }
+}
+
+struct Entry<K, V> {
+    key: K,
+    /// `None` for the evicted keys of `B1` and `B2`.
+    value: Option<V>,
+    weight: usize,
+    location: Location,
+}
+
+/// An Adaptive Replacement Cache.
+///
+/// The cached entries are split between `T1`, holding the entries used only once, and `T2`,
+/// holding the ones used again; both are LRU lists. The keys evicted from each list are
+/// remembered, without their value, in the "ghost" lists `B1` and `B2`. Inserting a key found
+/// in `B1` means that `T1` was too small, so its target share `p` grows; a key found in `B2`
+/// makes it shrink. An eviction then takes the least recently used entry of `T1` if it is
+/// above its target, and of `T2` otherwise.
+///
+/// Unlike an LRU cache, a scan of many keys used once only flushes `T1`, and unlike an LFU
+/// cache, entries that used to be popular do not stay forever.
+///
+/// With a weigher, all the sizes (including `p`) are measured in weight rather than in entries.
+///
+/// # Sources
+/// Megiddo and Modha, "ARC: a self-tuning, low overhead replacement cache", 2003
+pub struct ArcCache<K, V> {
+    map: HashMap<K, usize>,
+    entries: Slab<Entry<K, V>>,
+    /// The lists `T1`, `T2`, `B1` and `B2`, from the most to the least recently used entry.
+    lists: [List; 4],
+    /// The total weight of each list.
+    weights: [usize; 4],
+    /// The target weight of `T1`.
+    target: usize,
+    /// The number of entries of `T1` and `T2`.
+    len: usize,
+    limits: Limits<K, V>,
+}
+
+impl<K: Hash + Eq + Clone, V> ArcCache<K, V> {
+    /// Creates a cache holding at most `capacity` entries, or `capacity` total weight with a
+    /// weigher. It also remembers up to `capacity` evicted keys.
+    pub fn new(capacity: usize) -> Self {
+        ArcCache {
+            map: HashMap::new(),
+            entries: Slab::new(),
+            lists: [List::new(); 4],
+            weights: [0; 4],
+            target: 0,
+            len: 0,
+            limits: Limits::new(capacity),
+        }
+    }
+
+    /// Sets the function computing the weight of the entries inserted from now on.
+    pub fn with_weigher(mut self, weigher: impl Fn(&K, &V) -> usize + Send + 'static) -> Self {
+        self.limits.weigher = Some(Box::new(weigher));
+        self
+    }
+
+    /// Sets a function receiving the evicted entries.
+    pub fn with_eviction_listener(mut self, listener: impl FnMut(K, V) + Send + 'static) -> Self {
+        self.limits.listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Returns the weight that the entries used only once are allowed to take, as currently
+    /// adapted to the workload.
+    pub fn target_recent_weight(&self) -> usize {
+        self.target
+    }
+
+    fn detach(&mut self, index: usize) {
+        let entry = self.entries.get(index);
+        let (location, weight) = (entry.location, entry.weight);
+        self.entries
+            .unlink(&mut self.lists[location as usize], index);
+        self.weights[location as usize] -= weight;
+        if location.is_resident() {
+            self.limits.weight -= weight;
+            self.len -= 1;
+        }
+    }
+
+    fn attach(&mut self, index: usize, location: Location) {
+        let entry = self.entries.get_mut(index);
+        entry.location = location;
+        let weight = entry.weight;
+        self.entries
+            .push_front(&mut self.lists[location as usize], index);
+        self.weights[location as usize] += weight;
+        if location.is_resident() {
+            self.limits.weight += weight;
+            self.len += 1;
+        }
+    }
+
+    /// Removes the entry or evicted key of a node.
+    fn take(&mut self, index: usize) -> Entry<K, V> {
+        self.detach(index);
+        let entry = self.entries.remove(index);
+        self.map.remove(&entry.key);
+        entry
+    }
+
+    /// Forgets the least recently evicted key of `B1` or `B2`.
+    fn forget_ghost(&mut self, location: Location) {
+        let index = self.lists[location as usize].back().unwrap();
+        self.take(index);
+    }
+
+    /// Evicts the least recently used entry of `T1` if it is above its target (or at it, when
+    /// making room for a key of `B2`), and of `T2` otherwise, keeping its key in `B1` or `B2`.
+    fn replace(&mut self, hit_in_b2: bool) {
+        let t1 = self.weights[Location::T1 as usize];
+        let from_t1 = !self.lists[Location::T1 as usize].is_empty()
+            && (t1 > self.target
+                || (hit_in_b2 && t1 == self.target)
+                || self.lists[Location::T2 as usize].is_empty());
+        let (from, to) = if from_t1 {
+            (Location::T1, Location::B1)
+        } else {
+            (Location::T2, Location::B2)
+        };
+        let index = self.lists[from as usize].back().unwrap();
+        self.detach(index);
+        self.attach(index, to);
+        let entry = self.entries.get_mut(index);
+        let (key, value) = (entry.key.clone(), entry.value.take().unwrap());
+        self.limits.evict(key, value);
+    }
+
+    /// Forgets the oldest evicted keys until `T1` and `B1` weigh at most the capacity, and all
+    /// the lists at most twice the capacity.
+    fn trim_ghosts(&mut self, extra: usize) {
+        let capacity = self.limits.capacity;
+        while self.weights[Location::T1 as usize]
+            .saturating_add(self.weights[Location::B1 as usize])
+            .saturating_add(extra)
+            > capacity
+            && !self.lists[Location::B1 as usize].is_empty()
+        {
+            self.forget_ghost(Location::B1);
+        }
+        while self
+            .weights
+            .iter()
+            .fold(extra, |sum, &w| sum.saturating_add(w))
+            > capacity.saturating_mul(2)
+        {
+            if !self.lists[Location::B2 as usize].is_empty() {
+                self.forget_ghost(Location::B2);
+            } else if !self.lists[Location::B1 as usize].is_empty() {
+                self.forget_ghost(Location::B1);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for ArcCache<K, V> {
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let index = self.map.get(key).copied();
+        let index = index.filter(|&index| self.entries.get(index).location.is_resident());
+        let index = self.limits.record(index)?;
+        self.detach(index);
+        self.attach(index, Location::T2);
+        self.entries.get(index).value.as_ref()
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(*self.map.get(key)?).value.as_ref()
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let weight = self.limits.weigh(&key, &value);
+        let capacity = self.limits.capacity;
+        let (mut location, mut hit_in_b2, mut old) = (Location::T1, false, None);
+        if let Some(index) = self.map.get(&key).copied() {
+            let entry = self.take(index);
+            // A recently evicted key moves the target towards the list it was evicted from,
+            // by more when the other ghost list is larger
+            let [_, _, b1, b2] = self.weights;
+            match entry.location {
+                Location::B1 => {
+                    let step = entry.weight * (b2 / b1.saturating_add(entry.weight).max(1)).max(1);
+                    self.target = self.target.saturating_add(step).min(capacity);
+                }
+                Location::B2 => {
+                    let step = entry.weight * (b1 / b2.saturating_add(entry.weight).max(1)).max(1);
+                    self.target = self.target.saturating_sub(step);
+                    hit_in_b2 = true;
+                }
+                Location::T1 | Location::T2 => {}
+            }
+            location = Location::T2;
+            old = entry.value;
+        }
+        if weight > capacity {
+            self.limits.evict(key, value);
+            return old;
+        }
+
+        if location == Location::T1 {
+            self.trim_ghosts(weight);
+        }
+        while !self.limits.fits(weight) {
+            self.replace(hit_in_b2);
+        }
+        let index = self.entries.insert(Entry {
+            key: key.clone(),
+            value: Some(value),
+            weight,
+            location,
+        });
+        self.map.insert(key, index);
+        self.attach(index, location);
+        self.trim_ghosts(0);
+        old
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let index = *self.map.get(key)?;
+        self.take(index).value
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn weight(&self) -> usize {
+        self.limits.weight
+    }
+
+    fn capacity(&self) -> usize {
+        self.limits.capacity
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.limits.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Cache;
+    use super::ArcCache;
+
+    #[test]
+    fn test_scan_resistance_and_adaptation() {
+        let mut cache = ArcCache::new(4);
+        cache.insert('a', 1);
+        cache.insert('b', 2);
+        cache.get(&'a');
+        cache.get(&'b');
+
+        // A scan of keys used once only evicts other keys used once
+        for key in 'c'..='g' {
+            cache.insert(key, 0);
+        }
+        assert_eq!(cache.len(), 4);
+        assert!(cache.contains_key(&'a') && cache.contains_key(&'b'));
+        assert!(cache.contains_key(&'f') && cache.contains_key(&'g'));
+        assert_eq!(cache.target_recent_weight(), 0);
+        assert_eq!(cache.stats().evictions, 3);
+
+        // Inserting a key recently evicted from the keys used once gives them more room
+        assert_eq!(cache.get(&'d'), None);
+        assert_eq!(cache.insert('d', 4), None);
+        assert_eq!(cache.target_recent_weight(), 1);
+        assert!(cache.contains_key(&'d') && cache.contains_key(&'g'));
+        assert!(!cache.contains_key(&'f'));
+        assert_eq!(cache.remove(&'a'), Some(1));
+        assert_eq!(cache.remove(&'f'), None);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    #[test]
+    fn test_maximal_capacity() {
+        let mut cache = ArcCache::new(usize::MAX).with_weigher(|_, &weight| weight);
+        assert_eq!(cache.insert('a', usize::MAX - 1), None);
+        assert_eq!(cache.insert('b', 1), None);
+        assert_eq!(cache.weight(), usize::MAX);
+        assert_eq!(cache.insert('c', 1), None);
+        assert!(!cache.contains_key(&'a'));
+        assert!(cache.contains_key(&'b') && cache.contains_key(&'c'));
+        // 'a' comes back from the evicted keys
+        assert_eq!(cache.insert('a', 2), None);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.weight(), 4);
+    }
+}
@@ -0,0 +1,268 @@
+use super::list::{List, Slab, NIL};
+use super::{Cache, CacheStats, Limits};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    weight: usize,
+    /// The node of the bucket of the entry's frequency.
+    bucket: usize,
+}
+
+/// The entries used a given number of times, from the most to the least recently used one.
+struct Bucket {
+    frequency: u64,
+    entries: List,
+}
+
+/// A cache evicting the least frequently used entries, and the least recently used one among
+/// equally frequent entries.
+///
+/// The entries are grouped in buckets of equal frequency, themselves kept in a linked list by
+/// increasing frequency. An access moves an entry to the bucket of the next frequency, which
+/// is either the next bucket or a new one inserted after the current one, and an eviction takes
+/// the oldest entry of the first bucket: every operation takes `O(1)` time.
+///
+/// # Sources
+/// Shah, Mitra and Matani, "An O(1) algorithm for implementing the LFU cache eviction scheme",
+/// 2010
+pub struct LfuCache<K, V> {
+    map: HashMap<K, usize>,
+    entries: Slab<Entry<K, V>>,
+    buckets: Slab<Bucket>,
+    /// The buckets, by increasing frequency.
+    frequencies: List,
+    limits: Limits<K, V>,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+    /// Creates a cache holding at most `capacity` entries, or `capacity` total weight with a
+    /// weigher.
+    pub fn new(capacity: usize) -> Self {
+        LfuCache {
+            map: HashMap::new(),
+            entries: Slab::new(),
+            buckets: Slab::new(),
+            frequencies: List::new(),
+            limits: Limits::new(capacity),
+        }
+    }
+
+    /// Sets the function computing the weight of the entries inserted from now on.
+    pub fn with_weigher(mut self, weigher: impl Fn(&K, &V) -> usize + Send + 'static) -> Self {
+        self.limits.weigher = Some(Box::new(weigher));
+        self
+    }
+
+    /// Sets a function receiving the evicted entries.
+    pub fn with_eviction_listener(mut self, listener: impl FnMut(K, V) + Send + 'static) -> Self {
+        self.limits.listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Returns the number of accesses to a key since its insertion, the insertion included.
+    pub fn frequency(&self, key: &K) -> Option<u64> {
+        let entry = self.entries.get(*self.map.get(key)?);
+        Some(self.buckets.get(entry.bucket).frequency)
+    }
+
+    /// Unlinks an entry from its bucket, and drops the bucket if it becomes empty.
+    fn unlink(&mut self, index: usize) {
+        let bucket = self.entries.get(index).bucket;
+        let entries = &mut self.buckets.get_mut(bucket).entries;
+        self.entries.unlink(entries, index);
+        if entries.is_empty() {
+            self.buckets.unlink(&mut self.frequencies, bucket);
+            self.buckets.remove(bucket);
+        }
+    }
+
+    /// Moves an entry to the bucket of the next frequency.
+    fn touch(&mut self, index: usize) {
+        let bucket = self.entries.get(index).bucket;
+        let frequency = self.buckets.get(bucket).frequency + 1;
+        let target = match self.buckets.next(bucket) {
+            Some(next) if self.buckets.get(next).frequency == frequency => next,
+            _ => {
+                let new = self.buckets.insert(Bucket {
+                    frequency,
+                    entries: List::new(),
+                });
+                self.buckets
+                    .insert_after(&mut self.frequencies, bucket, new);
+                new
+            }
+        };
+        self.unlink(index);
+        self.entries
+            .push_front(&mut self.buckets.get_mut(target).entries, index);
+        self.entries.get_mut(index).bucket = target;
+    }
+
+    /// Removes the entry of a node from the cache.
+    fn take(&mut self, index: usize) -> Entry<K, V> {
+        self.unlink(index);
+        let entry = self.entries.remove(index);
+        self.map.remove(&entry.key);
+        self.limits.weight -= entry.weight;
+        entry
+    }
+
+    /// Evicts entries other than `protected` until `extra` more weight fits.
+    fn make_room(&mut self, extra: usize, protected: usize) {
+        while !self.limits.fits(extra) {
+            // Only the protected entry can be alone in the first bucket and still be the oldest,
+            // and it cannot be the last entry, since it fits in the cache on its own
+            let first = self.frequencies.front().unwrap();
+            let mut victim = self.buckets.get(first).entries.back().unwrap();
+            if victim == protected {
+                let second = self.buckets.next(first).unwrap();
+                victim = self.buckets.get(second).entries.back().unwrap();
+            }
+            let entry = self.take(victim);
+            self.limits.evict(entry.key, entry.value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for LfuCache<K, V> {
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let index = self.limits.record(self.map.get(key).copied())?;
+        self.touch(index);
+        Some(&self.entries.get(index).value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        Some(&self.entries.get(*self.map.get(key)?).value)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let weight = self.limits.weigh(&key, &value);
+        let existing = self.map.get(&key).copied();
+        if weight > self.limits.capacity {
+            self.limits.evict(key, value);
+            return existing.map(|index| self.take(index).value);
+        }
+
+        if let Some(index) = existing {
+            self.touch(index);
+            let entry = self.entries.get_mut(index);
+            self.limits.weight = self.limits.weight - entry.weight + weight;
+            entry.weight = weight;
+            let old = mem::replace(&mut entry.value, value);
+            self.make_room(0, index);
+            return Some(old);
+        }
+
+        self.make_room(weight, NIL);
+        let bucket = match self.frequencies.front() {
+            Some(first) if self.buckets.get(first).frequency == 1 => first,
+            _ => {
+                let new = self.buckets.insert(Bucket {
+                    frequency: 1,
+                    entries: List::new(),
+                });
+                self.buckets.push_front(&mut self.frequencies, new);
+                new
+            }
+        };
+        let index = self.entries.insert(Entry {
+            key: key.clone(),
+            value,
+            weight,
+            bucket,
+        });
+        self.entries
+            .push_front(&mut self.buckets.get_mut(bucket).entries, index);
+        self.map.insert(key, index);
+        self.limits.weight += weight;
+        None
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let index = *self.map.get(key)?;
+        Some(self.take(index).value)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn weight(&self) -> usize {
+        self.limits.weight
+    }
+
+    fn capacity(&self) -> usize {
+        self.limits.capacity
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.limits.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Cache;
+    use super::LfuCache;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_evicts_least_frequently_used() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&evicted);
+        let mut cache =
+            LfuCache::new(3).with_eviction_listener(move |k, _| sink.lock().unwrap().push(k));
+        cache.insert('a', 1);
+        cache.insert('b', 2);
+        cache.insert('c', 3);
+        cache.get(&'a');
+        cache.get(&'a');
+        cache.get(&'b');
+        assert_eq!(cache.frequency(&'a'), Some(3));
+        assert_eq!(cache.frequency(&'c'), Some(1));
+
+        cache.insert('d', 4);
+        assert_eq!(*evicted.lock().unwrap(), ['c']);
+        // `d` is now the only entry used once
+        cache.insert('e', 5);
+        assert_eq!(*evicted.lock().unwrap(), ['c', 'd']);
+
+        // Among equally frequent entries, the least recently used one goes first
+        cache.get(&'e');
+        assert_eq!(cache.insert('a', 10), Some(1));
+        assert_eq!(cache.frequency(&'a'), Some(4));
+        cache.insert('f', 6);
+        assert_eq!(*evicted.lock().unwrap(), ['c', 'd', 'b']);
+        assert_eq!(cache.stats().hits, 4);
+        assert_eq!(cache.stats().evictions, 3);
+    }
+
+    #[test]
+    fn test_growing_entry_evicts_others() {
+        let mut cache = LfuCache::new(6).with_weigher(|_, value: &Vec<u8>| value.len());
+        cache.insert(1, vec![0; 2]);
+        cache.insert(2, vec![0; 2]);
+        cache.insert(3, vec![0; 2]);
+        // The entry growing is used more than the others, but never evicted to make its own room
+        cache.get(&1);
+        cache.get(&1);
+        assert_eq!(cache.insert(1, vec![0; 5]), Some(vec![0; 2]));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.weight(), 5);
+        assert_eq!(cache.frequency(&1), Some(4));
+
+        // Even when it is the least frequently used one
+        cache.insert(2, vec![0; 1]);
+        for _ in 0..5 {
+            cache.get(&2);
+        }
+        assert!(cache.frequency(&2) > cache.frequency(&1));
+        assert_eq!(cache.insert(1, vec![0; 6]), Some(vec![0; 5]));
+        assert_eq!(cache.peek(&1), Some(&vec![0; 6]));
+        assert!(!cache.contains_key(&2));
+    }
+}
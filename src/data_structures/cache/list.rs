@@ -0,0 +1,135 @@
+//! Doubly linked lists whose nodes live in a shared slab and are referred to by index, so a
+//! cache can unlink any entry in `O(1)` from the index stored in its hash map.
+
+pub(super) const NIL: usize = usize::MAX;
+
+struct Slot<T> {
+    /// `None` for the free slots.
+    value: Option<T>,
+    prev: usize,
+    next: usize,
+}
+
+/// The storage of the nodes of any number of lists.
+pub(super) struct Slab<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+}
+
+/// A list of nodes of a `Slab`, from the front (most recent) to the back (least recent).
+#[derive(Clone, Copy)]
+pub(super) struct List {
+    front: usize,
+    back: usize,
+}
+
+impl List {
+    pub(super) fn new() -> Self {
+        List {
+            front: NIL,
+            back: NIL,
+        }
+    }
+
+    pub(super) fn front(&self) -> Option<usize> {
+        (self.front != NIL).then_some(self.front)
+    }
+
+    pub(super) fn back(&self) -> Option<usize> {
+        (self.back != NIL).then_some(self.back)
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.front == NIL
+    }
+}
+
+impl<T> Slab<T> {
+    pub(super) fn new() -> Self {
+        Slab {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Stores a value in a node that is not in any list yet.
+    pub(super) fn insert(&mut self, value: T) -> usize {
+        let slot = Slot {
+            value: Some(value),
+            prev: NIL,
+            next: NIL,
+        };
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = slot;
+                index
+            }
+            None => {
+                self.slots.push(slot);
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    /// Frees a node that is not in any list, and returns its value.
+    pub(super) fn remove(&mut self, index: usize) -> T {
+        self.free.push(index);
+        self.slots[index].value.take().unwrap()
+    }
+
+    pub(super) fn get(&self, index: usize) -> &T {
+        self.slots[index].value.as_ref().unwrap()
+    }
+
+    pub(super) fn get_mut(&mut self, index: usize) -> &mut T {
+        self.slots[index].value.as_mut().unwrap()
+    }
+
+    /// Returns the node after `index` in its list.
+    pub(super) fn next(&self, index: usize) -> Option<usize> {
+        let next = self.slots[index].next;
+        (next != NIL).then_some(next)
+    }
+
+    pub(super) fn push_front(&mut self, list: &mut List, index: usize) {
+        self.slots[index].prev = NIL;
+        self.slots[index].next = list.front;
+        match list.front() {
+            Some(front) => self.slots[front].prev = index,
+            None => list.back = index,
+        }
+        list.front = index;
+    }
+
+    /// Links `index` right after `after`, which is in `list`.
+    pub(super) fn insert_after(&mut self, list: &mut List, after: usize, index: usize) {
+        let next = self.slots[after].next;
+        self.slots[index].prev = after;
+        self.slots[index].next = next;
+        self.slots[after].next = index;
+        if next == NIL {
+            list.back = index;
+        } else {
+            self.slots[next].prev = index;
+        }
+    }
+
+    pub(super) fn unlink(&mut self, list: &mut List, index: usize) {
+        let Slot { prev, next, .. } = self.slots[index];
+        if prev == NIL {
+            list.front = next;
+        } else {
+            self.slots[prev].next = next;
+        }
+        if next == NIL {
+            list.back = prev;
+        } else {
+            self.slots[next].prev = prev;
+        }
+    }
+
+    pub(super) fn move_to_front(&mut self, list: &mut List, index: usize) {
+        self.unlink(list, index);
+        self.push_front(list, index);
+    }
+}
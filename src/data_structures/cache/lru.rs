@@ -0,0 +1,180 @@
+use super::list::{List, Slab};
+use super::{Cache, CacheStats, Limits};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    weight: usize,
+}
+
+/// A cache evicting the least recently used entries.
+///
+/// The entries are kept in a linked list from the most to the least recently used one, and a
+/// hash map gives the node of each key: an access moves the node to the front, and an eviction
+/// pops the back.
+pub struct LruCache<K, V> {
+    map: HashMap<K, usize>,
+    entries: Slab<Entry<K, V>>,
+    order: List,
+    limits: Limits<K, V>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Creates a cache holding at most `capacity` entries, or `capacity` total weight with a
+    /// weigher.
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            map: HashMap::new(),
+            entries: Slab::new(),
+            order: List::new(),
+            limits: Limits::new(capacity),
+        }
+    }
+
+    /// Sets the function computing the weight of the entries inserted from now on.
+    pub fn with_weigher(mut self, weigher: impl Fn(&K, &V) -> usize + Send + 'static) -> Self {
+        self.limits.weigher = Some(Box::new(weigher));
+        self
+    }
+
+    /// Sets a function receiving the evicted entries.
+    pub fn with_eviction_listener(mut self, listener: impl FnMut(K, V) + Send + 'static) -> Self {
+        self.limits.listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Returns the least recently used entry, the next one to be evicted.
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        let entry = self.entries.get(self.order.back()?);
+        Some((&entry.key, &entry.value))
+    }
+
+    /// Removes the entry of a node from the cache.
+    fn take(&mut self, index: usize) -> Entry<K, V> {
+        self.entries.unlink(&mut self.order, index);
+        let entry = self.entries.remove(index);
+        self.map.remove(&entry.key);
+        self.limits.weight -= entry.weight;
+        entry
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for LruCache<K, V> {
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let index = self.limits.record(self.map.get(key).copied())?;
+        self.entries.move_to_front(&mut self.order, index);
+        Some(&self.entries.get(index).value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        Some(&self.entries.get(*self.map.get(key)?).value)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old = self.remove(&key);
+        let weight = self.limits.weigh(&key, &value);
+        if weight > self.limits.capacity {
+            self.limits.evict(key, value);
+            return old;
+        }
+        while !self.limits.fits(weight) {
+            // The cache is not empty, since the entry fits in an empty one
+            let entry = self.take(self.order.back().unwrap());
+            self.limits.evict(entry.key, entry.value);
+        }
+        let index = self.entries.insert(Entry {
+            key: key.clone(),
+            value,
+            weight,
+        });
+        self.entries.push_front(&mut self.order, index);
+        self.map.insert(key, index);
+        self.limits.weight += weight;
+        old
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let index = *self.map.get(key)?;
+        Some(self.take(index).value)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn weight(&self) -> usize {
+        self.limits.weight
+    }
+
+    fn capacity(&self) -> usize {
+        self.limits.capacity
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.limits.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Cache, CacheStats};
+    use super::LruCache;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&evicted);
+        let mut cache =
+            LruCache::new(3).with_eviction_listener(move |k, v| sink.lock().unwrap().push((k, v)));
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.peek_lru(), Some((&"b", &2)));
+        cache.insert("d", 4);
+        assert_eq!(*evicted.lock().unwrap(), [("b", 2)]);
+        assert_eq!(cache.get(&"b"), None);
+
+        // Replacing a value makes it the most recently used one
+        assert_eq!(cache.insert("c", 30), Some(3));
+        cache.insert("e", 5);
+        assert_eq!(*evicted.lock().unwrap(), [("b", 2), ("a", 1)]);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.remove(&"d"), Some(4));
+        assert!(!cache.contains_key(&"d"));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 2
+            }
+        );
+        assert_eq!(cache.stats().hit_ratio(), Some(0.5));
+    }
+
+    #[test]
+    fn test_weighted_capacity() {
+        let mut cache = LruCache::new(10).with_weigher(|_, value: &String| value.len());
+        cache.insert(1, "abcd".to_string());
+        cache.insert(2, "efg".to_string());
+        cache.insert(3, "hij".to_string());
+        assert_eq!(cache.weight(), 10);
+
+        // Making room for 5 evicts the two least recently used entries
+        cache.insert(4, "klmno".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.weight(), 8);
+        assert!(cache.contains_key(&3) && cache.contains_key(&4));
+
+        // An entry heavier than the capacity is never stored
+        assert_eq!(cache.insert(3, "x".repeat(11)), Some("hij".to_string()));
+        assert!(!cache.contains_key(&3));
+        assert_eq!(cache.weight(), 5);
+        assert_eq!(cache.stats().evictions, 3);
+        assert_eq!(cache.capacity(), 10);
+    }
+}
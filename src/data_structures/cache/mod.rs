@@ -0,0 +1,259 @@
+//! Bounded caches: maps that keep at most a given total weight of entries, and evict some of
+//! them to make room for new ones. They differ in the entries they choose to evict:
+//!
+//! - `LruCache` evicts the least recently used entry.
+//! - `LfuCache` evicts the least frequently used entry, the least recently used one among
+//!   equally frequent entries.
+//! - `ArcCache` (Adaptive Replacement Cache) splits the entries between the ones used once and
+//!   the ones used several times, and adapts the share of each from the recently evicted keys.
+//!
+//! All the operations take `O(1)` time (amortized, for the hash map), and all the caches
+//! implement the `Cache` trait.
+//!
+//! By default each entry weighs 1, so the capacity is a number of entries; `with_weigher`
+//! gives each entry a custom weight instead, e.g. its size in bytes. `with_eviction_listener`
+//! registers a callback receiving every evicted entry.
+
+mod arc;
+mod lfu;
+mod list;
+mod lru;
+
+pub use self::arc::ArcCache;
+pub use self::lfu::LfuCache;
+pub use self::lru::LruCache;
+
+/// Computes the weight of an entry.
+pub type Weigher<K, V> = Box<dyn Fn(&K, &V) -> usize + Send>;
+/// Receives the entries evicted from a cache.
+pub type EvictionListener<K, V> = Box<dyn FnMut(K, V) + Send>;
+
+/// The hit and miss counters of a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of calls to `get` that found the key.
+    pub hits: u64,
+    /// The number of calls to `get` that did not find the key.
+    pub misses: u64,
+    /// The number of entries evicted to make room for others.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Returns the share of the lookups that found the key, or `None` before any lookup.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups > 0).then(|| self.hits as f64 / lookups as f64)
+    }
+}
+
+/// A map holding at most `capacity()` total weight of entries.
+pub trait Cache<K, V> {
+    /// Returns the value of a key, and records the access for the eviction policy and the
+    /// statistics.
+    fn get(&mut self, key: &K) -> Option<&V>;
+
+    /// Returns the value of a key, without recording the access.
+    fn peek(&self, key: &K) -> Option<&V>;
+
+    /// Inserts a key with its value, evicting other entries if needed, and returns the
+    /// previous value of the key. Replacing a value counts as an access.
+    ///
+    /// An entry heavier than the capacity is not stored: it goes straight to the eviction
+    /// listener, and the key loses its previous value.
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+
+    /// Removes a key, returning its value if it was present. Removed entries are not passed to
+    /// the eviction listener.
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// Returns the number of entries.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total weight of the entries.
+    fn weight(&self) -> usize;
+
+    /// Returns the maximal total weight of the entries.
+    fn capacity(&self) -> usize;
+
+    fn stats(&self) -> CacheStats;
+}
+
+/// The capacity, weigher, eviction listener and statistics shared by all the caches.
+struct Limits<K, V> {
+    capacity: usize,
+    weight: usize,
+    weigher: Option<Weigher<K, V>>,
+    listener: Option<EvictionListener<K, V>>,
+    stats: CacheStats,
+}
+
+impl<K, V> Limits<K, V> {
+    fn new(capacity: usize) -> Self {
+        Limits {
+            capacity,
+            weight: 0,
+            weigher: None,
+            listener: None,
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn weigh(&self, key: &K, value: &V) -> usize {
+        self.weigher
+            .as_ref()
+            .map_or(1, |weigher| weigher(key, value))
+    }
+
+    /// Checks if an entry of the given weight can be added to the current ones.
+    fn fits(&self, weight: usize) -> bool {
+        self.weight
+            .checked_add(weight)
+            .is_some_and(|total| total <= self.capacity)
+    }
+
+    /// Records a hit or a miss.
+    fn record<T>(&mut self, found: Option<T>) -> Option<T> {
+        match found {
+            Some(_) => self.stats.hits += 1,
+            None => self.stats.misses += 1,
+        }
+        found
+    }
+
+    /// Hands an evicted entry to the listener.
+    fn evict(&mut self, key: K, value: V) {
+        self.stats.evictions += 1;
+        if let Some(listener) = self.listener.as_mut() {
+            listener(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArcCache, Cache, EvictionListener, LfuCache, LruCache};
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    const CAPACITY: usize = 40;
+
+    fn weigh(key: u8, value: u16) -> usize {
+        usize::from(key % 3) + usize::from(value % 7)
+    }
+
+    /// Applies random operations to a cache and to a `HashMap` following the insertions,
+    /// removals and evictions, and checks that they agree and that the capacity is respected.
+    fn check_against_model<C: Cache<u8, u16>>(
+        make: impl FnOnce(EvictionListener<u8, u16>) -> C,
+        weighted: bool,
+        ops: Vec<(u8, u8, u16)>,
+    ) -> bool {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&evicted);
+        let mut cache = make(Box::new(move |key, value| {
+            sink.lock().unwrap().push((key, value))
+        }));
+        let mut model: HashMap<u8, u16> = HashMap::new();
+        let mut hits = 0;
+        for (op, key, value) in ops {
+            let key = key % 64;
+            match op % 4 {
+                0 | 1 => {
+                    if cache.insert(key, value) != model.insert(key, value) {
+                        return false;
+                    }
+                }
+                2 => {
+                    let found = cache.get(&key).copied();
+                    hits += u64::from(found.is_some());
+                    if found != model.get(&key).copied() {
+                        return false;
+                    }
+                }
+                _ => {
+                    if cache.remove(&key) != model.remove(&key) {
+                        return false;
+                    }
+                }
+            }
+            for (key, value) in evicted.lock().unwrap().drain(..) {
+                if model.remove(&key) != Some(value) {
+                    return false;
+                }
+            }
+            let weight: usize = model
+                .iter()
+                .map(|(&k, &v)| if weighted { weigh(k, v) } else { 1 })
+                .sum();
+            if cache.len() != model.len() || cache.weight() != weight || weight > CAPACITY {
+                return false;
+            }
+        }
+        model
+            .iter()
+            .all(|(key, value)| cache.peek(key) == Some(value))
+            && cache.stats().hits == hits
+    }
+
+    #[quickcheck]
+    fn prop_lru_matches_model(ops: Vec<(u8, u8, u16)>, weighted: bool) -> bool {
+        let make = |listener| {
+            let cache = LruCache::new(CAPACITY).with_eviction_listener(listener);
+            if weighted {
+                cache.with_weigher(|&k, &v| weigh(k, v))
+            } else {
+                cache
+            }
+        };
+        check_against_model(make, weighted, ops)
+    }
+
+    #[quickcheck]
+    fn prop_lfu_matches_model(ops: Vec<(u8, u8, u16)>, weighted: bool) -> bool {
+        let make = |listener| {
+            let cache = LfuCache::new(CAPACITY).with_eviction_listener(listener);
+            if weighted {
+                cache.with_weigher(|&k, &v| weigh(k, v))
+            } else {
+                cache
+            }
+        };
+        check_against_model(make, weighted, ops)
+    }
+
+    #[quickcheck]
+    fn prop_arc_matches_model(ops: Vec<(u8, u8, u16)>, weighted: bool) -> bool {
+        let make = |listener| {
+            let cache = ArcCache::new(CAPACITY).with_eviction_listener(listener);
+            if weighted {
+                cache.with_weigher(|&k, &v| weigh(k, v))
+            } else {
+                cache
+            }
+        };
+        check_against_model(make, weighted, ops)
+    }
+
+    #[test]
+    fn test_caches_are_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let (lru, lfu, arc) = (
+            LruCache::<u8, u16>::new(CAPACITY),
+            LfuCache::<u8, u16>::new(CAPACITY),
+            ArcCache::<u8, u16>::new(CAPACITY),
+        );
+        assert_send(&lru.with_eviction_listener(|_, _| {}));
+        assert_send(&lfu.with_weigher(|&k, &v| weigh(k, v)));
+        assert_send(&arc);
+    }
+}
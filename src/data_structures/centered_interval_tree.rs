@@ -0,0 +1,190 @@
+//! A centered interval tree is a static structure built at once from a set of closed
+//! intervals, answering overlap and stabbing queries.
+//!
+//! Each node picks a center point (the median of the endpoints of its intervals), and keeps
+//! the intervals containing that point, sorted both by start and by end. The intervals
+//! entirely before the center go to the left subtree, and the ones entirely after it go to
+//! the right subtree. Since the center is the median endpoint, each subtree gets at most half
+//! of the intervals, and the tree has a depth of `O(log(n))`.
+//!
+//! All the intervals of a node contain its center, so a query before the center only needs
+//! the intervals starting early enough (a prefix of the list sorted by start), and a query
+//! after it only needs those ending late enough. Building takes `O(n * log(n))` time, and a
+//! query reporting `k` intervals takes `O(log(n) + k)`.
+//!
+//! # Sources
+//! <https://en.wikipedia.org/wiki/Interval_tree#Centered_interval_tree>
+
+use super::Interval;
+
+struct Node<K> {
+    center: K,
+    /// The indices of the intervals containing the center, by increasing start.
+    by_start: Vec<usize>,
+    /// The indices of the intervals containing the center, by decreasing end.
+    by_end: Vec<usize>,
+    left: Option<Box<Node<K>>>,
+    right: Option<Box<Node<K>>>,
+}
+
+/// A static interval tree over closed intervals carrying values of type `V`.
+pub struct CenteredIntervalTree<K, V> {
+    intervals: Vec<(Interval<K>, V)>,
+    root: Option<Box<Node<K>>>,
+}
+
+impl<K: Ord + Clone, V> CenteredIntervalTree<K, V> {
+    /// Builds a tree over the given intervals, which may contain duplicates.
+    pub fn new(intervals: Vec<(Interval<K>, V)>) -> Self {
+        let root = Self::build(&intervals, (0..intervals.len()).collect());
+        CenteredIntervalTree { intervals, root }
+    }
+
+    fn build(intervals: &[(Interval<K>, V)], indices: Vec<usize>) -> Option<Box<Node<K>>> {
+        if indices.is_empty() {
+            return None;
+        }
+        let mut endpoints: Vec<&K> = indices
+            .iter()
+            .flat_map(|&i| [intervals[i].0.start(), intervals[i].0.end()])
+            .collect();
+        let mid = endpoints.len() / 2;
+        let center = endpoints.select_nth_unstable(mid).1.clone();
+
+        let (mut left, mut right, mut by_start) = (Vec::new(), Vec::new(), Vec::new());
+        for i in indices {
+            let interval = &intervals[i].0;
+            if *interval.end() < center {
+                left.push(i);
+            } else if *interval.start() > center {
+                right.push(i);
+            } else {
+                by_start.push(i);
+            }
+        }
+        let mut by_end = by_start.clone();
+        by_start.sort_by(|&a, &b| intervals[a].0.start().cmp(intervals[b].0.start()));
+        by_end.sort_by(|&a, &b| intervals[b].0.end().cmp(intervals[a].0.end()));
+
+        Some(Box::new(Node {
+            center,
+            by_start,
+            by_end,
+            left: Self::build(intervals, left),
+            right: Self::build(intervals, right),
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Returns the intervals overlapping `query`, with their values, in no particular order.
+    pub fn overlapping(&self, query: &Interval<K>) -> Vec<(&Interval<K>, &V)> {
+        let mut result = Vec::new();
+        let mut stack: Vec<&Node<K>> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let found = if *query.end() < node.center {
+                stack.extend(node.left.as_deref());
+                let count = node
+                    .by_start
+                    .partition_point(|&i| self.intervals[i].0.start() <= query.end());
+                &node.by_start[..count]
+            } else if *query.start() > node.center {
+                stack.extend(node.right.as_deref());
+                let count = node
+                    .by_end
+                    .partition_point(|&i| self.intervals[i].0.end() >= query.start());
+                &node.by_end[..count]
+            } else {
+                stack.extend(node.left.as_deref());
+                stack.extend(node.right.as_deref());
+                &node.by_start[..]
+            };
+            result.extend(found.iter().map(|&i| {
+                let (interval, value) = &self.intervals[i];
+                (interval, value)
+            }));
+        }
+        result
+    }
+
+    /// Returns the intervals containing `point`, with their values, in no particular order.
+    pub fn stabbing(&self, point: &K) -> Vec<(&Interval<K>, &V)> {
+        self.overlapping(&Interval::new(point.clone(), point.clone()))
+    }
+}
+
+impl<K: Ord + Clone, V> FromIterator<(Interval<K>, V)> for CenteredIntervalTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (Interval<K>, V)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CenteredIntervalTree, Interval};
+    use quickcheck_macros::quickcheck;
+
+    fn sorted_values(found: Vec<(&Interval<i32>, &char)>) -> Vec<char> {
+        let mut values: Vec<char> = found.into_iter().map(|(_, &v)| v).collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn test_queries() {
+        let tree: CenteredIntervalTree<i32, char> = [
+            (Interval::new(1, 3), 'a'),
+            (Interval::new(2, 8), 'b'),
+            (Interval::new(4, 4), 'c'),
+            (Interval::new(6, 10), 'd'),
+            (Interval::new(9, 12), 'e'),
+            (Interval::new(2, 8), 'f'),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(tree.len(), 6);
+        assert_eq!(sorted_values(tree.stabbing(&4)), ['b', 'c', 'f']);
+        assert_eq!(sorted_values(tree.stabbing(&9)), ['d', 'e']);
+        assert_eq!(sorted_values(tree.stabbing(&0)), []);
+        assert_eq!(
+            sorted_values(tree.overlapping(&Interval::new(3, 6))),
+            ['a', 'b', 'c', 'd', 'f']
+        );
+        assert_eq!(
+            sorted_values(tree.overlapping(&Interval::new(11, 20))),
+            ['e']
+        );
+
+        let empty: CenteredIntervalTree<i32, ()> = CenteredIntervalTree::new(Vec::new());
+        assert!(empty.is_empty());
+        assert!(empty.stabbing(&1).is_empty());
+    }
+
+    #[quickcheck]
+    fn prop_matches_linear_scan(intervals: Vec<(u8, u8)>, query: (u8, u8)) -> bool {
+        let to_interval = |(a, b): (u8, u8)| Interval::new(a.min(b), a.max(b));
+        let intervals: Vec<(Interval<u8>, usize)> =
+            intervals.into_iter().map(to_interval).zip(0..).collect();
+        let query = to_interval(query);
+        let tree = CenteredIntervalTree::new(intervals.clone());
+
+        let expected: Vec<usize> = intervals
+            .iter()
+            .filter(|(interval, _)| interval.overlaps(&query))
+            .map(|&(_, i)| i)
+            .collect();
+        let mut found: Vec<usize> = tree
+            .overlapping(&query)
+            .into_iter()
+            .map(|(_, &i)| i)
+            .collect();
+        found.sort_unstable();
+        found == expected
+    }
+}
@@ -0,0 +1,428 @@
+//! A double-ended queue stored in a growable ring buffer.
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::{Index, IndexMut};
+
+/// A double-ended queue in a ring buffer.
+///
+/// The elements occupy `len` consecutive slots of the buffer starting at `head`, wrapping
+/// around its end. Pushing to either end takes amortized `O(1)` time, since a full buffer
+/// doubles its size, and any element can be accessed in `O(1)` by its index from the front.
+pub struct Deque<T> {
+    /// `None` for the slots outside the elements.
+    buf: Vec<Option<T>>,
+    head: usize,
+    len: usize,
+}
+
+const MIN_CAPACITY: usize = 4;
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates a deque that can hold `capacity` elements before growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Deque {
+            buf: std::iter::repeat_with(|| None).take(capacity).collect(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements the deque can hold before growing.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the slot of the element at `index`, which may be up to `len`.
+    fn slot(&self, index: usize) -> usize {
+        let slot = self.head + index;
+        if slot >= self.buf.len() {
+            slot - self.buf.len()
+        } else {
+            slot
+        }
+    }
+
+    /// Makes room for one more element, moving the elements to the front of a buffer twice as
+    /// large if it is full.
+    fn grow(&mut self) {
+        if self.len < self.buf.len() {
+            return;
+        }
+        let capacity = (2 * self.buf.len()).max(MIN_CAPACITY);
+        let mut buf: Vec<Option<T>> = Vec::with_capacity(capacity);
+        buf.extend(self.buf.drain(self.head..));
+        buf.append(&mut self.buf);
+        buf.resize_with(capacity, || None);
+        self.buf = buf;
+        self.head = 0;
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.grow();
+        let slot = self.slot(self.len);
+        self.buf[slot] = Some(value);
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.grow();
+        self.head = self.head.checked_sub(1).unwrap_or(self.buf.len() - 1);
+        self.buf[self.head] = Some(value);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let value = self.buf[self.head].take();
+        self.head = self.slot(1);
+        self.len -= 1;
+        value
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let slot = self.slot(self.len);
+        self.buf[slot].take()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    /// Returns the element at `index` from the front.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        self.buf[self.slot(index)].as_ref()
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let slot = self.slot(index);
+        self.buf[slot].as_mut()
+    }
+
+    /// Swaps the elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len && j < self.len, "index out of bounds");
+        let (i, j) = (self.slot(i), self.slot(j));
+        self.buf.swap(i, j);
+    }
+
+    /// Rotates the deque `n` places to the left, so that the element at `n` becomes the front.
+    ///
+    /// This takes `O(min(n, len - n))` time, and `O(1)` when the buffer is full.
+    ///
+    /// # Panics
+    /// Panics if `n` is greater than the length.
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        if self.len == self.buf.len() {
+            // No free slot separates the back from the front, so only the head moves
+            self.head = self.slot(n % self.buf.len().max(1));
+        } else if n <= self.len - n {
+            for _ in 0..n {
+                let value = self.pop_front().unwrap();
+                self.push_back(value);
+            }
+        } else {
+            for _ in 0..self.len - n {
+                let value = self.pop_back().unwrap();
+                self.push_front(value);
+            }
+        }
+    }
+
+    /// Rotates the deque `n` places to the right, so that the element at `len - n` becomes
+    /// the front.
+    ///
+    /// # Panics
+    /// Panics if `n` is greater than the length.
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        self.rotate_left(self.len - n);
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+        self.head = 0;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            deque: self,
+            front: 0,
+            back: self.len,
+        }
+    }
+}
+
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for Deque<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for Deque<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Deque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Deque<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Deque<T> {}
+
+impl<T: Clone> Clone for Deque<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+/// An iterator over the elements of a `Deque`, from the front to the back.
+pub struct Iter<'a, T> {
+    deque: &'a Deque<T>,
+    /// The indices of the remaining elements are `front..back`.
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.deque.get(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.back - self.front, Some(self.back - self.front))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.deque.get(self.back)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// An iterator moving the elements out of a `Deque`.
+pub struct IntoIter<T> {
+    deque: Deque<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.deque.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.deque.len, Some(self.deque.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.deque.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { deque: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<T> Extend<T> for Deque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deque;
+    use quickcheck_macros::quickcheck;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_push_pop_and_wrap_around() {
+        let mut deque = Deque::with_capacity(4);
+        deque.push_back(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        deque.push_front(0);
+        // The front wrapped around to the end of the buffer
+        assert_eq!(deque.capacity(), 4);
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+        deque.push_back(4);
+        assert_eq!(deque.capacity(), 8);
+        assert_eq!(deque.len(), 5);
+        assert_eq!((deque[0], deque[4]), (0, 4));
+        deque[2] = 20;
+        assert_eq!(deque.get(5), None);
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(4));
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&3));
+        assert_eq!(deque.iter().rev().copied().collect::<Vec<_>>(), [3, 20, 1]);
+
+        deque.clear();
+        assert!(deque.is_empty());
+        assert_eq!(deque.pop_back(), None);
+        assert_eq!(deque.back(), None);
+    }
+
+    #[test]
+    fn test_rotations() {
+        let mut deque: Deque<i32> = (0..6).collect();
+        deque.rotate_left(2);
+        assert_eq!(deque, (2..6).chain(0..2).collect());
+        deque.rotate_right(5);
+        assert_eq!(deque, [3, 4, 5, 0, 1, 2].into_iter().collect());
+
+        // A full buffer rotates by moving its head only
+        let mut full: Deque<i32> = Deque::with_capacity(4);
+        full.extend(0..4);
+        full.rotate_left(3);
+        full.swap(0, 3);
+        assert_eq!(full.capacity(), 4);
+        assert_eq!(full.into_iter().collect::<Vec<_>>(), [2, 0, 1, 3]);
+
+        let mut empty: Deque<i32> = Deque::new();
+        empty.rotate_left(0);
+        assert!(empty.is_empty());
+    }
+
+    #[quickcheck]
+    fn prop_matches_vec_deque(ops: Vec<(u8, i32)>) -> bool {
+        let mut deque = Deque::new();
+        let mut model = VecDeque::new();
+        for (op, value) in ops {
+            let index = value.unsigned_abs() as usize % (model.len() + 1);
+            match op % 7 {
+                0 => {
+                    deque.push_back(value);
+                    model.push_back(value);
+                }
+                1 => {
+                    deque.push_front(value);
+                    model.push_front(value);
+                }
+                2 => {
+                    if deque.pop_front() != model.pop_front() {
+                        return false;
+                    }
+                }
+                3 => {
+                    if deque.pop_back() != model.pop_back() {
+                        return false;
+                    }
+                }
+                4 => {
+                    deque.rotate_left(index);
+                    model.rotate_left(index);
+                }
+                5 => {
+                    deque.rotate_right(index);
+                    model.rotate_right(index);
+                }
+                _ => {
+                    if deque.get(index) != model.get(index) {
+                        return false;
+                    }
+                }
+            }
+            if deque.len() != model.len() || !deque.iter().rev().eq(model.iter().rev()) {
+                return false;
+            }
+        }
+        deque.into_iter().eq(model)
+    }
+}
@@ -1,4 +1,4 @@
-use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
 /// A Fenwick Tree (also known as a Binary Indexed Tree) that supports efficient
 /// prefix sum, range sum and point queries, as well as point updates.
@@ -174,6 +174,307 @@ where
     }
 }
 
+impl<T> FenwickTree<T>
+where
+    T: Add<Output = T> + AddAssign + Sub<Output = T> + SubAssign + Copy + Default + PartialOrd,
+{
+    /// Finds the first index whose prefix sum reaches `target`.
+    ///
+    /// This descends the implicit tree one power of two at a time instead of binary searching
+    /// over `prefix_query`, so it takes `O(log n)` time. All values must be non-negative, so
+    /// that prefix sums never decrease.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The prefix sum to reach.
+    ///
+    /// # Returns
+    ///
+    /// The smallest zero-based index `i` with `prefix_query(i) >= target`, or the capacity of
+    /// the tree if even the total sum is smaller than `target`.
+    pub fn lower_bound(&self, target: T) -> usize {
+        self.descend(|sum| sum < target)
+    }
+
+    /// Finds the element of rank `k` when the tree counts occurrences of each index.
+    ///
+    /// With `value` at index `i` meaning that `i` occurs `value` times, this is the `k`-th
+    /// smallest index (counting from zero) in the multiset. All values must be non-negative.
+    ///
+    /// # Arguments
+    ///
+    /// * `k` - The zero-based rank to look up.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the smallest zero-based index `i` with `prefix_query(i) > k`
+    /// (`Ok(index)`) or an error (`FenwickTreeError::IndexOutOfBounds`) if the total count is
+    /// not larger than `k`.
+    pub fn find_kth(&self, k: T) -> Result<usize, FenwickTreeError> {
+        let index = self.descend(|sum| sum <= k);
+        if index >= self.data.len() - 1 {
+            return Err(FenwickTreeError::IndexOutOfBounds);
+        }
+        Ok(index)
+    }
+
+    /// Returns the length of the longest prefix whose sum satisfies `predicate`, assuming
+    /// that `predicate` only fails from some point on.
+    fn descend<P: Fn(T) -> bool>(&self, predicate: P) -> usize {
+        let len = self.data.len() - 1;
+        if len == 0 {
+            return 0;
+        }
+        let mut position = 0;
+        let mut sum = T::default();
+        let mut step = 1 << len.ilog2();
+        while step > 0 {
+            let next = position + step;
+            if next <= len && predicate(sum + self.data[next]) {
+                position = next;
+                sum += self.data[next];
+            }
+            step >>= 1;
+        }
+        position
+    }
+}
+
+/// A Fenwick Tree that adds a value to a whole range of elements at once, and still answers
+/// prefix and range sum queries in `O(log n)`.
+///
+/// It keeps two ordinary Fenwick Trees over the differences of the elements: `slopes` holds
+/// the difference array `d`, and `offsets` holds `d[i] * i`. The prefix sum up to `i` is then
+/// `prefix(slopes, i) * (i + 1) - prefix(offsets, i)`, so a range update only changes the
+/// two ends of the range in each tree.
+///
+/// The difference arrays go negative even when all elements are positive, so `T` must be a
+/// signed type. Unsigned types are rejected at compile time:
+///
+/// ```compile_fail
+/// use the_algorithms_rust::data_structures::RangeFenwickTree;
+///
+/// let tree = RangeFenwickTree::<u32>::with_capacity(4);
+/// ```
+pub struct RangeFenwickTree<T>
+where
+    T: Add<Output = T>
+        + AddAssign
+        + Sub<Output = T>
+        + SubAssign
+        + Mul<Output = T>
+        + Neg<Output = T>
+        + Copy
+        + Default,
+{
+    slopes: FenwickTree<T>,
+    offsets: FenwickTree<T>,
+}
+
+impl<T> RangeFenwickTree<T>
+where
+    T: Add<Output = T>
+        + AddAssign
+        + Sub<Output = T>
+        + SubAssign
+        + Mul<Output = T>
+        + Neg<Output = T>
+        + Copy
+        + Default
+        + TryFrom<usize>,
+{
+    /// Creates a new range-update Fenwick Tree with `capacity` elements, all set to the
+    /// default value of type `T`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        RangeFenwickTree {
+            slopes: FenwickTree::with_capacity(capacity),
+            offsets: FenwickTree::with_capacity(capacity),
+        }
+    }
+
+    /// Adds `value` to every element in the range `[left, right]`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success (`Ok`) or an error (`FenwickTreeError::InvalidRange`)
+    /// if the left index is greater than the right index or the right index is out of bounds.
+    pub fn range_update(
+        &mut self,
+        left: usize,
+        right: usize,
+        value: T,
+    ) -> Result<(), FenwickTreeError> {
+        if left > right || right >= self.capacity() {
+            return Err(FenwickTreeError::InvalidRange);
+        }
+        self.slopes.update(left, value)?;
+        self.offsets.update(left, value * index_value(left))?;
+        if right + 1 < self.capacity() {
+            self.slopes.update(right + 1, -value)?;
+            self.offsets
+                .update(right + 1, -(value * index_value(right + 1)))?;
+        }
+        Ok(())
+    }
+
+    /// Computes the sum of elements from the start of the tree up to `index`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the prefix sum (`Ok(sum)`) or an error
+    /// (`FenwickTreeError::IndexOutOfBounds`) if the index is out of bounds.
+    pub fn prefix_query(&self, index: usize) -> Result<T, FenwickTreeError> {
+        let slope = self.slopes.prefix_query(index)?;
+        let offset = self.offsets.prefix_query(index)?;
+        Ok(slope * index_value(index + 1) - offset)
+    }
+
+    /// Computes the sum of elements within the range `[left, right]`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the range sum (`Ok(sum)`) or an error
+    /// (`FenwickTreeError::InvalidRange`) if the left index is greater than the right index
+    /// or the right index is out of bounds.
+    pub fn range_query(&self, left: usize, right: usize) -> Result<T, FenwickTreeError> {
+        if left > right || right >= self.capacity() {
+            return Err(FenwickTreeError::InvalidRange);
+        }
+        let right_query = self.prefix_query(right)?;
+        let left_query = if left == 0 {
+            T::default()
+        } else {
+            self.prefix_query(left - 1)?
+        };
+        Ok(right_query - left_query)
+    }
+
+    /// Retrieves the value at `index`, which is the prefix sum of the difference array.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the value (`Ok(value)`) or an error
+    /// (`FenwickTreeError::IndexOutOfBounds`) if the index is out of bounds.
+    pub fn point_query(&self, index: usize) -> Result<T, FenwickTreeError> {
+        self.slopes.prefix_query(index)
+    }
+
+    fn capacity(&self) -> usize {
+        self.slopes.data.len() - 1
+    }
+}
+
+/// Converts an index into the value type, to scale the difference arrays.
+fn index_value<T: TryFrom<usize>>(index: usize) -> T {
+    T::try_from(index)
+        .ok()
+        .expect("index does not fit in the value type")
+}
+
+/// A two-dimensional Fenwick Tree over a grid, supporting point updates and sums over
+/// rectangles in `O(log(rows) * log(cols))`.
+///
+/// Each row of the outer tree is itself a Fenwick Tree over the columns, so both dimensions
+/// use the same lowbit navigation.
+pub struct FenwickTree2D<T>
+where
+    T: Add<Output = T> + AddAssign + Sub<Output = T> + SubAssign + Copy + Default,
+{
+    /// Internal storage with an unused first row and column, for 1-based indexing.
+    data: Vec<Vec<T>>,
+}
+
+impl<T> FenwickTree2D<T>
+where
+    T: Add<Output = T> + AddAssign + Sub<Output = T> + SubAssign + Copy + Default,
+{
+    /// Creates a new grid with `rows` rows and `cols` columns, all set to the default value
+    /// of type `T`.
+    pub fn with_capacity(rows: usize, cols: usize) -> Self {
+        FenwickTree2D {
+            data: vec![vec![T::default(); cols + 1]; rows + 1],
+        }
+    }
+
+    /// Adds `value` to the cell at `(row, col)`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success (`Ok`) or an error (`FenwickTreeError::IndexOutOfBounds`)
+    /// if the cell is outside the grid.
+    pub fn update(&mut self, row: usize, col: usize, value: T) -> Result<(), FenwickTreeError> {
+        if !self.contains(row, col) {
+            return Err(FenwickTreeError::IndexOutOfBounds);
+        }
+        let mut i = row + 1;
+        while i < self.data.len() {
+            let mut j = col + 1;
+            while j < self.data[i].len() {
+                self.data[i][j] += value;
+                j += lowbit(j);
+            }
+            i += lowbit(i);
+        }
+        Ok(())
+    }
+
+    /// Computes the sum of the rectangle from `(0, 0)` to `(row, col)`, inclusive.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the sum (`Ok(sum)`) or an error
+    /// (`FenwickTreeError::IndexOutOfBounds`) if the cell is outside the grid.
+    pub fn prefix_query(&self, row: usize, col: usize) -> Result<T, FenwickTreeError> {
+        if !self.contains(row, col) {
+            return Err(FenwickTreeError::IndexOutOfBounds);
+        }
+        let mut result = T::default();
+        let mut i = row + 1;
+        while i > 0 {
+            let mut j = col + 1;
+            while j > 0 {
+                result += self.data[i][j];
+                j -= lowbit(j);
+            }
+            i -= lowbit(i);
+        }
+        Ok(result)
+    }
+
+    /// Computes the sum of the rectangle with corners `top_left` and `bottom_right`, given
+    /// as inclusive `(row, col)` pairs.
+    ///
+    /// The sum is assembled from four prefix rectangles by inclusion-exclusion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the sum (`Ok(sum)`) or an error (`FenwickTreeError::InvalidRange`)
+    /// if a corner is outside the grid or `top_left` is below or right of `bottom_right`.
+    pub fn range_query(
+        &self,
+        top_left: (usize, usize),
+        bottom_right: (usize, usize),
+    ) -> Result<T, FenwickTreeError> {
+        let ((top, left), (bottom, right)) = (top_left, bottom_right);
+        if top > bottom || left > right || !self.contains(bottom, right) {
+            return Err(FenwickTreeError::InvalidRange);
+        }
+        let prefix = |row: Option<usize>, col: Option<usize>| match (row, col) {
+            (Some(row), Some(col)) => self.prefix_query(row, col),
+            _ => Ok(T::default()),
+        };
+        let (above, before) = (top.checked_sub(1), left.checked_sub(1));
+        Ok(prefix(Some(bottom), Some(right))?
+            - prefix(above, Some(right))?
+            - prefix(Some(bottom), before)?
+            + prefix(above, before)?)
+    }
+
+    fn contains(&self, row: usize, col: usize) -> bool {
+        row + 1 < self.data.len() && col + 1 < self.data[0].len()
+    }
+}
+
 /// Computes the lowest set bit (rightmost `1` bit) of a number.
 ///
 /// This function isolates the lowest set bit in the binary representation of `x`.
@@ -198,6 +499,7 @@ const fn lowbit(x: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quickcheck_macros::quickcheck;
 
     #[test]
     fn test_fenwick_tree() {
@@ -261,4 +563,84 @@ mod tests {
             Err(FenwickTreeError::IndexOutOfBounds)
         );
     }
+
+    #[test]
+    fn test_lower_bound_and_find_kth() {
+        let mut counts = FenwickTree::with_capacity(8);
+        for (index, count) in [(1, 2), (4, 1), (6, 3)] {
+            assert_eq!(counts.update(index, count), Ok(()));
+        }
+        // The multiset is {1, 1, 4, 6, 6, 6}.
+        let ranks: Vec<_> = (0..6).map(|k| counts.find_kth(k).unwrap()).collect();
+        assert_eq!(ranks, vec![1, 1, 4, 6, 6, 6]);
+        assert_eq!(counts.find_kth(6), Err(FenwickTreeError::IndexOutOfBounds));
+
+        assert_eq!(counts.lower_bound(0), 0);
+        assert_eq!(counts.lower_bound(3), 4);
+        assert_eq!(counts.lower_bound(4), 6);
+        assert_eq!(counts.lower_bound(7), 8);
+        assert_eq!(FenwickTree::<u32>::with_capacity(0).lower_bound(1), 0);
+    }
+
+    #[test]
+    fn test_range_fenwick_tree() {
+        let mut tree = RangeFenwickTree::with_capacity(6);
+        assert_eq!(tree.range_update(1, 3, 5), Ok(()));
+        assert_eq!(tree.range_update(2, 5, -2), Ok(()));
+        // The elements are [0, 5, 3, 3, -2, -2].
+        assert_eq!(tree.range_query(0, 5), Ok(7));
+        assert_eq!(tree.range_query(2, 4), Ok(4));
+        assert_eq!(tree.prefix_query(1), Ok(5));
+        assert_eq!(tree.point_query(4), Ok(-2));
+        assert_eq!(
+            tree.range_update(3, 6, 1),
+            Err(FenwickTreeError::InvalidRange)
+        );
+        assert_eq!(tree.range_query(3, 2), Err(FenwickTreeError::InvalidRange));
+        assert_eq!(tree.point_query(6), Err(FenwickTreeError::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn test_fenwick_tree_2d() {
+        let mut grid = FenwickTree2D::with_capacity(3, 4);
+        assert_eq!(grid.update(0, 0, 1), Ok(()));
+        assert_eq!(grid.update(1, 2, 5), Ok(()));
+        assert_eq!(grid.update(2, 3, -2), Ok(()));
+        assert_eq!(grid.update(2, 1, 4), Ok(()));
+        assert_eq!(grid.prefix_query(1, 2), Ok(6));
+        assert_eq!(grid.prefix_query(2, 3), Ok(8));
+        assert_eq!(grid.range_query((1, 1), (2, 3)), Ok(7));
+        assert_eq!(grid.range_query((2, 2), (2, 3)), Ok(-2));
+        assert_eq!(
+            grid.update(3, 0, 1),
+            Err(FenwickTreeError::IndexOutOfBounds)
+        );
+        assert_eq!(
+            grid.prefix_query(0, 4),
+            Err(FenwickTreeError::IndexOutOfBounds)
+        );
+        assert_eq!(
+            grid.range_query((1, 2), (1, 1)),
+            Err(FenwickTreeError::InvalidRange)
+        );
+    }
+
+    #[quickcheck]
+    fn prop_range_updates_match_naive(len: u8, updates: Vec<(u8, u8, i32)>) -> bool {
+        let len = usize::from(len % 32) + 1;
+        let mut tree = RangeFenwickTree::with_capacity(len);
+        let mut naive = vec![0i64; len];
+        for (a, b, value) in updates {
+            let (a, b) = (usize::from(a) % len, usize::from(b) % len);
+            let (left, right) = (a.min(b), a.max(b));
+            tree.range_update(left, right, i64::from(value)).unwrap();
+            for element in &mut naive[left..=right] {
+                *element += i64::from(value);
+            }
+        }
+        (0..len).all(|left| {
+            (left..len)
+                .all(|right| tree.range_query(left, right) == Ok(naive[left..=right].iter().sum()))
+        })
+    }
 }
@@ -0,0 +1,363 @@
+//! A Fibonacci heap.
+//!
+//! A Fibonacci heap is a list of heap-ordered trees. `push` and `meld` only add trees to the
+//! list, and `decrease_key` cuts the node off its parent and adds it to the list as well. To keep
+//! the trees bushy, a node that loses a second child is cut off its own parent too (a "cascading
+//! cut"). `pop` then consolidates the list, linking trees with the same number of children until
+//! all the roots have a different degree. `pop` takes `O(log(n))` amortized time, and the other
+//! operations take `O(1)` amortized time.
+//!
+//! Wikipedia: <https://en.wikipedia.org/wiki/Fibonacci_heap>
+
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::LinkedList;
+use std::rc::{Rc, Weak};
+
+use super::DecreaseKeyHeap;
+
+type Link<K, P> = Rc<Node<K, P>>;
+
+struct Node<K, P> {
+    /// The entry of the node, taken out when the node is popped.
+    entry: RefCell<Option<(K, P)>>,
+    children: RefCell<Vec<Link<K, P>>>,
+    parent: RefCell<Weak<Node<K, P>>>,
+    /// The index of the node in the `children` of its parent, to cut it off in `O(1)`.
+    index_in_parent: Cell<usize>,
+    /// Whether the node lost a child since it became the child of its parent.
+    marked: Cell<bool>,
+}
+
+impl<K, P: Ord> Node<K, P> {
+    /// Checks if the node may be the parent of `other`.
+    fn precedes(&self, other: &Self) -> bool {
+        match (&*self.entry.borrow(), &*other.entry.borrow()) {
+            (Some((_, priority)), Some((_, other_priority))) => priority <= other_priority,
+            _ => unreachable!("nodes in the heap have an entry"),
+        }
+    }
+}
+
+/// A min-heap of `(key, priority)` entries supporting `O(1)` `meld` and amortized `O(1)`
+/// `decrease_key`.
+///
+/// `push` returns a `FibonacciHeapHandle`, which `decrease_key` takes to find the entry again.
+/// A handle must only be used with the heap it was returned by, or with a heap this one has
+/// been melded into.
+pub struct FibonacciHeap<K, P> {
+    roots: LinkedList<Link<K, P>>,
+    min: Option<Link<K, P>>,
+    len: usize,
+}
+
+/// A handle to an entry of a `FibonacciHeap`.
+pub struct FibonacciHeapHandle<K, P>(Link<K, P>);
+
+impl<K, P> Clone for FibonacciHeapHandle<K, P> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<K, P: Ord> FibonacciHeap<K, P> {
+    /// Creates a new, empty heap.
+    pub fn new() -> Self {
+        Self {
+            roots: LinkedList::new(),
+            min: None,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of entries in the heap.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entry with the smallest priority, without removing it.
+    pub fn peek(&self) -> Option<Ref<'_, (K, P)>> {
+        let min = self.min.as_ref()?;
+        Some(Ref::map(min.entry.borrow(), |entry| {
+            entry.as_ref().expect("nodes in the heap have an entry")
+        }))
+    }
+
+    /// Adds an entry to the heap and returns its handle.
+    pub fn push(&mut self, key: K, priority: P) -> FibonacciHeapHandle<K, P> {
+        let node = Rc::new(Node {
+            entry: RefCell::new(Some((key, priority))),
+            children: RefCell::new(Vec::new()),
+            parent: RefCell::new(Weak::new()),
+            index_in_parent: Cell::new(0),
+            marked: Cell::new(false),
+        });
+        self.add_root(Rc::clone(&node));
+        self.len += 1;
+        FibonacciHeapHandle(node)
+    }
+
+    /// Removes and returns the entry with the smallest priority.
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        let min = self.min.take()?;
+        self.len -= 1;
+        let children = min.children.take();
+        for child in &children {
+            child.parent.replace(Weak::new());
+            child.marked.set(false);
+        }
+        let roots = std::mem::take(&mut self.roots)
+            .into_iter()
+            .filter(|root| !Rc::ptr_eq(root, &min))
+            .chain(children);
+
+        // Link the trees whose roots have the same degree, until all the degrees are different
+        let mut by_degree: Vec<Option<Link<K, P>>> = Vec::new();
+        for mut root in roots {
+            loop {
+                let degree = root.children.borrow().len();
+                if degree >= by_degree.len() {
+                    by_degree.resize(degree + 1, None);
+                }
+                match by_degree[degree].take() {
+                    Some(other) => root = link(root, other),
+                    None => {
+                        by_degree[degree] = Some(root);
+                        break;
+                    }
+                }
+            }
+        }
+        for root in by_degree.into_iter().flatten() {
+            self.add_root(root);
+        }
+        min.entry.take()
+    }
+
+    /// Lowers the priority of the entry behind `handle`.
+    ///
+    /// # Returns
+    /// `false`, leaving the heap untouched, if `priority` is not lower than the current one or
+    /// if the entry has already been popped.
+    pub fn decrease_key(&mut self, handle: &FibonacciHeapHandle<K, P>, priority: P) -> bool {
+        let node = &handle.0;
+        match node.entry.borrow_mut().as_mut() {
+            Some((_, current)) if priority < *current => *current = priority,
+            _ => return false,
+        }
+        let parent = node.parent.borrow().upgrade();
+        match parent {
+            Some(parent) if !parent.precedes(node) => {
+                cut(node);
+                self.add_root(Rc::clone(node));
+                self.cascading_cut(parent);
+            }
+            Some(_) => {}
+            None => {
+                // The node is a root already, it may have become the minimum
+                if self.min.as_ref().is_some_and(|min| !min.precedes(node)) {
+                    self.min = Some(Rc::clone(node));
+                }
+            }
+        }
+        true
+    }
+
+    /// Moves all the entries of `other` into this heap.
+    pub fn meld(&mut self, mut other: Self) {
+        self.roots.append(&mut other.roots);
+        self.min = match (self.min.take(), other.min.take()) {
+            (Some(min), Some(other_min)) if !min.precedes(&other_min) => Some(other_min),
+            (min, other_min) => min.or(other_min),
+        };
+        self.len += std::mem::take(&mut other.len);
+    }
+
+    /// Adds a tree to the list of roots, updating the minimum.
+    fn add_root(&mut self, node: Link<K, P>) {
+        node.marked.set(false);
+        if self.min.as_ref().is_none_or(|min| !min.precedes(&node)) {
+            self.min = Some(Rc::clone(&node));
+        }
+        self.roots.push_back(node);
+    }
+
+    /// Cuts off the ancestors of a node that lost a child, up to the first one that wasn't
+    /// marked yet.
+    fn cascading_cut(&mut self, mut node: Link<K, P>) {
+        loop {
+            let Some(parent) = node.parent.borrow().upgrade() else {
+                return;
+            };
+            if !node.marked.replace(true) {
+                return;
+            }
+            cut(&node);
+            self.add_root(node);
+            node = parent;
+        }
+    }
+}
+
+/// Makes the root with the larger priority a child of the other one, and returns the new root.
+fn link<K, P: Ord>(a: Link<K, P>, b: Link<K, P>) -> Link<K, P> {
+    let (parent, child) = if a.precedes(&b) { (a, b) } else { (b, a) };
+    child.parent.replace(Rc::downgrade(&parent));
+    child.marked.set(false);
+    let mut children = parent.children.borrow_mut();
+    child.index_in_parent.set(children.len());
+    children.push(child);
+    drop(children);
+    parent
+}
+
+/// Detaches a node from its parent.
+fn cut<K, P>(node: &Link<K, P>) {
+    let Some(parent) = node.parent.replace(Weak::new()).upgrade() else {
+        return;
+    };
+    let mut siblings = parent.children.borrow_mut();
+    let idx = node.index_in_parent.get();
+    siblings.swap_remove(idx);
+    if let Some(moved) = siblings.get(idx) {
+        moved.index_in_parent.set(idx);
+    }
+}
+
+impl<K, P: Ord> Default for FibonacciHeap<K, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Drops the nodes one at a time, since the trees can be too deep for a recursive drop.
+impl<K, P> Drop for FibonacciHeap<K, P> {
+    fn drop(&mut self) {
+        self.min = None;
+        let mut stack: Vec<Link<K, P>> = std::mem::take(&mut self.roots).into_iter().collect();
+        while let Some(node) = stack.pop() {
+            stack.append(&mut node.children.take());
+        }
+    }
+}
+
+impl<K, P: Ord> DecreaseKeyHeap<K, P> for FibonacciHeap<K, P> {
+    type Handle = FibonacciHeapHandle<K, P>;
+
+    fn push(&mut self, key: K, priority: P) -> Self::Handle {
+        FibonacciHeap::push(self, key, priority)
+    }
+
+    fn pop(&mut self) -> Option<(K, P)> {
+        FibonacciHeap::pop(self)
+    }
+
+    fn decrease_key(&mut self, handle: &Self::Handle, priority: P) -> bool {
+        FibonacciHeap::decrease_key(self, handle, priority)
+    }
+
+    fn len(&self) -> usize {
+        FibonacciHeap::len(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FibonacciHeap;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_push_pop_and_decrease_key() {
+        let mut heap = FibonacciHeap::new();
+        assert_eq!(heap.pop(), None);
+        let a = heap.push('a', 5);
+        let b = heap.push('b', 3);
+        heap.push('c', 8);
+        assert_eq!(heap.peek().map(|entry| *entry), Some(('b', 3)));
+
+        assert!(heap.decrease_key(&a, 1));
+        assert!(!heap.decrease_key(&a, 2));
+        assert_eq!(heap.pop(), Some(('a', 1)));
+        assert!(!heap.decrease_key(&a, 0));
+        assert_eq!(heap.pop(), Some(('b', 3)));
+        assert!(!heap.decrease_key(&b, 0));
+        assert_eq!(heap.len(), 1);
+    }
+
+    #[test]
+    fn test_cascading_cuts() {
+        let mut heap = FibonacciHeap::new();
+        let handles: Vec<_> = (0..64).map(|i| heap.push(i, i * 10)).collect();
+        // consolidates the rest into a single tree
+        assert_eq!(heap.pop(), Some((0, 0)));
+        for (i, handle) in handles.iter().enumerate().skip(1).rev() {
+            assert!(heap.decrease_key(handle, i as i32 * 10 - 1_000));
+        }
+        let popped: Vec<i32> = std::iter::from_fn(|| heap.pop().map(|(key, _)| key)).collect();
+        assert_eq!(popped, (1..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_meld() {
+        let mut evens = FibonacciHeap::new();
+        let mut odds = FibonacciHeap::new();
+        for i in 0..50 {
+            evens.push((), 2 * i);
+            odds.push((), 2 * i + 1);
+        }
+        let last = odds.push((), 1_000);
+        evens.meld(odds);
+        assert_eq!(evens.len(), 101);
+        // handles stay valid in the melded heap
+        assert!(evens.decrease_key(&last, -1));
+        let popped: Vec<i32> = std::iter::from_fn(|| evens.pop().map(|(_, p)| p)).collect();
+        assert_eq!(popped, (-1..100).collect::<Vec<_>>());
+    }
+
+    /// Applies random operations to the heap and to a list of the live priorities.
+    #[quickcheck]
+    fn prop_matches_sorted_model(ops: Vec<(u8, usize, i16)>) -> bool {
+        let mut heap = FibonacciHeap::new();
+        let mut handles = Vec::new();
+        // the priority of every pushed entry, `None` once popped
+        let mut model: Vec<Option<i16>> = Vec::new();
+        for (op, target, priority) in ops {
+            match op % 4 {
+                0 | 1 => {
+                    handles.push(heap.push(model.len(), priority));
+                    model.push(Some(priority));
+                }
+                2 if !handles.is_empty() => {
+                    let id = target % handles.len();
+                    let lower = model[id].is_some_and(|p| priority < p);
+                    if heap.decrease_key(&handles[id], priority) != lower {
+                        return false;
+                    }
+                    if lower {
+                        model[id] = Some(priority);
+                    }
+                }
+                _ => {
+                    let expected = model.iter().flatten().min().copied();
+                    match heap.pop() {
+                        Some((id, priority)) => {
+                            if Some(priority) != expected || model[id].take() != Some(priority) {
+                                return false;
+                            }
+                        }
+                        None => {
+                            if expected.is_some() {
+                                return false;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        heap.len() == model.iter().flatten().count()
+    }
+}
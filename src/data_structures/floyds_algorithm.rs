@@ -25,7 +25,7 @@ pub fn detect_cycle<T>(linked_list: &LinkedList<T>) -> Option<usize> {
             current = next;
         }
         if current == checkpoint {
-            return Some(linked_list.length as usize);
+            return Some(linked_list.length);
         }
     }
 
@@ -91,5 +91,12 @@ mod tests {
 
         assert!(has_cycle(&linked_list));
         assert_eq!(detect_cycle(&linked_list), Some(3));
+
+        // Break the cycle again, since dropping the list follows the links
+        unsafe {
+            if let Some(mut tail) = linked_list.tail {
+                tail.as_mut().next = None;
+            }
+        }
     }
 }
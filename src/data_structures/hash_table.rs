@@ -1,81 +1,509 @@
-use std::collections::LinkedList;
+//! An open-addressing hash map. All entries live directly in one array of slots; a key that
+//! collides with an occupied slot probes further slots in a fixed order until it finds its
+//! match or a free slot.
+//!
+//! Three probing strategies are available:
+//! * `Probing::Linear` tries the following slots one by one. Lookups stay cache friendly,
+//!   but collisions pile up into long runs (primary clustering).
+//! * `Probing::Quadratic` jumps `1, 2, 3, ...` slots further on each step, which breaks up
+//!   those runs. With a power-of-two capacity this still visits every slot.
+//! * `Probing::RobinHood` probes linearly, but on insertion a key that is further from its
+//!   home slot takes the place of one that is closer, which keeps all probe sequences short.
+//!
+//! Linear and quadratic probing delete by leaving a tombstone, so that probe sequences
+//! passing through the slot keep working. Robin Hood probing instead shifts the rest of the
+//! run back by one slot, which leaves no tombstones behind.
 
-pub struct HashTable<K, V> {
-    elements: Vec<LinkedList<(K, V)>>,
-    count: usize,
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+/// The smallest number of slots allocated once the table holds anything.
+const MIN_CAPACITY: usize = 8;
+
+/// The table grows once entries and tombstones fill this fraction of the slots.
+const MAX_LOAD_NUMERATOR: usize = 3;
+const MAX_LOAD_DENOMINATOR: usize = 4;
+
+/// The order in which a `HashTable` probes slots for a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Probing {
+    Linear,
+    Quadratic,
+    #[default]
+    RobinHood,
+}
+
+enum Slot<K, V> {
+    Empty,
+    /// A deleted entry, which lookups must probe past.
+    Tombstone,
+    Full {
+        hash: u64,
+        key: K,
+        value: V,
+    },
+}
+
+pub struct HashTable<K, V, S = RandomState> {
+    slots: Vec<Slot<K, V>>,
+    len: usize,
+    tombstones: usize,
+    probing: Probing,
+    hasher: S,
+}
+
+impl<K: Hash + Eq, V> HashTable<K, V> {
+    pub fn new() -> Self {
+        Self::with_probing(Probing::default())
+    }
+
+    pub fn with_probing(probing: Probing) -> Self {
+        Self::with_probing_and_hasher(probing, RandomState::new())
+    }
 }
 
-impl<K: Hashable + std::cmp::PartialEq, V> Default for HashTable<K, V> {
+impl<K: Hash + Eq, V> Default for HashTable<K, V> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-pub trait Hashable {
-    fn hash(&self) -> usize;
-}
+impl<K: Hash + Eq, V, S: BuildHasher> HashTable<K, V, S> {
+    pub fn with_probing_and_hasher(probing: Probing, hasher: S) -> Self {
+        HashTable {
+            slots: Vec::new(),
+            len: 0,
+            tombstones: 0,
+            probing,
+            hasher,
+        }
+    }
 
-impl<K: Hashable + std::cmp::PartialEq, V> HashTable<K, V> {
-    pub fn new() -> HashTable<K, V> {
-        let initial_capacity = 3000;
-        let mut elements = Vec::with_capacity(initial_capacity);
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of slots, which is always zero or a power of two.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
 
-        for _ in 0..initial_capacity {
-            elements.push(LinkedList::new());
+    pub fn probing(&self) -> Probing {
+        self.probing
+    }
+
+    /// Inserts a key-value pair and returns the value previously stored for the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
         }
+    }
 
-        HashTable { elements, count: 0 }
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hasher.hash_one(key), key)?;
+        match &self.slots[index] {
+            Slot::Full { value, .. } => Some(value),
+            _ => None,
+        }
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
-        if self.count >= self.elements.len() * 3 / 4 {
-            self.resize();
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hasher.hash_one(key), key)?;
+        match &mut self.slots[index] {
+            Slot::Full { value, .. } => Some(value),
+            _ => None,
         }
-        let index = key.hash() % self.elements.len();
-        self.elements[index].push_back((key, value));
-        self.count += 1;
     }
 
-    pub fn search(&self, key: K) -> Option<&V> {
-        let index = key.hash() % self.elements.len();
-        self.elements[index]
-            .iter()
-            .find(|(k, _)| *k == key)
-            .map(|(_, v)| v)
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(self.hasher.hash_one(key), key).is_some()
+    }
+
+    /// Removes a key and returns its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hasher.hash_one(key), key)?;
+        Some(self.remove_at(index).1)
+    }
+
+    /// Gets the entry for `key`, to inspect or update it in place with a single lookup.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hasher.hash_one(&key);
+        match self.find(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { table: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                table: self,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    /// Removes all entries, keeping the allocated slots.
+    pub fn clear(&mut self) {
+        self.slots.fill_with(|| Slot::Empty);
+        self.len = 0;
+        self.tombstones = 0;
+    }
+
+    /// Returns an iterator over the entries, in no particular order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            slots: self.slots.iter(),
+        }
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn home(&self, hash: u64) -> usize {
+        hash as usize & self.mask()
     }
 
-    fn resize(&mut self) {
-        let new_size = self.elements.len() * 2;
-        let mut new_elements = Vec::with_capacity(new_size);
+    /// Returns the slot visited at step `step` of the probe sequence for `hash`.
+    fn probe(&self, hash: u64, step: usize) -> usize {
+        let offset = match self.probing {
+            Probing::Linear | Probing::RobinHood => step,
+            // Triangular numbers visit every slot of a power-of-two table.
+            Probing::Quadratic => step * (step + 1) / 2,
+        };
+        (self.home(hash) + offset) & self.mask()
+    }
+
+    /// Returns how far the entry in slot `index` sits from its home slot.
+    fn distance(&self, hash: u64, index: usize) -> usize {
+        index.wrapping_sub(self.home(hash)) & self.mask()
+    }
+
+    /// Returns the slot that holds `key`.
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        for step in 0..self.slots.len() {
+            let index = self.probe(hash, step);
+            match &self.slots[index] {
+                Slot::Empty => return None,
+                Slot::Tombstone => {}
+                Slot::Full {
+                    hash: slot_hash,
+                    key: slot_key,
+                    ..
+                } => {
+                    if *slot_hash == hash && slot_key.borrow() == key {
+                        return Some(index);
+                    }
+                    // A Robin Hood run is sorted by distance, so the key cannot come later.
+                    if self.probing == Probing::RobinHood && self.distance(*slot_hash, index) < step
+                    {
+                        return None;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Stores an entry whose key is not in the table yet, and returns its slot.
+    fn insert_new(&mut self, hash: u64, key: K, value: V) -> usize {
+        self.reserve_one();
+        self.len += 1;
+        let mut carried = Slot::Full { hash, key, value };
+        let mut carried_hash = hash;
+        let mut placed = None;
+        let mut step = 0;
+        loop {
+            let index = self.probe(carried_hash, step);
+            match &self.slots[index] {
+                Slot::Empty => {
+                    self.slots[index] = carried;
+                    return placed.unwrap_or(index);
+                }
+                Slot::Tombstone => {
+                    self.tombstones -= 1;
+                    self.slots[index] = carried;
+                    return placed.unwrap_or(index);
+                }
+                Slot::Full {
+                    hash: slot_hash, ..
+                } => {
+                    let slot_distance = self.distance(*slot_hash, index);
+                    if self.probing == Probing::RobinHood && slot_distance < step {
+                        // Take the slot from the closer entry and carry that one further.
+                        let slot_hash = *slot_hash;
+                        carried = mem::replace(&mut self.slots[index], carried);
+                        carried_hash = slot_hash;
+                        placed.get_or_insert(index);
+                        step = slot_distance;
+                    }
+                }
+            }
+            step += 1;
+        }
+    }
 
-        for _ in 0..new_size {
-            new_elements.push(LinkedList::new());
+    /// Takes the entry out of slot `index`.
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        self.len -= 1;
+        let removed = if self.probing == Probing::RobinHood {
+            let removed = mem::replace(&mut self.slots[index], Slot::Empty);
+            // Shift the rest of the run back, so that no entry is left behind a gap.
+            let mut gap = index;
+            loop {
+                let next = (gap + 1) & self.mask();
+                match &self.slots[next] {
+                    Slot::Full { hash, .. } if self.distance(*hash, next) > 0 => {
+                        self.slots.swap(gap, next);
+                        gap = next;
+                    }
+                    _ => break removed,
+                }
+            }
+        } else {
+            self.tombstones += 1;
+            mem::replace(&mut self.slots[index], Slot::Tombstone)
+        };
+        match removed {
+            Slot::Full { key, value, .. } => (key, value),
+            _ => unreachable!("removed slot must be full"),
         }
+    }
 
-        for old_list in self.elements.drain(..) {
-            for (key, value) in old_list {
-                let new_index = key.hash() % new_size;
-                new_elements[new_index].push_back((key, value));
+    /// Makes room for one more entry, growing the table or clearing out tombstones when
+    /// the load factor would be exceeded.
+    fn reserve_one(&mut self) {
+        let used = self.len + self.tombstones + 1;
+        if used * MAX_LOAD_DENOMINATOR <= self.slots.len() * MAX_LOAD_NUMERATOR {
+            return;
+        }
+        // Only grow if live entries alone are above half the limit; otherwise
+        // rehashing at the same size is enough to get rid of the tombstones.
+        let capacity =
+            if (self.len + 1) * MAX_LOAD_DENOMINATOR * 2 > self.slots.len() * MAX_LOAD_NUMERATOR {
+                (self.slots.len() * 2).max(MIN_CAPACITY)
+            } else {
+                self.slots.len()
+            };
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, || Slot::Empty);
+        let old = mem::replace(&mut self.slots, slots);
+        self.len = 0;
+        self.tombstones = 0;
+        for slot in old {
+            if let Slot::Full { hash, key, value } = slot {
+                self.insert_new(hash, key, value);
             }
         }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for HashTable<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut table = Self::with_probing_and_hasher(Probing::default(), S::default());
+        table.extend(iter);
+        table
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for HashTable<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+/// A view into a single slot of a `HashTable`, which is either occupied or vacant.
+pub enum Entry<'a, K, V, S> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V, S> {
+    table: &'a mut HashTable<K, V, S>,
+    index: usize,
+}
+
+pub struct VacantEntry<'a, K, V, S> {
+    table: &'a mut HashTable<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, and returns the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Modifies the value in place if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Hash + Eq, V: Default, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match &self.table.slots[self.index] {
+            Slot::Full { key, .. } => key,
+            _ => unreachable!("occupied entry must point to a full slot"),
+        }
+    }
+
+    pub fn get(&self) -> &V {
+        match &self.table.slots[self.index] {
+            Slot::Full { value, .. } => value,
+            _ => unreachable!("occupied entry must point to a full slot"),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        match &mut self.table.slots[self.index] {
+            Slot::Full { value, .. } => value,
+            _ => unreachable!("occupied entry must point to a full slot"),
+        }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        match &mut self.table.slots[self.index] {
+            Slot::Full { value, .. } => value,
+            _ => unreachable!("occupied entry must point to a full slot"),
+        }
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
 
-        self.elements = new_elements;
+    /// Removes the entry from the table.
+    pub fn remove_entry(self) -> (K, V) {
+        self.table.remove_at(self.index)
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Inserts the entry, growing the table if needed, and returns the value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.table.insert_new(self.hash, self.key, value);
+        match &mut self.table.slots[index] {
+            Slot::Full { value, .. } => value,
+            _ => unreachable!("inserted slot must be full"),
+        }
+    }
+}
+
+/// An iterator over the entries of a `HashTable`.
+pub struct Iter<'a, K, V> {
+    slots: std::slice::Iter<'a, Slot<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.find_map(|slot| match slot {
+            Slot::Full { key, value, .. } => Some((key, value)),
+            _ => None,
+        })
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> IntoIterator for &'a HashTable<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
 
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, PartialEq, Eq, Hash)]
     struct TestKey(usize);
 
-    impl Hashable for TestKey {
-        fn hash(&self) -> usize {
-            self.0
+    /// A hasher that sends every key to a handful of home slots, to force long runs.
+    #[derive(Default)]
+    struct CollidingHasher(u64);
+
+    impl Hasher for CollidingHasher {
+        fn finish(&self) -> u64 {
+            self.0 % 3
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = self.0.wrapping_mul(31).wrapping_add(u64::from(byte));
+            }
         }
     }
 
+    const STRATEGIES: [Probing; 3] = [Probing::Linear, Probing::Quadratic, Probing::RobinHood];
+
     #[test]
     fn test_insert_and_search() {
         let mut hash_table = HashTable::new();
@@ -83,7 +511,7 @@ mod tests {
         let value = TestKey(10);
 
         hash_table.insert(key, value);
-        let result = hash_table.search(TestKey(1));
+        let result = hash_table.get(&TestKey(1));
 
         assert_eq!(result, Some(&TestKey(10)));
     }
@@ -91,13 +519,15 @@ mod tests {
     #[test]
     fn test_resize() {
         let mut hash_table = HashTable::new();
-        let initial_capacity = hash_table.elements.capacity();
+        hash_table.insert(TestKey(0), TestKey(10));
+        let initial_capacity = hash_table.capacity();
 
-        for i in 0..initial_capacity * 3 / 4 + 1 {
+        for i in 1..initial_capacity * 3 / 4 + 1 {
             hash_table.insert(TestKey(i), TestKey(i + 10));
         }
 
-        assert!(hash_table.elements.capacity() > initial_capacity);
+        assert!(hash_table.capacity() > initial_capacity);
+        assert_eq!(hash_table.len(), initial_capacity * 3 / 4 + 1);
     }
 
     #[test]
@@ -107,39 +537,167 @@ mod tests {
         let value = TestKey(10);
 
         hash_table.insert(key, value);
-        let result = hash_table.search(TestKey(2));
+        let result = hash_table.get(&TestKey(2));
 
         assert_eq!(result, None);
     }
 
     #[test]
     fn test_multiple_inserts_and_searches() {
-        let mut hash_table = HashTable::new();
-        for i in 0..10 {
-            hash_table.insert(TestKey(i), TestKey(i + 100));
-        }
+        for probing in STRATEGIES {
+            let mut hash_table = HashTable::with_probing(probing);
+            for i in 0..10 {
+                hash_table.insert(TestKey(i), TestKey(i + 100));
+            }
 
-        for i in 0..10 {
-            let result = hash_table.search(TestKey(i));
-            assert_eq!(result, Some(&TestKey(i + 100)));
+            for i in 0..10 {
+                let result = hash_table.get(&TestKey(i));
+                assert_eq!(result, Some(&TestKey(i + 100)));
+            }
         }
     }
 
+    #[test]
+    fn test_insert_replaces_existing_key() {
+        let mut hash_table = HashTable::new();
+        assert_eq!(hash_table.insert(TestKey(1), TestKey(100)), None);
+        assert_eq!(
+            hash_table.insert(TestKey(1), TestKey(200)),
+            Some(TestKey(100))
+        );
+
+        let result = hash_table.get(&TestKey(1));
+        assert_eq!(result, Some(&TestKey(200)));
+        assert_eq!(hash_table.len(), 1);
+    }
+
     #[test]
     fn test_not_overwrite_existing_key() {
         let mut hash_table = HashTable::new();
         hash_table.insert(TestKey(1), TestKey(100));
-        hash_table.insert(TestKey(1), TestKey(200));
+        hash_table.entry(TestKey(1)).or_insert(TestKey(200));
 
-        let result = hash_table.search(TestKey(1));
+        let result = hash_table.get(&TestKey(1));
         assert_eq!(result, Some(&TestKey(100)));
     }
 
     #[test]
     fn test_empty_search() {
         let hash_table: HashTable<TestKey, TestKey> = HashTable::new();
-        let result = hash_table.search(TestKey(1));
+        let result = hash_table.get(&TestKey(1));
 
         assert_eq!(result, None);
+        assert_eq!(hash_table.capacity(), 0);
+    }
+
+    #[test]
+    fn test_remove() {
+        for probing in STRATEGIES {
+            let mut hash_table: HashTable<String, usize> = HashTable::with_probing(probing);
+            for i in 0..100 {
+                hash_table.insert(i.to_string(), i);
+            }
+            for i in (0..100).step_by(3) {
+                assert_eq!(hash_table.remove(i.to_string().as_str()), Some(i));
+            }
+            assert_eq!(hash_table.remove("0"), None);
+            assert_eq!(hash_table.len(), 66);
+            for i in 0..100 {
+                let expected = (i % 3 != 0).then_some(i);
+                assert_eq!(hash_table.get(i.to_string().as_str()).copied(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_entry_api() {
+        let mut counts: HashTable<&str, u32> = HashTable::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            *counts.entry(word).or_default() += 1;
+        }
+        assert_eq!(counts.get("the"), Some(&3));
+        assert_eq!(counts.get("and"), Some(&2));
+        assert_eq!(counts.len(), 5);
+
+        counts
+            .entry("cat")
+            .and_modify(|count| *count += 10)
+            .or_insert(0);
+        counts
+            .entry("dog")
+            .and_modify(|count| *count += 10)
+            .or_insert(7);
+        assert_eq!(counts.get("cat"), Some(&11));
+        assert_eq!(counts.get("dog"), Some(&7));
+
+        match counts.entry("hat") {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), ("hat", 1)),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert!(!counts.contains_key("hat"));
+        assert_eq!(counts.entry("hat").key(), &"hat");
+    }
+
+    #[test]
+    fn test_iter_and_clear() {
+        let mut hash_table: HashTable<u32, u32> = (0..20).map(|i| (i, i * i)).collect();
+        *hash_table.get_mut(&3).unwrap() = 0;
+        let mut entries: Vec<_> = hash_table.iter().map(|(&k, &v)| (k, v)).collect();
+        entries.sort_unstable();
+        let expected: Vec<_> = (0..20)
+            .map(|i| (i, if i == 3 { 0 } else { i * i }))
+            .collect();
+        assert_eq!(entries, expected);
+
+        let capacity = hash_table.capacity();
+        hash_table.clear();
+        assert!(hash_table.is_empty());
+        assert_eq!(hash_table.capacity(), capacity);
+        assert_eq!((&hash_table).into_iter().count(), 0);
+    }
+
+    /// Churning through many keys with tombstones must not fill up the table.
+    #[test]
+    fn test_tombstones_are_cleaned_up() {
+        let mut hash_table = HashTable::with_probing(Probing::Linear);
+        for i in 0..10_000 {
+            hash_table.insert(i, i);
+            hash_table.remove(&i);
+        }
+        assert!(hash_table.is_empty());
+        assert!(hash_table.capacity() <= MIN_CAPACITY);
+    }
+
+    fn matches_hash_map<S: BuildHasher>(
+        mut table: HashTable<u8, u32, S>,
+        ops: &[(bool, u8, u32)],
+    ) -> bool {
+        let mut model = HashMap::new();
+        for &(is_insert, key, value) in ops {
+            let agrees = if is_insert {
+                table.insert(key, value) == model.insert(key, value)
+            } else {
+                table.remove(&key) == model.remove(&key)
+            };
+            if !agrees || table.len() != model.len() {
+                return false;
+            }
+        }
+        (0..=u8::MAX).all(|key| table.get(&key) == model.get(&key))
+            && table.iter().count() == model.len()
+    }
+
+    #[quickcheck]
+    fn prop_matches_hash_map(ops: Vec<(bool, u8, u32)>) -> bool {
+        STRATEGIES.into_iter().all(|probing| {
+            matches_hash_map(HashTable::with_probing(probing), &ops)
+                && matches_hash_map(
+                    HashTable::with_probing_and_hasher(
+                        probing,
+                        BuildHasherDefault::<CollidingHasher>::default(),
+                    ),
+                    &ops,
+                )
+        })
     }
 }
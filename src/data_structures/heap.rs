@@ -240,6 +240,37 @@ where
     }
 }
 
+/// A min-heap of `(key, priority)` entries whose priorities can be lowered after insertion.
+///
+/// `push` returns a handle to the entry, which `decrease_key` takes to find it again without
+/// searching the heap. This is the operation Dijkstra's and Prim's algorithms need when a
+/// shorter path or a cheaper edge to a vertex still in the queue is found.
+pub trait DecreaseKeyHeap<K, P: Ord> {
+    /// Identifies an entry of the heap.
+    type Handle;
+
+    /// Adds an entry to the heap and returns its handle.
+    fn push(&mut self, key: K, priority: P) -> Self::Handle;
+
+    /// Removes and returns the entry with the smallest priority.
+    fn pop(&mut self) -> Option<(K, P)>;
+
+    /// Lowers the priority of the entry behind `handle`.
+    ///
+    /// # Returns
+    /// `false`, leaving the heap untouched, if `priority` is not lower than the current one or
+    /// if the entry has already been popped.
+    fn decrease_key(&mut self, handle: &Self::Handle, priority: P) -> bool;
+
+    /// Returns the number of entries in the heap.
+    fn len(&self) -> usize;
+
+    /// Checks if the heap is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
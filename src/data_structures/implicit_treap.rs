@@ -0,0 +1,655 @@
+use super::{Max, Min, Monoid, Sum};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+/// Describes how an `ImplicitTreap` summarizes its elements for range aggregates.
+pub trait Summarize<T>: Monoid {
+    fn summarize(value: &T) -> Self::Value;
+}
+
+impl<T> Summarize<T> for () {
+    fn summarize(_value: &T) {}
+}
+
+impl<T> Summarize<T> for Sum<T>
+where
+    T: Clone,
+    Self: Monoid<Value = T>,
+{
+    fn summarize(value: &T) -> T {
+        value.clone()
+    }
+}
+
+impl<T> Summarize<T> for Min<T>
+where
+    T: Clone,
+    Self: Monoid<Value = T>,
+{
+    fn summarize(value: &T) -> T {
+        value.clone()
+    }
+}
+
+impl<T> Summarize<T> for Max<T>
+where
+    T: Clone,
+    Self: Monoid<Value = T>,
+{
+    fn summarize(value: &T) -> T {
+        value.clone()
+    }
+}
+
+/// An internal node of an `ImplicitTreap`.
+struct Node<T, S: Monoid> {
+    value: T,
+    priority: u64,
+    size: usize,
+    /// Aggregate of the subtree, in order.
+    summary: S::Value,
+    /// Aggregate of the subtree in reverse order, needed to reverse ranges of
+    /// non-commutative monoids.
+    reversed_summary: S::Value,
+    /// The children of this node still have to be swapped and reversed themselves.
+    reversed: bool,
+    left: Option<Box<Node<T, S>>>,
+    right: Option<Box<Node<T, S>>>,
+}
+
+type Tree<T, S> = Option<Box<Node<T, S>>>;
+
+/// A sequence based on a Treap whose key is the position of each element.
+///
+/// Instead of storing keys, each node stores the size of its subtree, so that the position
+/// of a node is the number of nodes before it in an in-order traversal. Splitting the
+/// sequence at a position and merging two sequences take `O(log(n))` expected time, and
+/// every other operation is built on them: inserting and removing at any index, cutting out
+/// or reversing a range, and concatenating sequences.
+///
+/// Nodes also cache an aggregate of their subtree in the monoid `S`, which answers range
+/// queries such as sums or minimums in `O(log(n))`.
+pub struct ImplicitTreap<T, S: Summarize<T> = ()> {
+    root: Tree<T, S>,
+}
+
+impl<T, S: Summarize<T>> Node<T, S> {
+    fn new(value: T) -> Self {
+        let summary = S::summarize(&value);
+        Node {
+            value,
+            priority: rand::random(),
+            size: 1,
+            reversed_summary: summary.clone(),
+            summary,
+            reversed: false,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// Reverses the subtree, deferring the work on the children.
+    fn toggle_reversed(&mut self) {
+        self.reversed = !self.reversed;
+        mem::swap(&mut self.summary, &mut self.reversed_summary);
+    }
+
+    /// Pushes a pending reversal down to the children.
+    fn push(&mut self) {
+        if self.reversed {
+            self.reversed = false;
+            mem::swap(&mut self.left, &mut self.right);
+            for child in [&mut self.left, &mut self.right].into_iter().flatten() {
+                child.toggle_reversed();
+            }
+        }
+    }
+
+    /// Recomputes the cached size and summaries from the children.
+    fn update(&mut self) {
+        let value = S::summarize(&self.value);
+        let (left, right) = (self.left.as_deref(), self.right.as_deref());
+        self.size = 1 + left.map_or(0, |n| n.size) + right.map_or(0, |n| n.size);
+        self.summary = S::combine(
+            &S::combine(&summary(left, false), &value),
+            &summary(right, false),
+        );
+        self.reversed_summary = S::combine(
+            &S::combine(&summary(right, true), &value),
+            &summary(left, true),
+        );
+    }
+
+    /// Returns the children in sequence order, given whether this node is read reversed,
+    /// along with whether the children are read reversed.
+    fn children(&self, flipped: bool) -> (&Tree<T, S>, &Tree<T, S>, bool) {
+        let flipped = flipped != self.reversed;
+        if flipped {
+            (&self.right, &self.left, flipped)
+        } else {
+            (&self.left, &self.right, flipped)
+        }
+    }
+}
+
+fn size<T, S: Monoid>(tree: &Tree<T, S>) -> usize {
+    tree.as_ref().map_or(0, |n| n.size)
+}
+
+/// Returns the aggregate of a subtree, read in reverse if `flipped`.
+fn summary<T, S: Monoid>(node: Option<&Node<T, S>>, flipped: bool) -> S::Value {
+    match node {
+        Some(node) if flipped => node.reversed_summary.clone(),
+        Some(node) => node.summary.clone(),
+        None => S::identity(),
+    }
+}
+
+/// Splits a tree into its first `index` elements and the rest.
+fn split<T, S: Summarize<T>>(tree: Tree<T, S>, index: usize) -> (Tree<T, S>, Tree<T, S>) {
+    let Some(mut node) = tree else {
+        return (None, None);
+    };
+    node.push();
+    let left_size = size(&node.left);
+    if index <= left_size {
+        let (left, rest) = split(node.left.take(), index);
+        node.left = rest;
+        node.update();
+        (left, Some(node))
+    } else {
+        let (rest, right) = split(node.right.take(), index - left_size - 1);
+        node.right = rest;
+        node.update();
+        (Some(node), right)
+    }
+}
+
+/// Concatenates two trees, keeping the node with the higher priority on top.
+fn merge<T, S: Summarize<T>>(left: Tree<T, S>, right: Tree<T, S>) -> Tree<T, S> {
+    match (left, right) {
+        (None, tree) | (tree, None) => tree,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.push();
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.push();
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// Aggregates the elements `start..end` of a subtree, read in reverse if `flipped`.
+fn fold<T, S: Summarize<T>>(
+    tree: &Tree<T, S>,
+    flipped: bool,
+    start: usize,
+    end: usize,
+) -> S::Value {
+    let Some(node) = tree else {
+        return S::identity();
+    };
+    if start >= end {
+        return S::identity();
+    }
+    if start == 0 && end >= node.size {
+        return summary(Some(node), flipped);
+    }
+    let (left, right, flipped) = node.children(flipped);
+    let left_size = size(left);
+    let mut result = fold(left, flipped, start, end.min(left_size));
+    if start <= left_size && left_size < end {
+        result = S::combine(&result, &S::summarize(&node.value));
+    }
+    let skipped = left_size + 1;
+    S::combine(
+        &result,
+        &fold(
+            right,
+            flipped,
+            start.saturating_sub(skipped),
+            end.saturating_sub(skipped),
+        ),
+    )
+}
+
+impl<T, S: Summarize<T>> ImplicitTreap<T, S> {
+    pub fn new() -> Self {
+        ImplicitTreap { root: None }
+    }
+
+    /// Returns the number of elements in the sequence.
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Returns `true` if the sequence contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the element at `index`.
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        let mut current = &self.root;
+        let mut flipped = false;
+        while let Some(node) = current {
+            let (left, right, child_flipped) = node.children(flipped);
+            let left_size = size(left);
+            if index == left_size {
+                return Some(&node.value);
+            }
+            if index < left_size {
+                current = left;
+            } else {
+                index -= left_size + 1;
+                current = right;
+            }
+            flipped = child_flipped;
+        }
+        None
+    }
+
+    /// Inserts `value` at `index`, shifting all elements after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len(), "insertion index out of bounds");
+        let (left, right) = split(self.root.take(), index);
+        let middle = Some(Box::new(Node::new(value)));
+        self.root = merge(merge(left, middle), right);
+    }
+
+    /// Appends `value` to the end of the sequence.
+    pub fn push(&mut self, value: T) {
+        let middle = Some(Box::new(Node::new(value)));
+        self.root = merge(self.root.take(), middle);
+    }
+
+    /// Removes and returns the element at `index`.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        let (left, rest) = split(self.root.take(), index);
+        let (middle, right) = split(rest, 1);
+        self.root = merge(left, right);
+        middle.map(|node| node.value)
+    }
+
+    /// Replaces the element at `index` and returns the old one.
+    pub fn set(&mut self, index: usize, value: T) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        let (left, rest) = split(self.root.take(), index);
+        let (mut middle, right) = split(rest, 1);
+        let node = middle.as_mut().unwrap();
+        let old = mem::replace(&mut node.value, value);
+        node.update();
+        self.root = merge(merge(left, middle), right);
+        Some(old)
+    }
+
+    /// Moves the elements from `index` on into a new sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn split_off(&mut self, index: usize) -> Self {
+        assert!(index <= self.len(), "split index out of bounds");
+        let (left, right) = split(self.root.take(), index);
+        self.root = left;
+        ImplicitTreap { root: right }
+    }
+
+    /// Moves all elements of `other` to the end of this sequence, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    /// Cuts the elements in `range` out of the sequence and returns them.
+    pub fn remove_range<R: RangeBounds<usize>>(&mut self, range: R) -> Self {
+        let (start, end) = self.bounds(range);
+        let (left, rest) = split(self.root.take(), start);
+        let (middle, right) = split(rest, end - start);
+        self.root = merge(left, right);
+        ImplicitTreap { root: middle }
+    }
+
+    /// Reverses the order of the elements in `range`.
+    pub fn reverse<R: RangeBounds<usize>>(&mut self, range: R) {
+        let (start, end) = self.bounds(range);
+        let (left, rest) = split(self.root.take(), start);
+        let (mut middle, right) = split(rest, end - start);
+        if let Some(node) = middle.as_mut() {
+            node.toggle_reversed();
+        }
+        self.root = merge(merge(left, middle), right);
+    }
+
+    /// Combines the summaries of the elements in `range`, in order.
+    pub fn aggregate<R: RangeBounds<usize>>(&self, range: R) -> S::Value {
+        let (start, end) = self.bounds(range);
+        fold(&self.root, false, start, end)
+    }
+
+    /// Returns the length of the longest prefix whose aggregate satisfies `predicate`.
+    ///
+    /// `predicate` must hold for the identity and be monotone: once it fails for a prefix,
+    /// it fails for every longer prefix. This finds, for example, the element that
+    /// contains a given offset when elements are summarized by their lengths.
+    pub fn partition_point<P: Fn(&S::Value) -> bool>(&self, predicate: P) -> usize {
+        let mut prefix = S::identity();
+        let mut count = 0;
+        let mut current = &self.root;
+        let mut flipped = false;
+        while let Some(node) = current {
+            let (left, right, child_flipped) = node.children(flipped);
+            let with_left = S::combine(&prefix, &summary(left.as_deref(), child_flipped));
+            if !predicate(&with_left) {
+                current = left;
+            } else {
+                let with_node = S::combine(&with_left, &S::summarize(&node.value));
+                if !predicate(&with_node) {
+                    return count + size(left);
+                }
+                prefix = with_node;
+                count += size(left) + 1;
+                current = right;
+            }
+            flipped = child_flipped;
+        }
+        count
+    }
+
+    /// Returns an iterator over the elements in order.
+    pub fn iter(&self) -> Iter<'_, T, S> {
+        self.range(..)
+    }
+
+    /// Returns an iterator over the elements in `range`.
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Iter<'_, T, S> {
+        let (mut start, end) = self.bounds(range);
+        let mut iter = Iter {
+            stack: Vec::new(),
+            remaining: end - start,
+        };
+        // Push the ancestors that come after the start, as an in-order traversal would.
+        let mut current = &self.root;
+        let mut flipped = false;
+        while let Some(node) = current {
+            let (left, right, child_flipped) = node.children(flipped);
+            let left_size = size(left);
+            if start <= left_size {
+                iter.stack.push((node.as_ref(), flipped));
+            }
+            if start == left_size {
+                break;
+            }
+            if start < left_size {
+                current = left;
+            } else {
+                start -= left_size + 1;
+                current = right;
+            }
+            flipped = child_flipped;
+        }
+        iter
+    }
+
+    /// Resolves `range` into `start..end`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or ends past the sequence.
+    fn bounds<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end && end <= self.len(), "range out of bounds");
+        (start, end)
+    }
+}
+
+impl<T, S: Summarize<T>> Default for ImplicitTreap<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, S: Summarize<T>> FromIterator<T> for ImplicitTreap<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut treap = ImplicitTreap::new();
+        for value in iter {
+            treap.push(value);
+        }
+        treap
+    }
+}
+
+/// An iterator over the elements of an `ImplicitTreap`.
+///
+/// This struct is created by the `iter` and `range` methods of `ImplicitTreap`. Pending
+/// reversals are applied on the fly, so iterating does not need mutable access.
+pub struct Iter<'a, T, S: Monoid> {
+    /// Nodes still to visit, with whether each one is read reversed.
+    stack: Vec<(&'a Node<T, S>, bool)>,
+    remaining: usize,
+}
+
+impl<'a, T, S: Monoid> Iterator for Iter<'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let (node, flipped) = self.stack.pop()?;
+        let flipped = flipped != node.reversed;
+        // Push the left path of the right subtree, in sequence order.
+        let mut child = if flipped { &node.left } else { &node.right };
+        let mut child_flipped = flipped;
+        while let Some(subtree) = child {
+            self.stack.push((subtree.as_ref(), child_flipped));
+            child_flipped = child_flipped != subtree.reversed;
+            child = if child_flipped {
+                &subtree.right
+            } else {
+                &subtree.left
+            };
+        }
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Max, Min, Monoid, Sum};
+    use super::{ImplicitTreap, Summarize};
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::quickcheck;
+
+    /// Concatenation of strings, a monoid that is not commutative.
+    struct Concat;
+
+    impl Monoid for Concat {
+        type Value = String;
+
+        fn identity() -> String {
+            String::new()
+        }
+
+        fn combine(left: &String, right: &String) -> String {
+            format!("{left}{right}")
+        }
+    }
+
+    impl Summarize<String> for Concat {
+        fn summarize(value: &String) -> String {
+            value.clone()
+        }
+    }
+
+    #[test]
+    fn sequence_editing() {
+        let mut treap: ImplicitTreap<char> = "hello".chars().collect();
+        treap.insert(5, '!');
+        treap.insert(0, '>');
+        assert_eq!(treap.iter().collect::<String>(), ">hello!");
+        assert_eq!(treap.remove(0), Some('>'));
+        assert_eq!(treap.remove(6), None);
+        assert_eq!(treap.set(0, 'j'), Some('h'));
+        assert_eq!(treap.get(4), Some(&'o'));
+
+        let removed = treap.remove_range(1..3);
+        assert_eq!(removed.iter().collect::<String>(), "el");
+        assert_eq!(treap.iter().collect::<String>(), "jlo!");
+
+        treap.reverse(..3);
+        assert_eq!(treap.iter().collect::<String>(), "olj!");
+        assert_eq!(treap.range(1..).collect::<String>(), "lj!");
+
+        let mut tail = treap.split_off(2);
+        assert_eq!(tail.iter().collect::<String>(), "j!");
+        tail.append(&mut treap);
+        assert!(treap.is_empty());
+        assert_eq!(tail.iter().collect::<String>(), "j!ol");
+    }
+
+    #[test]
+    fn aggregates() {
+        let mut sums: ImplicitTreap<i64, Sum<i64>> = (1..=10).collect();
+        assert_eq!(sums.aggregate(..), 55);
+        assert_eq!(sums.aggregate(2..5), 3 + 4 + 5);
+        sums.remove_range(..5);
+        assert_eq!(sums.aggregate(..), 40);
+
+        let mut mins: ImplicitTreap<i32, Min<i32>> = [5, 3, 8, 1, 9].into_iter().collect();
+        assert_eq!(mins.aggregate(..3), 3);
+        assert_eq!(mins.aggregate(2..=4), 1);
+        assert_eq!(mins.aggregate(2..2), i32::MAX);
+        mins.reverse(..);
+        assert_eq!(mins.aggregate(..2), 1);
+
+        let maxes: ImplicitTreap<u8, Max<u8>> = [5, 3, 8].into_iter().collect();
+        assert_eq!(maxes.aggregate(1..), 8);
+    }
+
+    #[test]
+    fn reversal_keeps_order_sensitive_aggregates() {
+        let mut treap: ImplicitTreap<String, Concat> =
+            "abcdefg".chars().map(String::from).collect();
+        treap.reverse(1..6);
+        assert_eq!(treap.aggregate(..), "afedcbg");
+        treap.reverse(2..4);
+        assert_eq!(treap.aggregate(1..5), "fdec");
+        assert_eq!(treap.aggregate(..), "afdecbg");
+    }
+
+    #[test]
+    fn partition_point_by_prefix_sum() {
+        let treap: ImplicitTreap<u32, Sum<u32>> = [3, 1, 4, 1, 5].into_iter().collect();
+        assert_eq!(treap.partition_point(|&sum| sum < 3), 0);
+        assert_eq!(treap.partition_point(|&sum| sum <= 4), 2);
+        assert_eq!(treap.partition_point(|&sum| sum < 9), 3);
+        assert_eq!(treap.partition_point(|&sum| sum <= 100), 5);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(usize, i64),
+        Remove(usize),
+        Reverse(usize, usize),
+        Cut(usize, usize),
+        Rotate(usize),
+    }
+
+    impl Arbitrary for Op {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let index = usize::arbitrary(g);
+            let other = usize::arbitrary(g);
+            match u8::arbitrary(g) % 5 {
+                0 | 1 => Op::Insert(index, i64::from(i32::arbitrary(g))),
+                2 => Op::Remove(index),
+                3 => Op::Reverse(index, other),
+                _ if bool::arbitrary(g) => Op::Cut(index, other),
+                _ => Op::Rotate(index),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn matches_vec_model(ops: Vec<Op>, queries: Vec<(usize, usize)>) -> bool {
+        let mut treap: ImplicitTreap<i64, Sum<i64>> = ImplicitTreap::new();
+        let mut model: Vec<i64> = Vec::new();
+        for op in ops {
+            let len = model.len();
+            let range = |a: usize, b: usize| {
+                let (a, b) = (a % (len + 1), b % (len + 1));
+                a.min(b)..a.max(b)
+            };
+            match op {
+                Op::Insert(index, value) => {
+                    treap.insert(index % (len + 1), value);
+                    model.insert(index % (len + 1), value);
+                }
+                Op::Remove(index) if len > 0 => {
+                    if treap.remove(index % len) != Some(model.remove(index % len)) {
+                        return false;
+                    }
+                }
+                Op::Remove(_) => {}
+                Op::Reverse(a, b) => {
+                    treap.reverse(range(a, b));
+                    model[range(a, b)].reverse();
+                }
+                Op::Cut(a, b) => {
+                    let cut = treap.remove_range(range(a, b));
+                    if !cut
+                        .iter()
+                        .eq(model.drain(range(a, b)).collect::<Vec<_>>().iter())
+                    {
+                        return false;
+                    }
+                }
+                Op::Rotate(index) => {
+                    let mut tail = treap.split_off(index % (len + 1));
+                    tail.append(&mut treap);
+                    treap = tail;
+                    model.rotate_left(index % (len + 1));
+                }
+            }
+        }
+        let len = model.len();
+        treap.len() == len
+            && treap.iter().eq(model.iter())
+            && (0..len).all(|i| treap.get(i) == Some(&model[i]))
+            && queries.into_iter().all(|(a, b)| {
+                let (a, b) = (a % (len + 1), b % (len + 1));
+                let range = a.min(b)..a.max(b);
+                treap.aggregate(range.clone()) == model[range.clone()].iter().sum::<i64>()
+                    && treap.range(range.clone()).eq(model[range].iter())
+            })
+    }
+}
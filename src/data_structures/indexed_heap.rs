@@ -0,0 +1,290 @@
+//! An indexed binary heap.
+//!
+//! This module provides an `IndexedHeap`: a binary min-heap of `(key, priority)` entries that
+//! also remembers where every key sits in the heap. Looking up a key is then enough to change
+//! its priority or to remove it, without searching the whole heap.
+
+use std::collections::BTreeMap;
+
+use super::DecreaseKeyHeap;
+
+/// A binary min-heap keyed by `K`, holding at most one entry per key.
+///
+/// Besides the usual `push`/`pop`, the priority of any key can be changed with `update` or
+/// `decrease_key`, and any key can be removed with `remove`, all in `O(log(n))` heap moves.
+/// Every move also updates the position of the key in a `BTreeMap`, so keys only need to be
+/// `Ord`.
+pub struct IndexedHeap<K, P> {
+    entries: Vec<(K, P)>,
+    positions: BTreeMap<K, usize>,
+}
+
+impl<K: Ord + Clone, P: Ord> IndexedHeap<K, P> {
+    /// Creates a new, empty heap.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            positions: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of entries in the heap.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks if the heap holds an entry for `key`.
+    pub fn contains(&self, key: &K) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// Returns the priority of `key`, if it is in the heap.
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.positions.get(key).map(|&idx| &self.entries[idx].1)
+    }
+
+    /// Returns the entry with the smallest priority, without removing it.
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.entries.first().map(|(key, priority)| (key, priority))
+    }
+
+    /// Adds `key` with the given priority.
+    ///
+    /// # Returns
+    /// The previous priority of `key` if it was already in the heap, in which case its priority
+    /// is replaced.
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if self.contains(&key) {
+            return self.update(&key, priority);
+        }
+        self.positions.insert(key.clone(), self.entries.len());
+        self.entries.push((key, priority));
+        self.heapify_up(self.entries.len() - 1);
+        None
+    }
+
+    /// Removes and returns the entry with the smallest priority.
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        if self.is_empty() {
+            return None;
+        }
+        self.remove_at(0)
+    }
+
+    /// Replaces the priority of `key`, whether it is lower or higher.
+    ///
+    /// # Returns
+    /// The previous priority, or `None` (leaving the heap untouched) if `key` is not in the heap.
+    pub fn update(&mut self, key: &K, priority: P) -> Option<P> {
+        let idx = *self.positions.get(key)?;
+        let old = std::mem::replace(&mut self.entries[idx].1, priority);
+        let idx = self.heapify_up(idx);
+        self.heapify_down(idx);
+        Some(old)
+    }
+
+    /// Lowers the priority of `key`.
+    ///
+    /// # Returns
+    /// `false`, leaving the heap untouched, if `key` is not in the heap or if `priority` is not
+    /// lower than its current priority.
+    pub fn decrease_key(&mut self, key: &K, priority: P) -> bool {
+        match self.positions.get(key) {
+            Some(&idx) if priority < self.entries[idx].1 => {
+                self.entries[idx].1 = priority;
+                self.heapify_up(idx);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes `key` from the heap and returns its priority.
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let idx = *self.positions.get(key)?;
+        self.remove_at(idx).map(|(_, priority)| priority)
+    }
+
+    /// Removes the entry at `idx` by moving the last entry in its place.
+    fn remove_at(&mut self, idx: usize) -> Option<(K, P)> {
+        let last = self.entries.len() - 1;
+        self.swap(idx, last);
+        let (key, priority) = self.entries.pop()?;
+        self.positions.remove(&key);
+        if idx < self.entries.len() {
+            let idx = self.heapify_up(idx);
+            self.heapify_down(idx);
+        }
+        Some((key, priority))
+    }
+
+    /// Swaps two entries, keeping track of their positions.
+    fn swap(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        self.entries.swap(a, b);
+        for idx in [a, b] {
+            if let Some(position) = self.positions.get_mut(&self.entries[idx].0) {
+                *position = idx;
+            }
+        }
+    }
+
+    /// Moves an entry upwards to restore the heap property, and returns its new index.
+    fn heapify_up(&mut self, mut idx: usize) -> usize {
+        while idx > 0 {
+            let pdx = (idx - 1) / 2;
+            if self.entries[idx].1 >= self.entries[pdx].1 {
+                break;
+            }
+            self.swap(idx, pdx);
+            idx = pdx;
+        }
+        idx
+    }
+
+    /// Moves an entry downwards to restore the heap property.
+    fn heapify_down(&mut self, mut idx: usize) {
+        loop {
+            let ldx = 2 * idx + 1;
+            let rdx = ldx + 1;
+            let mut smallest = idx;
+            if ldx < self.len() && self.entries[ldx].1 < self.entries[smallest].1 {
+                smallest = ldx;
+            }
+            if rdx < self.len() && self.entries[rdx].1 < self.entries[smallest].1 {
+                smallest = rdx;
+            }
+            if smallest == idx {
+                break;
+            }
+            self.swap(idx, smallest);
+            idx = smallest;
+        }
+    }
+}
+
+impl<K: Ord + Clone, P: Ord> Default for IndexedHeap<K, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The handle of an entry is its key.
+impl<K: Ord + Clone, P: Ord> DecreaseKeyHeap<K, P> for IndexedHeap<K, P> {
+    type Handle = K;
+
+    fn push(&mut self, key: K, priority: P) -> K {
+        IndexedHeap::push(self, key.clone(), priority);
+        key
+    }
+
+    fn pop(&mut self) -> Option<(K, P)> {
+        IndexedHeap::pop(self)
+    }
+
+    fn decrease_key(&mut self, key: &K, priority: P) -> bool {
+        IndexedHeap::decrease_key(self, key, priority)
+    }
+
+    fn len(&self) -> usize {
+        IndexedHeap::len(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IndexedHeap;
+    use quickcheck_macros::quickcheck;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_push_pop_and_update() {
+        let mut heap = IndexedHeap::new();
+        assert_eq!(heap.pop(), None);
+        heap.push("a", 5);
+        heap.push("b", 3);
+        heap.push("c", 8);
+        assert_eq!(heap.push("a", 9), Some(5));
+        assert_eq!(heap.peek(), Some((&"b", &3)));
+        assert_eq!(heap.len(), 3);
+
+        assert!(heap.decrease_key(&"c", 1));
+        assert!(!heap.decrease_key(&"c", 2));
+        assert!(!heap.decrease_key(&"z", 0));
+        assert_eq!(heap.update(&"b", 10), Some(3));
+        assert_eq!(heap.update(&"z", 10), None);
+        assert_eq!(heap.priority(&"a"), Some(&9));
+
+        assert_eq!(heap.pop(), Some(("c", 1)));
+        assert_eq!(heap.pop(), Some(("a", 9)));
+        assert_eq!(heap.pop(), Some(("b", 10)));
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut heap: IndexedHeap<u32, u32> = (0..10).fold(IndexedHeap::new(), |mut heap, i| {
+            heap.push(i, (i * 7) % 10);
+            heap
+        });
+        assert_eq!(heap.remove(&3), Some(1));
+        assert_eq!(heap.remove(&3), None);
+        assert!(!heap.contains(&3));
+        let popped: Vec<u32> = std::iter::from_fn(|| heap.pop().map(|(_, p)| p)).collect();
+        assert_eq!(popped, vec![0, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    /// Applies random operations to the heap and to a map of priorities.
+    #[quickcheck]
+    fn prop_matches_priority_map(ops: Vec<(u8, u8, i16)>) -> bool {
+        let mut heap = IndexedHeap::new();
+        let mut model: BTreeMap<u8, i16> = BTreeMap::new();
+        for (op, key, priority) in ops {
+            let key = key % 16;
+            match op % 5 {
+                0 | 1 => {
+                    if heap.push(key, priority) != model.insert(key, priority) {
+                        return false;
+                    }
+                }
+                2 => {
+                    let lower = model.get(&key).is_some_and(|&p| priority < p);
+                    if heap.decrease_key(&key, priority) != lower {
+                        return false;
+                    }
+                    if lower {
+                        model.insert(key, priority);
+                    }
+                }
+                3 => {
+                    if heap.remove(&key) != model.remove(&key) {
+                        return false;
+                    }
+                }
+                _ => {
+                    let expected = model.values().min().copied();
+                    match heap.pop() {
+                        Some((key, priority)) => {
+                            if Some(priority) != expected || model.remove(&key) != Some(priority) {
+                                return false;
+                            }
+                        }
+                        None => {
+                            if expected.is_some() {
+                                return false;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        heap.len() == model.len()
+    }
+}
@@ -0,0 +1,125 @@
+/// A set of integers from a bounded universe `0..U`, with ordered queries.
+///
+/// The implementations trade time against space differently, for `n` values of `w` bits:
+///
+/// - `VebTree` answers everything in `O(log w)`, but takes `O(U)` space whatever `n` is.
+/// - `YFastTrie` answers in `O(log w)` too (amortized, and expected because of hashing) in
+///   `O(n)` space.
+/// - `BitSet` inserts, removes and looks up in `O(1)` with `U` bits, but its successor and
+///   predecessor queries scan the bits up to the answer.
+pub trait IntegerSet {
+    /// Creates an empty set that can hold the integers of `0..universe`. The implementation may
+    /// round the universe up.
+    fn new(universe: u32) -> Self
+    where
+        Self: Sized;
+
+    /// Inserts a value, returning `false` if it was already in the set.
+    ///
+    /// # Panics
+    /// Panics if the value is outside the universe.
+    fn insert(&mut self, value: u32) -> bool;
+
+    /// Removes a value, returning `false` if it was not in the set.
+    fn remove(&mut self, value: u32) -> bool;
+
+    fn contains(&self, value: u32) -> bool;
+
+    /// Returns the number of values in the set.
+    fn len(&self) -> usize;
+
+    /// Checks if the set is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn min(&self) -> Option<u32>;
+
+    fn max(&self) -> Option<u32>;
+
+    /// Returns the smallest value of the set greater than `value`.
+    fn succ(&self, value: u32) -> Option<u32>;
+
+    /// Returns the largest value of the set smaller than `value`.
+    fn pred(&self, value: u32) -> Option<u32>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{BitSet, VebTree, YFastTrie};
+    use super::IntegerSet;
+    use quickcheck_macros::quickcheck;
+    use std::collections::BTreeSet;
+
+    const UNIVERSE: u32 = 1000;
+
+    /// Applies random operations to a set and to a `BTreeSet`, and checks that they agree,
+    /// including on queries slightly outside the universe.
+    fn check_against_model<S: IntegerSet>(ops: Vec<(u8, u16)>) -> bool {
+        let mut set = S::new(UNIVERSE);
+        let mut model = BTreeSet::new();
+        for (op, value) in ops {
+            let value = u32::from(value) % (UNIVERSE + 100);
+            let agrees = match op % 5 {
+                0 | 1 => {
+                    let value = value % UNIVERSE;
+                    set.insert(value) == model.insert(value)
+                }
+                2 => set.remove(value) == model.remove(&value),
+                3 => {
+                    set.succ(value) == model.range(value + 1..).next().copied()
+                        && set.pred(value) == model.range(..value).next_back().copied()
+                }
+                _ => set.contains(value) == model.contains(&value),
+            };
+            if !agrees
+                || set.len() != model.len()
+                || set.min() != model.first().copied()
+                || set.max() != model.last().copied()
+            {
+                return false;
+            }
+        }
+        set.is_empty() == model.is_empty()
+    }
+
+    #[quickcheck]
+    fn prop_veb_tree_matches_model(ops: Vec<(u8, u16)>) -> bool {
+        check_against_model::<VebTree>(ops)
+    }
+
+    #[quickcheck]
+    fn prop_y_fast_trie_matches_model(ops: Vec<(u8, u16)>) -> bool {
+        check_against_model::<YFastTrie>(ops)
+    }
+
+    #[quickcheck]
+    fn prop_bit_set_matches_model(ops: Vec<(u8, u16)>) -> bool {
+        check_against_model::<BitSet>(ops)
+    }
+
+    fn drain_in_random_order<S: IntegerSet>(universe: u32) {
+        let mut set = S::new(universe);
+        let values: Vec<u32> = (0..universe).filter(|value| value % 3 != 1).collect();
+        for &value in &values {
+            assert!(set.insert(value));
+        }
+        assert_eq!(set.len(), values.len());
+        // Remove every 7th value modulo the length, which visits each value once
+        let mut index = 0;
+        for removed in 0..values.len() {
+            index = (index + 7) % values.len();
+            assert!(set.remove(values[index]), "{removed}");
+            assert!(!set.contains(values[index]));
+        }
+        assert!(set.is_empty());
+        assert_eq!((set.min(), set.max()), (None, None));
+    }
+
+    #[test]
+    fn test_drain_in_random_order() {
+        drain_in_random_order::<VebTree>(4096);
+        drain_in_random_order::<YFastTrie>(4096);
+        drain_in_random_order::<BitSet>(4096);
+    }
+}
@@ -0,0 +1,233 @@
+//! An interval map assigns values to half-open ranges of keys, like painting segments of a line.
+//!
+//! The map is stored as a `BTreeMap` from the start of each range to its end and value. The
+//! ranges never overlap: assigning a value to a range overwrites the parts of the ranges it
+//! covers, splitting them if needed. Adjacent ranges with equal values are merged, so the map
+//! always holds the fewest possible ranges.
+//!
+//! Typical uses are memory maps, calendars, or the colors of a line painted several times.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A map from half-open ranges of keys of type `K` to values of type `V`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalMap<K, V> {
+    /// The end and value of each range, by start.
+    ranges: BTreeMap<K, (K, V)>,
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq> IntervalMap<K, V> {
+    pub fn new() -> Self {
+        IntervalMap {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of maximal ranges with a value.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns the value of `key`, if it is in a range.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_range(key).map(|(_, value)| value)
+    }
+
+    /// Returns the maximal range containing `key`, with its value.
+    pub fn get_range(&self, key: &K) -> Option<(Range<K>, &V)> {
+        let (start, (end, value)) = self.ranges.range(..=key).next_back()?;
+        (key < end).then(|| (start.clone()..end.clone(), value))
+    }
+
+    /// Assigns `value` to all the keys of `range`, replacing their previous values.
+    pub fn insert(&mut self, range: Range<K>, value: V) {
+        if range.start >= range.end {
+            return;
+        }
+        self.remove(range.clone());
+        let Range { mut start, mut end } = range;
+
+        // Merge with the ranges ending right at the start, and starting right at the end, if
+        // they have the same value
+        if let Some((left_start, (left_end, left_value))) = self.ranges.range(..&start).next_back()
+        {
+            if *left_end == start && *left_value == value {
+                start = left_start.clone();
+                self.ranges.remove(&start);
+            }
+        }
+        if let Some((right_end, right_value)) = self.ranges.get(&end) {
+            if *right_value == value {
+                let right_start = std::mem::replace(&mut end, right_end.clone());
+                self.ranges.remove(&right_start);
+            }
+        }
+        self.ranges.insert(start, (end, value));
+    }
+
+    /// Removes the values of all the keys of `range`.
+    pub fn remove(&mut self, range: Range<K>) {
+        if range.start >= range.end {
+            return;
+        }
+
+        // A range starting before `range` and overlapping it keeps its part before `range`,
+        // and its part after `range`, if any
+        if let Some((_, (end, value))) = self.ranges.range_mut(..&range.start).next_back() {
+            if *end > range.start {
+                let old_end = std::mem::replace(end, range.start.clone());
+                if old_end > range.end {
+                    let value = value.clone();
+                    self.ranges.insert(range.end.clone(), (old_end, value));
+                    return;
+                }
+            }
+        }
+
+        // The ranges starting inside `range` only keep their part after it
+        let inside: Vec<K> = self
+            .ranges
+            .range(range.start.clone()..range.end.clone())
+            .map(|(start, _)| start.clone())
+            .collect();
+        for start in inside {
+            let (end, value) = self.ranges.remove(&start).unwrap();
+            if end > range.end {
+                self.ranges.insert(range.end.clone(), (end, value));
+            }
+        }
+    }
+
+    /// Returns the maximal ranges overlapping `range`, with their values, in order.
+    pub fn overlapping(&self, range: Range<K>) -> impl Iterator<Item = (Range<K>, &V)> {
+        // An empty range overlaps nothing, and the range starting before `range` is only
+        // included if it reaches into it
+        let (before, inside) = if range.start < range.end {
+            let before = self
+                .ranges
+                .range(..&range.start)
+                .next_back()
+                .filter(|(_, (end, _))| *end > range.start);
+            (
+                before,
+                Some(self.ranges.range(range.start.clone()..range.end)),
+            )
+        } else {
+            (None, None)
+        };
+        before
+            .into_iter()
+            .chain(inside.into_iter().flatten())
+            .map(|(start, (end, value))| (start.clone()..end.clone(), value))
+    }
+
+    /// Returns all the maximal ranges with their values, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (Range<K>, &V)> {
+        self.ranges
+            .iter()
+            .map(|(start, (end, value))| (start.clone()..end.clone(), value))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq> Default for IntervalMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq> FromIterator<(Range<K>, V)> for IntervalMap<K, V> {
+    /// Assigns the values in order, so later ranges overwrite earlier ones.
+    fn from_iter<I: IntoIterator<Item = (Range<K>, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (range, value) in iter {
+            map.insert(range, value);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntervalMap;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_assign_and_merge() {
+        let mut map = IntervalMap::new();
+        map.insert(0..10, 'a');
+        map.insert(3..5, 'b');
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(0..3, &'a'), (3..5, &'b'), (5..10, &'a')]
+        );
+        assert_eq!(map.get(&4), Some(&'b'));
+        assert_eq!(map.get(&10), None);
+        assert_eq!(map.get_range(&7), Some((5..10, &'a')));
+
+        // Painting over the middle range merges the three ranges
+        map.insert(3..5, 'a');
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(0..10, &'a')]);
+
+        map.remove(2..4);
+        map.insert(12..15, 'a');
+        map.insert(10..12, 'a');
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(0..2, &'a'), (4..15, &'a')]
+        );
+        assert_eq!(
+            map.overlapping(1..5).collect::<Vec<_>>(),
+            [(0..2, &'a'), (4..15, &'a')]
+        );
+        assert_eq!(map.overlapping(2..4).count(), 0);
+        assert_eq!(map.overlapping(7..7).count(), 0);
+
+        // Empty ranges change nothing
+        map.insert(20..20, 'c');
+        map.remove(5..5);
+        assert_eq!(map.len(), 2);
+    }
+
+    /// Paints and erases ranges both in the map and in an array of values, and compares them.
+    #[quickcheck]
+    fn prop_matches_array(ops: Vec<(u8, u8, Option<bool>)>, query: (u8, u8)) -> bool {
+        const N: u8 = 40;
+        let mut map = IntervalMap::new();
+        let mut model = [None; N as usize];
+        for (a, b, value) in ops {
+            let (a, b) = (a % N, b % N);
+            let range = a.min(b)..a.max(b);
+            match value {
+                Some(value) => map.insert(range.clone(), value),
+                None => map.remove(range.clone()),
+            }
+            for key in range {
+                model[usize::from(key)] = value;
+            }
+        }
+
+        let ranges: Vec<_> = map.iter().collect();
+        // The ranges are non-empty and sorted, and the adjacent ones have different values
+        let minimal = ranges.iter().all(|(range, _)| range.start < range.end)
+            && ranges.windows(2).all(|w| {
+                w[0].0.end < w[1].0.start || (w[0].0.end == w[1].0.start && w[0].1 != w[1].1)
+            });
+        let values = (0..N).all(|key| map.get(&key) == model[usize::from(key)].as_ref());
+
+        let (a, b) = (query.0 % N, query.1 % N);
+        let query = a.min(b)..a.max(b) + 1;
+        let overlapping = map
+            .overlapping(query.clone())
+            .all(|(range, _)| range.start < query.end && query.start < range.end);
+        let count = ranges
+            .iter()
+            .filter(|(range, _)| range.start < query.end && query.start < range.end)
+            .count();
+        minimal && values && overlapping && map.overlapping(query).count() == count
+    }
+}
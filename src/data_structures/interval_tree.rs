@@ -0,0 +1,451 @@
+//! An interval tree maps closed intervals to values, and finds the intervals overlapping a
+//! given interval or containing a given point.
+//!
+//! It is an AVL tree ordered by the start of the intervals (then by their end), where each
+//! node is augmented with the largest end of its subtree: a query skips every subtree whose
+//! largest end is before the query, and stops once the starts go past it. Reporting the `k`
+//! overlapping intervals takes `O(log(n) + k * log(n))` time (`O(log(n) + k)` in practice),
+//! while insertions and removals take `O(log(n))`.
+//!
+//! # Sources
+//! <https://en.wikipedia.org/wiki/Interval_tree#Augmented_tree>
+
+use std::cmp::{max, Ordering};
+use std::mem;
+
+/// A closed interval `[start, end]`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<K> {
+    start: K,
+    end: K,
+}
+
+impl<K: Ord> Interval<K> {
+    /// Creates the interval `[start, end]`.
+    ///
+    /// # Panics
+    /// Panics if `start > end`.
+    pub fn new(start: K, end: K) -> Self {
+        assert!(start <= end, "an interval cannot end before it starts");
+        Interval { start, end }
+    }
+
+    pub fn start(&self) -> &K {
+        &self.start
+    }
+
+    pub fn end(&self) -> &K {
+        &self.end
+    }
+
+    /// Checks if both intervals have at least one point in common.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn contains(&self, point: &K) -> bool {
+        self.start <= *point && *point <= self.end
+    }
+}
+
+struct Node<K, V> {
+    interval: Interval<K>,
+    value: V,
+    /// The largest end of the intervals in the subtree rooted at this node.
+    max_end: K,
+    height: usize,
+    left: Subtree<K, V>,
+    right: Subtree<K, V>,
+}
+
+type Subtree<K, V> = Option<Box<Node<K, V>>>;
+
+/// Refers to the left or right subtree of a `Node`.
+#[derive(Clone, Copy)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    fn opposite(self) -> Self {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+/// A map from closed intervals to values, answering overlap and stabbing queries.
+///
+/// Each interval appears at most once; several values for the same interval can be stored as
+/// a `Vec`.
+pub struct IntervalTree<K, V> {
+    root: Subtree<K, V>,
+    len: usize,
+}
+
+impl<K: Ord + Clone, V> IntervalTree<K, V> {
+    pub fn new() -> Self {
+        IntervalTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts an interval with its value, returning the previous value of the interval if it
+    /// was already present.
+    pub fn insert(&mut self, interval: Interval<K>, value: V) -> Option<V> {
+        let old = insert(&mut self.root, interval, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Removes an interval, returning its value if it was present.
+    pub fn remove(&mut self, interval: &Interval<K>) -> Option<V> {
+        let removed = remove(&mut self.root, interval);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    pub fn get(&self, interval: &Interval<K>) -> Option<&V> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            current = match interval.cmp(&node.interval) {
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+            }
+        }
+        None
+    }
+
+    /// Returns the intervals overlapping `query`, with their values, ordered by start.
+    pub fn overlapping(&self, query: Interval<K>) -> Overlapping<'_, K, V> {
+        let mut iter = Overlapping {
+            stack: Vec::new(),
+            query,
+        };
+        iter.push_left_path(&self.root);
+        iter
+    }
+
+    /// Returns the intervals containing `point`, with their values, ordered by start.
+    pub fn stabbing(&self, point: &K) -> Overlapping<'_, K, V> {
+        self.overlapping(Interval::new(point.clone(), point.clone()))
+    }
+
+    /// Returns all the intervals with their values, ordered by start.
+    pub fn iter(&self) -> impl Iterator<Item = (&Interval<K>, &V)> {
+        let mut stack = Vec::new();
+        let mut current = &self.root;
+        std::iter::from_fn(move || {
+            while let Some(node) = current {
+                stack.push(node.as_ref());
+                current = &node.left;
+            }
+            let node = stack.pop()?;
+            current = &node.right;
+            Some((&node.interval, &node.value))
+        })
+    }
+}
+
+impl<K: Ord + Clone, V> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V> FromIterator<(Interval<K>, V)> for IntervalTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (Interval<K>, V)>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        for (interval, value) in iter {
+            tree.insert(interval, value);
+        }
+        tree
+    }
+}
+
+/// An iterator over the intervals of an `IntervalTree` overlapping a query interval.
+pub struct Overlapping<'a, K, V> {
+    /// The nodes whose left subtree has been visited (or skipped), in in-order.
+    stack: Vec<&'a Node<K, V>>,
+    query: Interval<K>,
+}
+
+impl<'a, K: Ord, V> Overlapping<'a, K, V> {
+    /// Pushes the left path of a subtree, skipping the subtrees ending before the query.
+    fn push_left_path(&mut self, mut current: &'a Subtree<K, V>) {
+        while let Some(node) = current {
+            if node.max_end < self.query.start {
+                return;
+            }
+            self.stack.push(node);
+            current = &node.left;
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Overlapping<'a, K, V> {
+    type Item = (&'a Interval<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            if node.interval.start > self.query.end {
+                // All the following intervals start after the query
+                self.stack.clear();
+                return None;
+            }
+            self.push_left_path(&node.right);
+            if node.interval.end >= self.query.start {
+                return Some((&node.interval, &node.value));
+            }
+        }
+        None
+    }
+}
+
+/// Recursive helper function for `IntervalTree` insertion.
+fn insert<K: Ord + Clone, V>(
+    tree: &mut Subtree<K, V>,
+    interval: Interval<K>,
+    value: V,
+) -> Option<V> {
+    let Some(node) = tree else {
+        *tree = Some(Box::new(Node {
+            max_end: interval.end.clone(),
+            interval,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        }));
+        return None;
+    };
+    let old = match interval.cmp(&node.interval) {
+        Ordering::Equal => return Some(mem::replace(&mut node.value, value)),
+        Ordering::Less => insert(&mut node.left, interval, value),
+        Ordering::Greater => insert(&mut node.right, interval, value),
+    };
+    node.rebalance();
+    old
+}
+
+/// Recursive helper function for `IntervalTree` deletion.
+fn remove<K: Ord + Clone, V>(tree: &mut Subtree<K, V>, interval: &Interval<K>) -> Option<V> {
+    let node = tree.as_mut()?;
+    let removed = match interval.cmp(&node.interval) {
+        Ordering::Less => remove(&mut node.left, interval),
+        Ordering::Greater => remove(&mut node.right, interval),
+        Ordering::Equal => {
+            let mut node = tree.take().unwrap();
+            *tree = match (node.left.take(), node.right.take()) {
+                (None, None) => None,
+                (Some(child), None) | (None, Some(child)) => Some(child),
+                (Some(left), Some(right)) => {
+                    let mut right = Some(right);
+                    // `right` is not empty, so it has a smallest node
+                    let mut root = take_min(&mut right).unwrap();
+                    root.left = Some(left);
+                    root.right = right;
+                    root.rebalance();
+                    Some(root)
+                }
+            };
+            return Some(node.value);
+        }
+    };
+    if removed.is_some() {
+        node.rebalance();
+    }
+    removed
+}
+
+/// Removes the smallest node from the tree, if one exists.
+fn take_min<K: Ord + Clone, V>(tree: &mut Subtree<K, V>) -> Subtree<K, V> {
+    let mut node = tree.take()?;
+    match take_min(&mut node.left) {
+        Some(min) => {
+            node.rebalance();
+            *tree = Some(node);
+            Some(min)
+        }
+        None => {
+            *tree = node.right.take();
+            node.update();
+            Some(node)
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> Node<K, V> {
+    fn child_mut(&mut self, side: Side) -> &mut Subtree<K, V> {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    fn height(subtree: &Subtree<K, V>) -> usize {
+        subtree.as_ref().map_or(0, |node| node.height)
+    }
+
+    /// Recomputes the `height` and `max_end` fields from the children.
+    fn update(&mut self) {
+        self.height = 1 + max(Self::height(&self.left), Self::height(&self.right));
+        let mut max_end = &self.interval.end;
+        for child in [&self.left, &self.right].into_iter().flatten() {
+            max_end = max(max_end, &child.max_end);
+        }
+        self.max_end = max_end.clone();
+    }
+
+    /// Moves the child on the opposite of `side` up, and this node down on `side`.
+    fn rotate(&mut self, side: Side) {
+        let mut subtree = self.child_mut(side.opposite()).take().unwrap();
+        *self.child_mut(side.opposite()) = subtree.child_mut(side).take();
+        self.update();
+        mem::swap(self, subtree.as_mut());
+        *self.child_mut(side) = Some(subtree);
+        self.update();
+    }
+
+    /// Updates this node and rotates it if its subtrees differ in height by 2.
+    fn rebalance(&mut self) {
+        self.update();
+        let (left, right) = (Self::height(&self.left), Self::height(&self.right));
+        let heavy = if left > right + 1 {
+            Side::Left
+        } else if right > left + 1 {
+            Side::Right
+        } else {
+            return;
+        };
+        let child = self.child_mut(heavy).as_mut().unwrap();
+        let (inner, outer) = match heavy {
+            Side::Left => (Self::height(&child.right), Self::height(&child.left)),
+            Side::Right => (Self::height(&child.left), Self::height(&child.right)),
+        };
+        // A heavy inner grandchild first needs to be moved to the outside
+        if inner > outer {
+            child.rotate(heavy);
+        }
+        self.rotate(heavy.opposite());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interval, IntervalTree, Node};
+    use quickcheck_macros::quickcheck;
+
+    /// Checks the balance and the `max_end` of every node, and returns the height.
+    fn check_node(node: &Option<Box<Node<u8, usize>>>) -> usize {
+        let Some(node) = node else { return 0 };
+        let (left, right) = (check_node(&node.left), check_node(&node.right));
+        assert!(left.abs_diff(right) <= 1);
+        assert_eq!(node.height, 1 + left.max(right));
+        let max_end = [&node.left, &node.right]
+            .into_iter()
+            .flatten()
+            .map(|child| child.max_end)
+            .chain([node.interval.end])
+            .max();
+        assert_eq!(Some(node.max_end), max_end);
+        node.height
+    }
+
+    #[test]
+    fn test_overlap_and_stabbing() {
+        let mut tree: IntervalTree<i32, &str> = [
+            (Interval::new(15, 20), "a"),
+            (Interval::new(10, 30), "b"),
+            (Interval::new(17, 19), "c"),
+            (Interval::new(5, 20), "d"),
+            (Interval::new(12, 15), "e"),
+            (Interval::new(30, 40), "f"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(tree.len(), 6);
+
+        let found: Vec<&str> = tree
+            .overlapping(Interval::new(6, 7))
+            .map(|(_, v)| *v)
+            .collect();
+        assert_eq!(found, ["d"]);
+        let found: Vec<&str> = tree.stabbing(&15).map(|(_, v)| *v).collect();
+        assert_eq!(found, ["d", "b", "e", "a"]);
+        let found: Vec<&str> = tree.stabbing(&30).map(|(_, v)| *v).collect();
+        assert_eq!(found, ["b", "f"]);
+        assert_eq!(tree.stabbing(&41).next(), None);
+
+        assert_eq!(tree.insert(Interval::new(17, 19), "g"), Some("c"));
+        assert_eq!(tree.get(&Interval::new(17, 19)), Some(&"g"));
+        assert_eq!(tree.remove(&Interval::new(10, 30)), Some("b"));
+        assert_eq!(tree.remove(&Interval::new(10, 30)), None);
+        assert_eq!(tree.len(), 5);
+        let found: Vec<&str> = tree
+            .overlapping(Interval::new(21, 35))
+            .map(|(_, v)| *v)
+            .collect();
+        assert_eq!(found, ["f"]);
+        let starts: Vec<i32> = tree.iter().map(|(i, _)| *i.start()).collect();
+        assert_eq!(starts, [5, 12, 15, 17, 30]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_reversed_interval() {
+        Interval::new(3, 2);
+    }
+
+    /// Applies random insertions and removals to a tree and a list, and compares the
+    /// overlapping intervals.
+    #[quickcheck]
+    fn prop_matches_linear_scan(ops: Vec<(bool, u8, u8)>, query: (u8, u8)) -> bool {
+        let mut tree = IntervalTree::new();
+        let mut model: Vec<(Interval<u8>, usize)> = Vec::new();
+        for (i, (insert, a, b)) in ops.into_iter().enumerate() {
+            let (a, b) = (a % 64, b % 64);
+            let interval = Interval::new(a.min(b), a.max(b));
+            let index = model.iter().position(|(m, _)| *m == interval);
+            if insert {
+                let old = index.map(|index| model.remove(index).1);
+                model.push((interval.clone(), i));
+                if tree.insert(interval, i) != old {
+                    return false;
+                }
+            } else {
+                let old = index.map(|index| model.remove(index).1);
+                if tree.remove(&interval) != old {
+                    return false;
+                }
+            }
+            check_node(&tree.root);
+        }
+
+        let query = Interval::new(query.0.min(query.1), query.0.max(query.1));
+        model.sort();
+        let expected: Vec<(Interval<u8>, usize)> = model
+            .iter()
+            .filter(|(interval, _)| interval.overlaps(&query))
+            .cloned()
+            .collect();
+        let found: Vec<(Interval<u8>, usize)> = tree
+            .overlapping(query)
+            .map(|(interval, &value)| (interval.clone(), value))
+            .collect();
+        tree.len() == model.len() && found == expected
+    }
+}
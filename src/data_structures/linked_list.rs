@@ -1,7 +1,11 @@
 use std::fmt::{self, Display, Formatter};
+use std::iter::FusedIterator;
 use std::marker::PhantomData;
+use std::mem;
 use std::ptr::NonNull;
 
+type Link<T> = Option<NonNull<Node<T>>>;
+
 pub struct Node<T> {
     pub val: T,
     pub next: Option<NonNull<Node<T>>>,
@@ -18,8 +22,13 @@ impl<T> Node<T> {
     }
 }
 
+/// A doubly linked list owning its nodes through raw pointers.
+///
+/// Besides the indexed operations, which walk from the nearest end of the list, a `CursorMut`
+/// inserts, removes, splits and splices at its position in `O(1)`, and `append` joins two lists
+/// in `O(1)`.
 pub struct LinkedList<T> {
-    pub length: u32,
+    pub length: usize,
     pub head: Option<NonNull<Node<T>>>,
     pub tail: Option<NonNull<Node<T>>>,
     // Act like we own boxed nodes since we construct and leak them
@@ -42,159 +51,247 @@ impl<T> LinkedList<T> {
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        // Safety: the nodes are leaked boxes owned by this list, borrowed along with it
+        self.head.map(|node| unsafe { &(*node.as_ptr()).val })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).val })
+    }
+
     pub fn insert_at_head(&mut self, obj: T) {
-        let mut node = Box::new(Node::new(obj));
-        node.next = self.head;
-        node.prev = None;
-        let node_ptr = NonNull::new(Box::into_raw(node));
-        match self.head {
-            None => self.tail = node_ptr,
-            Some(head_ptr) => unsafe { (*head_ptr.as_ptr()).prev = node_ptr },
-        }
-        self.head = node_ptr;
-        self.length += 1;
+        self.link_before(self.head, obj);
     }
 
     pub fn insert_at_tail(&mut self, obj: T) {
-        let mut node = Box::new(Node::new(obj));
-        node.next = None;
-        node.prev = self.tail;
-        let node_ptr = NonNull::new(Box::into_raw(node));
-        match self.tail {
-            None => self.head = node_ptr,
-            Some(tail_ptr) => unsafe { (*tail_ptr.as_ptr()).next = node_ptr },
-        }
-        self.tail = node_ptr;
-        self.length += 1;
+        self.link_before(None, obj);
     }
 
-    pub fn insert_at_ith(&mut self, index: u32, obj: T) {
+    /// Inserts `obj` so that it ends up at `index`, shifting the following elements.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length.
+    pub fn insert_at_ith(&mut self, index: usize, obj: T) {
         if self.length < index {
             panic!("Index out of bounds");
         }
+        let next = self.ith_node(index);
+        self.link_before(next, obj);
+    }
 
-        if index == 0 || self.head.is_none() {
-            self.insert_at_head(obj);
-            return;
-        }
-
-        if self.length == index {
-            self.insert_at_tail(obj);
-            return;
-        }
+    pub fn delete_head(&mut self) -> Option<T> {
+        let head = self.head?;
+        // Safety: head is a node of this list
+        Some(unsafe { self.unlink(head) }.val)
+    }
 
-        if let Some(mut ith_node) = self.head {
-            for _ in 0..index {
-                unsafe {
-                    match (*ith_node.as_ptr()).next {
-                        None => panic!("Index out of bounds"),
-                        Some(next_ptr) => ith_node = next_ptr,
-                    }
-                }
-            }
+    pub fn delete_tail(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        // Safety: tail is a node of this list
+        Some(unsafe { self.unlink(tail) }.val)
+    }
 
-            let mut node = Box::new(Node::new(obj));
-            unsafe {
-                node.prev = (*ith_node.as_ptr()).prev;
-                node.next = Some(ith_node);
-                if let Some(p) = (*ith_node.as_ptr()).prev {
-                    let node_ptr = NonNull::new(Box::into_raw(node));
-                    println!("{:?}", (*p.as_ptr()).next);
-                    (*p.as_ptr()).next = node_ptr;
-                    (*ith_node.as_ptr()).prev = node_ptr;
-                    self.length += 1;
-                }
-            }
+    /// Removes the element at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is not smaller than the length.
+    pub fn delete_ith(&mut self, index: usize) -> Option<T> {
+        if self.length <= index {
+            panic!("Index out of bounds");
         }
+        let node = self.ith_node(index)?;
+        // Safety: ith_node only returns nodes of this list
+        Some(unsafe { self.unlink(node) }.val)
     }
 
-    pub fn delete_head(&mut self) -> Option<T> {
-        // Safety: head_ptr points to a leaked boxed node managed by this list
-        // We reassign pointers that pointed to the head node
-        if self.length == 0 {
-            return None;
-        }
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.ith_node(index)
+            .map(|ptr| unsafe { &(*ptr.as_ptr()).val })
+    }
 
-        self.head.map(|head_ptr| unsafe {
-            let old_head = Box::from_raw(head_ptr.as_ptr());
-            match old_head.next {
-                Some(mut next_ptr) => next_ptr.as_mut().prev = None,
-                None => self.tail = None,
-            }
-            self.head = old_head.next;
-            self.length = self.length.checked_add_signed(-1).unwrap_or(0);
-            old_head.val
-        })
-        // None
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.ith_node(index)
+            .map(|ptr| unsafe { &mut (*ptr.as_ptr()).val })
     }
 
-    pub fn delete_tail(&mut self) -> Option<T> {
-        // Safety: tail_ptr points to a leaked boxed node managed by this list
-        // We reassign pointers that pointed to the tail node
-        self.tail.map(|tail_ptr| unsafe {
-            let old_tail = Box::from_raw(tail_ptr.as_ptr());
-            match old_tail.prev {
-                Some(mut prev) => prev.as_mut().next = None,
-                None => self.head = None,
-            }
-            self.tail = old_tail.prev;
-            self.length -= 1;
-            old_tail.val
-        })
+    /// Moves all the elements of `other` to the end of the list in `O(1)`, leaving `other`
+    /// empty.
+    pub fn append(&mut self, other: &mut Self) {
+        let other = mem::take(other);
+        // Safety: the tail and the ghost position around it are adjacent in this list
+        unsafe { self.splice(self.tail, None, other) }
     }
 
-    pub fn delete_ith(&mut self, index: u32) -> Option<T> {
+    /// Splits the list in two at `index`, returning the elements from `index` on.
+    ///
+    /// Finding the node takes `O(min(index, len - index))`, and the split itself `O(1)`; use
+    /// `CursorMut::split_after` to split at a known position in `O(1)`.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length.
+    pub fn split_off(&mut self, index: usize) -> Self {
         if self.length < index {
             panic!("Index out of bounds");
         }
+        match self.ith_node(index) {
+            // Safety: ith_node only returns nodes of this list
+            Some(node) => unsafe { self.split_before(node, index) },
+            None => Self::new(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.length,
+            marker: PhantomData,
+        }
+    }
 
-        if index == 0 || self.head.is_none() {
-            return self.delete_head();
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.length,
+            marker: PhantomData,
         }
+    }
 
-        if self.length == index {
-            return self.delete_tail();
+    /// Returns a cursor on the first element, or on the "ghost" position if the list is empty.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head,
+            index: 0,
+            list: self,
         }
+    }
 
-        if let Some(mut ith_node) = self.head {
-            for _ in 0..index {
-                unsafe {
-                    match (*ith_node.as_ptr()).next {
-                        None => panic!("Index out of bounds"),
-                        Some(next_ptr) => ith_node = next_ptr,
-                    }
-                }
-            }
+    /// Returns a cursor on the last element, or on the "ghost" position if the list is empty.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail,
+            index: self.length.saturating_sub(1),
+            list: self,
+        }
+    }
 
-            unsafe {
-                let old_ith = Box::from_raw(ith_node.as_ptr());
-                if let Some(mut prev) = old_ith.prev {
-                    prev.as_mut().next = old_ith.next;
+    /// Returns the node at `index`, walking from the nearest end of the list.
+    fn ith_node(&self, index: usize) -> Link<T> {
+        if index >= self.length {
+            return None;
+        }
+        // Safety: the walk stays within the `length` nodes of the list
+        unsafe {
+            if index < self.length / 2 {
+                let mut node = self.head?;
+                for _ in 0..index {
+                    node = (*node.as_ptr()).next?;
                 }
-                if let Some(mut next) = old_ith.next {
-                    next.as_mut().prev = old_ith.prev;
+                Some(node)
+            } else {
+                let mut node = self.tail?;
+                for _ in index + 1..self.length {
+                    node = (*node.as_ptr()).prev?;
                 }
+                Some(node)
+            }
+        }
+    }
 
-                self.length -= 1;
-                Some(old_ith.val)
+    /// Links a new node holding `obj` before `next`, or at the tail if `next` is `None`.
+    fn link_before(&mut self, next: Link<T>, obj: T) {
+        let prev = match next {
+            // Safety: next is a node of this list
+            Some(next) => unsafe { (*next.as_ptr()).prev },
+            None => self.tail,
+        };
+        let mut node = Box::new(Node::new(obj));
+        node.prev = prev;
+        node.next = next;
+        let node = NonNull::from(Box::leak(node));
+        // Safety: prev and next are adjacent nodes of this list, or its ends
+        unsafe {
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(node),
+                None => self.head = Some(node),
             }
-        } else {
-            None
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(node),
+                None => self.tail = Some(node),
+            }
+        }
+        self.length += 1;
+    }
+
+    /// Unlinks a node and takes back ownership of it.
+    ///
+    /// # Safety
+    /// `node` must be a node of this list.
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> Box<Node<T>> {
+        let node = Box::from_raw(node.as_ptr());
+        match node.prev {
+            Some(prev) => (*prev.as_ptr()).next = node.next,
+            None => self.head = node.next,
         }
+        match node.next {
+            Some(next) => (*next.as_ptr()).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        self.length -= 1;
+        node
     }
 
-    pub fn get(&self, index: i32) -> Option<&T> {
-        Self::get_ith_node(self.head, index).map(|ptr| unsafe { &(*ptr.as_ptr()).val })
+    /// Moves the nodes of `other` between `prev` and `next`.
+    ///
+    /// # Safety
+    /// `prev` and `next` must be adjacent nodes of this list, `None` standing for the position
+    /// before the head or after the tail.
+    unsafe fn splice(&mut self, prev: Link<T>, next: Link<T>, mut other: Self) {
+        let (Some(head), Some(tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        (*head.as_ptr()).prev = prev;
+        (*tail.as_ptr()).next = next;
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = Some(head),
+            None => self.head = Some(head),
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).prev = Some(tail),
+            None => self.tail = Some(tail),
+        }
+        self.length += mem::take(&mut other.length);
     }
 
-    fn get_ith_node(node: Option<NonNull<Node<T>>>, index: i32) -> Option<NonNull<Node<T>>> {
-        match node {
-            None => None,
-            Some(next_ptr) => match index {
-                0 => Some(next_ptr),
-                _ => Self::get_ith_node(unsafe { (*next_ptr.as_ptr()).next }, index - 1),
-            },
+    /// Detaches `node` and the nodes after it into a new list.
+    ///
+    /// # Safety
+    /// `node` must be the node of this list at `index`.
+    unsafe fn split_before(&mut self, node: NonNull<Node<T>>, index: usize) -> Self {
+        let prev = (*node.as_ptr()).prev.take();
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = None,
+            None => self.head = None,
         }
+        let rest = LinkedList {
+            length: self.length - index,
+            head: Some(node),
+            tail: self.tail,
+            marker: PhantomData,
+        };
+        self.tail = prev;
+        self.length = index;
+        rest
     }
 }
 
@@ -205,15 +302,304 @@ impl<T> Drop for LinkedList<T> {
     }
 }
 
+/// A cursor over a `LinkedList` that can edit the list around its position.
+///
+/// Besides the elements, the cursor can be on a "ghost" position between the tail and the
+/// head, at index `len`: moving forward from the tail or backward from the head reaches it,
+/// and moving on from it wraps around to the other end.
+pub struct CursorMut<'a, T> {
+    /// `None` on the ghost position.
+    current: Link<T>,
+    index: usize,
+    list: &'a mut LinkedList<T>,
+}
+
+impl<T> CursorMut<'_, T> {
+    /// Returns the index of the current element, or `None` on the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        // Safety: the node is borrowed through the cursor, which borrows the list mutably
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).val })
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        self.next_node()
+            .map(|node| unsafe { &mut (*node.as_ptr()).val })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        self.prev_node()
+            .map(|node| unsafe { &mut (*node.as_ptr()).val })
+    }
+
+    pub fn move_next(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index + 1,
+            None => 0,
+        };
+        self.current = self.next_node();
+    }
+
+    pub fn move_prev(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index.checked_sub(1).unwrap_or(self.list.length),
+            None => self.list.length.saturating_sub(1),
+        };
+        self.current = self.prev_node();
+    }
+
+    /// Inserts an element before the current one, or at the back on the ghost position.
+    pub fn insert_before(&mut self, obj: T) {
+        self.list.link_before(self.current, obj);
+        self.index += 1;
+    }
+
+    /// Inserts an element after the current one, or at the front on the ghost position.
+    pub fn insert_after(&mut self, obj: T) {
+        self.list.link_before(self.next_node(), obj);
+        if self.current.is_none() {
+            self.index += 1;
+        }
+    }
+
+    /// Removes the current element and moves to the next one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current?;
+        self.current = self.next_node();
+        // Safety: the current node is a node of the list
+        Some(unsafe { self.list.unlink(node) }.val)
+    }
+
+    /// Moves the elements of `other` after the current one, or at the front on the ghost
+    /// position, in `O(1)`.
+    pub fn splice_after(&mut self, other: LinkedList<T>) {
+        if self.current.is_none() {
+            self.index += other.length;
+        }
+        // Safety: the current node and the next one are adjacent
+        unsafe { self.list.splice(self.current, self.next_node(), other) }
+    }
+
+    /// Moves the elements of `other` before the current one, or at the back on the ghost
+    /// position, in `O(1)`.
+    pub fn splice_before(&mut self, other: LinkedList<T>) {
+        self.index += other.length;
+        // Safety: the previous node and the current one are adjacent
+        unsafe { self.list.splice(self.prev_node(), self.current, other) }
+    }
+
+    /// Splits off the elements after the current one in `O(1)`, or all of them on the ghost
+    /// position.
+    pub fn split_after(&mut self) -> LinkedList<T> {
+        match (self.current, self.next_node()) {
+            // Safety: the next node is at the next index
+            (Some(_), Some(next)) => unsafe { self.list.split_before(next, self.index + 1) },
+            (Some(_), None) => LinkedList::new(),
+            (None, _) => {
+                self.index = 0;
+                mem::take(self.list)
+            }
+        }
+    }
+
+    fn next_node(&self) -> Link<T> {
+        match self.current {
+            // Safety: the current node is a node of the list
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => self.list.head,
+        }
+    }
+
+    fn prev_node(&self) -> Link<T> {
+        match self.current {
+            // Safety: the current node is a node of the list
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        }
+    }
+}
+
+/// An iterator over the elements of a `LinkedList`, from the head to the tail.
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    /// The number of elements left, so the two ends never cross.
+    len: usize,
+    marker: PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.head?;
+        self.len -= 1;
+        // Safety: the node is one of the `len` remaining nodes of the borrowed list
+        unsafe {
+            self.head = (*node.as_ptr()).next;
+            Some(&(*node.as_ptr()).val)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.tail?;
+        self.len -= 1;
+        unsafe {
+            self.tail = (*node.as_ptr()).prev;
+            Some(&(*node.as_ptr()).val)
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// An iterator over mutable references to the elements of a `LinkedList`.
+pub struct IterMut<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    marker: PhantomData<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.head?;
+        self.len -= 1;
+        // Safety: each node is yielded once, since `len` stops the two ends from crossing
+        unsafe {
+            self.head = (*node.as_ptr()).next;
+            Some(&mut (*node.as_ptr()).val)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.tail?;
+        self.len -= 1;
+        unsafe {
+            self.tail = (*node.as_ptr()).prev;
+            Some(&mut (*node.as_ptr()).val)
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// An iterator moving the elements out of a `LinkedList`.
+pub struct IntoIter<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.list.delete_head()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.length, Some(self.list.length))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.list.delete_tail()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for obj in iter {
+            self.insert_at_tail(obj);
+        }
+    }
+}
+
 impl<T> Display for LinkedList<T>
 where
     T: Display,
 {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        match self.head {
-            Some(node) => write!(f, "{}", unsafe { node.as_ref() }),
-            None => Ok(()),
+        for (i, val) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{val}")?;
         }
+        Ok(())
     }
 }
 
@@ -231,9 +617,15 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::collections::VecDeque;
     use std::convert::TryInto;
 
     use super::LinkedList;
+    use quickcheck_macros::quickcheck;
+
+    fn to_vec<T: Clone>(list: &LinkedList<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
 
     #[test]
     fn insert_at_tail_works() {
@@ -499,4 +891,183 @@ mod tests {
         assert!(retrived_item.is_some());
         assert_eq!("B", *retrived_item.unwrap());
     }
+
+    #[test]
+    fn delete_ith_at_tail_updates_tail() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        assert_eq!(list.delete_ith(2), Some(3));
+        assert_eq!(list.back(), Some(&2));
+        list.insert_at_tail(4);
+        assert_eq!(to_vec(&list), [1, 2, 4]);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), [4, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn delete_ith_past_the_end_panics() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        list.delete_ith(3);
+    }
+
+    #[test]
+    fn iterators_work_from_both_ends() {
+        let mut list: LinkedList<i32> = (1..=5).collect();
+        assert_eq!(list.iter().len(), 5);
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.copied().collect::<Vec<_>>(), [2, 3, 4]);
+
+        for val in &mut list {
+            *val *= 10;
+        }
+        *list.get_mut(0).unwrap() += 1;
+        assert_eq!(to_vec(&list), [11, 20, 30, 40, 50]);
+        assert_eq!(list.to_string(), "11, 20, 30, 40, 50");
+
+        let mut into_iter = list.into_iter();
+        assert_eq!(into_iter.next_back(), Some(50));
+        assert_eq!(into_iter.collect::<Vec<_>>(), [11, 20, 30, 40]);
+    }
+
+    #[test]
+    fn cursor_edits_around_its_position() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        cursor.insert_before(10);
+        cursor.insert_after(20);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.peek_prev(), Some(&mut 10));
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 20));
+
+        // Past the tail is the ghost position, then the head again
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        cursor.insert_after(0);
+        cursor.insert_before(4);
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current()), (Some(0), Some(&mut 0)));
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!((cursor.index(), cursor.current()), (Some(5), Some(&mut 4)));
+        assert_eq!(to_vec(&list), [0, 1, 10, 20, 3, 4]);
+
+        let mut cursor = list.cursor_back_mut();
+        while cursor.remove_current().is_some() {
+            cursor.move_prev();
+        }
+        assert!(list.is_empty());
+        assert_eq!(list.head, None);
+        assert_eq!(list.tail, None);
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        let mut list: LinkedList<i32> = (0..10).collect();
+        let mut back = list.split_off(7);
+        let mut middle = list.split_off(2);
+        assert_eq!(to_vec(&list), [0, 1]);
+        assert_eq!(to_vec(&middle), [2, 3, 4, 5, 6]);
+        assert_eq!(to_vec(&back), [7, 8, 9]);
+        assert!(list.split_off(2).is_empty());
+
+        back.append(&mut list);
+        middle.append(&mut back);
+        assert!(back.is_empty());
+        assert_eq!(to_vec(&middle), [2, 3, 4, 5, 6, 7, 8, 9, 0, 1]);
+        assert_eq!(middle.len(), 10);
+        list.append(&mut middle);
+        assert_eq!(list.front(), Some(&2));
+        assert_eq!(list.split_off(0).len(), 10);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn cursor_splices_and_splits() {
+        let mut list: LinkedList<i32> = (0..3).collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after((10..12).collect());
+        cursor.splice_before((20..22).collect());
+        assert_eq!(cursor.index(), Some(2));
+        cursor.move_next();
+        let rest = cursor.split_after();
+        assert_eq!(cursor.current(), Some(&mut 10));
+        assert_eq!(to_vec(&rest), [11, 1, 2]);
+
+        cursor.move_next();
+        cursor.splice_after((30..32).collect());
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!((cursor.index(), cursor.current()), (Some(5), Some(&mut 10)));
+        assert_eq!(to_vec(&list), [30, 31, 20, 21, 0, 10]);
+
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert_eq!(all.len(), 6);
+        assert!(list.is_empty());
+    }
+
+    #[quickcheck]
+    fn prop_matches_vec_deque(ops: Vec<(u8, u8, i32)>) -> bool {
+        let mut list = LinkedList::new();
+        let mut model = VecDeque::new();
+        for (op, index, val) in ops {
+            let index = usize::from(index) % (model.len() + 1);
+            match op % 6 {
+                0 => {
+                    list.insert_at_ith(index, val);
+                    model.insert(index, val);
+                }
+                1 if index < model.len() => {
+                    if list.delete_ith(index) != model.remove(index) {
+                        return false;
+                    }
+                }
+                2 => {
+                    if list.delete_head() != model.pop_front()
+                        || list.delete_tail() != model.pop_back()
+                    {
+                        return false;
+                    }
+                }
+                3 => {
+                    let mut back = list.split_off(index);
+                    let model_back = model.split_off(index);
+                    if to_vec(&back) != Vec::from(model_back.clone()) {
+                        return false;
+                    }
+                    back.append(&mut list);
+                    list = back;
+                    model = model_back.into_iter().chain(model).collect();
+                }
+                4 => {
+                    let mut cursor = list.cursor_front_mut();
+                    for _ in 0..index {
+                        cursor.move_next();
+                    }
+                    if cursor.remove_current() != model.remove(index) {
+                        return false;
+                    }
+                    cursor.insert_before(val);
+                    model.insert(index, val);
+                }
+                _ => {
+                    if list.get(index) != model.get(index) {
+                        return false;
+                    }
+                }
+            }
+            if list.len() != model.len() || !list.iter().rev().eq(model.iter().rev()) {
+                return false;
+            }
+        }
+        list.into_iter().eq(model)
+    }
 }
@@ -1,45 +1,101 @@
 mod avl_tree;
 mod b_tree;
 mod binary_search_tree;
+mod bit_set;
+mod bounded_queue;
+pub mod cache;
+mod centered_interval_tree;
+mod deque;
 mod fenwick_tree;
+mod fibonacci_heap;
 mod floyds_algorithm;
 pub mod graph;
 mod hash_table;
 mod heap;
+mod implicit_treap;
+mod indexed_heap;
+mod integer_set;
+mod interval_map;
+mod interval_tree;
 mod lazy_segment_tree;
 mod linked_list;
+mod monoid;
+mod monoid_segment_tree;
+mod pairing_heap;
+pub mod persistent;
 mod probabilistic;
 mod queue;
+mod radix_trie;
 mod range_minimum_query;
 mod rb_tree;
+mod rollback_union_find;
+mod rope;
 mod segment_tree;
 mod segment_tree_recursive;
+mod skip_list;
+mod sparse_table;
+pub mod spatial;
 mod stack_using_singly_linked_list;
 mod treap;
 mod trie;
 mod union_find;
 mod veb_tree;
+mod weighted_union_find;
+mod y_fast_trie;
 
-pub use self::avl_tree::AVLTree;
-pub use self::b_tree::BTree;
+pub use self::avl_tree::{AVLTree, Range as AVLTreeRange};
+pub use self::b_tree::{
+    BTree, Entry as BTreeEntry, OccupiedEntry as BTreeOccupiedEntry, Range as BTreeRange,
+    VacantEntry as BTreeVacantEntry,
+};
 pub use self::binary_search_tree::BinarySearchTree;
-pub use self::fenwick_tree::FenwickTree;
+pub use self::bit_set::BitSet;
+pub use self::bounded_queue::{BoundedQueue, PopError, PushError};
+pub use self::centered_interval_tree::CenteredIntervalTree;
+pub use self::deque::{Deque, IntoIter as DequeIntoIter, Iter as DequeIter};
+pub use self::fenwick_tree::{FenwickTree, FenwickTree2D, RangeFenwickTree};
+pub use self::fibonacci_heap::{FibonacciHeap, FibonacciHeapHandle};
 pub use self::floyds_algorithm::{detect_cycle, has_cycle};
 pub use self::graph::DirectedGraph;
 pub use self::graph::UndirectedGraph;
-pub use self::hash_table::HashTable;
-pub use self::heap::Heap;
+pub use self::hash_table::{
+    Entry as HashTableEntry, HashTable, Iter as HashTableIter,
+    OccupiedEntry as HashTableOccupiedEntry, Probing, VacantEntry as HashTableVacantEntry,
+};
+pub use self::heap::{DecreaseKeyHeap, Heap};
+pub use self::implicit_treap::{ImplicitTreap, Iter as ImplicitTreapIter, Summarize};
+pub use self::indexed_heap::IndexedHeap;
+pub use self::integer_set::IntegerSet;
+pub use self::interval_map::IntervalMap;
+pub use self::interval_tree::{Interval, IntervalTree, Overlapping as IntervalTreeOverlapping};
 pub use self::lazy_segment_tree::LazySegmentTree;
-pub use self::linked_list::LinkedList;
+pub use self::linked_list::{
+    CursorMut as LinkedListCursorMut, IntoIter as LinkedListIntoIter, Iter as LinkedListIter,
+    IterMut as LinkedListIterMut, LinkedList,
+};
+pub use self::monoid::{Action, AddAction, AffineAction, AssignAction, Max, Min, Monoid, Sum};
+pub use self::monoid_segment_tree::MonoidSegmentTree;
+pub use self::pairing_heap::{PairingHeap, PairingHeapHandle};
 pub use self::probabilistic::bloom_filter;
 pub use self::probabilistic::count_min_sketch;
+pub use self::probabilistic::cuckoo_filter;
+pub use self::probabilistic::hyperloglog;
+pub use self::probabilistic::kll_sketch;
+pub use self::probabilistic::min_hash;
 pub use self::queue::Queue;
-pub use self::range_minimum_query::RangeMinimumQuery;
-pub use self::rb_tree::RBTree;
+pub use self::radix_trie::{Iter as RadixTrieIter, RadixTrie};
+pub use self::range_minimum_query::{FischerHeunRmq, RangeError, RangeMinimumQuery};
+pub use self::rb_tree::{RBTree, RBTreeRange};
+pub use self::rollback_union_find::RollbackUnionFind;
+pub use self::rope::Rope;
 pub use self::segment_tree::SegmentTree;
 pub use self::segment_tree_recursive::SegmentTree as SegmentTreeRecursive;
+pub use self::skip_list::{ConcurrentSkipList, Range as SkipListRange, SkipList};
+pub use self::sparse_table::{DisjointSparseTable, SparseTable};
 pub use self::stack_using_singly_linked_list::Stack;
 pub use self::treap::Treap;
-pub use self::trie::Trie;
-pub use self::union_find::UnionFind;
+pub use self::trie::{Iter as TrieIter, Trie};
+pub use self::union_find::{DisjointSetUnion, Members as DisjointSetUnionMembers, UnionFind};
 pub use self::veb_tree::VebTree;
+pub use self::weighted_union_find::{WeightedUnionFind, WeightedUnionFindError};
+pub use self::y_fast_trie::YFastTrie;
@@ -0,0 +1,331 @@
+//! Monoids describe how range queries combine values: an associative operation with an
+//! identity element. Range structures are generic over a monoid type instead of taking a
+//! closure, so that the identity is known without a sample value.
+//!
+//! `Sum`, `Min` and `Max` cover the common cases; other aggregates implement `Monoid`
+//! on a type of their own.
+//!
+//! Range updates are monoids too: an `Action` is a monoid of updates that can also be
+//! applied to the aggregate of a whole range at once, which is what lazy propagation needs.
+
+use std::marker::PhantomData;
+use std::ops::Add;
+
+/// An associative binary operation with an identity element.
+///
+/// Implementations must satisfy `combine(a, combine(b, c)) == combine(combine(a, b), c)` and
+/// `combine(identity(), a) == a == combine(a, identity())`. The operation does not have to
+/// be commutative.
+pub trait Monoid {
+    type Value: Clone;
+
+    fn identity() -> Self::Value;
+
+    fn combine(left: &Self::Value, right: &Self::Value) -> Self::Value;
+}
+
+/// The trivial monoid, for structures that do not need to aggregate anything.
+impl Monoid for () {
+    type Value = ();
+
+    fn identity() {}
+
+    fn combine(_left: &(), _right: &()) {}
+}
+
+/// Addition, with zero (`T::default()`) as identity.
+pub struct Sum<T>(PhantomData<T>);
+
+impl<T: Clone + Default + Add<Output = T>> Monoid for Sum<T> {
+    type Value = T;
+
+    fn identity() -> T {
+        T::default()
+    }
+
+    fn combine(left: &T, right: &T) -> T {
+        left.clone() + right.clone()
+    }
+}
+
+/// Minimum, with the largest value of the type as identity.
+pub struct Min<T>(PhantomData<T>);
+
+/// Maximum, with the smallest value of the type as identity.
+pub struct Max<T>(PhantomData<T>);
+
+macro_rules! impl_min_max {
+    ($($t:ty: $min:expr, $max:expr;)*) => {
+        $(
+            impl Monoid for Min<$t> {
+                type Value = $t;
+
+                fn identity() -> $t {
+                    $max
+                }
+
+                fn combine(left: &$t, right: &$t) -> $t {
+                    (*left).min(*right)
+                }
+            }
+
+            impl Monoid for Max<$t> {
+                type Value = $t;
+
+                fn identity() -> $t {
+                    $min
+                }
+
+                fn combine(left: &$t, right: &$t) -> $t {
+                    (*left).max(*right)
+                }
+            }
+        )*
+    };
+}
+
+impl_min_max! {
+    i8: i8::MIN, i8::MAX;
+    i16: i16::MIN, i16::MAX;
+    i32: i32::MIN, i32::MAX;
+    i64: i64::MIN, i64::MAX;
+    i128: i128::MIN, i128::MAX;
+    isize: isize::MIN, isize::MAX;
+    u8: u8::MIN, u8::MAX;
+    u16: u16::MIN, u16::MAX;
+    u32: u32::MIN, u32::MAX;
+    u64: u64::MIN, u64::MAX;
+    u128: u128::MIN, u128::MAX;
+    usize: usize::MIN, usize::MAX;
+    f32: f32::NEG_INFINITY, f32::INFINITY;
+    f64: f64::NEG_INFINITY, f64::INFINITY;
+}
+
+/// A monoid of updates acting on the values of the monoid `M`.
+///
+/// `combine(first, then)` must be the update that applies `first` and then `then`, and
+/// `apply(update, aggregate, len)` must update the aggregate of `len` values as if `update`
+/// had been applied to each of them.
+pub trait Action<M: Monoid>: Monoid {
+    fn apply(update: &Self::Value, aggregate: &M::Value, len: usize) -> M::Value;
+}
+
+/// The trivial update, for structures that only support point updates.
+impl<M: Monoid> Action<M> for () {
+    fn apply(_update: &(), aggregate: &M::Value, _len: usize) -> M::Value {
+        aggregate.clone()
+    }
+}
+
+/// Adds a value to every element.
+pub struct AddAction<T>(PhantomData<T>);
+
+/// Assigns a value to every element. `None` leaves the elements unchanged.
+pub struct AssignAction<T>(PhantomData<T>);
+
+/// Replaces every element `x` by `a * x + b`, for an update `(a, b)`.
+pub struct AffineAction<T>(PhantomData<T>);
+
+/// Converts the length of a range into the value type, to scale updates by it.
+trait FromLen {
+    fn from_len(len: usize) -> Self;
+}
+
+macro_rules! impl_from_len {
+    (integers: $($int:ty),*; floats: $($float:ty),*) => {
+        $(
+            impl FromLen for $int {
+                fn from_len(len: usize) -> $int {
+                    <$int>::try_from(len).unwrap_or_else(|_| {
+                        panic!("a range of {len} elements does not fit in {}", stringify!($int))
+                    })
+                }
+            }
+        )*
+        $(
+            impl FromLen for $float {
+                fn from_len(len: usize) -> $float {
+                    len as $float
+                }
+            }
+        )*
+    };
+}
+
+impl_from_len! {
+    integers: i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize;
+    floats: f32, f64
+}
+
+macro_rules! impl_actions {
+    ($($t:ty: $zero:expr, $one:expr;)*) => {
+        $(
+            impl Monoid for AddAction<$t> {
+                type Value = $t;
+
+                fn identity() -> $t {
+                    $zero
+                }
+
+                fn combine(first: &$t, then: &$t) -> $t {
+                    first + then
+                }
+            }
+
+            impl Action<Sum<$t>> for AddAction<$t> {
+                fn apply(update: &$t, aggregate: &$t, len: usize) -> $t {
+                    aggregate + update * <$t>::from_len(len)
+                }
+            }
+
+            // An empty range has no elements to add to, and its aggregate is the identity.
+            impl Action<Min<$t>> for AddAction<$t> {
+                fn apply(update: &$t, aggregate: &$t, len: usize) -> $t {
+                    if len == 0 {
+                        *aggregate
+                    } else {
+                        aggregate + update
+                    }
+                }
+            }
+
+            impl Action<Max<$t>> for AddAction<$t> {
+                fn apply(update: &$t, aggregate: &$t, len: usize) -> $t {
+                    if len == 0 {
+                        *aggregate
+                    } else {
+                        aggregate + update
+                    }
+                }
+            }
+
+            impl Monoid for AssignAction<$t> {
+                type Value = Option<$t>;
+
+                fn identity() -> Option<$t> {
+                    None
+                }
+
+                fn combine(first: &Option<$t>, then: &Option<$t>) -> Option<$t> {
+                    then.or(*first)
+                }
+            }
+
+            impl Action<Sum<$t>> for AssignAction<$t> {
+                fn apply(update: &Option<$t>, aggregate: &$t, len: usize) -> $t {
+                    update.map_or(*aggregate, |value| value * <$t>::from_len(len))
+                }
+            }
+
+            impl Action<Min<$t>> for AssignAction<$t> {
+                fn apply(update: &Option<$t>, aggregate: &$t, _len: usize) -> $t {
+                    update.unwrap_or(*aggregate)
+                }
+            }
+
+            impl Action<Max<$t>> for AssignAction<$t> {
+                fn apply(update: &Option<$t>, aggregate: &$t, _len: usize) -> $t {
+                    update.unwrap_or(*aggregate)
+                }
+            }
+
+            impl Monoid for AffineAction<$t> {
+                type Value = ($t, $t);
+
+                fn identity() -> ($t, $t) {
+                    ($one, $zero)
+                }
+
+                fn combine(first: &($t, $t), then: &($t, $t)) -> ($t, $t) {
+                    (then.0 * first.0, then.0 * first.1 + then.1)
+                }
+            }
+
+            impl Action<Sum<$t>> for AffineAction<$t> {
+                fn apply(update: &($t, $t), aggregate: &$t, len: usize) -> $t {
+                    update.0 * aggregate + update.1 * <$t>::from_len(len)
+                }
+            }
+        )*
+    };
+}
+
+impl_actions! {
+    i8: 0, 1;
+    i16: 0, 1;
+    i32: 0, 1;
+    i64: 0, 1;
+    i128: 0, 1;
+    isize: 0, 1;
+    u8: 0, 1;
+    u16: 0, 1;
+    u32: 0, 1;
+    u64: 0, 1;
+    u128: 0, 1;
+    usize: 0, 1;
+    f32: 0.0, 1.0;
+    f64: 0.0, 1.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold<M: Monoid>(values: &[M::Value]) -> M::Value {
+        values
+            .iter()
+            .fold(M::identity(), |acc, value| M::combine(&acc, value))
+    }
+
+    #[test]
+    fn common_monoids() {
+        assert_eq!(fold::<Sum<i32>>(&[3, -1, 4]), 6);
+        assert_eq!(fold::<Min<i32>>(&[3, -1, 4]), -1);
+        assert_eq!(fold::<Max<u8>>(&[3, 1, 4]), 4);
+        assert_eq!(fold::<Min<f64>>(&[]), f64::INFINITY);
+        assert_eq!(fold::<Sum<f64>>(&[0.5, 0.25]), 0.75);
+    }
+
+    #[test]
+    fn actions_compose_in_order() {
+        let update = AffineAction::<i32>::combine(&(2, 1), &(3, -4));
+        assert_eq!(update, (6, -1));
+        assert_eq!(
+            <AffineAction<i32> as Action<Sum<i32>>>::apply(&update, &10, 2),
+            58
+        );
+        let assign = AssignAction::<i32>::combine(&Some(1), &None);
+        assert_eq!(
+            <AssignAction<i32> as Action<Min<i32>>>::apply(&assign, &5, 3),
+            1
+        );
+        assert_eq!(<AddAction<i32> as Action<Sum<i32>>>::apply(&4, &1, 3), 13);
+    }
+
+    #[test]
+    fn adding_to_an_empty_range_keeps_the_identity() {
+        assert_eq!(
+            <AddAction<i32> as Action<Min<i32>>>::apply(&1, &i32::MAX, 0),
+            i32::MAX
+        );
+        assert_eq!(
+            <AddAction<u8> as Action<Max<u8>>>::apply(&1, &u8::MIN, 0),
+            u8::MIN
+        );
+        // Elements equal to the identity are still updated
+        assert_eq!(
+            <AddAction<u8> as Action<Max<u8>>>::apply(&3, &u8::MIN, 2),
+            3
+        );
+        assert_eq!(
+            <AddAction<i32> as Action<Min<i32>>>::apply(&-1, &i32::MAX, 2),
+            i32::MAX - 1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a range of 300 elements does not fit in i8")]
+    fn range_length_must_fit_the_value_type() {
+        <AddAction<i8> as Action<Sum<i8>>>::apply(&0, &0, 300);
+    }
+}
@@ -0,0 +1,439 @@
+//! A segment tree that is generic over the `Monoid` it aggregates and the `Action` its
+//! range updates apply, with lazy propagation of the updates.
+//!
+//! Choosing the monoid and the action picks the operations:
+//! * `MonoidSegmentTree<Sum<i64>>` only supports point updates and range sums,
+//! * `MonoidSegmentTree<Min<i64>, AddAction<i64>>` adds a value to a range and finds minimums,
+//! * `MonoidSegmentTree<Sum<i64>, AffineAction<i64>>` maps a range through `x -> a * x + b`.
+
+use super::{Action, Monoid};
+use std::ops::Range;
+
+/// A segment tree over the monoid `M`, whose range updates come from the action `A`.
+///
+/// Every operation takes `O(log(n))` time. Updates to a range are stored at the `O(log(n))`
+/// nodes that cover it and pushed down to the children only when a later operation needs
+/// to look inside those nodes.
+pub struct MonoidSegmentTree<M: Monoid, A: Action<M> = ()> {
+    len: usize,
+    tree: Vec<M::Value>,
+    /// The update pending for the children of each node, already applied to the node.
+    lazy: Vec<A::Value>,
+}
+
+impl<M: Monoid, A: Action<M>> MonoidSegmentTree<M, A> {
+    /// Creates a tree of `len` identity elements.
+    pub fn new(len: usize) -> Self {
+        Self::from_vec(&vec![M::identity(); len])
+    }
+
+    /// Creates a tree holding `values`.
+    pub fn from_vec(values: &[M::Value]) -> Self {
+        let len = values.len();
+        let mut tree = MonoidSegmentTree {
+            len,
+            tree: vec![M::identity(); 4 * len],
+            lazy: vec![A::identity(); 4 * len],
+        };
+        if len != 0 {
+            tree.build(values, 1, 0..len);
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn get(&mut self, index: usize) -> M::Value {
+        self.query(index..index + 1)
+    }
+
+    /// Replaces the element at `index` by `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn set(&mut self, index: usize, value: M::Value) {
+        assert!(index < self.len, "index out of bounds");
+        self.set_recursive(1, 0..self.len, index, value);
+    }
+
+    /// Combines the elements in `range`, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or ends past the tree.
+    pub fn query(&mut self, range: Range<usize>) -> M::Value {
+        self.check_range(&range);
+        if range.is_empty() {
+            return M::identity();
+        }
+        self.query_recursive(1, 0..self.len, &range)
+    }
+
+    /// Applies `update` to every element in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or ends past the tree.
+    pub fn apply(&mut self, range: Range<usize>, update: &A::Value) {
+        self.check_range(&range);
+        if !range.is_empty() {
+            self.apply_recursive(1, 0..self.len, &range, update);
+        }
+    }
+
+    /// Returns the largest `end` such that `predicate(query(start..end))` holds.
+    ///
+    /// `predicate` must hold for the identity and be monotone: once it fails for a range,
+    /// it fails for every longer range with the same start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start > len`.
+    pub fn max_right<P: Fn(&M::Value) -> bool>(&mut self, start: usize, predicate: P) -> usize {
+        assert!(start <= self.len, "index out of bounds");
+        let mut prefix = M::identity();
+        if start == self.len {
+            return start;
+        }
+        self.max_right_recursive(1, 0..self.len, start, &mut prefix, &predicate)
+            .unwrap_or(self.len)
+    }
+
+    /// Returns the smallest `start` such that `predicate(query(start..end))` holds.
+    ///
+    /// `predicate` must hold for the identity and be monotone: once it fails for a range,
+    /// it fails for every longer range with the same end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `end > len`.
+    pub fn min_left<P: Fn(&M::Value) -> bool>(&mut self, end: usize, predicate: P) -> usize {
+        assert!(end <= self.len, "index out of bounds");
+        let mut suffix = M::identity();
+        if end == 0 {
+            return end;
+        }
+        self.min_left_recursive(1, 0..self.len, end, &mut suffix, &predicate)
+            .unwrap_or(0)
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "range out of bounds"
+        );
+    }
+
+    fn build(&mut self, values: &[M::Value], idx: usize, range: Range<usize>) {
+        if range.end - range.start == 1 {
+            self.tree[idx] = values[range.start].clone();
+        } else {
+            let mid = range.start + (range.end - range.start) / 2;
+            self.build(values, 2 * idx, range.start..mid);
+            self.build(values, 2 * idx + 1, mid..range.end);
+            self.pull(idx);
+        }
+    }
+
+    fn pull(&mut self, idx: usize) {
+        self.tree[idx] = M::combine(&self.tree[2 * idx], &self.tree[2 * idx + 1]);
+    }
+
+    /// Applies `update` to the node `idx`, which covers `len` elements.
+    fn apply_node(&mut self, idx: usize, len: usize, update: &A::Value) {
+        self.tree[idx] = A::apply(update, &self.tree[idx], len);
+        if len > 1 {
+            self.lazy[idx] = A::combine(&self.lazy[idx], update);
+        }
+    }
+
+    /// Pushes the pending update of the node `idx` down to its children.
+    fn push(&mut self, idx: usize, range: &Range<usize>) {
+        let update = std::mem::replace(&mut self.lazy[idx], A::identity());
+        let mid = range.start + (range.end - range.start) / 2;
+        self.apply_node(2 * idx, mid - range.start, &update);
+        self.apply_node(2 * idx + 1, range.end - mid, &update);
+    }
+
+    fn set_recursive(&mut self, idx: usize, range: Range<usize>, index: usize, value: M::Value) {
+        if range.end - range.start == 1 {
+            self.tree[idx] = value;
+            return;
+        }
+        self.push(idx, &range);
+        let mid = range.start + (range.end - range.start) / 2;
+        if index < mid {
+            self.set_recursive(2 * idx, range.start..mid, index, value);
+        } else {
+            self.set_recursive(2 * idx + 1, mid..range.end, index, value);
+        }
+        self.pull(idx);
+    }
+
+    fn query_recursive(
+        &mut self,
+        idx: usize,
+        range: Range<usize>,
+        target: &Range<usize>,
+    ) -> M::Value {
+        if target.start <= range.start && range.end <= target.end {
+            return self.tree[idx].clone();
+        }
+        self.push(idx, &range);
+        let mid = range.start + (range.end - range.start) / 2;
+        let mut result = M::identity();
+        if target.start < mid {
+            result = self.query_recursive(2 * idx, range.start..mid, target);
+        }
+        if mid < target.end {
+            let right = self.query_recursive(2 * idx + 1, mid..range.end, target);
+            result = M::combine(&result, &right);
+        }
+        result
+    }
+
+    fn apply_recursive(
+        &mut self,
+        idx: usize,
+        range: Range<usize>,
+        target: &Range<usize>,
+        update: &A::Value,
+    ) {
+        if target.start <= range.start && range.end <= target.end {
+            self.apply_node(idx, range.end - range.start, update);
+            return;
+        }
+        self.push(idx, &range);
+        let mid = range.start + (range.end - range.start) / 2;
+        if target.start < mid {
+            self.apply_recursive(2 * idx, range.start..mid, target, update);
+        }
+        if mid < target.end {
+            self.apply_recursive(2 * idx + 1, mid..range.end, target, update);
+        }
+        self.pull(idx);
+    }
+
+    /// Extends `prefix` with the nodes from `start` on for as long as `predicate` holds, and
+    /// returns the index of the first element that makes it fail.
+    fn max_right_recursive<P: Fn(&M::Value) -> bool>(
+        &mut self,
+        idx: usize,
+        range: Range<usize>,
+        start: usize,
+        prefix: &mut M::Value,
+        predicate: &P,
+    ) -> Option<usize> {
+        if range.end <= start {
+            return None;
+        }
+        if start <= range.start {
+            let extended = M::combine(prefix, &self.tree[idx]);
+            if predicate(&extended) {
+                *prefix = extended;
+                return None;
+            }
+            if range.end - range.start == 1 {
+                return Some(range.start);
+            }
+        }
+        self.push(idx, &range);
+        let mid = range.start + (range.end - range.start) / 2;
+        self.max_right_recursive(2 * idx, range.start..mid, start, prefix, predicate)
+            .or_else(|| {
+                self.max_right_recursive(2 * idx + 1, mid..range.end, start, prefix, predicate)
+            })
+    }
+
+    /// Extends `suffix` with the nodes before `end` for as long as `predicate` holds, and
+    /// returns the index after the first element that makes it fail.
+    fn min_left_recursive<P: Fn(&M::Value) -> bool>(
+        &mut self,
+        idx: usize,
+        range: Range<usize>,
+        end: usize,
+        suffix: &mut M::Value,
+        predicate: &P,
+    ) -> Option<usize> {
+        if end <= range.start {
+            return None;
+        }
+        if range.end <= end {
+            let extended = M::combine(&self.tree[idx], suffix);
+            if predicate(&extended) {
+                *suffix = extended;
+                return None;
+            }
+            if range.end - range.start == 1 {
+                return Some(range.end);
+            }
+        }
+        self.push(idx, &range);
+        let mid = range.start + (range.end - range.start) / 2;
+        self.min_left_recursive(2 * idx + 1, mid..range.end, end, suffix, predicate)
+            .or_else(|| self.min_left_recursive(2 * idx, range.start..mid, end, suffix, predicate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MonoidSegmentTree;
+    use crate::data_structures::{AddAction, AffineAction, AssignAction, Max, Min, Sum};
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn point_updates() {
+        let mut tree = MonoidSegmentTree::<Sum<i32>>::from_vec(&[1, 2, 3, 4, 5]);
+        assert_eq!(tree.query(0..5), 15);
+        tree.set(2, 10);
+        assert_eq!(tree.query(1..4), 16);
+        assert_eq!(tree.get(2), 10);
+        assert_eq!(tree.query(3..3), 0);
+        assert!(MonoidSegmentTree::<Sum<i32>>::new(0).is_empty());
+    }
+
+    #[test]
+    fn range_add_and_assign() {
+        let mut tree = MonoidSegmentTree::<Min<i32>, AddAction<i32>>::from_vec(&[5, 3, 8, 1, 9]);
+        tree.apply(0..3, &-4);
+        assert_eq!(tree.query(0..3), -1);
+        assert_eq!(tree.query(2..5), 1);
+        tree.apply(3..4, &10);
+        assert_eq!(tree.query(0..5), -1);
+        assert_eq!(tree.query(3..5), 9);
+
+        // Elements equal to the identity of the monoid are updated like any other
+        let mut tree = MonoidSegmentTree::<Max<u8>, AddAction<u8>>::from_vec(&[0, 0, 0]);
+        tree.apply(0..3, &3);
+        assert_eq!(tree.query(0..3), 3);
+        let mut tree = MonoidSegmentTree::<Min<i32>, AddAction<i32>>::new(5);
+        tree.apply(1..4, &-1);
+        assert_eq!(tree.query(0..5), i32::MAX - 1);
+        assert_eq!(tree.get(0), i32::MAX);
+
+        let mut tree = MonoidSegmentTree::<Max<u8>, AssignAction<u8>>::new(6);
+        tree.apply(1..5, &Some(7));
+        tree.apply(2..3, &Some(1));
+        assert_eq!(tree.query(0..6), 7);
+        assert_eq!(tree.query(2..3), 1);
+        assert_eq!(tree.get(0), 0);
+
+        let mut tree = MonoidSegmentTree::<Sum<i64>, AssignAction<i64>>::new(4);
+        tree.apply(0..4, &Some(3));
+        tree.apply(1..3, &None);
+        assert_eq!(tree.query(0..4), 12);
+    }
+
+    #[test]
+    fn binary_search() {
+        let mut tree = MonoidSegmentTree::<Sum<u32>>::from_vec(&[3, 1, 4, 1, 5, 9, 2, 6]);
+        assert_eq!(tree.max_right(0, |&sum| sum <= 8), 3);
+        assert_eq!(tree.max_right(2, |&sum| sum <= 10), 5);
+        assert_eq!(tree.max_right(0, |&sum| sum < 100), 8);
+        assert_eq!(tree.max_right(8, |_| false), 8);
+        assert_eq!(tree.min_left(8, |&sum| sum <= 8), 6);
+        assert_eq!(tree.min_left(5, |&sum| sum < 6), 4);
+        assert_eq!(tree.min_left(8, |&sum| sum < 100), 0);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Affine(usize, usize, i64, i64),
+        Set(usize, i64),
+        Query(usize, usize),
+        MaxRight(usize, i64),
+        MinLeft(usize, i64),
+    }
+
+    impl Arbitrary for Op {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let (a, b) = (usize::arbitrary(g), usize::arbitrary(g));
+            // Small coefficients keep repeated updates from overflowing.
+            let small = |g: &mut Gen| i64::from(i8::arbitrary(g));
+            match u8::arbitrary(g) % 5 {
+                0 => Op::Affine(a, b, small(g) % 2, small(g)),
+                1 => Op::Set(a, small(g)),
+                2 => Op::Query(a, b),
+                3 => Op::MaxRight(a, small(g)),
+                _ => Op::MinLeft(a, small(g)),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn matches_vec_model(initial: Vec<i8>, ops: Vec<Op>) -> bool {
+        let mut model: Vec<i64> = initial.into_iter().map(i64::from).collect();
+        let mut sums = MonoidSegmentTree::<Sum<i64>, AffineAction<i64>>::from_vec(&model);
+        let mut maxes = MonoidSegmentTree::<Max<i64>, AddAction<i64>>::from_vec(&model);
+        // The maximum tree only sees the additive part of the updates.
+        let mut shifted = model.clone();
+        let len = model.len();
+        let range = |a: usize, b: usize| {
+            let (a, b) = (a % (len + 1), b % (len + 1));
+            a.min(b)..a.max(b)
+        };
+        let max_of = |values: &[i64]| values.iter().copied().max().unwrap_or(i64::MIN);
+        for op in ops {
+            match op {
+                Op::Affine(a, b, mul, add) => {
+                    sums.apply(range(a, b), &(mul, add));
+                    maxes.apply(range(a, b), &add);
+                    for (value, max) in model[range(a, b)].iter_mut().zip(&mut shifted[range(a, b)])
+                    {
+                        *value = mul * *value + add;
+                        *max += add;
+                    }
+                }
+                Op::Set(index, value) if len > 0 => {
+                    sums.set(index % len, value);
+                    maxes.set(index % len, value);
+                    model[index % len] = value;
+                    shifted[index % len] = value;
+                }
+                Op::Set(..) => {}
+                Op::Query(a, b) => {
+                    if sums.query(range(a, b)) != model[range(a, b)].iter().sum::<i64>()
+                        || maxes.query(range(a, b)) != max_of(&shifted[range(a, b)])
+                    {
+                        return false;
+                    }
+                }
+                Op::MaxRight(start, bound) => {
+                    let start = start % (len + 1);
+                    let expected = (start..=len)
+                        .take_while(|&end| max_of(&shifted[start..end]) <= bound)
+                        .last()
+                        .unwrap();
+                    if maxes.max_right(start, |&max| max <= bound) != expected {
+                        return false;
+                    }
+                }
+                Op::MinLeft(end, bound) => {
+                    let end = end % (len + 1);
+                    let expected = (0..=end)
+                        .rev()
+                        .take_while(|&start| max_of(&shifted[start..end]) <= bound)
+                        .last()
+                        .unwrap();
+                    if maxes.min_left(end, |&max| max <= bound) != expected {
+                        return false;
+                    }
+                }
+            }
+        }
+        (0..len).all(|i| sums.get(i) == model[i] && maxes.get(i) == shifted[i])
+    }
+}
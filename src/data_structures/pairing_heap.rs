@@ -0,0 +1,300 @@
+//! A pairing heap.
+//!
+//! A pairing heap is a heap-ordered tree with any number of children per node. Two heaps are
+//! melded by making the root with the larger priority a child of the other root, which is all
+//! `push`, `meld` and `decrease_key` need. The work is deferred to `pop`: the children of the
+//! removed root are melded in pairs from left to right, then the pairs are melded from right to
+//! left. This takes `O(log(n))` amortized time, while the other operations take `O(1)`.
+//!
+//! Wikipedia: <https://en.wikipedia.org/wiki/Pairing_heap>
+
+use std::cell::{Cell, Ref, RefCell};
+use std::rc::{Rc, Weak};
+
+use super::DecreaseKeyHeap;
+
+type Link<K, P> = Rc<Node<K, P>>;
+
+struct Node<K, P> {
+    /// The entry of the node, taken out when the node is popped.
+    entry: RefCell<Option<(K, P)>>,
+    children: RefCell<Vec<Link<K, P>>>,
+    parent: RefCell<Weak<Node<K, P>>>,
+    /// The index of the node in the `children` of its parent, to cut it off in `O(1)`.
+    index_in_parent: Cell<usize>,
+}
+
+impl<K, P: Ord> Node<K, P> {
+    /// Checks if the node may be the parent of `other`.
+    fn precedes(&self, other: &Self) -> bool {
+        match (&*self.entry.borrow(), &*other.entry.borrow()) {
+            (Some((_, priority)), Some((_, other_priority))) => priority <= other_priority,
+            _ => unreachable!("nodes in the heap have an entry"),
+        }
+    }
+}
+
+/// A min-heap of `(key, priority)` entries supporting `O(1)` `meld` and `decrease_key`.
+///
+/// `push` returns a `PairingHeapHandle`, which `decrease_key` takes to find the entry again.
+/// A handle must only be used with the heap it was returned by, or with a heap this one has
+/// been melded into.
+pub struct PairingHeap<K, P> {
+    root: Option<Link<K, P>>,
+    len: usize,
+}
+
+/// A handle to an entry of a `PairingHeap`.
+pub struct PairingHeapHandle<K, P>(Link<K, P>);
+
+impl<K, P> Clone for PairingHeapHandle<K, P> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<K, P: Ord> PairingHeap<K, P> {
+    /// Creates a new, empty heap.
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    /// Returns the number of entries in the heap.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entry with the smallest priority, without removing it.
+    pub fn peek(&self) -> Option<Ref<'_, (K, P)>> {
+        let root = self.root.as_ref()?;
+        Some(Ref::map(root.entry.borrow(), |entry| {
+            entry.as_ref().expect("nodes in the heap have an entry")
+        }))
+    }
+
+    /// Adds an entry to the heap and returns its handle.
+    pub fn push(&mut self, key: K, priority: P) -> PairingHeapHandle<K, P> {
+        let node = Rc::new(Node {
+            entry: RefCell::new(Some((key, priority))),
+            children: RefCell::new(Vec::new()),
+            parent: RefCell::new(Weak::new()),
+            index_in_parent: Cell::new(0),
+        });
+        self.root = Some(match self.root.take() {
+            Some(root) => link(root, Rc::clone(&node)),
+            None => Rc::clone(&node),
+        });
+        self.len += 1;
+        PairingHeapHandle(node)
+    }
+
+    /// Removes and returns the entry with the smallest priority.
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        let root = self.root.take()?;
+        self.len -= 1;
+        let children = root.children.take();
+        for child in &children {
+            child.parent.replace(Weak::new());
+        }
+        let mut pairs = Vec::with_capacity(children.len() / 2 + 1);
+        let mut children = children.into_iter();
+        while let Some(first) = children.next() {
+            pairs.push(match children.next() {
+                Some(second) => link(first, second),
+                None => first,
+            });
+        }
+        self.root = pairs.into_iter().rev().reduce(link);
+        root.entry.take()
+    }
+
+    /// Lowers the priority of the entry behind `handle`.
+    ///
+    /// # Returns
+    /// `false`, leaving the heap untouched, if `priority` is not lower than the current one or
+    /// if the entry has already been popped.
+    pub fn decrease_key(&mut self, handle: &PairingHeapHandle<K, P>, priority: P) -> bool {
+        let node = &handle.0;
+        match node.entry.borrow_mut().as_mut() {
+            Some((_, current)) if priority < *current => *current = priority,
+            _ => return false,
+        }
+        // The subtree of the node stays heap-ordered, only the link to its parent may be broken.
+        if cut(node) {
+            let root = self
+                .root
+                .take()
+                .expect("a node with a parent is below the root");
+            self.root = Some(link(root, Rc::clone(node)));
+        }
+        true
+    }
+
+    /// Moves all the entries of `other` into this heap.
+    pub fn meld(&mut self, mut other: Self) {
+        self.root = match (self.root.take(), other.root.take()) {
+            (Some(root), Some(other_root)) => Some(link(root, other_root)),
+            (root, other_root) => root.or(other_root),
+        };
+        self.len += std::mem::take(&mut other.len);
+    }
+}
+
+/// Makes the root with the larger priority a child of the other one, and returns the new root.
+fn link<K, P: Ord>(a: Link<K, P>, b: Link<K, P>) -> Link<K, P> {
+    let (parent, child) = if a.precedes(&b) { (a, b) } else { (b, a) };
+    child.parent.replace(Rc::downgrade(&parent));
+    let mut children = parent.children.borrow_mut();
+    child.index_in_parent.set(children.len());
+    children.push(child);
+    drop(children);
+    parent
+}
+
+/// Detaches a node from its parent, returning `false` if it had none.
+fn cut<K, P>(node: &Link<K, P>) -> bool {
+    let Some(parent) = node.parent.replace(Weak::new()).upgrade() else {
+        return false;
+    };
+    let mut siblings = parent.children.borrow_mut();
+    let idx = node.index_in_parent.get();
+    siblings.swap_remove(idx);
+    if let Some(moved) = siblings.get(idx) {
+        moved.index_in_parent.set(idx);
+    }
+    true
+}
+
+impl<K, P: Ord> Default for PairingHeap<K, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Drops the nodes one at a time, since the tree can be too deep for a recursive drop.
+impl<K, P> Drop for PairingHeap<K, P> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Link<K, P>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            stack.append(&mut node.children.take());
+        }
+    }
+}
+
+impl<K, P: Ord> DecreaseKeyHeap<K, P> for PairingHeap<K, P> {
+    type Handle = PairingHeapHandle<K, P>;
+
+    fn push(&mut self, key: K, priority: P) -> Self::Handle {
+        PairingHeap::push(self, key, priority)
+    }
+
+    fn pop(&mut self) -> Option<(K, P)> {
+        PairingHeap::pop(self)
+    }
+
+    fn decrease_key(&mut self, handle: &Self::Handle, priority: P) -> bool {
+        PairingHeap::decrease_key(self, handle, priority)
+    }
+
+    fn len(&self) -> usize {
+        PairingHeap::len(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PairingHeap;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_push_pop_and_decrease_key() {
+        let mut heap = PairingHeap::new();
+        assert_eq!(heap.pop(), None);
+        let a = heap.push('a', 5);
+        let b = heap.push('b', 3);
+        heap.push('c', 8);
+        assert_eq!(heap.peek().map(|entry| *entry), Some(('b', 3)));
+
+        assert!(heap.decrease_key(&a, 1));
+        assert!(!heap.decrease_key(&a, 2));
+        assert_eq!(heap.pop(), Some(('a', 1)));
+        assert!(!heap.decrease_key(&a, 0));
+        assert_eq!(heap.pop(), Some(('b', 3)));
+        assert!(!heap.decrease_key(&b, 0));
+        assert_eq!(heap.len(), 1);
+    }
+
+    #[test]
+    fn test_meld() {
+        let mut evens = PairingHeap::new();
+        let mut odds = PairingHeap::new();
+        for i in 0..50 {
+            evens.push((), 2 * i);
+            odds.push((), 2 * i + 1);
+        }
+        let last = odds.push((), 1_000);
+        evens.meld(odds);
+        assert_eq!(evens.len(), 101);
+        // handles stay valid in the melded heap
+        assert!(evens.decrease_key(&last, -1));
+        let popped: Vec<i32> = std::iter::from_fn(|| evens.pop().map(|(_, p)| p)).collect();
+        assert_eq!(popped, (-1..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_deep_heap_drops() {
+        let mut heap = PairingHeap::new();
+        for i in (0..200_000).rev() {
+            heap.push((), i);
+        }
+        assert_eq!(heap.len(), 200_000);
+    }
+
+    /// Applies random operations to the heap and to a list of the live priorities.
+    #[quickcheck]
+    fn prop_matches_sorted_model(ops: Vec<(u8, usize, i16)>) -> bool {
+        let mut heap = PairingHeap::new();
+        let mut handles = Vec::new();
+        // the priority of every pushed entry, `None` once popped
+        let mut model: Vec<Option<i16>> = Vec::new();
+        for (op, target, priority) in ops {
+            match op % 4 {
+                0 | 1 => {
+                    handles.push(heap.push(model.len(), priority));
+                    model.push(Some(priority));
+                }
+                2 if !handles.is_empty() => {
+                    let id = target % handles.len();
+                    let lower = model[id].is_some_and(|p| priority < p);
+                    if heap.decrease_key(&handles[id], priority) != lower {
+                        return false;
+                    }
+                    if lower {
+                        model[id] = Some(priority);
+                    }
+                }
+                _ => {
+                    let expected = model.iter().flatten().min().copied();
+                    match heap.pop() {
+                        Some((id, priority)) => {
+                            if Some(priority) != expected || model[id].take() != Some(priority) {
+                                return false;
+                            }
+                        }
+                        None => {
+                            if expected.is_some() {
+                                return false;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        heap.len() == model.iter().flatten().count()
+    }
+}
@@ -0,0 +1,325 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+type Link<K, V> = Option<Rc<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    priority: u64,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K: Clone, V: Clone> Node<K, V> {
+    /// Returns a copy of this node with new children.
+    fn with_children(&self, left: Link<K, V>, right: Link<K, V>) -> Rc<Node<K, V>> {
+        Rc::new(Node {
+            key: self.key.clone(),
+            value: self.value.clone(),
+            priority: self.priority,
+            left,
+            right,
+        })
+    }
+}
+
+/// A persistent ordered map, based on a Treap.
+///
+/// Updates copy only the nodes on the path from the root to the changed key, so `insert`
+/// and `remove` take `O(log(n))` expected time and memory, and the old map stays valid.
+pub struct PersistentMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+    pub fn new() -> Self {
+        PersistentMap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the value stored for `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            current = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+            };
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns a new map in which `key` maps to `value`.
+    pub fn insert(&self, key: K, value: V) -> Self {
+        if self.contains_key(&key) {
+            return PersistentMap {
+                root: replace(&self.root, key, value),
+                len: self.len,
+            };
+        }
+        let node = Node {
+            key,
+            value,
+            priority: rand::random(),
+            left: None,
+            right: None,
+        };
+        PersistentMap {
+            root: Some(insert(&self.root, node)),
+            len: self.len + 1,
+        }
+    }
+
+    /// Returns a new map without `key`.
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match remove(&self.root, key) {
+            Some(root) => PersistentMap {
+                root,
+                len: self.len - 1,
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns an iterator over the entries in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+}
+
+/// Copies the path to the existing `key` and stores `value` there.
+fn replace<K: Ord + Clone, V: Clone>(tree: &Link<K, V>, key: K, value: V) -> Link<K, V> {
+    let node = tree.as_ref()?;
+    match key.cmp(&node.key) {
+        Ordering::Less => {
+            Some(node.with_children(replace(&node.left, key, value), node.right.clone()))
+        }
+        Ordering::Greater => {
+            Some(node.with_children(node.left.clone(), replace(&node.right, key, value)))
+        }
+        Ordering::Equal => Some(Rc::new(Node {
+            key,
+            value,
+            priority: node.priority,
+            left: node.left.clone(),
+            right: node.right.clone(),
+        })),
+    }
+}
+
+/// Inserts a node whose key is not in the tree yet.
+fn insert<K: Ord + Clone, V: Clone>(tree: &Link<K, V>, mut new: Node<K, V>) -> Rc<Node<K, V>> {
+    match tree {
+        Some(node) if node.priority >= new.priority => {
+            if new.key < node.key {
+                let left = insert(&node.left, new);
+                node.with_children(Some(left), node.right.clone())
+            } else {
+                let right = insert(&node.right, new);
+                node.with_children(node.left.clone(), Some(right))
+            }
+        }
+        _ => {
+            let (left, right) = split(tree, &new.key);
+            new.left = left;
+            new.right = right;
+            Rc::new(new)
+        }
+    }
+}
+
+/// Splits a tree into the keys smaller than `key` and the keys larger than it.
+fn split<K: Ord + Clone, V: Clone>(tree: &Link<K, V>, key: &K) -> (Link<K, V>, Link<K, V>) {
+    match tree {
+        None => (None, None),
+        Some(node) if node.key < *key => {
+            let (middle, right) = split(&node.right, key);
+            (Some(node.with_children(node.left.clone(), middle)), right)
+        }
+        Some(node) => {
+            let (left, middle) = split(&node.left, key);
+            (left, Some(node.with_children(middle, node.right.clone())))
+        }
+    }
+}
+
+/// Concatenates two trees whose keys are all smaller in `left` than in `right`.
+fn merge<K: Clone, V: Clone>(left: &Link<K, V>, right: &Link<K, V>) -> Link<K, V> {
+    match (left, right) {
+        (None, tree) | (tree, None) => tree.clone(),
+        (Some(l), Some(r)) => {
+            if l.priority > r.priority {
+                Some(l.with_children(l.left.clone(), merge(&l.right, right)))
+            } else {
+                Some(r.with_children(merge(left, &r.left), r.right.clone()))
+            }
+        }
+    }
+}
+
+/// Copies the path to `key` without it, or returns `None` if the key is not in the tree.
+fn remove<K, V, Q>(tree: &Link<K, V>, key: &Q) -> Option<Link<K, V>>
+where
+    K: Ord + Clone + Borrow<Q>,
+    V: Clone,
+    Q: Ord + ?Sized,
+{
+    let node = tree.as_ref()?;
+    match key.cmp(node.key.borrow()) {
+        Ordering::Less => {
+            let left = remove(&node.left, key)?;
+            Some(Some(node.with_children(left, node.right.clone())))
+        }
+        Ordering::Greater => {
+            let right = remove(&node.right, key)?;
+            Some(Some(node.with_children(node.left.clone(), right)))
+        }
+        Ordering::Equal => Some(merge(&node.left, &node.right)),
+    }
+}
+
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> Self {
+        PersistentMap {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for PersistentMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> FromIterator<(K, V)> for PersistentMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentMap::new(), |map, (key, value)| {
+                map.insert(key, value)
+            })
+    }
+}
+
+/// An iterator over the entries of a `PersistentMap` in key order.
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut node: Option<&'a Node<K, V>>) {
+        while let Some(n) = node {
+            self.stack.push(n);
+            node = n.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentMap;
+    use quickcheck_macros::quickcheck;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn old_versions_survive_updates() {
+        let empty = PersistentMap::new();
+        let v1 = empty.insert("b", 2).insert("a", 1);
+        let v2 = v1.insert("c", 3).insert("a", 10);
+        let v3 = v2.remove("b");
+        assert!(empty.is_empty());
+        assert_eq!(v1.iter().collect::<Vec<_>>(), vec![(&"a", &1), (&"b", &2)]);
+        assert_eq!(v2.get("a"), Some(&10));
+        assert_eq!(v2.len(), 3);
+        assert!(!v3.contains_key("b"));
+        assert!(v2.contains_key("b"));
+        assert_eq!(v3.first(), Some((&"a", &10)));
+        assert_eq!(v3.last(), Some((&"c", &3)));
+        assert_eq!(v3.remove("z").len(), 2);
+    }
+
+    /// Applies random edits to random earlier versions and compares all versions against
+    /// `BTreeMap`s.
+    #[quickcheck]
+    fn versions_match_btree_map(ops: Vec<(usize, bool, u8, u32)>) -> bool {
+        let mut versions = vec![PersistentMap::new()];
+        let mut models = vec![BTreeMap::new()];
+        for (base, is_insert, key, value) in ops {
+            let base = base % versions.len();
+            let mut model = models[base].clone();
+            let next = if is_insert {
+                model.insert(key, value);
+                versions[base].insert(key, value)
+            } else {
+                model.remove(&key);
+                versions[base].remove(&key)
+            };
+            versions.push(next);
+            models.push(model);
+        }
+        versions.iter().zip(&models).all(|(version, model)| {
+            version.len() == model.len()
+                && version.iter().eq(model.iter())
+                && (0..=u8::MAX).all(|key| version.get(&key) == model.get(&key))
+        })
+    }
+}
@@ -0,0 +1,19 @@
+//! Persistent data structures never change once built. Every update returns a new version
+//! and leaves the old one valid, sharing all unchanged nodes between versions through
+//! reference counting ("path copying"), so an update only allocates the nodes on the path
+//! it touches.
+//!
+//! Cloning any of these structures is `O(1)`, which makes them a good fit for undo
+//! histories, backtracking searches and snapshots.
+
+mod map;
+mod segment_tree;
+mod stack;
+mod union_find;
+mod vector;
+
+pub use self::map::{Iter as PersistentMapIter, PersistentMap};
+pub use self::segment_tree::PersistentSegmentTree;
+pub use self::stack::{Iter as PersistentStackIter, PersistentStack};
+pub use self::union_find::PersistentUnionFind;
+pub use self::vector::{Iter as PersistentVectorIter, PersistentVector};
@@ -0,0 +1,233 @@
+use crate::data_structures::Monoid;
+use std::ops::Range;
+use std::rc::Rc;
+
+type Link<V> = Rc<Node<V>>;
+
+struct Node<V> {
+    value: V,
+    /// Both children, or none for a leaf.
+    children: Option<(Link<V>, Link<V>)>,
+}
+
+/// A persistent segment tree over the monoid `M`.
+///
+/// `set` copies the `O(log(n))` nodes on the path to the changed leaf and returns a new
+/// version, so every earlier version can still answer range queries. Keeping the version
+/// after each prefix of updates answers questions about the array "as of" a point in
+/// time, such as counting values below a bound in a range of positions.
+pub struct PersistentSegmentTree<M: Monoid> {
+    len: usize,
+    root: Option<Rc<Node<M::Value>>>,
+}
+
+impl<M: Monoid> PersistentSegmentTree<M> {
+    /// Creates a tree of `len` identity elements.
+    pub fn new(len: usize) -> Self {
+        Self::from_vec(&vec![M::identity(); len])
+    }
+
+    /// Creates a tree holding `values`.
+    pub fn from_vec(values: &[M::Value]) -> Self {
+        PersistentSegmentTree {
+            len: values.len(),
+            root: (!values.is_empty()).then(|| build::<M>(values)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at `index`.
+    pub fn get(&self, index: usize) -> Option<&M::Value> {
+        if index >= self.len {
+            return None;
+        }
+        let mut node = self.root.as_ref()?;
+        let mut range = 0..self.len;
+        while let Some((left, right)) = &node.children {
+            let mid = range.start + (range.end - range.start) / 2;
+            if index < mid {
+                node = left;
+                range.end = mid;
+            } else {
+                node = right;
+                range.start = mid;
+            }
+        }
+        Some(&node.value)
+    }
+
+    /// Returns a new version with the element at `index` replaced by `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn set(&self, index: usize, value: M::Value) -> Self {
+        assert!(index < self.len, "index out of bounds");
+        let root = self.root.as_ref().unwrap();
+        PersistentSegmentTree {
+            len: self.len,
+            root: Some(set::<M>(root, 0..self.len, index, value)),
+        }
+    }
+
+    /// Combines the elements in `range`, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or ends past the tree.
+    pub fn query(&self, range: Range<usize>) -> M::Value {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "range out of bounds"
+        );
+        match &self.root {
+            Some(root) if !range.is_empty() => query::<M>(root, 0..self.len, &range),
+            _ => M::identity(),
+        }
+    }
+}
+
+fn build<M: Monoid>(values: &[M::Value]) -> Rc<Node<M::Value>> {
+    if let [value] = values {
+        return Rc::new(Node {
+            value: value.clone(),
+            children: None,
+        });
+    }
+    let (left, right) = values.split_at(values.len() / 2);
+    join::<M>(build::<M>(left), build::<M>(right))
+}
+
+fn join<M: Monoid>(left: Rc<Node<M::Value>>, right: Rc<Node<M::Value>>) -> Rc<Node<M::Value>> {
+    Rc::new(Node {
+        value: M::combine(&left.value, &right.value),
+        children: Some((left, right)),
+    })
+}
+
+fn set<M: Monoid>(
+    node: &Node<M::Value>,
+    range: Range<usize>,
+    index: usize,
+    value: M::Value,
+) -> Rc<Node<M::Value>> {
+    let Some((left, right)) = &node.children else {
+        return Rc::new(Node {
+            value,
+            children: None,
+        });
+    };
+    let mid = range.start + (range.end - range.start) / 2;
+    if index < mid {
+        join::<M>(
+            set::<M>(left, range.start..mid, index, value),
+            Rc::clone(right),
+        )
+    } else {
+        join::<M>(
+            Rc::clone(left),
+            set::<M>(right, mid..range.end, index, value),
+        )
+    }
+}
+
+fn query<M: Monoid>(node: &Node<M::Value>, range: Range<usize>, target: &Range<usize>) -> M::Value {
+    if target.start <= range.start && range.end <= target.end {
+        return node.value.clone();
+    }
+    let Some((left, right)) = &node.children else {
+        return M::identity();
+    };
+    let mid = range.start + (range.end - range.start) / 2;
+    let mut result = M::identity();
+    if target.start < mid {
+        result = query::<M>(left, range.start..mid, target);
+    }
+    if mid < target.end {
+        result = M::combine(&result, &query::<M>(right, mid..range.end, target));
+    }
+    result
+}
+
+impl<M: Monoid> Clone for PersistentSegmentTree<M> {
+    fn clone(&self) -> Self {
+        PersistentSegmentTree {
+            len: self.len,
+            root: self.root.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentSegmentTree;
+    use crate::data_structures::{Max, Sum};
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn versioned_queries() {
+        let v0 = PersistentSegmentTree::<Max<i32>>::from_vec(&[1, 5, 2, 4]);
+        let v1 = v0.set(1, 0);
+        let v2 = v1.set(3, 9);
+        assert_eq!(v0.query(0..4), 5);
+        assert_eq!(v1.query(0..4), 4);
+        assert_eq!(v2.query(0..4), 9);
+        assert_eq!(v2.query(0..3), 2);
+        assert_eq!(v2.query(2..2), i32::MIN);
+        assert_eq!(v0.get(1), Some(&5));
+        assert_eq!(v2.get(4), None);
+        assert!(PersistentSegmentTree::<Max<i32>>::new(0).is_empty());
+    }
+
+    /// Counts the values in `values[range]` that are below a bound, using one version per
+    /// prefix of the values sorted by size.
+    #[test]
+    fn count_smaller_in_range() {
+        let values = [5, 1, 4, 1, 3, 9, 2, 6];
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by_key(|&i| values[i]);
+        let mut versions = vec![PersistentSegmentTree::<Sum<u32>>::new(values.len())];
+        for &i in &order {
+            let next = versions.last().unwrap().set(i, 1);
+            versions.push(next);
+        }
+        let count_below = |range: std::ops::Range<usize>, bound: i32| {
+            let taken = order.iter().filter(|&&i| values[i] < bound).count();
+            versions[taken].query(range)
+        };
+        assert_eq!(count_below(0..8, 4), 4);
+        assert_eq!(count_below(2..6, 4), 2);
+        assert_eq!(count_below(5..6, 10), 1);
+    }
+
+    #[quickcheck]
+    fn versions_match_vec_model(initial: Vec<i32>, ops: Vec<(usize, usize, i32)>) -> bool {
+        let initial: Vec<i64> = initial.into_iter().map(i64::from).collect();
+        let mut versions = vec![PersistentSegmentTree::<Sum<i64>>::from_vec(&initial)];
+        let mut models = vec![initial];
+        for (base, index, value) in ops {
+            let base = base % versions.len();
+            if models[base].is_empty() {
+                continue;
+            }
+            let mut model = models[base].clone();
+            let (index, value) = (index % model.len(), i64::from(value));
+            model[index] = value;
+            versions.push(versions[base].set(index, value));
+            models.push(model);
+        }
+        versions.iter().zip(&models).all(|(version, model)| {
+            (0..=model.len()).all(|start| {
+                (start..=model.len())
+                    .all(|end| version.query(start..end) == model[start..end].iter().sum::<i64>())
+            })
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+/// A persistent stack, implemented as a singly linked list whose tails are shared.
+///
+/// `push` and `pop` return a new stack in `O(1)` time and leave `self` unchanged.
+pub struct PersistentStack<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+impl<T> PersistentStack<T> {
+    pub fn new() -> Self {
+        PersistentStack { head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Returns the element on top of the stack.
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    /// Returns a new stack with `elem` on top of this one.
+    pub fn push(&self, elem: T) -> Self {
+        PersistentStack {
+            head: Some(Rc::new(Node {
+                elem,
+                next: self.head.clone(),
+            })),
+            len: self.len + 1,
+        }
+    }
+
+    /// Returns the stack below the top element, or `None` if the stack is empty.
+    pub fn pop(&self) -> Option<Self> {
+        self.head.as_ref().map(|node| PersistentStack {
+            head: node.next.clone(),
+            len: self.len - 1,
+        })
+    }
+
+    /// Returns an iterator from the top of the stack to the bottom.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T> Clone for PersistentStack<T> {
+    fn clone(&self) -> Self {
+        PersistentStack {
+            head: self.head.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for PersistentStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for PersistentStack<T> {
+    /// Frees the nodes that no other stack shares iteratively, since dropping a long
+    /// list recursively would overflow the call stack.
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => head = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// Builds a stack whose top is the last element of the iterator.
+impl<T> FromIterator<T> for PersistentStack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentStack::new(), |stack, elem| stack.push(elem))
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentStack;
+
+    #[test]
+    fn versions_share_tails() {
+        let empty = PersistentStack::new();
+        let one = empty.push(1);
+        let two = one.push(2);
+        let other = one.push(3);
+        assert!(empty.is_empty());
+        assert_eq!(two.iter().copied().collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), vec![3, 1]);
+        assert_eq!(two.peek(), Some(&2));
+        let popped = two.pop().unwrap();
+        assert_eq!(popped.len(), 1);
+        assert_eq!(popped.peek(), Some(&1));
+        assert_eq!(one.len(), 1);
+        assert!(empty.pop().is_none());
+    }
+
+    #[test]
+    fn long_stack_drops() {
+        let stack: PersistentStack<u32> = (0..1_000_000).collect();
+        let shared = stack.pop().unwrap();
+        drop(stack);
+        assert_eq!(shared.peek(), Some(&999_998));
+        assert_eq!(shared.len(), 999_999);
+    }
+}
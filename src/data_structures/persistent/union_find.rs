@@ -0,0 +1,140 @@
+use super::PersistentVector;
+
+/// A persistent Union-Find structure over the elements `0..n`.
+///
+/// The parent links and set sizes are kept in `PersistentVector`s, so `union` returns a new
+/// version in `O(log(n))` vector updates and leaves `self` unchanged. Path compression would
+/// have to copy the whole path on every `find`, so only union by size is used: trees have a
+/// height of `O(log(n))`, and `find` takes `O(log(n) * log32(n))` time.
+#[derive(Clone)]
+pub struct PersistentUnionFind {
+    parent: PersistentVector<usize>,
+    size: PersistentVector<usize>,
+    count: usize,
+}
+
+impl PersistentUnionFind {
+    /// Creates `n` disjoint sets, one for each element of `0..n`.
+    pub fn new(n: usize) -> Self {
+        PersistentUnionFind {
+            parent: (0..n).collect(),
+            size: std::iter::repeat_n(1, n).collect(),
+            count: n,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Returns the number of disjoint sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the root of the set containing `element`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `element >= len`.
+    pub fn find(&self, mut element: usize) -> usize {
+        loop {
+            let parent = *self.parent.get(element).expect("element out of bounds");
+            if parent == element {
+                return element;
+            }
+            element = parent;
+        }
+    }
+
+    /// Checks if two elements belong to the same set.
+    pub fn is_same_set(&self, first_elem: usize, sec_elem: usize) -> bool {
+        self.find(first_elem) == self.find(sec_elem)
+    }
+
+    /// Returns the number of elements in the set containing `element`.
+    pub fn size(&self, element: usize) -> usize {
+        self.root_size(self.find(element))
+    }
+
+    /// Returns a new version in which the sets containing the two elements are merged.
+    pub fn union(&self, first_elem: usize, sec_elem: usize) -> Self {
+        let mut first_root = self.find(first_elem);
+        let mut sec_root = self.find(sec_elem);
+        if first_root == sec_root {
+            return self.clone();
+        }
+        if self.root_size(first_root) < self.root_size(sec_root) {
+            std::mem::swap(&mut first_root, &mut sec_root);
+        }
+        PersistentUnionFind {
+            parent: self.parent.set(sec_root, first_root),
+            size: self.size.set(
+                first_root,
+                self.root_size(first_root) + self.root_size(sec_root),
+            ),
+            count: self.count - 1,
+        }
+    }
+
+    fn root_size(&self, root: usize) -> usize {
+        *self.size.get(root).expect("element out of bounds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentUnionFind;
+
+    #[test]
+    fn test_versions() {
+        let empty = PersistentUnionFind::new(0);
+        assert!(empty.is_empty());
+
+        let v0 = PersistentUnionFind::new(6);
+        let v1 = v0.union(0, 1);
+        let v2 = v1.union(2, 3).union(1, 3);
+        let v3 = v1.union(4, 5);
+
+        assert_eq!(v0.count(), 6);
+        assert!(!v0.is_same_set(0, 1));
+        assert_eq!(v0.size(0), 1);
+
+        assert!(v1.is_same_set(0, 1));
+        assert!(!v1.is_same_set(0, 3));
+        assert_eq!(v1.count(), 5);
+
+        assert!(v2.is_same_set(0, 2));
+        assert_eq!(v2.size(3), 4);
+        assert!(!v2.is_same_set(4, 5));
+        assert_eq!(v2.count(), 3);
+
+        // v3 branches off v1, it doesn't see the unions of v2
+        assert!(v3.is_same_set(4, 5));
+        assert!(!v3.is_same_set(0, 3));
+        assert_eq!(v3.size(1), 2);
+        assert_eq!(v3.union(0, 1).count(), v3.count());
+    }
+
+    #[test]
+    fn test_many_versions() {
+        let n = 200;
+        let mut versions = vec![PersistentUnionFind::new(n)];
+        for i in 1..n {
+            let next = versions[i - 1].union(i - 1, i);
+            versions.push(next);
+        }
+        for (i, version) in versions.iter().enumerate() {
+            assert_eq!(version.count(), n - i);
+            assert_eq!(version.size(0), i + 1);
+            assert!(version.is_same_set(0, i));
+            if i + 1 < n {
+                assert!(!version.is_same_set(0, i + 1));
+            }
+        }
+    }
+}
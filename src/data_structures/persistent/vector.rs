@@ -0,0 +1,370 @@
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+const BITS: u32 = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+enum Node<T> {
+    Branch(Vec<Rc<Node<T>>>),
+    Leaf(Vec<T>),
+}
+
+impl<T> Node<T> {
+    fn children(&self) -> &[Rc<Node<T>>] {
+        match self {
+            Node::Branch(children) => children,
+            Node::Leaf(_) => unreachable!("leaves only appear at level 0"),
+        }
+    }
+
+    fn elems(&self) -> &[T] {
+        match self {
+            Node::Leaf(elems) => elems,
+            Node::Branch(_) => unreachable!("branches only appear above level 0"),
+        }
+    }
+}
+
+/// A persistent vector, implemented as a 32-way trie of its indices.
+///
+/// Each level of the trie consumes 5 bits of an index, so `get`, `set` and `pop` take
+/// `O(log32(n))` time, which is at most 7 levels for any realistic length. As in Clojure's
+/// vectors, the last (up to) 32 elements live in a separate tail, which makes `push` copy
+/// only the tail most of the time.
+pub struct PersistentVector<T> {
+    len: usize,
+    /// The number of index bits consumed above the leaves, a multiple of `BITS`.
+    shift: u32,
+    root: Rc<Node<T>>,
+    tail: Rc<[T]>,
+}
+
+impl<T: Clone> PersistentVector<T> {
+    pub fn new() -> Self {
+        PersistentVector {
+            len: 0,
+            shift: BITS,
+            root: Rc::new(Node::Branch(Vec::new())),
+            tail: Rc::from(Vec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at `index`.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        self.chunk_for(index).get(index & MASK)
+    }
+
+    /// Returns the last element.
+    pub fn last(&self) -> Option<&T> {
+        self.tail.last()
+    }
+
+    /// Returns a new vector with `elem` appended.
+    pub fn push(&self, elem: T) -> Self {
+        if self.tail.len() < WIDTH {
+            let mut tail = self.tail.to_vec();
+            tail.push(elem);
+            return PersistentVector {
+                len: self.len + 1,
+                shift: self.shift,
+                root: Rc::clone(&self.root),
+                tail: Rc::from(tail),
+            };
+        }
+        // The tail is full: move it into the trie and start a new one.
+        let leaf = Rc::new(Node::Leaf(self.tail.to_vec()));
+        let (root, shift) = if (self.len >> BITS) > (1 << self.shift) {
+            let path = new_path(self.shift, leaf);
+            (
+                Rc::new(Node::Branch(vec![Rc::clone(&self.root), path])),
+                self.shift + BITS,
+            )
+        } else {
+            (self.push_tail(self.shift, &self.root, leaf), self.shift)
+        };
+        PersistentVector {
+            len: self.len + 1,
+            shift,
+            root,
+            tail: Rc::from(vec![elem]),
+        }
+    }
+
+    /// Returns a new vector without the last element, or `None` if the vector is empty.
+    pub fn pop(&self) -> Option<Self> {
+        match self.len {
+            0 => return None,
+            1 => return Some(Self::new()),
+            _ => {}
+        }
+        if self.tail.len() > 1 {
+            let mut tail = self.tail.to_vec();
+            tail.pop();
+            return Some(PersistentVector {
+                len: self.len - 1,
+                shift: self.shift,
+                root: Rc::clone(&self.root),
+                tail: Rc::from(tail),
+            });
+        }
+        // The tail becomes empty: the last leaf of the trie becomes the new tail.
+        let tail = self.chunk_for(self.len - 2).to_vec();
+        let mut root = self
+            .pop_tail(self.shift, &self.root)
+            .unwrap_or_else(|| Rc::new(Node::Branch(Vec::new())));
+        let mut shift = self.shift;
+        if shift > BITS && root.children().len() == 1 {
+            root = Rc::clone(&root.children()[0]);
+            shift -= BITS;
+        }
+        Some(PersistentVector {
+            len: self.len - 1,
+            shift,
+            root,
+            tail: Rc::from(tail),
+        })
+    }
+
+    /// Returns a new vector with the element at `index` replaced by `elem`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn set(&self, index: usize, elem: T) -> Self {
+        assert!(index < self.len, "index out of bounds");
+        let mut result = self.clone();
+        if index >= self.tail_offset() {
+            let mut tail = self.tail.to_vec();
+            tail[index & MASK] = elem;
+            result.tail = Rc::from(tail);
+        } else {
+            result.root = set_in(self.shift, &self.root, index, elem);
+        }
+        result
+    }
+
+    /// Returns an iterator over the elements in order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vector: self,
+            index: 0,
+            chunk: &[],
+        }
+    }
+
+    /// Returns the index of the first element in the tail.
+    fn tail_offset(&self) -> usize {
+        self.len - self.tail.len()
+    }
+
+    /// Returns the leaf or tail that holds `index`.
+    fn chunk_for(&self, index: usize) -> &[T] {
+        if index >= self.tail_offset() {
+            return &self.tail;
+        }
+        let mut node = &self.root;
+        let mut level = self.shift;
+        while level > 0 {
+            node = &node.children()[(index >> level) & MASK];
+            level -= BITS;
+        }
+        node.elems()
+    }
+
+    /// Copies the path to the slot after the last leaf, and puts `leaf` there.
+    fn push_tail(&self, level: u32, parent: &Node<T>, leaf: Rc<Node<T>>) -> Rc<Node<T>> {
+        let index = ((self.len - 1) >> level) & MASK;
+        let mut children = parent.children().to_vec();
+        if level == BITS {
+            children.push(leaf);
+        } else if let Some(child) = children.get(index) {
+            children[index] = self.push_tail(level - BITS, child, leaf);
+        } else {
+            children.push(new_path(level - BITS, leaf));
+        }
+        Rc::new(Node::Branch(children))
+    }
+
+    /// Copies the path to the last leaf without that leaf, or returns `None` if nothing
+    /// would be left of `node`.
+    fn pop_tail(&self, level: u32, node: &Node<T>) -> Option<Rc<Node<T>>> {
+        let index = ((self.len - 2) >> level) & MASK;
+        let mut children = node.children().to_vec();
+        if level > BITS {
+            match self.pop_tail(level - BITS, &children[index]) {
+                Some(child) => children[index] = child,
+                None if index == 0 => return None,
+                None => children.truncate(index),
+            }
+        } else if index == 0 {
+            return None;
+        } else {
+            children.truncate(index);
+        }
+        Some(Rc::new(Node::Branch(children)))
+    }
+}
+
+/// Wraps `node` in branches up to `level`.
+fn new_path<T>(level: u32, node: Rc<Node<T>>) -> Rc<Node<T>> {
+    if level == 0 {
+        node
+    } else {
+        Rc::new(Node::Branch(vec![new_path(level - BITS, node)]))
+    }
+}
+
+/// Copies the path to `index` below `node`, with the element replaced by `elem`.
+fn set_in<T: Clone>(level: u32, node: &Node<T>, index: usize, elem: T) -> Rc<Node<T>> {
+    let slot = (index >> level) & MASK;
+    match node {
+        Node::Leaf(elems) => {
+            let mut elems = elems.clone();
+            elems[slot] = elem;
+            Rc::new(Node::Leaf(elems))
+        }
+        Node::Branch(children) => {
+            let mut children = children.clone();
+            children[slot] = set_in(level - BITS, &children[slot], index, elem);
+            Rc::new(Node::Branch(children))
+        }
+    }
+}
+
+impl<T> Clone for PersistentVector<T> {
+    fn clone(&self) -> Self {
+        PersistentVector {
+            len: self.len,
+            shift: self.shift,
+            root: Rc::clone(&self.root),
+            tail: Rc::clone(&self.tail),
+        }
+    }
+}
+
+impl<T: Clone> Default for PersistentVector<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> FromIterator<T> for PersistentVector<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentVector::new(), |vector, elem| vector.push(elem))
+    }
+}
+
+/// An iterator over a `PersistentVector`, which looks up each leaf only once.
+pub struct Iter<'a, T> {
+    vector: &'a PersistentVector<T>,
+    index: usize,
+    /// The rest of the current leaf.
+    chunk: &'a [T],
+}
+
+impl<'a, T: Clone> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.chunk.is_empty() {
+            if self.index >= self.vector.len {
+                return None;
+            }
+            self.chunk = self.vector.chunk_for(self.index);
+        }
+        let (first, rest) = self.chunk.split_first()?;
+        self.chunk = rest;
+        self.index += 1;
+        Some(first)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.vector.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentVector;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn grows_through_several_levels() {
+        let n = 40_000;
+        let vector: PersistentVector<usize> = (0..n).collect();
+        assert_eq!(vector.len(), n);
+        assert!((0..n).all(|i| vector.get(i) == Some(&i)));
+        assert_eq!(vector.get(n), None);
+        assert!(vector.iter().copied().eq(0..n));
+
+        let updated = vector.set(1234, 0).set(n - 1, 0);
+        assert_eq!(updated.get(1234), Some(&0));
+        assert_eq!(updated.last(), Some(&0));
+        assert_eq!(vector.get(1234), Some(&1234));
+
+        let mut shrinking = vector.clone();
+        for expected in (0..n).rev() {
+            assert_eq!(shrinking.last(), Some(&expected));
+            shrinking = shrinking.pop().unwrap();
+        }
+        assert!(shrinking.is_empty());
+        assert!(shrinking.pop().is_none());
+        assert_eq!(vector.len(), n);
+    }
+
+    /// Applies random edits to random earlier versions and compares all versions against
+    /// plain vectors.
+    #[quickcheck]
+    fn versions_match_vec_model(ops: Vec<(usize, u8, u16)>) -> bool {
+        let mut versions = vec![PersistentVector::new()];
+        let mut models: Vec<Vec<u16>> = vec![Vec::new()];
+        for (base, op, value) in ops {
+            let base = base % versions.len();
+            let (version, mut model) = (&versions[base], models[base].clone());
+            let next = match op % 4 {
+                0 if !model.is_empty() => {
+                    model.pop();
+                    version.pop().unwrap()
+                }
+                1 if !model.is_empty() => {
+                    let index = usize::from(value) % model.len();
+                    model[index] = value;
+                    version.set(index, value)
+                }
+                // Pushing whole leaves reaches the deeper levels of the trie.
+                2 => {
+                    let mut next = version.clone();
+                    for i in 0..200 {
+                        next = next.push(i);
+                        model.push(i);
+                    }
+                    next
+                }
+                _ => {
+                    model.push(value);
+                    version.push(value)
+                }
+            };
+            versions.push(next);
+            models.push(model);
+        }
+        versions
+            .iter()
+            .zip(&models)
+            .all(|(version, model)| version.len() == model.len() && version.iter().eq(model))
+    }
+}
@@ -1,5 +1,7 @@
-use std::collections::hash_map::{DefaultHasher, RandomState};
-use std::hash::{BuildHasher, Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use super::stable_hash;
 
 /// A Bloom Filter <https://en.wikipedia.org/wiki/Bloom_filter> is a probabilistic data structure testing whether an element belongs to a set or not
 /// Therefore, its contract looks very close to the one of a set, for example a `HashSet`
@@ -103,7 +105,38 @@ impl<T: Hash> BloomFilter<T> for SingleBinaryBloomFilter {
 pub struct MultiBinaryBloomFilter {
     filter_size: usize,
     bytes: Vec<u8>,
-    hash_builders: Vec<RandomState>,
+    hash_count: usize,
+}
+
+/// Computing `hash_count` completely independent hashes for every item would be quite costly.
+/// It turns out a Bloom Filter works just as well with two hashes `h1` and `h2`, the `i`-th index being `h1 + i * h2`
+/// (see "Less Hashing, Same Performance" by Kirsch and Mitzenmacher).
+/// Two filters with the same dimensions set the same bits for the same items, which is what allows merging them,
+/// or sending one to another process.
+fn indexes<T: Hash>(
+    item: &T,
+    filter_size: usize,
+    hash_count: usize,
+) -> impl Iterator<Item = usize> {
+    let h1 = stable_hash(0, item);
+    let h2 = stable_hash(1, item) | 1; // an odd step, so that the indexes don't all collapse onto `h1`
+    (0..hash_count as u64)
+        .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % filter_size as u64) as usize)
+}
+
+/// Check Wikipedia for these formulae
+fn optimal_dimensions(
+    estimated_count_of_items: usize,
+    max_false_positive_probability: f64,
+) -> (usize, usize) {
+    let optimal_filter_size = (-(estimated_count_of_items as f64)
+        * max_false_positive_probability.ln()
+        / (2.0_f64.ln().powi(2)))
+    .ceil() as usize;
+    let optimal_hash_count = ((optimal_filter_size as f64 / estimated_count_of_items as f64)
+        * 2.0_f64.ln())
+    .ceil() as usize;
+    (optimal_filter_size, optimal_hash_count)
 }
 
 impl MultiBinaryBloomFilter {
@@ -112,7 +145,7 @@ impl MultiBinaryBloomFilter {
         Self {
             filter_size,
             bytes: vec![0; bytes_count],
-            hash_builders: vec![RandomState::new(); hash_count],
+            hash_count,
         }
     }
 
@@ -120,38 +153,89 @@ impl MultiBinaryBloomFilter {
         estimated_count_of_items: usize,
         max_false_positive_probability: f64,
     ) -> Self {
-        // Check Wikipedia for these formulae
-        let optimal_filter_size = (-(estimated_count_of_items as f64)
-            * max_false_positive_probability.ln()
-            / (2.0_f64.ln().powi(2)))
-        .ceil() as usize;
-        let optimal_hash_count = ((optimal_filter_size as f64 / estimated_count_of_items as f64)
-            * 2.0_f64.ln())
-        .ceil() as usize;
-        Self::with_dimensions(optimal_filter_size, optimal_hash_count)
+        let (filter_size, hash_count) =
+            optimal_dimensions(estimated_count_of_items, max_false_positive_probability);
+        Self::with_dimensions(filter_size, hash_count)
+    }
+
+    /// A lookup for an absent item is a false positive if all of its `hash_count` bits happen to be set.
+    /// Rather than assuming how many items were inserted, we can measure which fraction of the bits is set right now.
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let set_bits: u32 = self.bytes.iter().map(|byte| byte.count_ones()).sum();
+        (f64::from(set_bits) / self.filter_size as f64).powf(self.hash_count as f64)
+    }
+
+    /// Since the same item sets the same bits in both filters, OR-ing the bits gives exactly the filter
+    /// we would have obtained by inserting the items of both filters into a single one.
+    pub fn union(&mut self, other: &Self) -> Result<(), FilterError> {
+        self.check_dimensions(other)?;
+        for (byte, other_byte) in self.bytes.iter_mut().zip(&other.bytes) {
+            *byte |= other_byte;
+        }
+        Ok(())
+    }
+
+    /// AND-ing the bits keeps every item present in both filters, so there still are no false negatives.
+    /// There may be more false positives than with a filter built from the actual intersection though:
+    /// a bit can be set by an item of the first filter, and by a different item of the second one.
+    pub fn intersection(&mut self, other: &Self) -> Result<(), FilterError> {
+        self.check_dimensions(other)?;
+        for (byte, other_byte) in self.bytes.iter_mut().zip(&other.bytes) {
+            *byte &= other_byte;
+        }
+        Ok(())
+    }
+
+    fn check_dimensions(&self, other: &Self) -> Result<(), FilterError> {
+        if self.filter_size == other.filter_size && self.hash_count == other.hash_count {
+            Ok(())
+        } else {
+            Err(FilterError::DimensionMismatch)
+        }
+    }
+
+    /// Serializes the filter as: the magic bytes `BLM`, the format version byte, `filter_size` and `hash_count` (as little-endian `u64`),
+    /// then the bits.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BLOOM_MAGIC.len() + 17 + self.bytes.len());
+        bytes.extend_from_slice(BLOOM_MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&(self.filter_size as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.hash_count as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.bytes);
+        bytes
+    }
+
+    /// Reads back a filter written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FilterError> {
+        let rest = read_header(bytes, BLOOM_MAGIC)?;
+        let (filter_size, rest) = read_u64(rest)?;
+        let (hash_count, rest) = read_u64(rest)?;
+        // Check the header against the actual payload before allocating anything
+        if filter_size == 0 || hash_count > MAX_HASH_COUNT || rest.len() != filter_size.div_ceil(8)
+        {
+            return Err(FilterError::InvalidFormat);
+        }
+        Ok(Self {
+            filter_size,
+            bytes: rest.to_vec(),
+            hash_count,
+        })
     }
 }
 
 impl<Item: Hash> BloomFilter<Item> for MultiBinaryBloomFilter {
     fn insert(&mut self, item: Item) {
-        for builder in &self.hash_builders {
-            let mut hasher = builder.build_hasher();
-            item.hash(&mut hasher);
-            let hash = builder.hash_one(&item);
-            let index = hash % self.filter_size as u64;
-            let byte_index = index as usize / 8; // this is this byte that we need to modify
+        for index in indexes(&item, self.filter_size, self.hash_count) {
+            let byte_index = index / 8; // this is this byte that we need to modify
             let bit_index = (index % 8) as u8; // we cannot only OR with value 1 this time, since we have 8 bits
             self.bytes[byte_index] |= 1 << bit_index;
         }
     }
 
     fn contains(&self, item: &Item) -> bool {
-        for builder in &self.hash_builders {
-            let mut hasher = builder.build_hasher();
-            item.hash(&mut hasher);
-            let hash = builder.hash_one(item);
-            let index = hash % self.filter_size as u64;
-            let byte_index = index as usize / 8; // this is this byte that we need to modify
+        for index in indexes(item, self.filter_size, self.hash_count) {
+            let byte_index = index / 8; // this is this byte that we need to check
             let bit_index = (index % 8) as u8; // we cannot only OR with value 1 this time, since we have 8 bits
             if self.bytes[byte_index] & (1 << bit_index) == 0 {
                 return false;
@@ -161,10 +245,144 @@ impl<Item: Hash> BloomFilter<Item> for MultiBinaryBloomFilter {
     }
 }
 
+/// One thing a Bloom Filter cannot do is removing an item: resetting its bits to `0` could also remove other items sharing one of these bits.
+/// A Counting Bloom Filter fixes this by replacing every bit with a small counter:
+/// inserting an item increments its counters, removing it decrements them, and an item may be present if all its counters are non-zero.
+///
+/// The price to pay is memory: here every counter is a `u8`, so the filter is 8 times larger than a `MultiBinaryBloomFilter` of the same size.
+/// A counter that reaches `u8::MAX` is never decremented again, since we don't know how many insertions it lost track of.
+/// It's extremely unlikely with decent dimensions, and only costs a few false positives, never a false negative.
+pub struct CountingBloomFilter {
+    counters: Vec<u8>,
+    hash_count: usize,
+}
+
+impl CountingBloomFilter {
+    pub fn with_dimensions(filter_size: usize, hash_count: usize) -> Self {
+        Self {
+            counters: vec![0; filter_size],
+            hash_count,
+        }
+    }
+
+    pub fn from_estimate(
+        estimated_count_of_items: usize,
+        max_false_positive_probability: f64,
+    ) -> Self {
+        let (filter_size, hash_count) =
+            optimal_dimensions(estimated_count_of_items, max_false_positive_probability);
+        Self::with_dimensions(filter_size, hash_count)
+    }
+
+    /// Removes an item, returning `false` (and leaving the filter untouched) if it is definitely absent.
+    /// Only remove items that were actually inserted: removing a false positive decrements counters belonging to other items,
+    /// which may then be reported as absent.
+    pub fn remove<Item: Hash>(&mut self, item: &Item) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+        for index in indexes(item, self.counters.len(), self.hash_count) {
+            if self.counters[index] != u8::MAX {
+                self.counters[index] -= 1;
+            }
+        }
+        true
+    }
+
+    /// Same as for `MultiBinaryBloomFilter`, with non-zero counters playing the role of set bits.
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let non_zero = self.counters.iter().filter(|&&counter| counter > 0).count();
+        (non_zero as f64 / self.counters.len() as f64).powf(self.hash_count as f64)
+    }
+
+    /// Serializes the filter as: the magic bytes `CBF`, the format version byte, the number of counters and `hash_count`
+    /// (as little-endian `u64`), then the counters.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(COUNTING_MAGIC.len() + 17 + self.counters.len());
+        bytes.extend_from_slice(COUNTING_MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&(self.counters.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.hash_count as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.counters);
+        bytes
+    }
+
+    /// Reads back a filter written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FilterError> {
+        let rest = read_header(bytes, COUNTING_MAGIC)?;
+        let (filter_size, rest) = read_u64(rest)?;
+        let (hash_count, rest) = read_u64(rest)?;
+        if filter_size == 0 || hash_count > MAX_HASH_COUNT || rest.len() != filter_size {
+            return Err(FilterError::InvalidFormat);
+        }
+        Ok(Self {
+            counters: rest.to_vec(),
+            hash_count,
+        })
+    }
+}
+
+impl<Item: Hash> BloomFilter<Item> for CountingBloomFilter {
+    fn insert(&mut self, item: Item) {
+        for index in indexes(&item, self.counters.len(), self.hash_count) {
+            self.counters[index] = self.counters[index].saturating_add(1);
+        }
+    }
+
+    fn contains(&self, item: &Item) -> bool {
+        indexes(item, self.counters.len(), self.hash_count).all(|index| self.counters[index] > 0)
+    }
+}
+
+/// Errors that can occur when combining, filling or deserializing filters
+#[derive(Debug, PartialEq, Eq)]
+pub enum FilterError {
+    /// The filters don't have the same size and number of hash functions, so their bits don't mean the same thing
+    DimensionMismatch,
+    /// A cuckoo filter could not make room for a new item
+    Full,
+    /// The bytes don't hold a filter written by `to_bytes`
+    InvalidFormat,
+    /// The filter was written by `to_bytes` with another format version, whose bits don't mean the same thing
+    UnsupportedVersion(u8),
+}
+
+const BLOOM_MAGIC: &[u8; 3] = b"BLM";
+const COUNTING_MAGIC: &[u8; 3] = b"CBF";
+/// Written after the magic bytes of every filter. It changes whenever the layout or the hashing of items does:
+/// version 1 hashed items with `DefaultHasher`, version 2 with `stable_hash`.
+pub(super) const FORMAT_VERSION: u8 = 2;
+/// Every lookup computes `hash_count` indexes, so a deserialized filter asking for more than this is rejected.
+/// `from_estimate` stays far below it, even for the smallest positive false positive probability.
+const MAX_HASH_COUNT: usize = 1 << 16;
+
+/// Checks the magic bytes and the format version at the start of `bytes`, returning the remaining bytes.
+pub(super) fn read_header<'a>(bytes: &'a [u8], magic: &[u8; 3]) -> Result<&'a [u8], FilterError> {
+    let rest = bytes
+        .strip_prefix(magic.as_slice())
+        .ok_or(FilterError::InvalidFormat)?;
+    match rest.split_first() {
+        Some((&FORMAT_VERSION, rest)) => Ok(rest),
+        Some((&version, _)) => Err(FilterError::UnsupportedVersion(version)),
+        None => Err(FilterError::InvalidFormat),
+    }
+}
+
+/// Reads a little-endian `u64` at the start of `bytes`, returning it along with the remaining bytes.
+pub(super) fn read_u64(bytes: &[u8]) -> Result<(usize, &[u8]), FilterError> {
+    let (value, rest) = bytes
+        .split_first_chunk::<8>()
+        .ok_or(FilterError::InvalidFormat)?;
+    let value =
+        usize::try_from(u64::from_le_bytes(*value)).map_err(|_| FilterError::InvalidFormat)?;
+    Ok((value, rest))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::data_structures::probabilistic::bloom_filter::{
-        BasicBloomFilter, BloomFilter, MultiBinaryBloomFilter, SingleBinaryBloomFilter,
+        BasicBloomFilter, BloomFilter, CountingBloomFilter, FilterError, MultiBinaryBloomFilter,
+        SingleBinaryBloomFilter,
     };
     use quickcheck::{Arbitrary, Gen};
     use quickcheck_macros::quickcheck;
@@ -266,4 +484,200 @@ mod tests {
         let fp_rate = false_positives as f64 / tests as f64;
         assert!(fp_rate < 1.0); // This isn't really a test, but so that you have the `fp_rate` variable to print out, or evaluate
     }
+
+    #[quickcheck]
+    fn a_counting_bloom_filter_forgets_removed_items(TestSet { to_insert, to_test }: TestSet) {
+        let mut counting_filter = CountingBloomFilter::from_estimate(to_insert.len(), 0.01);
+        for item in &to_insert {
+            counting_filter.insert(*item);
+        }
+        let (to_remove, to_keep): (Vec<i32>, Vec<i32>) =
+            to_insert.iter().copied().partition(|item| item % 2 == 0);
+        for item in &to_remove {
+            assert!(counting_filter.remove(item));
+        }
+        for item in &to_keep {
+            assert!(counting_filter.contains(item));
+        }
+        let false_positives = to_remove
+            .iter()
+            .chain(&to_test)
+            .filter(|item| !to_insert.contains(item) || item.rem_euclid(2) == 0)
+            .filter(|item| counting_filter.contains(item))
+            .count();
+        assert!(false_positives <= (to_remove.len() + to_test.len()) / 10);
+    }
+
+    #[test]
+    fn removing_an_absent_item_leaves_the_counting_filter_untouched() {
+        let mut counting_filter = CountingBloomFilter::with_dimensions(1_000, 3);
+        counting_filter.insert("present");
+        assert!(!counting_filter.remove(&"absent"));
+        assert!(counting_filter.contains(&"present"));
+        assert!(counting_filter.remove(&"present"));
+        assert!(!counting_filter.contains(&"present"));
+        assert_eq!(counting_filter.estimated_false_positive_rate(), 0.0);
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let mut evens = MultiBinaryBloomFilter::with_dimensions(10_000, 4);
+        let mut small = MultiBinaryBloomFilter::with_dimensions(10_000, 4);
+        for i in 0..200 {
+            evens.insert(2 * i);
+            small.insert(i);
+        }
+        let mut union = MultiBinaryBloomFilter::from_bytes(&evens.to_bytes()).unwrap();
+        union.union(&small).unwrap();
+        assert!((0..200).all(|i| union.contains(&i) && union.contains(&(2 * i))));
+
+        let mut intersection = MultiBinaryBloomFilter::from_bytes(&evens.to_bytes()).unwrap();
+        intersection.intersection(&small).unwrap();
+        assert!((0..100).all(|i| intersection.contains(&(2 * i))));
+        let odd_false_positives = (0..100)
+            .filter(|i| intersection.contains(&(2 * i + 1)))
+            .count();
+        assert!(odd_false_positives < 5);
+
+        let other_dimensions = MultiBinaryBloomFilter::with_dimensions(10_000, 5);
+        assert_eq!(
+            union.union(&other_dimensions),
+            Err(FilterError::DimensionMismatch)
+        );
+        assert_eq!(
+            union.intersection(&MultiBinaryBloomFilter::with_dimensions(9_999, 4)),
+            Err(FilterError::DimensionMismatch)
+        );
+    }
+
+    #[test]
+    fn false_positive_rate_estimate() {
+        let n = 1_000;
+        let mut binary_filter = MultiBinaryBloomFilter::from_estimate(n, FALSE_POSITIVE_MAX);
+        assert_eq!(binary_filter.estimated_false_positive_rate(), 0.0);
+        for item in 0..n {
+            binary_filter.insert(item);
+        }
+        let estimate = binary_filter.estimated_false_positive_rate();
+        assert!(estimate > FALSE_POSITIVE_MAX / 2.0 && estimate < FALSE_POSITIVE_MAX * 1.5);
+        let measured = (n..11 * n)
+            .filter(|item| binary_filter.contains(item))
+            .count() as f64
+            / (10 * n) as f64;
+        assert!((measured - estimate).abs() < 0.02);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut binary_filter = MultiBinaryBloomFilter::with_dimensions(100, 3);
+        let mut counting_filter = CountingBloomFilter::with_dimensions(100, 3);
+        for word in ["ship", "me", "around"] {
+            binary_filter.insert(word);
+            counting_filter.insert(word);
+        }
+        let binary_bytes = binary_filter.to_bytes();
+        let counting_bytes = counting_filter.to_bytes();
+        let restored_binary = MultiBinaryBloomFilter::from_bytes(&binary_bytes).unwrap();
+        let mut restored_counting = CountingBloomFilter::from_bytes(&counting_bytes).unwrap();
+        assert!(restored_binary.contains(&"me"));
+        assert!(restored_counting.remove(&"me"));
+        assert!(restored_counting.contains(&"ship"));
+        assert_eq!(restored_binary.to_bytes(), binary_bytes);
+
+        // Truncated, wrong kind of filter, or garbage
+        assert_eq!(
+            MultiBinaryBloomFilter::from_bytes(&binary_bytes[..binary_bytes.len() - 1]).err(),
+            Some(FilterError::InvalidFormat)
+        );
+        assert_eq!(
+            MultiBinaryBloomFilter::from_bytes(&counting_bytes).err(),
+            Some(FilterError::InvalidFormat)
+        );
+        assert_eq!(
+            CountingBloomFilter::from_bytes(&[1, 2, 3]).err(),
+            Some(FilterError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn serialized_bytes_are_pinned() {
+        // Any change to these bytes breaks the filters already written: it needs a new format version
+        let mut binary_filter = MultiBinaryBloomFilter::with_dimensions(16, 2);
+        binary_filter.insert("pinned");
+        let binary_bytes = [
+            b'B',
+            b'L',
+            b'M',
+            2,
+            16,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            2,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0b1100_0000,
+            0,
+        ];
+        assert_eq!(binary_filter.to_bytes(), binary_bytes);
+
+        let mut counting_filter = CountingBloomFilter::with_dimensions(6, 2);
+        counting_filter.insert("pinned");
+        counting_filter.insert("bytes");
+        let counting_bytes = [
+            b'C', b'B', b'F', 2, 6, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 1,
+        ];
+        assert_eq!(counting_filter.to_bytes(), counting_bytes);
+
+        // Version 1 hashed items differently
+        let mut version_1 = binary_bytes;
+        version_1[3] = 1;
+        assert_eq!(
+            MultiBinaryBloomFilter::from_bytes(&version_1).err(),
+            Some(FilterError::UnsupportedVersion(1))
+        );
+        assert_eq!(
+            CountingBloomFilter::from_bytes(b"CBF").err(),
+            Some(FilterError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn hostile_headers_are_rejected_before_allocating() {
+        let header = |magic: &[u8], filter_size: u64, hash_count: u64| {
+            let mut bytes = magic.to_vec();
+            bytes.extend_from_slice(&filter_size.to_le_bytes());
+            bytes.extend_from_slice(&hash_count.to_le_bytes());
+            bytes
+        };
+        for bytes in [
+            header(b"BLM\x02", 1 << 60, 3),
+            header(b"BLM\x02", 0, 3),
+            header(b"BLM\x02", u64::MAX, 3),
+            [header(b"BLM\x02", 8, 1 << 40), vec![0]].concat(),
+        ] {
+            assert_eq!(
+                MultiBinaryBloomFilter::from_bytes(&bytes).err(),
+                Some(FilterError::InvalidFormat)
+            );
+        }
+        assert_eq!(
+            CountingBloomFilter::from_bytes(&[header(b"CBF\x02", 1, 1 << 40), vec![0]].concat())
+                .err(),
+            Some(FilterError::InvalidFormat)
+        );
+        assert!(MultiBinaryBloomFilter::from_bytes(
+            &[header(b"BLM\x02", 9, 2), vec![0, 0]].concat()
+        )
+        .is_ok());
+    }
 }
@@ -1,6 +1,8 @@
-use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::hash::{BuildHasher, Hash};
+use std::hash::Hash;
+
+use super::stable_hash;
 
 /// A probabilistic data structure holding an approximate count for diverse items efficiently (using constant space)
 ///
@@ -91,7 +93,7 @@ pub trait CountMinSketch {
 pub struct HashCountMinSketch<Item: Hash, const WIDTH: usize, const DEPTH: usize> {
     phantom: std::marker::PhantomData<Item>, // just a marker for Item to be used
     counts: [[usize; WIDTH]; DEPTH],
-    hashers: [RandomState; DEPTH],
+    conservative: bool,
 }
 
 impl<Item: Hash, const WIDTH: usize, const DEPTH: usize> Debug
@@ -106,12 +108,49 @@ impl<T: Hash, const WIDTH: usize, const DEPTH: usize> Default
     for HashCountMinSketch<T, WIDTH, DEPTH>
 {
     fn default() -> Self {
-        let hashers = std::array::from_fn(|_| RandomState::new());
-
         Self {
             phantom: Default::default(),
             counts: [[0; WIDTH]; DEPTH],
-            hashers,
+            conservative: false,
+        }
+    }
+}
+
+impl<Item: Hash, const WIDTH: usize, const DEPTH: usize> HashCountMinSketch<Item, WIDTH, DEPTH> {
+    /// Can we reduce the overestimation? When incrementing "TEST" by 1 in the example above, its count is going to be the minimum of its counters.
+    /// A counter above that minimum already counts other items, so there is no need to increment it as long as it stays above the new count of "TEST".
+    /// This is a "conservative update": every counter is only raised up to `min + count`.
+    /// Counts still can't be underestimated, but there are a lot less collisions.
+    /// The price to pay is that, like in a Bloom Filter, it no longer makes sense to decrement counts.
+    pub fn conservative() -> Self {
+        Self {
+            conservative: true,
+            ..Self::default()
+        }
+    }
+
+    /// Every row hashes the item with its own index as the seed, so that each row acts as a different hash function.
+    /// Two sketches with the same `WIDTH` and `DEPTH` use the same hash functions, which is what makes it possible to merge them.
+    fn column(row: usize, item: &Item) -> usize {
+        (stable_hash(row as u64, item) % WIDTH as u64) as usize
+    }
+
+    /// Adding the counters of two sketches gives exactly the sketch we would have obtained by counting both streams in a single one.
+    /// This is how counts computed by different shards (or during different time windows) can be combined.
+    pub fn merge(&mut self, other: &Self) {
+        for (row, other_row) in self.counts.iter_mut().zip(&other.counts) {
+            for (count, other_count) in row.iter_mut().zip(other_row) {
+                *count += other_count;
+            }
+        }
+    }
+
+    /// To track "recent" frequencies instead of all-time ones, we can periodically multiply every counter by a `factor` between `0` and `1`:
+    /// with a factor of `0.5` applied at the end of every time window, an occurrence counts half as much after each window.
+    /// This gives (approximate, since counters are rounded down) exponentially decayed counts, a smooth version of a sliding window.
+    pub fn decay(&mut self, factor: f64) {
+        for count in self.counts.iter_mut().flatten() {
+            *count = (*count as f64 * factor) as usize;
         }
     }
 }
@@ -126,35 +165,131 @@ impl<Item: Hash, const WIDTH: usize, const DEPTH: usize> CountMinSketch
     }
 
     fn increment_by(&mut self, item: Self::Item, count: usize) {
-        for (row, r) in self.hashers.iter_mut().enumerate() {
-            let mut h = r.build_hasher();
-            item.hash(&mut h);
-            let hashed = r.hash_one(&item);
-            let col = (hashed % WIDTH as u64) as usize;
-            self.counts[row][col] += count;
+        let columns: [usize; DEPTH] = std::array::from_fn(|row| Self::column(row, &item));
+        if self.conservative {
+            let new_count = self.get_count(item) + count;
+            for (row, col) in columns.into_iter().enumerate() {
+                self.counts[row][col] = self.counts[row][col].max(new_count);
+            }
+        } else {
+            for (row, col) in columns.into_iter().enumerate() {
+                self.counts[row][col] += count;
+            }
         }
     }
 
     fn get_count(&self, item: Self::Item) -> usize {
-        self.hashers
-            .iter()
-            .enumerate()
-            .map(|(row, r)| {
-                let mut h = r.build_hasher();
-                item.hash(&mut h);
-                let hashed = r.hash_one(&item);
-                let col = (hashed % WIDTH as u64) as usize;
-                self.counts[row][col]
-            })
+        (0..DEPTH)
+            .map(|row| self.counts[row][Self::column(row, &item)])
             .min()
             .unwrap()
     }
 }
 
+/// A Count-min Sketch tells us how often we've seen an item, but not which items are the most frequent: it doesn't store the items.
+/// To find the "heavy hitters" of a stream, we can keep the `k` items with the highest estimated counts next to the sketch,
+/// in the spirit of the Space-Saving algorithm <https://www.cs.ucsb.edu/sites/default/files/documents/2005-23.pdf>:
+///     * when an item comes in, its count is updated in the sketch
+///     * if it is one of the `k` tracked items, its tracked count is updated
+///     * otherwise, if its estimated count is greater than the smallest tracked count, it replaces that item
+///
+/// Any item occurring more than `1 / k` of the time (plus the sketch's error) ends up being tracked.
+pub struct TopK<Item: Hash + Eq + Clone, const WIDTH: usize, const DEPTH: usize> {
+    sketch: HashCountMinSketch<Item, WIDTH, DEPTH>,
+    k: usize,
+    tracked: HashMap<Item, usize>,
+}
+
+impl<Item: Hash + Eq + Clone, const WIDTH: usize, const DEPTH: usize> TopK<Item, WIDTH, DEPTH> {
+    pub fn new(k: usize) -> Self {
+        Self::with_sketch(k, HashCountMinSketch::default())
+    }
+
+    /// Tracks the top-k items on top of an existing (possibly conservative) sketch.
+    pub fn with_sketch(k: usize, sketch: HashCountMinSketch<Item, WIDTH, DEPTH>) -> Self {
+        Self {
+            sketch,
+            k,
+            tracked: HashMap::with_capacity(k + 1),
+        }
+    }
+
+    pub fn increment(&mut self, item: Item) {
+        self.increment_by(item, 1);
+    }
+
+    pub fn increment_by(&mut self, item: Item, count: usize) {
+        self.sketch.increment_by(item.clone(), count);
+        let estimate = self.sketch.get_count(item.clone());
+        self.track(item, estimate);
+    }
+
+    /// Returns the estimated count of any item, tracked or not.
+    pub fn get_count(&self, item: Item) -> usize {
+        self.sketch.get_count(item)
+    }
+
+    /// Returns the tracked items and their estimated counts, the most frequent first.
+    pub fn top(&self) -> Vec<(Item, usize)> {
+        let mut top: Vec<(Item, usize)> = self
+            .tracked
+            .iter()
+            .map(|(item, &count)| (item.clone(), count))
+            .collect();
+        top.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        top
+    }
+
+    /// Merges the sketches, then keeps the `k` items of both tracked sets with the highest counts in the merged sketch.
+    pub fn merge(&mut self, other: &Self) {
+        self.sketch.merge(&other.sketch);
+        let candidates: Vec<Item> = self
+            .tracked
+            .drain()
+            .map(|(item, _)| item)
+            .chain(other.tracked.keys().cloned())
+            .collect();
+        for item in candidates {
+            let estimate = self.sketch.get_count(item.clone());
+            self.track(item, estimate);
+        }
+    }
+
+    /// Decays the sketch and the tracked counts alike, see `HashCountMinSketch::decay`.
+    pub fn decay(&mut self, factor: f64) {
+        self.sketch.decay(factor);
+        for count in self.tracked.values_mut() {
+            *count = (*count as f64 * factor) as usize;
+        }
+    }
+
+    fn track(&mut self, item: Item, estimate: usize) {
+        if let Some(count) = self.tracked.get_mut(&item) {
+            *count = estimate;
+            return;
+        }
+        if self.tracked.len() < self.k {
+            self.tracked.insert(item, estimate);
+            return;
+        }
+        let smallest = self
+            .tracked
+            .iter()
+            .min_by_key(|(_, &count)| count)
+            .map(|(item, &count)| (item.clone(), count));
+        if let Some((smallest_item, smallest_count)) = smallest {
+            if estimate > smallest_count {
+                self.tracked.remove(&smallest_item);
+                self.tracked.insert(item, estimate);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::data_structures::probabilistic::count_min_sketch::{
-        CountMinSketch, HashCountMinSketch,
+        CountMinSketch, HashCountMinSketch, TopK,
     };
     use quickcheck::{Arbitrary, Gen};
     use std::collections::HashSet;
@@ -244,4 +379,86 @@ mod tests {
             assert!(exact_ratio > 0.7); // the proof is quite hard, but this should be OK
         }
     }
+
+    #[quickcheck_macros::quickcheck]
+    fn conservative_update_is_tighter(test_items: Vec<TestItem>) {
+        let mut sketch: HashCountMinSketch<String, 20, 4> = HashCountMinSketch::default();
+        let mut conservative: HashCountMinSketch<String, 20, 4> =
+            HashCountMinSketch::conservative();
+        let mut exact = std::collections::HashMap::new();
+        for TestItem { item, count } in &test_items {
+            sketch.increment_by(item.clone(), *count);
+            conservative.increment_by(item.clone(), *count);
+            *exact.entry(item.clone()).or_insert(0) += count;
+        }
+        for (item, count) in exact {
+            let conservative_count = conservative.get_count(item.clone());
+            assert!(count <= conservative_count);
+            assert!(conservative_count <= sketch.get_count(item));
+        }
+    }
+
+    #[test]
+    fn merge_and_decay() {
+        let mut monday: HashCountMinSketch<&str, 50, 5> = HashCountMinSketch::default();
+        let mut tuesday: HashCountMinSketch<&str, 50, 5> = HashCountMinSketch::default();
+        let mut both: HashCountMinSketch<&str, 50, 5> = HashCountMinSketch::default();
+        for (word, count) in [("rust", 10), ("bloom", 3)] {
+            monday.increment_by(word, count);
+            both.increment_by(word, count);
+        }
+        for (word, count) in [("rust", 5), ("sketch", 7)] {
+            tuesday.increment_by(word, count);
+            both.increment_by(word, count);
+        }
+        monday.merge(&tuesday);
+        assert_eq!(monday.counts, both.counts);
+        assert!(monday.get_count("rust") >= 15);
+
+        monday.decay(0.5);
+        assert_eq!(monday.get_count("rust"), both.get_count("rust") / 2);
+        monday.decay(0.0);
+        assert_eq!(monday.get_count("rust"), 0);
+    }
+
+    /// A stream where item `i` (among 1000) occurs `1000 / (i + 1)` times
+    fn zipf_stream() -> impl Iterator<Item = u32> {
+        (0..1_000_u32).flat_map(|i| std::iter::repeat_n(i, 1_000 / (i as usize + 1)))
+    }
+
+    #[test]
+    fn top_k_finds_heavy_hitters() {
+        let mut top_k: TopK<u32, 200, 5> = TopK::new(5);
+        let mut items: Vec<u32> = zipf_stream().collect();
+        // interleave the stream, so that the heavy hitters don't come first
+        items.sort_by_key(|i| i.wrapping_mul(2_654_435_761) % 97);
+        for item in items {
+            top_k.increment(item);
+        }
+        let top = top_k.top();
+        let top_items: Vec<u32> = top.iter().map(|(item, _)| *item).collect();
+        assert_eq!(top_items, vec![0, 1, 2, 3, 4]);
+        assert!(top[0].1 >= 1_000);
+        assert!(top_k.get_count(999) >= 1);
+
+        top_k.decay(0.5);
+        assert_eq!(top_k.top()[0].1, top[0].1 / 2);
+    }
+
+    #[test]
+    fn merged_top_k() {
+        let mut first: TopK<u32, 200, 5> = TopK::with_sketch(3, HashCountMinSketch::conservative());
+        let mut second: TopK<u32, 200, 5> =
+            TopK::with_sketch(3, HashCountMinSketch::conservative());
+        // each half alone has a misleading top-3
+        for (item, count) in [(1, 50), (2, 40), (3, 30), (4, 20)] {
+            first.increment_by(item, count);
+        }
+        for (item, count) in [(5, 60), (4, 20), (6, 20), (3, 15)] {
+            second.increment_by(item, count);
+        }
+        first.merge(&second);
+        let top_items: Vec<u32> = first.top().into_iter().map(|(item, _)| item).collect();
+        assert_eq!(top_items, vec![5, 1, 3]);
+    }
 }
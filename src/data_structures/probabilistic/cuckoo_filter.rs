@@ -0,0 +1,375 @@
+use std::hash::Hash;
+
+use super::bloom_filter::{read_header, read_u64, FilterError, FORMAT_VERSION};
+use super::stable_hash;
+
+/// A Cuckoo Filter <https://www.cs.cmu.edu/~dga/papers/cuckoo-conext2014.pdf> answers the same question as a Bloom Filter
+/// (is this item *maybe* in the set, or *definitely not*?), but also supports removing items.
+///
+/// Instead of setting bits, it stores a small fingerprint of every item in one of two candidate buckets.
+/// The second bucket is computed from the first one and the fingerprint alone (`i2 = i1 ^ hash(fingerprint)`),
+/// so a fingerprint can be moved between its two buckets without knowing the original item.
+///
+/// When both buckets of a new item are full, one of the fingerprints already stored there is kicked out to its own alternate bucket,
+/// which may kick out another one, and so on, just like cuckoo hashing.
+/// If that goes on for too long the filter is considered full and `insert` fails.
+///
+/// Looking up or removing an item only ever needs to check its two buckets.
+/// Removing an item that was never inserted may remove another item sharing the same fingerprint, so only remove items known to be present.
+pub struct CuckooFilter {
+    /// `BUCKET_SIZE` fingerprints per bucket, `EMPTY` marking a free slot
+    fingerprints: Vec<u16>,
+    bucket_count: usize,
+    len: usize,
+    /// A fingerprint that could not be placed during the last failed insertion, kept so that nothing is lost
+    victim: Option<(usize, u16)>,
+}
+
+const BUCKET_SIZE: usize = 4;
+const EMPTY: u16 = 0;
+const MAX_KICKS: usize = 500;
+const MAGIC: &[u8; 3] = b"CKF";
+
+impl CuckooFilter {
+    /// Creates a filter with room for at least `capacity` items.
+    /// Cuckoo filters only fill up to ~95% in practice, so a bit of headroom is added.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let bucket_count = (capacity * 100 / 95)
+            .div_ceil(BUCKET_SIZE)
+            .next_power_of_two();
+        Self {
+            fingerprints: vec![EMPTY; bucket_count * BUCKET_SIZE],
+            bucket_count,
+            len: 0,
+            victim: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts an item, or returns `FilterError::Full` if no room could be made for it.
+    /// Inserting the same item twice stores it twice, so that it must be removed twice as well.
+    pub fn insert<T: Hash>(&mut self, item: &T) -> Result<(), FilterError> {
+        if self.victim.is_some() {
+            return Err(FilterError::Full);
+        }
+        let (mut index, mut fingerprint) = self.locate(item);
+        if self.try_store(index, fingerprint) || {
+            index = self.alternate(index, fingerprint);
+            self.try_store(index, fingerprint)
+        } {
+            self.len += 1;
+            return Ok(());
+        }
+        // Both buckets are full: kick fingerprints out to their alternate bucket until one finds a free slot
+        for kick in 0..MAX_KICKS {
+            let slot = index * BUCKET_SIZE + (fingerprint as usize + kick) % BUCKET_SIZE;
+            std::mem::swap(&mut self.fingerprints[slot], &mut fingerprint);
+            index = self.alternate(index, fingerprint);
+            if self.try_store(index, fingerprint) {
+                self.len += 1;
+                return Ok(());
+            }
+        }
+        // The item made it in, but the last fingerprint kicked out had nowhere to go
+        self.victim = Some((index, fingerprint));
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns `false` if the item is definitely absent, `true` if it may be present.
+    pub fn contains<T: Hash>(&self, item: &T) -> bool {
+        let (index, fingerprint) = self.locate(item);
+        let alternate = self.alternate(index, fingerprint);
+        self.bucket(index).contains(&fingerprint)
+            || self.bucket(alternate).contains(&fingerprint)
+            || self
+                .victim
+                .is_some_and(|(i, f)| f == fingerprint && (i == index || i == alternate))
+    }
+
+    /// Removes one copy of the item, returning whether a matching fingerprint was found.
+    pub fn remove<T: Hash>(&mut self, item: &T) -> bool {
+        let (index, fingerprint) = self.locate(item);
+        let alternate = self.alternate(index, fingerprint);
+        if self.victim == Some((index, fingerprint))
+            || self.victim == Some((alternate, fingerprint))
+        {
+            self.victim = None;
+            self.len -= 1;
+            return true;
+        }
+        for bucket in [index, alternate] {
+            let start = bucket * BUCKET_SIZE;
+            if let Some(slot) = self.fingerprints[start..start + BUCKET_SIZE]
+                .iter()
+                .position(|&f| f == fingerprint)
+            {
+                self.fingerprints[start + slot] = EMPTY;
+                self.len -= 1;
+                // Now that there's room again, try to put the victim back into the table
+                if let Some((victim_index, victim)) = self.victim.take() {
+                    let victim_alternate = self.alternate(victim_index, victim);
+                    if !self.try_store(victim_index, victim)
+                        && !self.try_store(victim_alternate, victim)
+                    {
+                        self.victim = Some((victim_index, victim));
+                    }
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The probability of a false positive, given how full the filter currently is.
+    /// Each lookup compares against up to `2 * BUCKET_SIZE` stored fingerprints of 16 bits.
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let load = self.len as f64 / self.fingerprints.len() as f64;
+        let per_comparison = 1.0 / f64::from(u16::MAX);
+        1.0 - (1.0 - per_comparison).powf(2.0 * BUCKET_SIZE as f64 * load)
+    }
+
+    /// Serializes the filter as: the magic bytes `CKF`, the format version byte, the bucket count, the item count (both as little-endian `u64`),
+    /// a victim flag byte followed by the victim bucket (`u64`) and fingerprint (`u16`) if set,
+    /// then every fingerprint as a little-endian `u16`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 28 + 2 * self.fingerprints.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&(self.bucket_count as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.len as u64).to_le_bytes());
+        match self.victim {
+            Some((index, fingerprint)) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&(index as u64).to_le_bytes());
+                bytes.extend_from_slice(&fingerprint.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        for fingerprint in &self.fingerprints {
+            bytes.extend_from_slice(&fingerprint.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reads back a filter written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FilterError> {
+        let rest = read_header(bytes, MAGIC)?;
+        let (bucket_count, rest) = read_u64(rest)?;
+        let (len, rest) = read_u64(rest)?;
+        let (victim, rest) = match rest.split_first() {
+            Some((0, rest)) => (None, rest),
+            Some((1, rest)) => {
+                let (index, rest) = read_u64(rest)?;
+                let (fingerprint, rest) =
+                    rest.split_at_checked(2).ok_or(FilterError::InvalidFormat)?;
+                let fingerprint = u16::from_le_bytes([fingerprint[0], fingerprint[1]]);
+                (Some((index, fingerprint)), rest)
+            }
+            _ => return Err(FilterError::InvalidFormat),
+        };
+        let payload_len = bucket_count
+            .checked_mul(2 * BUCKET_SIZE)
+            .ok_or(FilterError::InvalidFormat)?;
+        if !bucket_count.is_power_of_two()
+            || rest.len() != payload_len
+            || victim
+                .is_some_and(|(index, fingerprint)| index >= bucket_count || fingerprint == EMPTY)
+        {
+            return Err(FilterError::InvalidFormat);
+        }
+        let fingerprints: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        // `remove` decrements `len` for every fingerprint it finds, so it must count them exactly
+        let occupied = fingerprints.iter().filter(|&&f| f != EMPTY).count();
+        if len != occupied + usize::from(victim.is_some()) {
+            return Err(FilterError::InvalidFormat);
+        }
+        Ok(Self {
+            fingerprints,
+            bucket_count,
+            len,
+            victim,
+        })
+    }
+
+    /// Computes the first bucket and the (never empty) fingerprint of an item.
+    fn locate<T: Hash>(&self, item: &T) -> (usize, u16) {
+        let hash = stable_hash(0, item);
+        let fingerprint = match (hash >> 48) as u16 {
+            EMPTY => 1,
+            fingerprint => fingerprint,
+        };
+        (hash as usize & (self.bucket_count - 1), fingerprint)
+    }
+
+    /// The other bucket a fingerprint may live in. Applying it twice gives back the original bucket.
+    fn alternate(&self, index: usize, fingerprint: u16) -> usize {
+        (index ^ stable_hash(0, &fingerprint) as usize) & (self.bucket_count - 1)
+    }
+
+    fn bucket(&self, index: usize) -> &[u16] {
+        &self.fingerprints[index * BUCKET_SIZE..(index + 1) * BUCKET_SIZE]
+    }
+
+    fn try_store(&mut self, index: usize, fingerprint: u16) -> bool {
+        let start = index * BUCKET_SIZE;
+        match self.fingerprints[start..start + BUCKET_SIZE]
+            .iter_mut()
+            .find(|f| **f == EMPTY)
+        {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CuckooFilter;
+    use crate::data_structures::bloom_filter::FilterError;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashSet;
+
+    #[test]
+    fn insert_contains_remove() {
+        let mut filter = CuckooFilter::with_capacity(1_000);
+        for i in 0..1_000 {
+            assert_eq!(filter.insert(&i), Ok(()));
+        }
+        assert_eq!(filter.len(), 1_000);
+        assert!((0..1_000).all(|i| filter.contains(&i)));
+        for i in (0..1_000).step_by(2) {
+            assert!(filter.remove(&i));
+        }
+        assert_eq!(filter.len(), 500);
+        assert!((1..1_000).step_by(2).all(|i| filter.contains(&i)));
+        let false_positives = (0..1_000).step_by(2).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 10);
+    }
+
+    #[test]
+    fn duplicates_are_counted() {
+        let mut filter = CuckooFilter::with_capacity(16);
+        filter.insert(&"cuckoo").unwrap();
+        filter.insert(&"cuckoo").unwrap();
+        assert!(filter.remove(&"cuckoo"));
+        assert!(filter.contains(&"cuckoo"));
+        assert!(filter.remove(&"cuckoo"));
+        assert!(!filter.contains(&"cuckoo"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn reports_when_full() {
+        let mut filter = CuckooFilter::with_capacity(8);
+        let inserted = (0..100_usize)
+            .take_while(|i| filter.insert(i).is_ok())
+            .count();
+        assert!((8..100).contains(&inserted));
+        assert_eq!(filter.insert(&1_000), Err(FilterError::Full));
+        // Every item accepted so far, including the victim, is still found
+        assert!((0..inserted).all(|i| filter.contains(&i)));
+        assert!(filter.remove(&0_usize));
+        assert!((1..inserted).all(|i| filter.contains(&i)));
+    }
+
+    #[test]
+    fn false_positive_estimate_grows_with_load() {
+        let mut filter = CuckooFilter::with_capacity(1_000);
+        assert_eq!(filter.estimated_false_positive_rate(), 0.0);
+        for i in 0..500 {
+            filter.insert(&i).unwrap();
+        }
+        let half_full = filter.estimated_false_positive_rate();
+        for i in 500..1_000 {
+            filter.insert(&i).unwrap();
+        }
+        assert!(0.0 < half_full && half_full < filter.estimated_false_positive_rate());
+        assert!(filter.estimated_false_positive_rate() < 0.001);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut filter = CuckooFilter::with_capacity(100);
+        for word in ["a", "b", "c"] {
+            filter.insert(&word).unwrap();
+        }
+        let bytes = filter.to_bytes();
+        let restored = CuckooFilter::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.len(), 3);
+        assert!(restored.contains(&"b"));
+        assert_eq!(restored.to_bytes(), bytes);
+
+        assert_eq!(
+            CuckooFilter::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(FilterError::InvalidFormat)
+        );
+        assert_eq!(
+            CuckooFilter::from_bytes(b"BLM\x02").err(),
+            Some(FilterError::InvalidFormat)
+        );
+
+        // A bucket count whose payload size overflows, and a length that doesn't match the stored fingerprints
+        let mut huge = b"CKF\x02".to_vec();
+        huge.extend_from_slice(&(1u64 << 62).to_le_bytes());
+        huge.extend_from_slice(&0u64.to_le_bytes());
+        huge.push(0);
+        assert_eq!(
+            CuckooFilter::from_bytes(&huge).err(),
+            Some(FilterError::InvalidFormat)
+        );
+        let mut wrong_len = bytes.clone();
+        wrong_len[12..20].copy_from_slice(&4u64.to_le_bytes());
+        assert_eq!(
+            CuckooFilter::from_bytes(&wrong_len).err(),
+            Some(FilterError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn serialized_bytes_are_pinned() {
+        // Any change to these bytes breaks the filters already written: it needs a new format version
+        let mut filter = CuckooFilter::with_capacity(4);
+        filter.insert(&"pinned").unwrap();
+        let bytes = [
+            b'C', b'K', b'F', 2, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0x07, 0x8b, 0,
+            0, 0, 0, 0, 0,
+        ];
+        assert_eq!(filter.to_bytes(), bytes);
+
+        let mut version_1 = bytes;
+        version_1[3] = 1;
+        assert_eq!(
+            CuckooFilter::from_bytes(&version_1).err(),
+            Some(FilterError::UnsupportedVersion(1))
+        );
+    }
+
+    #[quickcheck]
+    fn no_false_negatives(to_insert: HashSet<i32>, to_remove: Vec<i32>) -> bool {
+        let mut filter = CuckooFilter::with_capacity(2 * to_insert.len());
+        for item in &to_insert {
+            filter.insert(item).unwrap();
+        }
+        let mut present = to_insert.clone();
+        for item in to_remove.iter().filter(|item| to_insert.contains(item)) {
+            if present.remove(item) {
+                assert!(filter.remove(item));
+            }
+        }
+        filter.len() == present.len() && present.iter().all(|item| filter.contains(item))
+    }
+}
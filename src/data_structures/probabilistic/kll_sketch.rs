@@ -0,0 +1,235 @@
+/// A KLL sketch <https://arxiv.org/abs/1603.05346> (Karnin, Lang and Liberty) estimates quantiles of a stream of numbers
+/// (the median, the 99th percentile...) using a small, bounded amount of memory.
+///
+/// Items are kept in a stack of compactors. Every item starts in compactor `0` with a weight of `1`.
+/// When a compactor is full, it is sorted and every other item (starting at a random offset) is promoted to the next compactor,
+/// while the rest is dropped: the promoted items now stand for `2^level` items each, so the total weight is (almost) unchanged.
+/// The rank of any value moves by at most one weight unit per compaction, in a random direction, so the errors tend to cancel out.
+///
+/// The capacities of the compactors decrease geometrically (by a factor `2/3`) from the top one, which holds `k` items:
+/// the sketch keeps `O(k)` items, and the rank error is about `1.7 / k` (1% of the number of items for the default `k = 200`).
+///
+/// Two sketches are merged by concatenating their compactors level by level, then compacting whatever overflows:
+/// shards can build their own sketch and have them combined.
+pub struct KllSketch {
+    k: usize,
+    compactors: Vec<Vec<f64>>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+pub const DEFAULT_K: usize = 200;
+const CAPACITY_DECAY: f64 = 2.0 / 3.0;
+const MIN_CAPACITY: usize = 2;
+
+impl KllSketch {
+    /// Creates a sketch whose top compactor holds `k` items (at least 8).
+    pub fn new(k: usize) -> Self {
+        Self {
+            k: k.max(8),
+            compactors: vec![Vec::new()],
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Returns the number of items inserted so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Inserts a value. NaNs are ignored, since they don't have a rank.
+    pub fn insert(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.compactors[0].push(value);
+        self.compress();
+    }
+
+    /// Returns the (approximate) fraction of the inserted values lower than or equal to `value`.
+    pub fn rank(&self, value: f64) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let weight: u64 = self
+            .weighted_items()
+            .filter(|&(item, _)| item <= value)
+            .map(|(_, weight)| weight)
+            .sum();
+        weight as f64 / self.count as f64
+    }
+
+    /// Returns an (approximate) value whose rank is `q`, for `q` between `0` and `1`.
+    /// The extremes are exact: `quantile(0.0)` is the minimum and `quantile(1.0)` the maximum.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.is_empty() || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        if q == 0.0 {
+            return Some(self.min);
+        }
+        if q == 1.0 {
+            return Some(self.max);
+        }
+        let mut items: Vec<(f64, u64)> = self.weighted_items().collect();
+        items.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let target = q * self.count as f64;
+        let mut cumulated = 0;
+        for (item, weight) in items {
+            cumulated += weight;
+            if cumulated as f64 >= target {
+                return Some(item);
+            }
+        }
+        Some(self.max)
+    }
+
+    /// Adds all the values of `other` to this sketch.
+    pub fn merge(&mut self, other: &Self) {
+        if other.is_empty() {
+            return;
+        }
+        while self.compactors.len() < other.compactors.len() {
+            self.compactors.push(Vec::new());
+        }
+        for (compactor, other_compactor) in self.compactors.iter_mut().zip(&other.compactors) {
+            compactor.extend_from_slice(other_compactor);
+        }
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.compress();
+    }
+
+    /// Returns every retained item along with the number of inserted items it stands for.
+    fn weighted_items(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        self.compactors
+            .iter()
+            .enumerate()
+            .flat_map(|(level, compactor)| compactor.iter().map(move |&item| (item, 1 << level)))
+    }
+
+    /// The top compactor holds `k` items, and each one below holds `2/3` of the one above it.
+    fn capacity(&self, level: usize) -> usize {
+        let depth = self.compactors.len() - level - 1;
+        ((self.k as f64 * CAPACITY_DECAY.powi(depth as i32)).ceil() as usize).max(MIN_CAPACITY)
+    }
+
+    /// Compacts full compactors, from the bottom up, until the sketch fits in its total capacity.
+    fn compress(&mut self) {
+        loop {
+            let size: usize = self.compactors.iter().map(Vec::len).sum();
+            let capacity: usize = (0..self.compactors.len())
+                .map(|level| self.capacity(level))
+                .sum();
+            if size < capacity {
+                return;
+            }
+            let Some(level) = (0..self.compactors.len())
+                .find(|&level| self.compactors[level].len() >= self.capacity(level))
+            else {
+                return;
+            };
+            if level + 1 == self.compactors.len() {
+                self.compactors.push(Vec::new());
+            }
+            self.compact(level);
+        }
+    }
+
+    /// Promotes every other item of a compactor to the next one, keeping one item behind if their number is odd.
+    fn compact(&mut self, level: usize) {
+        let mut items = std::mem::take(&mut self.compactors[level]);
+        items.sort_by(f64::total_cmp);
+        if items.len() % 2 == 1 {
+            // The largest item stays, so that the promoted items pair up
+            let kept = items.pop().unwrap();
+            self.compactors[level].push(kept);
+        }
+        let offset = usize::from(rand::random::<bool>());
+        let promoted = items.into_iter().skip(offset).step_by(2);
+        self.compactors[level + 1].extend(promoted);
+    }
+}
+
+impl Default for KllSketch {
+    fn default() -> Self {
+        Self::new(DEFAULT_K)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KllSketch;
+
+    const N: u64 = 100_000;
+
+    /// A permutation of `0..N`, so that values don't arrive sorted
+    fn shuffled() -> impl Iterator<Item = f64> {
+        (0..N).map(|i| (i * 7_919 % N) as f64)
+    }
+
+    fn assert_quantiles_close(sketch: &KllSketch) {
+        for q in [0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99] {
+            let estimate = sketch.quantile(q).unwrap();
+            let error = (estimate / N as f64 - q).abs();
+            assert!(error < 0.03, "quantile {q}: {estimate}");
+            let rank_error = (sketch.rank(q * N as f64) - q).abs();
+            assert!(rank_error < 0.03, "rank of {q}");
+        }
+    }
+
+    #[test]
+    fn quantiles_of_a_stream() {
+        let mut sketch = KllSketch::default();
+        for value in shuffled() {
+            sketch.insert(value);
+        }
+        assert_eq!(sketch.count(), N);
+        assert_eq!(sketch.quantile(0.0), Some(0.0));
+        assert_eq!(sketch.quantile(1.0), Some((N - 1) as f64));
+        assert_quantiles_close(&sketch);
+        let retained: usize = sketch.compactors.iter().map(Vec::len).sum();
+        assert!(retained < 1_000, "{retained} items retained");
+    }
+
+    #[test]
+    fn merged_shards() {
+        let mut shards: Vec<KllSketch> = (0..4).map(|_| KllSketch::default()).collect();
+        for (i, value) in shuffled().enumerate() {
+            shards[i % 4].insert(value);
+        }
+        let mut merged = KllSketch::default();
+        for shard in &shards {
+            merged.merge(shard);
+        }
+        assert_eq!(merged.count(), N);
+        assert_quantiles_close(&merged);
+    }
+
+    #[test]
+    fn small_and_empty_sketches() {
+        let mut sketch = KllSketch::new(50);
+        assert!(sketch.is_empty());
+        assert_eq!(sketch.quantile(0.5), None);
+        assert_eq!(sketch.rank(1.0), 0.0);
+        for value in [3.0, 1.0, f64::NAN, 2.0] {
+            sketch.insert(value);
+        }
+        // Few enough items to be kept exactly
+        assert_eq!(sketch.count(), 3);
+        assert_eq!(sketch.quantile(0.5), Some(2.0));
+        assert_eq!(sketch.rank(2.5), 2.0 / 3.0);
+        assert_eq!(sketch.quantile(1.5), None);
+    }
+}
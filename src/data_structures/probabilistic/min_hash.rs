@@ -0,0 +1,117 @@
+use std::hash::Hash;
+
+use super::stable_hash;
+
+/// MinHash <https://en.wikipedia.org/wiki/MinHash> estimates how similar two sets are, without storing them.
+///
+/// The Jaccard similarity of two sets `A` and `B` is `|A ∩ B| / |A ∪ B|`.
+/// Take a random hash function `h`, and look at the item of `A ∪ B` with the smallest hash:
+/// it belongs to both sets with probability exactly `|A ∩ B| / |A ∪ B|`, in which case `min(h(A)) == min(h(B))`.
+///
+/// So a MinHash signature keeps, for each of `num_hashes` hash functions, the smallest hash of the items of a set.
+/// The fraction of hash functions for which two signatures agree estimates the Jaccard similarity, with a standard error of `1 / sqrt(num_hashes)`.
+///
+/// Since the signature of `A ∪ B` is the element-wise minimum of the signatures of `A` and `B`, signatures built by different shards can be merged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinHash {
+    signature: Vec<u64>,
+}
+
+/// Enum representing the possible errors that can occur when comparing or merging MinHash signatures.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MinHashError {
+    /// The signatures were computed with a different number of hash functions.
+    LengthMismatch,
+}
+
+impl MinHash {
+    pub fn new(num_hashes: usize) -> Self {
+        Self {
+            signature: vec![u64::MAX; num_hashes],
+        }
+    }
+
+    /// Returns the smallest hash seen for each hash function (`u64::MAX` while the set is empty).
+    pub fn signature(&self) -> &[u64] {
+        &self.signature
+    }
+
+    /// The `i`-th hash function is the stable hash seeded with `i`.
+    pub fn insert<T: Hash>(&mut self, item: &T) {
+        for (seed, min) in self.signature.iter_mut().enumerate() {
+            *min = (*min).min(stable_hash(seed as u64, item));
+        }
+    }
+
+    /// Returns the estimated Jaccard similarity of the two sets.
+    pub fn jaccard(&self, other: &Self) -> Result<f64, MinHashError> {
+        if self.signature.len() != other.signature.len() {
+            return Err(MinHashError::LengthMismatch);
+        }
+        if self.signature.is_empty() {
+            return Ok(0.0);
+        }
+        let equal = self
+            .signature
+            .iter()
+            .zip(&other.signature)
+            .filter(|(a, b)| a == b)
+            .count();
+        Ok(equal as f64 / self.signature.len() as f64)
+    }
+
+    /// Turns this signature into the signature of the union of both sets.
+    pub fn merge(&mut self, other: &Self) -> Result<(), MinHashError> {
+        if self.signature.len() != other.signature.len() {
+            return Err(MinHashError::LengthMismatch);
+        }
+        for (min, &other_min) in self.signature.iter_mut().zip(&other.signature) {
+            *min = (*min).min(other_min);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MinHash, MinHashError};
+
+    fn signature_of(items: impl Iterator<Item = u32>) -> MinHash {
+        let mut min_hash = MinHash::new(256);
+        for item in items {
+            min_hash.insert(&item);
+        }
+        min_hash
+    }
+
+    #[test]
+    fn estimates_jaccard_similarity() {
+        // |A ∩ B| = 500 and |A ∪ B| = 1500: the similarity is 1/3
+        let a = signature_of(0..1_000);
+        let b = signature_of(500..1_500);
+        let similarity = a.jaccard(&b).unwrap();
+        // The standard error is 1/16, allow for about 2 times as much
+        assert!((similarity - 1.0 / 3.0).abs() < 0.12, "{similarity}");
+        assert_eq!(a.jaccard(&a), Ok(1.0));
+        assert!(a.jaccard(&signature_of(2_000..3_000)).unwrap() < 0.05);
+    }
+
+    #[test]
+    fn merged_shards_give_the_signature_of_the_union() {
+        let mut merged = signature_of((0..1_000).filter(|i| i % 3 == 0));
+        merged
+            .merge(&signature_of((0..1_000).filter(|i| i % 3 != 0)))
+            .unwrap();
+        assert_eq!(merged, signature_of(0..1_000));
+    }
+
+    #[test]
+    fn signatures_must_have_the_same_length() {
+        let mut a = MinHash::new(16);
+        let b = MinHash::new(32);
+        assert_eq!(a.jaccard(&b), Err(MinHashError::LengthMismatch));
+        assert_eq!(a.merge(&b), Err(MinHashError::LengthMismatch));
+        assert_eq!(MinHash::new(0).jaccard(&MinHash::new(0)), Ok(0.0));
+        assert!(a.signature().iter().all(|&min| min == u64::MAX));
+    }
+}
@@ -1,2 +1,162 @@
+//! Probabilistic data structures: filters answering membership queries, and sketches
+//! estimating counts, cardinalities, quantiles and similarities.
+//!
+//! Filters and sketches built separately are merged, compared or shipped between programs,
+//! which only makes sense if they hash items the same way. `std`'s `DefaultHasher` can't be
+//! used for this: its algorithm is unspecified and may change between Rust releases, and it
+//! hashes integers in native byte order and `usize` with the width of the platform.
+//!
+//! Every structure here hashes items with `stable_hash` instead: SipHash-1-3, keyed with
+//! `(seed, 0)`, over a canonical encoding of the item in which integers are written in
+//! little-endian order and `usize`/`isize` as 64 bits. Items hash the same on every platform
+//! and with every compiler, as long as their `Hash` implementation feeds the same values.
+
 pub mod bloom_filter;
 pub mod count_min_sketch;
+pub mod cuckoo_filter;
+pub mod hyperloglog;
+pub mod kll_sketch;
+pub mod min_hash;
+
+use std::hash::{Hash, Hasher};
+
+/// Hashes `item` with the hash function selected by `seed`.
+fn stable_hash<T: Hash + ?Sized>(seed: u64, item: &T) -> u64 {
+    let mut hasher = StableHasher::new(seed);
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// SipHash-1-3 <https://www.aumasson.jp/siphash/siphash.pdf>, fed with the canonical encoding
+/// of the values written to it.
+#[derive(Debug, Clone)]
+struct StableHasher {
+    state: [u64; 4],
+    /// Bytes that don't make a whole message word yet, in the low bytes.
+    tail: u64,
+    tail_len: usize,
+    len: usize,
+}
+
+impl StableHasher {
+    fn new(seed: u64) -> Self {
+        let (k0, k1) = (seed, 0);
+        StableHasher {
+            state: [
+                k0 ^ 0x736f_6d65_7073_6575,
+                k1 ^ 0x646f_7261_6e64_6f6d,
+                k0 ^ 0x6c79_6765_6e65_7261,
+                k1 ^ 0x7465_6462_7974_6573,
+            ],
+            tail: 0,
+            tail_len: 0,
+            len: 0,
+        }
+    }
+
+    fn round(state: &mut [u64; 4]) {
+        let [v0, v1, v2, v3] = state;
+        *v0 = v0.wrapping_add(*v1);
+        *v1 = v1.rotate_left(13) ^ *v0;
+        *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3);
+        *v3 = v3.rotate_left(16) ^ *v2;
+        *v0 = v0.wrapping_add(*v3);
+        *v3 = v3.rotate_left(21) ^ *v0;
+        *v2 = v2.wrapping_add(*v1);
+        *v1 = v1.rotate_left(17) ^ *v2;
+        *v2 = v2.rotate_left(32);
+    }
+
+    fn compress(state: &mut [u64; 4], word: u64) {
+        state[3] ^= word;
+        Self::round(state);
+        state[0] ^= word;
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.len = self.len.wrapping_add(bytes.len());
+        for &byte in bytes {
+            self.tail |= u64::from(byte) << (8 * self.tail_len);
+            self.tail_len += 1;
+            if self.tail_len == 8 {
+                Self::compress(&mut self.state, self.tail);
+                self.tail = 0;
+                self.tail_len = 0;
+            }
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_i16(&mut self, value: i16) {
+        self.write_u16(value as u16);
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_i128(&mut self, value: i128) {
+        self.write_u128(value as u128);
+    }
+
+    fn write_isize(&mut self, value: isize) {
+        self.write_u64(value as i64 as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.state;
+        Self::compress(&mut state, ((self.len as u64) << 56) | self.tail);
+        state[2] ^= 0xff;
+        for _ in 0..3 {
+            Self::round(&mut state);
+        }
+        state.iter().fold(0, |hash, v| hash ^ v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stable_hash;
+
+    #[test]
+    fn hashes_are_pinned() {
+        // These values are part of the serialization format of the filters: they must never change
+        assert_eq!(stable_hash(0, ""), 0x3040_6ea5_23c5_3def);
+        assert_eq!(stable_hash(0, "stable"), 0xa395_6889_19ca_1ee6);
+        assert_eq!(stable_hash(7, &42u64), 0xe54f_4621_0467_2788);
+    }
+
+    #[test]
+    fn integers_hash_independently_of_the_platform() {
+        assert_eq!(stable_hash(3, &42usize), stable_hash(3, &42u64));
+        assert_eq!(stable_hash(3, &-42isize), stable_hash(3, &-42i64));
+        assert_eq!(stable_hash(3, &0x0102u16), stable_hash(3, &(2u8, 1u8)));
+        assert_ne!(stable_hash(3, &42u64), stable_hash(4, &42u64));
+    }
+}
@@ -0,0 +1,393 @@
+//! This module provides a radix tree (also called a compressed trie or Patricia tree).
+//!
+//! In a plain `Trie`, a key of length `k` takes up to `k` nodes, most of which have a single
+//! child when keys don't share long prefixes. A radix tree labels each edge with a whole run
+//! of keys instead, and merges every node that has a single child and no value into its
+//! child. The tree then has at most `2 * n` nodes for `n` values, whatever the lengths of
+//! the keys.
+//!
+//! Wikipedia: <https://en.wikipedia.org/wiki/Radix_tree>
+
+use std::collections::btree_map::{self, Entry};
+use std::collections::BTreeMap;
+
+/// A node of the radix tree, reached through an edge labeled by `label`.
+#[derive(Debug)]
+struct Node<Key, Type> {
+    /// The run of keys leading to this node from its parent, empty only for the root.
+    label: Vec<Key>,
+    /// The children of the node, indexed by the first key of their label.
+    children: BTreeMap<Key, Node<Key, Type>>,
+    /// The value associated with this node, if any.
+    value: Option<Type>,
+}
+
+impl<Key, Type> Node<Key, Type> {
+    fn new(label: Vec<Key>, value: Option<Type>) -> Self {
+        Self {
+            label,
+            children: BTreeMap::new(),
+            value,
+        }
+    }
+}
+
+/// A radix tree mapping sequences of keys to values, with the same interface as `Trie`.
+#[derive(Debug)]
+pub struct RadixTrie<Key, Type> {
+    /// The root node, which holds the value of the empty sequence.
+    root: Node<Key, Type>,
+    /// The number of values stored in the tree.
+    len: usize,
+}
+
+impl<Key: Ord + Clone, Type> RadixTrie<Key, Type> {
+    /// Creates a new, empty `RadixTrie`.
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(Vec::new(), None),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of values stored in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the tree holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a value, associating it with a sequence of keys.
+    ///
+    /// # Returns
+    /// The value previously associated with the sequence of keys, if any.
+    pub fn insert(&mut self, key: impl IntoIterator<Item = Key>, value: Type) -> Option<Type> {
+        let key: Vec<Key> = key.into_iter().collect();
+        let mut node = &mut self.root;
+        let mut matched = 0;
+        while matched < key.len() {
+            let child = match node.children.entry(key[matched].clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(Node::new(key[matched..].to_vec(), Some(value)));
+                    self.len += 1;
+                    return None;
+                }
+                Entry::Occupied(entry) => entry.into_mut(),
+            };
+            let common = common_prefix_len(&child.label, &key[matched..]);
+            if common < child.label.len() {
+                // The key leaves the edge halfway: split it with a node at the fork
+                let suffix = child.label.split_off(common);
+                let mut lower = Node::new(suffix, child.value.take());
+                std::mem::swap(&mut lower.children, &mut child.children);
+                child.children.insert(lower.label[0].clone(), lower);
+            }
+            matched += common;
+            node = child;
+        }
+        let old = node.value.replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Retrieves a reference to the value associated with a sequence of keys, if it exists.
+    pub fn get(&self, key: impl IntoIterator<Item = Key>) -> Option<&Type> {
+        let key: Vec<Key> = key.into_iter().collect();
+        let (node, matched) = self.find(&key)?;
+        if matched == key.len() {
+            node.value.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Retrieves a mutable reference to the value associated with a sequence of keys, if it exists.
+    pub fn get_mut(&mut self, key: impl IntoIterator<Item = Key>) -> Option<&mut Type> {
+        let key: Vec<Key> = key.into_iter().collect();
+        let mut node = &mut self.root;
+        let mut matched = 0;
+        while matched < key.len() {
+            node = node.children.get_mut(&key[matched])?;
+            if !key[matched..].starts_with(&node.label) {
+                return None;
+            }
+            matched += node.label.len();
+        }
+        node.value.as_mut()
+    }
+
+    /// Removes the value associated with a sequence of keys, merging the nodes that are left
+    /// with a single child and no value.
+    ///
+    /// # Returns
+    /// The removed value, or `None` if the sequence of keys wasn't in the tree.
+    pub fn remove(&mut self, key: impl IntoIterator<Item = Key>) -> Option<Type> {
+        let key: Vec<Key> = key.into_iter().collect();
+        let removed = Self::remove_from(&mut self.root, &key);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    fn remove_from(node: &mut Node<Key, Type>, key: &[Key]) -> Option<Type> {
+        let Some(first) = key.first() else {
+            return node.value.take();
+        };
+        let child = node.children.get_mut(first)?;
+        let rest = key.strip_prefix(child.label.as_slice())?;
+        let removed = Self::remove_from(child, rest)?;
+        if child.value.is_none() {
+            match child.children.len() {
+                0 => {
+                    node.children.remove(first);
+                }
+                1 => {
+                    let (_, grandchild) = child.children.pop_first()?;
+                    child.label.extend(grandchild.label);
+                    child.children = grandchild.children;
+                    child.value = grandchild.value;
+                }
+                _ => {}
+            }
+        }
+        Some(removed)
+    }
+
+    /// Returns the length of the longest sequence stored in the tree that is a prefix of `key`,
+    /// along with its value.
+    pub fn longest_prefix(&self, key: impl IntoIterator<Item = Key>) -> Option<(usize, &Type)> {
+        let key: Vec<Key> = key.into_iter().collect();
+        let mut node = &self.root;
+        let mut matched = 0;
+        let mut longest = node.value.as_ref().map(|value| (0, value));
+        while matched < key.len() {
+            let Some(child) = node.children.get(&key[matched]) else {
+                break;
+            };
+            if !key[matched..].starts_with(&child.label) {
+                break;
+            }
+            node = child;
+            matched += node.label.len();
+            if let Some(value) = &node.value {
+                longest = Some((matched, value));
+            }
+        }
+        longest
+    }
+
+    /// Returns an iterator over all the sequences of keys and their values, in lexicographic
+    /// order.
+    pub fn iter(&self) -> Iter<'_, Key, Type> {
+        Iter::new(&self.root, Vec::new())
+    }
+
+    /// Returns an iterator over the sequences of keys starting with `prefix` and their values,
+    /// in lexicographic order.
+    pub fn iter_prefix(&self, prefix: impl IntoIterator<Item = Key>) -> Iter<'_, Key, Type> {
+        let mut prefix: Vec<Key> = prefix.into_iter().collect();
+        match self.find(&prefix) {
+            Some((node, matched)) => {
+                // The prefix may end in the middle of the label of the node
+                prefix
+                    .extend_from_slice(&node.label[node.label.len() - (matched - prefix.len())..]);
+                Iter::new(node, prefix)
+            }
+            None => Iter {
+                stack: Vec::new(),
+                path: Vec::new(),
+                pending: None,
+            },
+        }
+    }
+
+    /// Returns the highest node whose path starts with `key`, along with the length of its
+    /// path, which is longer than `key` if `key` ends in the middle of its label.
+    fn find(&self, key: &[Key]) -> Option<(&Node<Key, Type>, usize)> {
+        let mut node = &self.root;
+        let mut matched = 0;
+        while matched < key.len() {
+            node = node.children.get(&key[matched])?;
+            let rest = &key[matched..];
+            if !rest.starts_with(&node.label) && !node.label.starts_with(rest) {
+                return None;
+            }
+            matched += node.label.len();
+        }
+        Some((node, matched))
+    }
+}
+
+/// Returns the number of leading keys which are equal in both sequences.
+fn common_prefix_len<Key: PartialEq>(a: &[Key], b: &[Key]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+impl<Key: Ord + Clone, Type> Default for RadixTrie<Key, Type> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key: Ord + Clone, Type, K: IntoIterator<Item = Key>> FromIterator<(K, Type)>
+    for RadixTrie<Key, Type>
+{
+    fn from_iter<I: IntoIterator<Item = (K, Type)>>(iter: I) -> Self {
+        let mut trie = RadixTrie::new();
+        for (key, value) in iter {
+            trie.insert(key, value);
+        }
+        trie
+    }
+}
+
+/// The children of a node left to visit, and the length of the sequence of keys leading to the
+/// node.
+type Frame<'a, Key, Type> = (btree_map::Values<'a, Key, Node<Key, Type>>, usize);
+
+/// An iterator over the entries of a `RadixTrie` in lexicographic order, yielding each
+/// sequence of keys as a `Vec`.
+pub struct Iter<'a, Key, Type> {
+    /// A frame for every node on the path from the start node.
+    stack: Vec<Frame<'a, Key, Type>>,
+    /// The sequence of keys leading to the last visited node.
+    path: Vec<Key>,
+    /// The value of the start node, which comes before the values below it.
+    pending: Option<&'a Type>,
+}
+
+impl<'a, Key, Type> Iter<'a, Key, Type> {
+    fn new(start: &'a Node<Key, Type>, path: Vec<Key>) -> Self {
+        Self {
+            stack: vec![(start.children.values(), path.len())],
+            path,
+            pending: start.value.as_ref(),
+        }
+    }
+}
+
+impl<'a, Key: Clone, Type> Iterator for Iter<'a, Key, Type> {
+    type Item = (Vec<Key>, &'a Type);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.pending.take() {
+            return Some((self.path.clone(), value));
+        }
+        loop {
+            let (children, len) = self.stack.last_mut()?;
+            match children.next() {
+                Some(child) => {
+                    self.path.truncate(*len);
+                    self.path.extend_from_slice(&child.label);
+                    self.stack.push((child.children.values(), self.path.len()));
+                    if let Some(value) = &child.value {
+                        return Some((self.path.clone(), value));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::Trie;
+    use quickcheck_macros::quickcheck;
+
+    fn count_nodes<Key, Type>(node: &Node<Key, Type>) -> usize {
+        1 + node.children.values().map(count_nodes).sum::<usize>()
+    }
+
+    #[test]
+    fn test_split_and_merge_edges() {
+        let mut trie = RadixTrie::new();
+        assert_eq!(trie.insert("romane".chars(), 1), None);
+        assert_eq!(trie.insert("romanus".chars(), 2), None);
+        assert_eq!(trie.insert("romulus".chars(), 3), None);
+        assert_eq!(trie.insert("rom".chars(), 4), None);
+        assert_eq!(trie.insert("romane".chars(), 5), Some(1));
+        assert_eq!(trie.len(), 4);
+        // root, "rom", "an", "e", "us", "ulus"
+        assert_eq!(count_nodes(&trie.root), 6);
+
+        assert_eq!(trie.get("roman".chars()), None);
+        assert_eq!(trie.get("romanus".chars()), Some(&2));
+        assert_eq!(trie.get("ro".chars()), None);
+        *trie.get_mut("rom".chars()).unwrap() += 10;
+        assert_eq!(trie.get("rom".chars()), Some(&14));
+
+        assert_eq!(trie.remove("roman".chars()), None);
+        assert_eq!(trie.remove("romanus".chars()), Some(2));
+        // "an" and "e" have been merged back
+        assert_eq!(count_nodes(&trie.root), 4);
+        assert_eq!(trie.get("romane".chars()), Some(&5));
+        assert_eq!(trie.remove("rom".chars()), Some(14));
+        assert_eq!(trie.remove("romane".chars()), Some(5));
+        assert_eq!(trie.remove("romulus".chars()), Some(3));
+        assert!(trie.is_empty());
+        assert_eq!(count_nodes(&trie.root), 1);
+    }
+
+    #[test]
+    fn test_iter_prefix_and_longest_prefix() {
+        let trie: RadixTrie<char, usize> = ["test", "team", "toast", "te", "slow"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| (word.chars(), i))
+            .collect();
+
+        let words = |iter: Iter<'_, char, usize>| -> Vec<String> {
+            iter.map(|(key, _)| key.into_iter().collect()).collect()
+        };
+        assert_eq!(words(trie.iter()), ["slow", "te", "team", "test", "toast"]);
+        assert_eq!(
+            words(trie.iter_prefix("te".chars())),
+            ["te", "team", "test"]
+        );
+        // the prefix ends in the middle of an edge
+        assert_eq!(words(trie.iter_prefix("tea".chars())), ["team"]);
+        assert_eq!(words(trie.iter_prefix("to".chars())), ["toast"]);
+        assert!(words(trie.iter_prefix("tx".chars())).is_empty());
+
+        assert_eq!(trie.longest_prefix("teams".chars()), Some((4, &1)));
+        assert_eq!(trie.longest_prefix("tease".chars()), Some((2, &3)));
+        assert_eq!(trie.longest_prefix("toa".chars()), None);
+    }
+
+    /// Applies the same operations to a `RadixTrie` and a `Trie`.
+    #[quickcheck]
+    fn prop_matches_trie(ops: Vec<(bool, Vec<u8>)>) -> bool {
+        let mut radix = RadixTrie::new();
+        let mut trie = Trie::new();
+        for (i, (insert, key)) in ops.into_iter().enumerate() {
+            // few distinct keys, so that they share prefixes
+            let key: Vec<u8> = key.into_iter().map(|k| k % 3).take(6).collect();
+            let same = if insert {
+                radix.insert(key.clone(), i) == trie.insert(key.clone(), i)
+            } else {
+                radix.remove(key.clone()) == trie.remove(key.clone())
+            };
+            let prefix = &key[..key.len() / 2];
+            if !same
+                || radix.len() != trie.len()
+                || radix.longest_prefix(key.clone()) != trie.longest_prefix(key.clone())
+                || !radix
+                    .iter_prefix(prefix.to_vec())
+                    .eq(trie.iter_prefix(prefix.to_vec()))
+            {
+                return false;
+            }
+        }
+        radix.iter().eq(trie.iter())
+    }
+}
@@ -7,8 +7,17 @@
     Query complexity: O(1)
 
     Wikipedia: <https://en.wikipedia.org/wiki/Range_minimum_query>
+
+    The FischerHeunRmq brings the precomputation down to O(n) for large inputs: it splits
+    the array into blocks of about log(n)/4 elements, keeps a sparse table over the block
+    minima only, and answers queries inside a block from lookup tables shared by all blocks
+    with the same Cartesian tree shape, of which there are few.
+
+    Precomputation complexity: O(n)
+    Query complexity: O(1)
 */
 
+use super::SparseTable;
 use std::cmp::PartialOrd;
 use std::fmt;
 
@@ -23,70 +32,147 @@ impl fmt::Display for RangeError {
 }
 
 pub struct RangeMinimumQuery<T: PartialOrd + Copy> {
-    // the current version makes a copy of the input array, but this could be changed
-    // to references if needed (in that case, we dont need T to implement the Copy trait)
-    array: Vec<T>,
-    sparse_table: Vec<Vec<usize>>,
+    sparse_table: SparseTable<T, fn(&T, &T) -> T>,
 }
 
 impl<T: PartialOrd + Copy> RangeMinimumQuery<T> {
     pub fn new(input: &[T]) -> RangeMinimumQuery<T> {
         RangeMinimumQuery {
+            sparse_table: SparseTable::new(input, min),
+        }
+    }
+
+    pub fn get_range_min(&self, start: usize, end: usize) -> Result<T, RangeError> {
+        self.sparse_table.query(start, end)
+    }
+}
+
+/// Blocks of at most this size keep the shape lookup tables small.
+const MAX_BLOCK_SIZE: usize = 8;
+
+pub struct FischerHeunRmq<T: PartialOrd + Copy> {
+    array: Vec<T>,
+    block_size: usize,
+    /// The Cartesian tree shape of each block, as an index into `in_block`.
+    shapes: Vec<usize>,
+    /// For each shape that occurs, the offset of the minimum of every range `i..=j` inside
+    /// a block of that shape, at index `i * block_size + j`.
+    in_block: Vec<Vec<u8>>,
+    block_minima: SparseTable<T, fn(&T, &T) -> T>,
+}
+
+impl<T: PartialOrd + Copy> FischerHeunRmq<T> {
+    pub fn new(input: &[T]) -> FischerHeunRmq<T> {
+        let block_size =
+            (input.len().checked_ilog2().unwrap_or(0) as usize / 4).clamp(1, MAX_BLOCK_SIZE);
+        let mut in_block = vec![Vec::new(); 1 << (2 * block_size + 1)];
+        let mut shapes = Vec::new();
+        let mut minima = Vec::new();
+        for block in input.chunks(block_size) {
+            let shape = cartesian_shape(block);
+            if in_block[shape].is_empty() {
+                in_block[shape] = block_table(block, block_size);
+            }
+            shapes.push(shape);
+            minima.push(block[usize::from(in_block[shape][block.len() - 1])]);
+        }
+        FischerHeunRmq {
             array: input.to_vec(),
-            sparse_table: build_sparse_table(input),
+            block_size,
+            shapes,
+            in_block,
+            block_minima: SparseTable::new(&minima, min),
         }
     }
 
     pub fn get_range_min(&self, start: usize, end: usize) -> Result<T, RangeError> {
-        if start >= end || start >= self.array.len() || end > self.array.len() {
+        if start >= end || end > self.array.len() {
             return Err(RangeError);
         }
-        let loglen = (end - start).ilog2() as usize;
-        let idx: usize = end - (1 << loglen);
-        let a = self.sparse_table[loglen][start];
-        let b = self.sparse_table[loglen][idx];
-        if self.array[a] < self.array[b] {
-            return Ok(self.array[a]);
+        let (first, last) = (start / self.block_size, (end - 1) / self.block_size);
+        if first == last {
+            return Ok(self.block_min(first, start, end - 1));
+        }
+        let mut result = min(
+            &self.block_min(first, start, (first + 1) * self.block_size - 1),
+            &self.block_min(last, last * self.block_size, end - 1),
+        );
+        if first + 1 < last {
+            result = min(&result, &self.block_minima.query(first + 1, last)?);
         }
-        Ok(self.array[b])
+        Ok(result)
+    }
+
+    /// Returns the minimum of `array[from..=to]`, which lies inside block `block`.
+    fn block_min(&self, block: usize, from: usize, to: usize) -> T {
+        let offset = block * self.block_size;
+        let table = &self.in_block[self.shapes[block]];
+        let best = table[(from - offset) * self.block_size + to - offset];
+        self.array[offset + usize::from(best)]
     }
 }
 
-fn build_sparse_table<T: PartialOrd>(array: &[T]) -> Vec<Vec<usize>> {
-    let mut table: Vec<Vec<usize>> = vec![(0..array.len()).collect()];
-    let len = array.len();
-
-    for loglen in 1..=len.ilog2() {
-        let mut row = Vec::new();
-        for i in 0..=len - (1 << loglen) {
-            let a = table[table.len() - 1][i];
-            let b = table[table.len() - 1][i + (1 << (loglen - 1))];
-            if array[a] < array[b] {
-                row.push(a);
-            } else {
-                row.push(b);
+fn min<T: PartialOrd + Copy>(a: &T, b: &T) -> T {
+    if b < a {
+        *b
+    } else {
+        *a
+    }
+}
+
+/// Encodes the shape of the Cartesian tree of `block` as the pushes (1) and pops (0) of the
+/// stack that builds it, after a leading 1. Blocks with the same shape have their minima at
+/// the same offsets for every range.
+fn cartesian_shape<T: PartialOrd>(block: &[T]) -> usize {
+    let mut shape = 1;
+    let mut stack: Vec<&T> = Vec::new();
+    for value in block {
+        while stack.last().is_some_and(|&top| top > value) {
+            stack.pop();
+            shape <<= 1;
+        }
+        stack.push(value);
+        shape = (shape << 1) | 1;
+    }
+    shape
+}
+
+/// Computes the offset of the leftmost minimum of every range `i..=j` inside `block`.
+fn block_table<T: PartialOrd>(block: &[T], block_size: usize) -> Vec<u8> {
+    let mut table = vec![0; block_size * block_size];
+    for i in 0..block.len() {
+        let mut best = i;
+        for j in i..block.len() {
+            if block[j] < block[best] {
+                best = j;
             }
+            table[i * block_size + j] = best as u8;
         }
-        table.push(row);
     }
     table
 }
 
 #[cfg(test)]
 mod tests {
-    use super::build_sparse_table;
-    macro_rules! test_build_sparse_table {
+    use super::RangeMinimumQuery;
+    /// `expected[k][i]` is the index of the minimum of the range of length `2^k` starting at `i`
+    macro_rules! test_power_of_two_ranges {
         ($($name:ident: $inputs:expr,)*) => {
         $(
             #[test]
             fn $name() {
                 let (array, expected) = $inputs;
-                assert_eq!(build_sparse_table(&array), expected);
+                let rmq = RangeMinimumQuery::new(&array);
+                for (k, row) in expected.iter().enumerate() {
+                    for (start, &index) in row.iter().enumerate() {
+                        assert_eq!(rmq.get_range_min(start, start + (1 << k)), Ok(array[index]));
+                    }
+                }
             }
         )*
         }
     }
-    test_build_sparse_table! {
+    test_power_of_two_ranges! {
     small: ([1, 6, 3], vec![vec![0, 1, 2], vec![0, 2]]),
     tc_1: ([1, 3, 6, 123, 7, 235, 3, -4, 6, 2], vec![
         vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
@@ -130,4 +216,45 @@ mod tests {
         assert_eq!(Ok(12.2), sparse_v1.get_range_min(3, 5));
         assert_eq!(Ok(0.0), sparse_v1.get_range_min(2, 3));
     }
+
+    #[test]
+    fn fischer_heun_query_tests() {
+        let v1 = vec![1, 3, 6, 123, 7, 235, 3, -4, 6, 2];
+        let rmq = super::FischerHeunRmq::new(&v1);
+
+        assert_eq!(Ok(3), rmq.get_range_min(1, 6));
+        assert_eq!(Ok(-4), rmq.get_range_min(0, 10));
+        assert_eq!(Ok(6), rmq.get_range_min(8, 9));
+        assert!(rmq.get_range_min(4, 3).is_err());
+        assert!(rmq.get_range_min(0, 1000).is_err());
+        assert!(super::FischerHeunRmq::<i32>::new(&[])
+            .get_range_min(0, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn fischer_heun_large_input() {
+        // Few distinct values make many blocks share a shape, and ties must still agree.
+        let values: Vec<u8> = (0..5000u32).map(|i| (i * 7919 % 13) as u8).collect();
+        let rmq = super::FischerHeunRmq::new(&values);
+        let sparse = super::RangeMinimumQuery::new(&values);
+        for start in (0..values.len()).step_by(37) {
+            for end in (start + 1..=values.len()).step_by(53) {
+                assert_eq!(
+                    rmq.get_range_min(start, end),
+                    sparse.get_range_min(start, end)
+                );
+            }
+        }
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn fischer_heun_matches_naive(values: Vec<i8>) -> bool {
+        let rmq = super::FischerHeunRmq::new(&values);
+        (0..values.len()).all(|start| {
+            (start + 1..=values.len()).all(|end| {
+                rmq.get_range_min(start, end) == Ok(*values[start..end].iter().min().unwrap())
+            })
+        })
+    }
 }
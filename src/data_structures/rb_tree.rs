@@ -1,6 +1,8 @@
 use std::boxed::Box;
 use std::cmp::{Ord, Ordering};
 use std::iter::Iterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
 use std::ptr::null_mut;
 
 #[derive(Copy, Clone)]
@@ -13,6 +15,8 @@ pub struct RBNode<K: Ord, V> {
     key: K,
     value: V,
     color: Color,
+    /// Number of nodes in the subtree rooted at this node.
+    size: usize,
     parent: *mut RBNode<K, V>,
     left: *mut RBNode<K, V>,
     right: *mut RBNode<K, V>,
@@ -24,6 +28,7 @@ impl<K: Ord, V> RBNode<K, V> {
             key,
             value,
             color: Color::Red,
+            size: 1,
             parent: null_mut(),
             left: null_mut(),
             right: null_mut(),
@@ -86,6 +91,11 @@ impl<K: Ord, V> RBTree<K, V> {
                 self.root = node;
             }
             (*node).parent = parent;
+            let mut ancestor = parent;
+            while !ancestor.is_null() {
+                (*ancestor).size += 1;
+                ancestor = (*ancestor).parent;
+            }
             insert_fixup(self, node);
         }
     }
@@ -117,6 +127,17 @@ impl<K: Ord, V> RBTree<K, V> {
             let cr = (*node).right;
             let mut deleted_color;
 
+            /* Every ancestor of the node that leaves its position loses one descendant. */
+            let mut ancestor = if cl.is_null() || cr.is_null() {
+                (*node).parent
+            } else {
+                (*leftmost(cr)).parent
+            };
+            while !ancestor.is_null() {
+                (*ancestor).size -= 1;
+                ancestor = (*ancestor).parent;
+            }
+
             if cl.is_null() {
                 replace_node(self, parent, node, cr);
                 if cr.is_null() {
@@ -157,11 +178,8 @@ impl<K: Ord, V> RBTree<K, V> {
                 (*cl).color = Color::Black;
                 deleted_color = Color::Red;
             } else {
-                let mut victim = (*node).right;
-                while !(*victim).left.is_null() {
-                    victim = (*victim).left;
-                }
-                if victim == (*node).right {
+                let victim = leftmost(cr);
+                if victim == cr {
                     /* Case 4 - victim is the right child of node
                      *
                      *     N         N         n
@@ -177,6 +195,7 @@ impl<K: Ord, V> RBTree<K, V> {
 
                     replace_node(self, parent, node, victim);
                     (*victim).parent = parent;
+                    (*victim).size = (*node).size;
                     deleted_color = (*victim).color;
                     (*victim).color = (*node).color;
                     (*victim).left = cl;
@@ -205,6 +224,7 @@ impl<K: Ord, V> RBTree<K, V> {
                     }
                     replace_node(self, parent, node, victim);
                     (*victim).parent = parent;
+                    (*victim).size = (*node).size;
                     (*victim).color = (*node).color;
                     (*victim).left = cl;
                     (*victim).right = cr;
@@ -216,7 +236,8 @@ impl<K: Ord, V> RBTree<K, V> {
 
             /* release resource */
             drop(Box::from_raw(node));
-            if matches!(deleted_color, Color::Black) {
+            /* Removing the last node leaves nothing to fix. */
+            if matches!(deleted_color, Color::Black) && !parent.is_null() {
                 delete_fixup(self, parent);
             }
         }
@@ -233,6 +254,479 @@ impl<K: Ord, V> RBTree<K, V> {
         }
         iterator
     }
+
+    /// Returns the number of entries in the tree.
+    pub fn len(&self) -> usize {
+        unsafe { size(self.root) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_null()
+    }
+
+    /// Returns the entry with the `index`-th smallest key, counting from zero.
+    pub fn select(&self, index: usize) -> Option<(&K, &V)> {
+        unsafe { entry(select_node(self.root, index)) }
+    }
+
+    /// Returns the number of keys smaller than `key`.
+    pub fn rank(&self, key: &K) -> usize {
+        unsafe { count_before(self.root, |k| k < key) }
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.select(0)
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.select(self.len().checked_sub(1)?)
+    }
+
+    /// Returns the entry with the largest key smaller than `key`.
+    pub fn predecessor(&self, key: &K) -> Option<(&K, &V)> {
+        self.select(self.rank(key).checked_sub(1)?)
+    }
+
+    /// Returns the entry with the smallest key larger than `key`.
+    pub fn successor(&self, key: &K) -> Option<(&K, &V)> {
+        self.select(unsafe { count_before(self.root, |k| k <= key) })
+    }
+
+    /// Iterates over the entries whose keys lie in `range`, from either end.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> RBTreeRange<'_, K, V> {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.rank(key),
+            Bound::Excluded(key) => unsafe { count_before(self.root, |k| k <= key) },
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => unsafe { count_before(self.root, |k| k <= key) },
+            Bound::Excluded(key) => self.rank(key),
+            Bound::Unbounded => self.len(),
+        };
+        let remaining = end.saturating_sub(start);
+        unsafe {
+            RBTreeRange {
+                front: select_node(self.root, start),
+                back: if remaining == 0 {
+                    null_mut()
+                } else {
+                    select_node(self.root, end - 1)
+                },
+                remaining,
+                marker: PhantomData,
+            }
+        }
+    }
+
+    /// Moves the entries with keys greater than or equal to `key` into a new tree.
+    ///
+    /// The tree is split along the search path for `key`, joining the subtrees hanging off
+    /// either side of it, in `O(log n)` time.
+    pub fn split_off(&mut self, key: &K) -> RBTree<K, V> {
+        unsafe {
+            let root = std::mem::replace(&mut self.root, null_mut());
+            let ((left, _), (right, _)) = split(root, black_height(root), key);
+            self.root = detach(left);
+            RBTree {
+                root: detach(right),
+            }
+        }
+    }
+
+    /// Moves all entries of `other` into this tree, leaving `other` empty.
+    ///
+    /// When all keys of one tree are smaller than those of the other, both are joined in
+    /// `O(log n + log m)` time. Otherwise values of `other` win over values of keys present
+    /// in both trees, and the sorted nodes of both trees are merged and rebuilt into one
+    /// tree in `O(n + m)` time.
+    pub fn append(&mut self, other: &mut RBTree<K, V>) {
+        unsafe {
+            let (ours, theirs) = (self.root, other.root);
+            if ours.is_null() || theirs.is_null() {
+                self.root = if ours.is_null() { theirs } else { ours };
+                other.root = null_mut();
+                return;
+            }
+            let (low, high) = if (*rightmost(ours)).key < (*leftmost(theirs)).key {
+                (ours, theirs)
+            } else if (*rightmost(theirs)).key < (*leftmost(ours)).key {
+                (theirs, ours)
+            } else {
+                (null_mut(), null_mut())
+            };
+            if !low.is_null() {
+                other.root = null_mut();
+                let (middle, (high, high_height)) = split_first(high, black_height(high));
+                self.root = join((low, black_height(low)), middle, (high, high_height)).0;
+                return;
+            }
+
+            let ours = take_nodes(self);
+            let theirs = take_nodes(other);
+            let mut merged = Vec::with_capacity(ours.len() + theirs.len());
+            let mut ours = ours.into_iter().peekable();
+            for node in theirs {
+                while let Some(&mine) = ours.peek() {
+                    match (*mine).key.cmp(&(*node).key) {
+                        Ordering::Less => merged.push(mine),
+                        Ordering::Equal => drop(Box::from_raw(mine)),
+                        Ordering::Greater => break,
+                    }
+                    ours.next();
+                }
+                merged.push(node);
+            }
+            merged.extend(ours);
+            self.root = build(&merged);
+        }
+    }
+}
+
+#[inline]
+unsafe fn size<K: Ord, V>(node: *const RBNode<K, V>) -> usize {
+    if node.is_null() {
+        0
+    } else {
+        (*node).size
+    }
+}
+
+#[inline]
+unsafe fn leftmost<K: Ord, V>(mut node: *mut RBNode<K, V>) -> *mut RBNode<K, V> {
+    while !(*node).left.is_null() {
+        node = (*node).left;
+    }
+    node
+}
+
+#[inline]
+unsafe fn rightmost<K: Ord, V>(mut node: *mut RBNode<K, V>) -> *mut RBNode<K, V> {
+    while !(*node).right.is_null() {
+        node = (*node).right;
+    }
+    node
+}
+
+/// Returns the node that follows `node` in key order, or null.
+unsafe fn next_node<K: Ord, V>(mut node: *mut RBNode<K, V>) -> *mut RBNode<K, V> {
+    if !(*node).right.is_null() {
+        return leftmost((*node).right);
+    }
+    while !(*node).parent.is_null() && (*(*node).parent).right == node {
+        node = (*node).parent;
+    }
+    (*node).parent
+}
+
+/// Returns the node that precedes `node` in key order, or null.
+unsafe fn prev_node<K: Ord, V>(mut node: *mut RBNode<K, V>) -> *mut RBNode<K, V> {
+    if !(*node).left.is_null() {
+        return rightmost((*node).left);
+    }
+    while !(*node).parent.is_null() && (*(*node).parent).left == node {
+        node = (*node).parent;
+    }
+    (*node).parent
+}
+
+/// Returns the node with the `index`-th smallest key, or null.
+unsafe fn select_node<K: Ord, V>(
+    mut node: *mut RBNode<K, V>,
+    mut index: usize,
+) -> *mut RBNode<K, V> {
+    while !node.is_null() {
+        let left_size = size((*node).left);
+        node = match index.cmp(&left_size) {
+            Ordering::Less => (*node).left,
+            Ordering::Equal => return node,
+            Ordering::Greater => {
+                index -= left_size + 1;
+                (*node).right
+            }
+        }
+    }
+    null_mut()
+}
+
+/// Counts the keys for which `is_before` holds, given that it holds for a prefix of the keys.
+unsafe fn count_before<K: Ord, V, F: Fn(&K) -> bool>(
+    mut node: *mut RBNode<K, V>,
+    is_before: F,
+) -> usize {
+    let mut count = 0;
+    while !node.is_null() {
+        if is_before(&(*node).key) {
+            count += size((*node).left) + 1;
+            node = (*node).right;
+        } else {
+            node = (*node).left;
+        }
+    }
+    count
+}
+
+unsafe fn entry<'a, K: Ord, V>(node: *const RBNode<K, V>) -> Option<(&'a K, &'a V)> {
+    if node.is_null() {
+        None
+    } else {
+        Some((&(*node).key, &(*node).value))
+    }
+}
+
+/// Detaches all nodes of `tree` in key order, leaving it empty.
+unsafe fn take_nodes<K: Ord, V>(tree: &mut RBTree<K, V>) -> Vec<*mut RBNode<K, V>> {
+    let mut nodes = Vec::with_capacity(tree.len());
+    if !tree.root.is_null() {
+        let mut node = leftmost(tree.root);
+        while !node.is_null() {
+            nodes.push(node);
+            node = next_node(node);
+        }
+    }
+    tree.root = null_mut();
+    nodes
+}
+
+/// A subtree along with its black height, the number of black nodes on any path from its
+/// root down to a leaf (including the root itself).
+type Subtree<K, V> = (*mut RBNode<K, V>, usize);
+
+#[inline]
+unsafe fn is_red<K: Ord, V>(node: *const RBNode<K, V>) -> bool {
+    !node.is_null() && matches!((*node).color, Color::Red)
+}
+
+/// Counts the black nodes along the leftmost path of `node`.
+unsafe fn black_height<K: Ord, V>(mut node: *const RBNode<K, V>) -> usize {
+    let mut height = 0;
+    while !node.is_null() {
+        height += usize::from(!is_red(node));
+        node = (*node).left;
+    }
+    height
+}
+
+/// Returns the black height of the children of a node with black height `height`.
+#[inline]
+unsafe fn child_height<K: Ord, V>(node: *const RBNode<K, V>, height: usize) -> usize {
+    height - usize::from(!is_red(node))
+}
+
+/// Makes `node` the root of a standalone subtree.
+#[inline]
+unsafe fn detach<K: Ord, V>(node: *mut RBNode<K, V>) -> *mut RBNode<K, V> {
+    if !node.is_null() {
+        (*node).parent = null_mut();
+    }
+    node
+}
+
+/// Links `left` and `right` below `node` and updates its size.
+unsafe fn attach<K: Ord, V>(
+    left: *mut RBNode<K, V>,
+    node: *mut RBNode<K, V>,
+    right: *mut RBNode<K, V>,
+) -> *mut RBNode<K, V> {
+    (*node).left = left;
+    (*node).right = right;
+    for child in [left, right] {
+        if !child.is_null() {
+            (*child).parent = node;
+        }
+    }
+    (*node).size = size(left) + size(right) + 1;
+    node
+}
+
+/// Joins `left`, `middle` and `right`, whose keys are in ascending order, into one tree
+/// with a black root.
+///
+/// The middle node is attached, red, where the spine of the taller tree meets the black
+/// height of the shorter one. A red node above it is then fixed with a single rotation,
+/// so this takes time proportional to the difference of black heights.
+unsafe fn join<K: Ord, V>(
+    (left, mut left_height): Subtree<K, V>,
+    middle: *mut RBNode<K, V>,
+    (right, mut right_height): Subtree<K, V>,
+) -> Subtree<K, V> {
+    if is_red(left) {
+        (*left).color = Color::Black;
+        left_height += 1;
+    }
+    if is_red(right) {
+        (*right).color = Color::Black;
+        right_height += 1;
+    }
+    let (root, height) = match left_height.cmp(&right_height) {
+        Ordering::Greater => (
+            join_right(left, left_height, middle, right, right_height),
+            left_height,
+        ),
+        Ordering::Less => (
+            join_left(left, left_height, middle, right, right_height),
+            right_height,
+        ),
+        Ordering::Equal => {
+            (*middle).color = Color::Black;
+            return (detach(attach(left, middle, right)), left_height + 1);
+        }
+    };
+    if is_red(root) {
+        (*root).color = Color::Black;
+        return (detach(root), height + 1);
+    }
+    (detach(root), height)
+}
+
+/// Attaches `middle` and `right` along the right spine of the taller tree `tree`.
+unsafe fn join_right<K: Ord, V>(
+    tree: *mut RBNode<K, V>,
+    height: usize,
+    middle: *mut RBNode<K, V>,
+    right: *mut RBNode<K, V>,
+    right_height: usize,
+) -> *mut RBNode<K, V> {
+    if !is_red(tree) && height == right_height {
+        (*middle).color = Color::Red;
+        return attach(tree, middle, right);
+    }
+    let child = join_right(
+        (*tree).right,
+        child_height(tree, height),
+        middle,
+        right,
+        right_height,
+    );
+    attach((*tree).left, tree, child);
+    if !is_red(tree) && is_red(child) && is_red((*child).right) {
+        (*(*child).right).color = Color::Black;
+        return rotate_subtree_left(tree);
+    }
+    tree
+}
+
+/// Attaches `left` and `middle` along the left spine of the taller tree `tree`.
+unsafe fn join_left<K: Ord, V>(
+    left: *mut RBNode<K, V>,
+    left_height: usize,
+    middle: *mut RBNode<K, V>,
+    tree: *mut RBNode<K, V>,
+    height: usize,
+) -> *mut RBNode<K, V> {
+    if !is_red(tree) && height == left_height {
+        (*middle).color = Color::Red;
+        return attach(left, middle, tree);
+    }
+    let child = join_left(
+        left,
+        left_height,
+        middle,
+        (*tree).left,
+        child_height(tree, height),
+    );
+    attach(child, tree, (*tree).right);
+    if !is_red(tree) && is_red(child) && is_red((*child).left) {
+        (*(*child).left).color = Color::Black;
+        return rotate_subtree_right(tree);
+    }
+    tree
+}
+
+/// Left rotation within a detached subtree, returning its new root. Colors are left to the caller.
+unsafe fn rotate_subtree_left<K: Ord, V>(node: *mut RBNode<K, V>) -> *mut RBNode<K, V> {
+    let pivot = (*node).right;
+    attach((*node).left, node, (*pivot).left);
+    attach(node, pivot, (*pivot).right)
+}
+
+/// Right rotation within a detached subtree, returning its new root. Colors are left to the caller.
+unsafe fn rotate_subtree_right<K: Ord, V>(node: *mut RBNode<K, V>) -> *mut RBNode<K, V> {
+    let pivot = (*node).left;
+    attach((*pivot).right, node, (*node).right);
+    attach((*pivot).left, pivot, node)
+}
+
+/// Splits `tree` into the subtrees with keys smaller than `key` and the rest.
+///
+/// Every node on the search path is joined back with the subtree hanging off its other
+/// side. The joins on each side take time proportional to the increase of black height,
+/// which adds up to `O(log n)`.
+unsafe fn split<K: Ord, V>(
+    tree: *mut RBNode<K, V>,
+    height: usize,
+    key: &K,
+) -> (Subtree<K, V>, Subtree<K, V>) {
+    if tree.is_null() {
+        return ((null_mut(), 0), (null_mut(), 0));
+    }
+    let (left, right) = ((*tree).left, (*tree).right);
+    let child_height = child_height(tree, height);
+    if (*tree).key < *key {
+        let (smaller, larger) = split(right, child_height, key);
+        (join((left, child_height), tree, smaller), larger)
+    } else {
+        let (smaller, larger) = split(left, child_height, key);
+        (smaller, join(larger, tree, (right, child_height)))
+    }
+}
+
+/// Detaches the node with the smallest key of a non-empty `tree`, returning it along with
+/// the remaining subtree.
+unsafe fn split_first<K: Ord, V>(
+    tree: *mut RBNode<K, V>,
+    height: usize,
+) -> (*mut RBNode<K, V>, Subtree<K, V>) {
+    let (left, right) = ((*tree).left, (*tree).right);
+    let child_height = child_height(tree, height);
+    if left.is_null() {
+        // A node without a left child has at most one red child, with no children of its own
+        let rest = detach(right);
+        if is_red(rest) {
+            (*rest).color = Color::Black;
+            return (tree, (rest, child_height + 1));
+        }
+        return (tree, (rest, child_height));
+    }
+    let (first, rest) = split_first(left, child_height);
+    (first, join(rest, tree, (right, child_height)))
+}
+
+/// Links sorted detached nodes into a balanced red-black tree and returns its root.
+///
+/// Splitting at the middle keeps all empty subtrees within one level of each other,
+/// so coloring the nodes of the deepest level red gives every path the same number
+/// of black nodes.
+unsafe fn build<K: Ord, V>(nodes: &[*mut RBNode<K, V>]) -> *mut RBNode<K, V> {
+    unsafe fn link<K: Ord, V>(
+        nodes: &[*mut RBNode<K, V>],
+        parent: *mut RBNode<K, V>,
+        depth: u32,
+        red_depth: u32,
+    ) -> *mut RBNode<K, V> {
+        if nodes.is_empty() {
+            return null_mut();
+        }
+        let mid = nodes.len() / 2;
+        let node = nodes[mid];
+        (*node).parent = parent;
+        (*node).left = link(&nodes[..mid], node, depth + 1, red_depth);
+        (*node).right = link(&nodes[mid + 1..], node, depth + 1, red_depth);
+        (*node).size = nodes.len();
+        (*node).color = if depth == red_depth {
+            Color::Red
+        } else {
+            Color::Black
+        };
+        node
+    }
+
+    if nodes.is_empty() {
+        return null_mut();
+    }
+    let root = link(nodes, null_mut(), 0, nodes.len().ilog2());
+    (*root).color = Color::Black;
+    root
 }
 
 #[inline]
@@ -446,9 +940,12 @@ unsafe fn delete_fixup<K: Ord, V>(tree: &mut RBTree<K, V>, mut parent: *mut RBNo
                 if matches!((*parent).color, Color::Black) {
                     node = parent;
                     parent = (*node).parent;
-                    continue;
+                    if !parent.is_null() {
+                        continue;
+                    }
+                } else {
+                    (*parent).color = Color::Black;
                 }
-                (*parent).color = Color::Black;
             }
         } else {
             /* node = (*parent).right */
@@ -461,7 +958,7 @@ unsafe fn delete_fixup<K: Ord, V>(tree: &mut RBTree<K, V>, mut parent: *mut RBNo
                 right_rotate(tree, parent);
                 (*parent).color = Color::Red;
                 (*sibling).color = Color::Black;
-                sibling = (*parent).right;
+                sibling = (*parent).left;
             }
             sl = (*sibling).left;
             sr = (*sibling).right;
@@ -491,9 +988,12 @@ unsafe fn delete_fixup<K: Ord, V>(tree: &mut RBTree<K, V>, mut parent: *mut RBNo
                 if matches!((*parent).color, Color::Black) {
                     node = parent;
                     parent = (*node).parent;
-                    continue;
+                    if !parent.is_null() {
+                        continue;
+                    }
+                } else {
+                    (*parent).color = Color::Black;
                 }
-                (*parent).color = Color::Black;
             }
         }
         break;
@@ -525,6 +1025,8 @@ unsafe fn left_rotate<K: Ord, V>(tree: &mut RBTree<K, V>, x: *mut RBNode<K, V>)
     if !c.is_null() {
         (*c).parent = x;
     }
+    (*y).size = (*x).size;
+    (*x).size = size((*x).left) + size(c) + 1;
     if p.is_null() {
         tree.root = y;
     } else if (*p).left == x {
@@ -560,6 +1062,8 @@ unsafe fn right_rotate<K: Ord, V>(tree: &mut RBTree<K, V>, x: *mut RBNode<K, V>)
     if !c.is_null() {
         (*c).parent = x;
     }
+    (*y).size = (*x).size;
+    (*x).size = size(c) + size((*x).right) + 1;
     if p.is_null() {
         tree.root = y;
     } else if (*p).left == x {
@@ -609,9 +1113,99 @@ impl<'a, K: Ord, V> Iterator for RBTreeIterator<'a, K, V> {
     }
 }
 
+/// A double-ended iterator over a key range of an `RBTree`.
+pub struct RBTreeRange<'a, K: Ord, V> {
+    front: *mut RBNode<K, V>,
+    back: *mut RBNode<K, V>,
+    remaining: usize,
+    marker: PhantomData<&'a RBNode<K, V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for RBTreeRange<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = self.front;
+            self.front = next_node(node);
+            entry(node)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: Ord, V> DoubleEndedIterator for RBTreeRange<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = self.back;
+            self.back = prev_node(node);
+            entry(node)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::RBTree;
+    use super::{Color, RBNode, RBTree};
+    use quickcheck_macros::quickcheck;
+    use std::ops::Bound;
+    use std::ptr::null_mut;
+
+    /// Checks parent links, subtree sizes and coloring, and returns the black height.
+    fn check_node<K: Ord, V>(node: *mut RBNode<K, V>, parent: *mut RBNode<K, V>) -> usize {
+        if node.is_null() {
+            return 1;
+        }
+        unsafe {
+            assert!((*node).parent == parent);
+            let (left, right) = ((*node).left, (*node).right);
+            if matches!((*node).color, Color::Red) {
+                for child in [left, right] {
+                    assert!(child.is_null() || matches!((*child).color, Color::Black));
+                }
+            }
+            assert_eq!((*node).size, super::size(left) + super::size(right) + 1);
+            let black_height = check_node(left, node);
+            assert_eq!(check_node(right, node), black_height);
+            black_height + usize::from(matches!((*node).color, Color::Black))
+        }
+    }
+
+    fn check_invariants<K: Ord, V>(tree: &RBTree<K, V>) {
+        check_node(tree.root, null_mut());
+        unsafe {
+            assert!(tree.root.is_null() || matches!((*tree.root).color, Color::Black));
+        }
+    }
+
+    /// Builds a tree with `keys` and the sorted, deduplicated model of its keys.
+    fn with_model(keys: &[u8], removed: &[u8]) -> (RBTree<u8, u32>, Vec<u8>) {
+        let mut tree = RBTree::new();
+        let mut model = Vec::new();
+        for &key in keys {
+            tree.insert(key, u32::from(key) * 7);
+            if let Err(index) = model.binary_search(&key) {
+                model.insert(index, key);
+            }
+        }
+        for key in removed {
+            tree.delete(key);
+            model.retain(|k| k != key);
+        }
+        check_invariants(&tree);
+        (tree, model)
+    }
 
     #[test]
     fn find() {
@@ -649,4 +1243,170 @@ mod tests {
         let s: String = tree.iter().map(|x| x.value).collect();
         assert_eq!(s, "hlo orl!");
     }
+
+    #[test]
+    fn order_statistics() {
+        let tree: RBTree<char, usize> = {
+            let mut tree = RBTree::new();
+            for (i, c) in "redblack".chars().enumerate() {
+                tree.insert(c, i);
+            }
+            tree
+        };
+        // Keys: a b c d e k l r
+        assert_eq!(tree.len(), 8);
+        assert_eq!(tree.select(0), Some((&'a', &5)));
+        assert_eq!(tree.select(7), Some((&'r', &0)));
+        assert_eq!(tree.select(8), None);
+        assert_eq!(tree.rank(&'d'), 3);
+        assert_eq!(tree.rank(&'f'), 5);
+        assert_eq!(tree.first(), Some((&'a', &5)));
+        assert_eq!(tree.last(), Some((&'r', &0)));
+        assert_eq!(tree.predecessor(&'k'), Some((&'e', &1)));
+        assert_eq!(tree.successor(&'k'), Some((&'l', &4)));
+        assert_eq!(tree.predecessor(&'a'), None);
+        assert_eq!(tree.successor(&'z'), None);
+        let keys: String = tree.range('c'..'l').map(|(k, _)| *k).collect();
+        assert_eq!(keys, "cdek");
+        let keys: String = tree.range(..='c').rev().map(|(k, _)| *k).collect();
+        assert_eq!(keys, "cba");
+    }
+
+    #[test]
+    fn empty_tree() {
+        let mut tree = RBTree::<u8, u8>::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.first(), None);
+        assert_eq!(tree.last(), None);
+        assert_eq!(tree.range(..).next(), None);
+        tree.insert(1, 1);
+        tree.delete(&1);
+        assert!(tree.is_empty());
+        assert!(tree.split_off(&0).is_empty());
+    }
+
+    #[test]
+    fn split_off_and_append_disjoint_trees() {
+        let mut tree = RBTree::new();
+        for key in 0..1000i32 {
+            tree.insert(key, key * 2);
+        }
+        for at in [0, 1, 500, 999, 1000, 2000] {
+            let mut right = tree.split_off(&at);
+            check_invariants(&tree);
+            check_invariants(&right);
+            assert_eq!(tree.len(), at.min(1000) as usize);
+            assert!(right.iter().map(|n| n.key).eq(at.min(1000)..1000));
+
+            // Append in either order
+            if at % 2 == 0 {
+                tree.append(&mut right);
+            } else {
+                right.append(&mut tree);
+                std::mem::swap(&mut tree, &mut right);
+            }
+            check_invariants(&tree);
+            assert!(right.is_empty());
+            assert!(tree
+                .iter()
+                .map(|n| (n.key, n.value))
+                .eq((0..1000).map(|k| (k, k * 2))));
+        }
+
+        // Trees of very different black heights
+        let mut small = RBTree::new();
+        small.insert(-1, 0);
+        small.append(&mut tree);
+        check_invariants(&small);
+        assert_eq!(small.len(), 1001);
+        let mut large = small.split_off(&-1);
+        large.append(&mut RBTree::new());
+        let mut big = RBTree::new();
+        big.insert(5000, 0);
+        large.append(&mut big);
+        check_invariants(&large);
+        assert_eq!(large.len(), 1002);
+        assert_eq!(large.last(), Some((&5000, &0)));
+    }
+
+    #[quickcheck]
+    fn order_statistics_match_model(keys: Vec<u8>, removed: Vec<u8>, probe: u8) -> bool {
+        let (tree, model) = with_model(&keys, &removed);
+        let rank = model.partition_point(|&k| k < probe);
+        let after = model.partition_point(|&k| k <= probe);
+        tree.len() == model.len()
+            && (0..=model.len()).all(|i| tree.select(i).map(|(k, _)| *k) == model.get(i).copied())
+            && tree.rank(&probe) == rank
+            && tree.predecessor(&probe).map(|(k, _)| *k) == rank.checked_sub(1).map(|i| model[i])
+            && tree.successor(&probe).map(|(k, _)| *k) == model.get(after).copied()
+            && tree.first().map(|(k, _)| *k) == model.first().copied()
+            && tree.last().map(|(k, _)| *k) == model.last().copied()
+    }
+
+    #[quickcheck]
+    fn ranges_match_model(keys: Vec<u8>, start: u8, end: u8, steps: Vec<bool>) -> bool {
+        let (tree, model) = with_model(&keys, &[]);
+        let ranges = [
+            (Bound::Included(start), Bound::Excluded(end)),
+            (Bound::Excluded(start), Bound::Included(end)),
+            (Bound::Unbounded, Bound::Included(end)),
+            (Bound::Included(start), Bound::Unbounded),
+        ];
+        ranges.iter().all(|&range| {
+            let mut expected = model
+                .iter()
+                .filter(|k| std::ops::RangeBounds::contains(&range, *k));
+            let mut actual = tree.range(range).map(|(k, _)| k);
+            steps.iter().all(|&forward| {
+                if forward {
+                    actual.next() == expected.next()
+                } else {
+                    actual.next_back() == expected.next_back()
+                }
+            }) && actual.eq(expected)
+        })
+    }
+
+    #[quickcheck]
+    fn split_off_then_append_restores_the_tree(keys: Vec<u8>, removed: Vec<u8>, at: u8) -> bool {
+        let (mut tree, model) = with_model(&keys, &removed);
+        let mut right = tree.split_off(&at);
+        right.append(&mut tree);
+        check_invariants(&right);
+        tree.is_empty() && right.iter().map(|n| n.key).eq(model.iter().copied())
+    }
+
+    #[quickcheck]
+    fn split_off_and_append_match_model(keys: Vec<u8>, others: Vec<u8>, at: u8) -> bool {
+        let (mut tree, model) = with_model(&keys, &[]);
+        let right = tree.split_off(&at);
+        check_invariants(&tree);
+        check_invariants(&right);
+        let split_ok = tree
+            .iter()
+            .map(|n| n.key)
+            .eq(model.iter().copied().filter(|&k| k < at))
+            && right
+                .iter()
+                .map(|n| n.key)
+                .eq(model.iter().copied().filter(|&k| k >= at))
+            && tree.len() + right.len() == model.len();
+
+        let (mut tree, mut model) = with_model(&keys, &[]);
+        let mut other = RBTree::new();
+        for &key in &others {
+            other.insert(key, u32::from(key) + 1);
+            if let Err(index) = model.binary_search(&key) {
+                model.insert(index, key);
+            }
+        }
+        tree.append(&mut other);
+        check_invariants(&tree);
+        split_ok
+            && other.is_empty()
+            && tree.iter().map(|n| n.key).eq(model.iter().copied())
+            && others
+                .iter()
+                .all(|key| tree.find(key) == Some(&(u32::from(*key) + 1)))
+    }
 }
@@ -0,0 +1,181 @@
+//! A Union-Find (Disjoint Set) structure whose unions can be undone.
+//!
+//! Path compression rewrites parent links during `find`, which can't be cheaply reverted,
+//! so this variant only uses union by size: every tree has a height of `O(log(n))` and
+//! `find` takes `O(log(n))` time. A union then changes exactly one parent link and one
+//! size, which are recorded on a stack and restored in `O(1)` by `undo`.
+//!
+//! This is what offline algorithms need when they explore a tree of states and come back
+//! up, e.g. offline dynamic connectivity (see `graph::offline_dynamic_connectivity`).
+
+/// A Union-Find structure over the elements `0..n`, with an undo stack of its unions.
+#[derive(Debug, Clone)]
+pub struct RollbackUnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    /// The root attached to another one by each union, or `None` if it merged nothing.
+    history: Vec<Option<usize>>,
+    count: usize,
+}
+
+impl RollbackUnionFind {
+    /// Creates `n` disjoint sets, one for each element of `0..n`.
+    pub fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            history: Vec::new(),
+            count: n,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// Checks if there are no elements at all.
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Returns the number of disjoint sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the root of the set containing `element`.
+    pub fn find(&self, mut element: usize) -> usize {
+        while self.parent[element] != element {
+            element = self.parent[element];
+        }
+        element
+    }
+
+    /// Checks if two elements belong to the same set.
+    pub fn is_same_set(&self, first_elem: usize, sec_elem: usize) -> bool {
+        self.find(first_elem) == self.find(sec_elem)
+    }
+
+    /// Returns the number of elements in the set containing `element`.
+    pub fn size(&self, element: usize) -> usize {
+        self.size[self.find(element)]
+    }
+
+    /// Merges the sets containing the two elements.
+    ///
+    /// Every call is recorded, even if both elements were already in the same set, so that
+    /// each `union` is reverted by exactly one `undo`.
+    ///
+    /// # Returns
+    /// `true` if two disjoint sets have been merged.
+    pub fn union(&mut self, first_elem: usize, sec_elem: usize) -> bool {
+        let mut first_root = self.find(first_elem);
+        let mut sec_root = self.find(sec_elem);
+        if first_root == sec_root {
+            self.history.push(None);
+            return false;
+        }
+        if self.size[first_root] < self.size[sec_root] {
+            std::mem::swap(&mut first_root, &mut sec_root);
+        }
+        self.parent[sec_root] = first_root;
+        self.size[first_root] += self.size[sec_root];
+        self.history.push(Some(sec_root));
+        self.count -= 1;
+        true
+    }
+
+    /// Reverts the last `union` that hasn't been undone yet.
+    ///
+    /// # Returns
+    /// `false` if there is no union left to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(last) = self.history.pop() else {
+            return false;
+        };
+        if let Some(child) = last {
+            let root = self.parent[child];
+            self.size[root] -= self.size[child];
+            self.parent[child] = child;
+            self.count += 1;
+        }
+        true
+    }
+
+    /// Returns the number of recorded unions, which `rollback` takes to come back to the
+    /// current state.
+    pub fn snapshot(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Undoes all the unions made since `snapshot` was returned by `snapshot()`.
+    pub fn rollback(&mut self, snapshot: usize) {
+        while self.history.len() > snapshot {
+            self.undo();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RollbackUnionFind;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_union_and_undo() {
+        let mut uf = RollbackUnionFind::new(6);
+        assert_eq!(uf.len(), 6);
+        assert!(uf.union(0, 1));
+        assert!(uf.union(2, 3));
+        let snapshot = uf.snapshot();
+
+        assert!(uf.union(1, 3));
+        assert!(!uf.union(0, 2));
+        assert!(uf.union(4, 0));
+        assert!(uf.is_same_set(4, 3));
+        assert_eq!(uf.size(2), 5);
+        assert_eq!(uf.count(), 2);
+
+        assert!(uf.undo());
+        assert!(!uf.is_same_set(4, 3));
+        assert!(uf.is_same_set(0, 3));
+        assert_eq!(uf.size(4), 1);
+
+        uf.rollback(snapshot);
+        assert!(!uf.is_same_set(0, 3));
+        assert!(uf.is_same_set(0, 1));
+        assert_eq!(uf.size(2), 2);
+        assert_eq!(uf.count(), 4);
+
+        uf.rollback(0);
+        assert_eq!(uf.count(), 6);
+        assert!(!uf.undo());
+    }
+
+    /// Replays the remaining unions from scratch after every undo.
+    #[quickcheck]
+    fn prop_undo_restores_previous_state(unions: Vec<(u8, u8)>, undos: u8) -> bool {
+        let n = 16;
+        let unions: Vec<(usize, usize)> = unions
+            .into_iter()
+            .map(|(a, b)| (usize::from(a) % n, usize::from(b) % n))
+            .collect();
+        let mut uf = RollbackUnionFind::new(n);
+        for &(a, b) in &unions {
+            uf.union(a, b);
+        }
+        let kept = unions.len().saturating_sub(usize::from(undos));
+        uf.rollback(kept);
+
+        let mut expected = RollbackUnionFind::new(n);
+        for &(a, b) in &unions[..kept] {
+            expected.union(a, b);
+        }
+        uf.count() == expected.count()
+            && (0..n).all(|a| {
+                uf.size(a) == expected.size(a)
+                    && (0..n).all(|b| uf.is_same_set(a, b) == expected.is_same_set(a, b))
+            })
+    }
+}
@@ -0,0 +1,356 @@
+use super::{ImplicitTreap, Monoid, Summarize};
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+/// Chunks grow by merging small insertions up to this many bytes.
+const MAX_CHUNK: usize = 512;
+
+/// The number of chars and line breaks in a piece of text.
+#[derive(Clone, Copy, Default)]
+struct TextSummary {
+    chars: usize,
+    newlines: usize,
+}
+
+struct TextMetrics;
+
+impl Monoid for TextMetrics {
+    type Value = TextSummary;
+
+    fn identity() -> TextSummary {
+        TextSummary::default()
+    }
+
+    fn combine(left: &TextSummary, right: &TextSummary) -> TextSummary {
+        TextSummary {
+            chars: left.chars + right.chars,
+            newlines: left.newlines + right.newlines,
+        }
+    }
+}
+
+impl Summarize<String> for TextMetrics {
+    fn summarize(value: &String) -> TextSummary {
+        TextSummary {
+            chars: value.chars().count(),
+            newlines: value.matches('\n').count(),
+        }
+    }
+}
+
+/// A text buffer for editing large documents.
+///
+/// The text is stored as a sequence of small chunks in an `ImplicitTreap`, which also tracks
+/// the number of chars and line breaks before every chunk. Inserting or removing text and
+/// converting between char positions and line numbers take `O(log(n))` time plus the size of
+/// the edit, where `n` is the number of chunks, instead of shifting the whole document.
+///
+/// All positions count Unicode scalar values (`char`s), not bytes. Lines are separated by
+/// `'\n'`, so a document with `k` line breaks has `k + 1` lines.
+#[derive(Default)]
+pub struct Rope {
+    /// Non-empty chunks of text, each at most `MAX_CHUNK` bytes unless inserted that way.
+    chunks: ImplicitTreap<String, TextMetrics>,
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Rope {
+            chunks: ImplicitTreap::new(),
+        }
+    }
+
+    /// Returns the number of chars in the text.
+    pub fn len_chars(&self) -> usize {
+        self.chunks.aggregate(..).chars
+    }
+
+    /// Returns the number of lines in the text, which is one more than its line breaks.
+    pub fn len_lines(&self) -> usize {
+        self.chunks.aggregate(..).newlines + 1
+    }
+
+    /// Returns `true` if the text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns the char at `char_idx`.
+    pub fn char(&self, char_idx: usize) -> Option<char> {
+        let (chunk, before) = self.locate(char_idx);
+        self.chunks.get(chunk)?.chars().nth(char_idx - before.chars)
+    }
+
+    /// Inserts `text` so that its first char ends up at `char_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is past the end of the text.
+    pub fn insert(&mut self, char_idx: usize, text: &str) {
+        assert!(char_idx <= self.len_chars(), "char index out of bounds");
+        if text.is_empty() {
+            return;
+        }
+        let index = self.split_chunk_at(char_idx);
+        if index > 0 {
+            let previous = self.chunks.get(index - 1).unwrap();
+            if previous.len() + text.len() <= MAX_CHUNK {
+                let merged = format!("{previous}{text}");
+                self.chunks.set(index - 1, merged);
+                return;
+            }
+        }
+        let mut tail = self.chunks.split_off(index);
+        let mut inserted = chunks(text).collect();
+        self.chunks.append(&mut inserted);
+        self.chunks.append(&mut tail);
+    }
+
+    /// Removes the chars in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or ends past the text.
+    pub fn remove<R: RangeBounds<usize>>(&mut self, range: R) {
+        let (start, end) = self.bounds(range);
+        let first = self.split_chunk_at(start);
+        let last = self.split_chunk_at(end);
+        self.chunks.remove_range(first..last);
+    }
+
+    /// Returns the chars in `range` as a `String`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or ends past the text.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> String {
+        let (start, end) = self.bounds(range);
+        let (chunk, before) = self.locate(start);
+        self.chunks
+            .range(chunk..)
+            .flat_map(|text| text.chars())
+            .skip(start - before.chars)
+            .take(end - start)
+            .collect()
+    }
+
+    /// Returns the char index at which line `line` starts.
+    pub fn line_to_char(&self, line: usize) -> Option<usize> {
+        if line == 0 {
+            return Some(0);
+        }
+        if line >= self.len_lines() {
+            return None;
+        }
+        // The chunk holding the line break that ends line `line - 1`.
+        let chunk = self
+            .chunks
+            .partition_point(|summary| summary.newlines < line);
+        let before = self.chunks.aggregate(..chunk);
+        let offset = self
+            .chunks
+            .get(chunk)?
+            .chars()
+            .enumerate()
+            .filter(|&(_, c)| c == '\n')
+            .nth(line - before.newlines - 1)?
+            .0;
+        Some(before.chars + offset + 1)
+    }
+
+    /// Returns the line that contains the char at `char_idx`.
+    ///
+    /// The end of the text, `len_chars`, belongs to the last line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx > len_chars`.
+    pub fn char_to_line(&self, char_idx: usize) -> usize {
+        assert!(char_idx <= self.len_chars(), "char index out of bounds");
+        let (chunk, before) = self.locate(char_idx);
+        let inside = self.chunks.get(chunk).map_or(0, |text| {
+            text.chars()
+                .take(char_idx - before.chars)
+                .filter(|&c| c == '\n')
+                .count()
+        });
+        before.newlines + inside
+    }
+
+    /// Returns the text of line `line`, including its line break if it has one.
+    pub fn line(&self, line: usize) -> Option<String> {
+        let start = self.line_to_char(line)?;
+        let end = self
+            .line_to_char(line + 1)
+            .unwrap_or_else(|| self.len_chars());
+        Some(self.slice(start..end))
+    }
+
+    /// Moves the text from `char_idx` on into a new `Rope`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx > len_chars`.
+    pub fn split_off(&mut self, char_idx: usize) -> Self {
+        assert!(char_idx <= self.len_chars(), "char index out of bounds");
+        let index = self.split_chunk_at(char_idx);
+        Rope {
+            chunks: self.chunks.split_off(index),
+        }
+    }
+
+    /// Moves the text of `other` to the end of this `Rope`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.chunks.append(&mut other.chunks);
+    }
+
+    /// Returns the index of the chunk that contains `char_idx`, and the summary of the
+    /// chunks before it. The end of the text maps to one past the last chunk.
+    fn locate(&self, char_idx: usize) -> (usize, TextSummary) {
+        let chunk = self
+            .chunks
+            .partition_point(|summary| summary.chars <= char_idx);
+        (chunk, self.chunks.aggregate(..chunk))
+    }
+
+    /// Splits the chunk that contains `char_idx` so that a chunk starts there, and
+    /// returns the index of that chunk.
+    fn split_chunk_at(&mut self, char_idx: usize) -> usize {
+        let (chunk, before) = self.locate(char_idx);
+        if before.chars == char_idx {
+            return chunk;
+        }
+        let mut head = self.chunks.get(chunk).unwrap().clone();
+        let (byte_idx, _) = head.char_indices().nth(char_idx - before.chars).unwrap();
+        let tail = head.split_off(byte_idx);
+        self.chunks.set(chunk, head);
+        self.chunks.insert(chunk + 1, tail);
+        chunk + 1
+    }
+
+    /// Resolves `range` into `start..end`.
+    fn bounds<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let len = self.len_chars();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        };
+        assert!(start <= end && end <= len, "range out of bounds");
+        (start, end)
+    }
+}
+
+/// Cuts `text` into pieces of at most `MAX_CHUNK` bytes, at char boundaries.
+fn chunks(mut text: &str) -> impl Iterator<Item = String> + '_ {
+    std::iter::from_fn(move || {
+        if text.is_empty() {
+            return None;
+        }
+        let mut end = text.len().min(MAX_CHUNK);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, rest) = text.split_at(end);
+        text = rest;
+        Some(chunk.to_string())
+    })
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        Rope {
+            chunks: chunks(text).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks.iter().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rope;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn editing_lines() {
+        let mut rope = Rope::from("fn main() {\n}\n");
+        assert_eq!(rope.len_lines(), 3);
+        rope.insert(12, "    println!(\"héllo\");\n");
+        assert_eq!(
+            rope.to_string(),
+            "fn main() {\n    println!(\"héllo\");\n}\n"
+        );
+        assert_eq!(rope.line(1).unwrap(), "    println!(\"héllo\");\n");
+        assert_eq!(rope.line(3).unwrap(), "");
+        assert_eq!(rope.line(4), None);
+        assert_eq!(rope.line_to_char(2), Some(35));
+        assert_eq!(rope.char_to_line(35), 2);
+        assert_eq!(rope.char_to_line(34), 1);
+        assert_eq!(rope.char(27), Some('é'));
+
+        rope.remove(12..35);
+        assert_eq!(rope.to_string(), "fn main() {\n}\n");
+        let mut tail = rope.split_off(3);
+        assert_eq!(tail.slice(..4), "main");
+        tail.append(&mut rope);
+        assert!(rope.is_empty());
+        assert_eq!(tail.to_string(), "main() {\n}\nfn ");
+    }
+
+    #[test]
+    fn large_document() {
+        let line = "ünïcödé text\n";
+        let mut rope = Rope::from(line.repeat(1000).as_str());
+        assert_eq!(rope.len_chars(), 13 * 1000);
+        assert_eq!(rope.len_lines(), 1001);
+        assert_eq!(rope.line_to_char(500), Some(13 * 500));
+        assert_eq!(rope.char_to_line(13 * 500 - 1), 499);
+        rope.remove(13 * 10..13 * 990);
+        assert_eq!(rope.len_lines(), 21);
+        assert_eq!(rope.line(15).unwrap(), line);
+    }
+
+    #[quickcheck]
+    fn matches_string_model(edits: Vec<(usize, usize, String, u8)>, queries: Vec<usize>) -> bool {
+        let mut rope = Rope::new();
+        let mut model: Vec<char> = Vec::new();
+        for (a, b, text, repeat) in edits {
+            let len = model.len();
+            let (a, b) = (a % (len + 1), b % (len + 1));
+            if repeat % 3 == 0 {
+                rope.remove(a.min(b)..a.max(b));
+                model.drain(a.min(b)..a.max(b));
+            } else {
+                let text = text.repeat(usize::from(repeat % 64));
+                rope.insert(a, &text);
+                model.splice(a..a, text.chars());
+            }
+        }
+        let text: String = model.iter().collect();
+        let lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let len = model.len();
+        rope.to_string() == text
+            && rope.len_chars() == len
+            && rope.len_lines() == text.matches('\n').count() + 1
+            && lines
+                .iter()
+                .enumerate()
+                .all(|(i, &line)| rope.line(i).unwrap() == line)
+            && queries.into_iter().all(|i| {
+                let i = i % (len + 1);
+                rope.char(i) == model.get(i).copied()
+                    && rope.char_to_line(i) == model[..i].iter().filter(|&&c| c == '\n').count()
+                    && rope.slice(i..) == model[i..].iter().collect::<String>()
+            })
+    }
+}
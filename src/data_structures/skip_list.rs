@@ -0,0 +1,553 @@
+//! A skip list is an ordered map built from a sorted linked list with express lanes: each node
+//! is also linked at a random number of levels above the bottom one, a node reaching level
+//! `l + 1` with probability `p` once it reaches level `l`. A search runs along the top level,
+//! dropping one level whenever the next node would overshoot the key, so it visits
+//! `O(log(n) / p)` nodes on average, without any rebalancing.
+//!
+//! Each link also records how many bottom-level steps it skips (its width), which gives the
+//! rank of a key and the key of a rank in the same `O(log(n))` expected time.
+//!
+//! The levels are drawn from a seedable `ChaCha8Rng`, so a list built with a given seed always
+//! has the same shape.
+//!
+//! # Sources
+//! - Pugh, "Skip lists: a probabilistic alternative to balanced trees", 1990
+//! - <https://en.wikipedia.org/wiki/Skip_list>
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::sync::{PoisonError, RwLock};
+
+/// The maximal number of levels, enough for `2^32` keys with `p = 1/2`.
+const MAX_LEVEL: usize = 32;
+/// The index of the head node, which has no key and all the levels.
+const HEAD: usize = 0;
+/// The index standing for the end of the list.
+const NIL: usize = usize::MAX;
+
+#[derive(Clone, Copy)]
+struct Link {
+    next: usize,
+    /// The number of bottom-level steps from this node to `next`, counting the end of the
+    /// list as the position after the last node.
+    width: usize,
+}
+
+struct Node<K, V> {
+    /// `None` for the head node and the free nodes.
+    entry: Option<(K, V)>,
+    links: Vec<Link>,
+}
+
+/// An ordered map based on a skip list.
+pub struct SkipList<K, V> {
+    /// The nodes, the head first; the slots of the removed nodes are reused.
+    nodes: Vec<Node<K, V>>,
+    free: Vec<usize>,
+    len: usize,
+    probability: f64,
+    rng: ChaCha8Rng,
+}
+
+/// The last node before a key on each level, with its position (the head being at position 0
+/// and the `i`-th key at position `i + 1`).
+type SearchPath = [(usize, usize); MAX_LEVEL];
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Creates an empty list with `p = 1/2` and a random seed.
+    pub fn new() -> Self {
+        Self::with_probability(0.5, rand::thread_rng().gen())
+    }
+
+    /// Creates an empty list with `p = 1/2`, whose shape is determined by `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_probability(0.5, seed)
+    }
+
+    /// Creates an empty list where a node reaching a level also reaches the next one with the
+    /// given probability. A smaller probability uses less memory, but makes searches longer.
+    ///
+    /// # Panics
+    /// Panics if `probability` is not strictly between 0 and 1.
+    pub fn with_probability(probability: f64, seed: u64) -> Self {
+        assert!(
+            0.0 < probability && probability < 1.0,
+            "the level probability must be strictly between 0 and 1"
+        );
+        SkipList {
+            nodes: vec![Node {
+                entry: None,
+                links: vec![
+                    Link {
+                        next: NIL,
+                        width: 1
+                    };
+                    MAX_LEVEL
+                ],
+            }],
+            free: Vec::new(),
+            len: 0,
+            probability,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn key(&self, node: usize) -> &K {
+        &self.nodes[node].entry.as_ref().unwrap().0
+    }
+
+    fn entry(&self, node: usize) -> Option<(&K, &V)> {
+        let (key, value) = self.nodes.get(node)?.entry.as_ref()?;
+        Some((key, value))
+    }
+
+    /// Returns the last node on each level for which `is_before` holds, given that it holds for
+    /// a prefix of the keys.
+    fn search_path(&self, is_before: impl Fn(&K) -> bool) -> SearchPath {
+        let mut path = [(HEAD, 0); MAX_LEVEL];
+        let (mut node, mut position) = (HEAD, 0);
+        for level in (0..MAX_LEVEL).rev() {
+            loop {
+                let link = self.nodes[node].links[level];
+                if link.next == NIL || !is_before(self.key(link.next)) {
+                    break;
+                }
+                node = link.next;
+                position += link.width;
+            }
+            path[level] = (node, position);
+        }
+        path
+    }
+
+    /// Returns the first node for which `is_before` does not hold, or `NIL`.
+    fn first_not_before(&self, is_before: impl Fn(&K) -> bool) -> usize {
+        let (node, _) = self.search_path(is_before)[0];
+        self.nodes[node].links[0].next
+    }
+
+    /// Returns the node holding `key`, or `NIL`.
+    fn find(&self, key: &K) -> usize {
+        let node = self.first_not_before(|k| k < key);
+        if node != NIL && self.key(node) == key {
+            node
+        } else {
+            NIL
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        while level < MAX_LEVEL && self.rng.gen_bool(self.probability) {
+            level += 1;
+        }
+        level
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entry(self.find(key)).map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = self.find(key);
+        let (_, value) = self.nodes.get_mut(node)?.entry.as_mut()?;
+        Some(value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key) != NIL
+    }
+
+    /// Inserts a key with its value, returning the previous value of the key if it was
+    /// already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let path = self.search_path(|k| *k < key);
+        let next = self.nodes[path[0].0].links[0].next;
+        if next != NIL && *self.key(next) == key {
+            let (_, old) = self.nodes[next].entry.as_mut().unwrap();
+            return Some(mem::replace(old, value));
+        }
+
+        let height = self.random_level();
+        let position = path[0].1 + 1;
+        let node = self.free.pop().unwrap_or_else(|| {
+            self.nodes.push(Node {
+                entry: None,
+                links: Vec::new(),
+            });
+            self.nodes.len() - 1
+        });
+        let mut links = Vec::with_capacity(height);
+        for (level, &(prev, prev_position)) in path.iter().enumerate() {
+            let link = &mut self.nodes[prev].links[level];
+            if level < height {
+                // The node after `prev` moves one position further
+                links.push(Link {
+                    next: link.next,
+                    width: prev_position + link.width + 1 - position,
+                });
+                *link = Link {
+                    next: node,
+                    width: position - prev_position,
+                };
+            } else {
+                link.width += 1;
+            }
+        }
+        self.nodes[node] = Node {
+            entry: Some((key, value)),
+            links,
+        };
+        self.len += 1;
+        None
+    }
+
+    /// Removes a key, returning its value if it was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let path = self.search_path(|k| k < key);
+        let node = self.nodes[path[0].0].links[0].next;
+        if node == NIL || self.key(node) != key {
+            return None;
+        }
+
+        let links = mem::take(&mut self.nodes[node].links);
+        for (level, &(prev, _)) in path.iter().enumerate() {
+            let link = &mut self.nodes[prev].links[level];
+            if link.next == node {
+                *link = Link {
+                    next: links[level].next,
+                    width: link.width + links[level].width - 1,
+                };
+            } else {
+                link.width -= 1;
+            }
+        }
+        self.free.push(node);
+        self.len -= 1;
+        self.nodes[node].entry.take().map(|(_, value)| value)
+    }
+
+    /// Returns the number of keys smaller than `key`.
+    pub fn rank(&self, key: &K) -> usize {
+        self.search_path(|k| k < key)[0].1
+    }
+
+    /// Returns the `index`-th smallest key with its value, counting from zero.
+    pub fn select(&self, index: usize) -> Option<(&K, &V)> {
+        let target = index.checked_add(1)?;
+        let (mut node, mut position) = (HEAD, 0);
+        for level in (0..MAX_LEVEL).rev() {
+            loop {
+                let link = self.nodes[node].links[level];
+                if link.next == NIL || position + link.width > target {
+                    break;
+                }
+                node = link.next;
+                position += link.width;
+            }
+            if position == target {
+                return self.entry(node);
+            }
+        }
+        None
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.entry(self.nodes[HEAD].links[0].next)
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.select(self.len.checked_sub(1)?)
+    }
+
+    /// Returns an iterator over the keys and values, in ascending order of keys.
+    pub fn iter(&self) -> Range<'_, K, V> {
+        self.range(..)
+    }
+
+    /// Returns an iterator over the keys within `range` and their values, in ascending order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        // The node before the range, and the numbers of keys before and up to its end
+        let (before, skipped) = match range.start_bound() {
+            Bound::Included(start) => self.search_path(|k| k < start)[0],
+            Bound::Excluded(start) => self.search_path(|k| k <= start)[0],
+            Bound::Unbounded => (HEAD, 0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.search_path(|k| k <= end)[0].1,
+            Bound::Excluded(end) => self.search_path(|k| k < end)[0].1,
+            Bound::Unbounded => self.len,
+        };
+        Range {
+            list: self,
+            next: self.nodes[before].links[0].next,
+            remaining: end.saturating_sub(skipped),
+        }
+    }
+}
+
+impl<K: Ord, V> Default for SkipList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipList<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for (key, value) in iter {
+            list.insert(key, value);
+        }
+        list
+    }
+}
+
+/// An iterator over a range of entries of a `SkipList`.
+pub struct Range<'a, K, V> {
+    list: &'a SkipList<K, V>,
+    next: usize,
+    remaining: usize,
+}
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.next;
+        self.next = self.list.nodes[node].links[0].next;
+        self.remaining -= 1;
+        self.list.entry(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: Ord, V> ExactSizeIterator for Range<'_, K, V> {}
+
+/// A `SkipList` shared between threads: any number of threads can read it while another one
+/// updates it.
+///
+/// The list is behind a `RwLock`: the readers hold the read lock together, and a writer only
+/// takes the write lock for the duration of a single update. Since references cannot outlive
+/// the lock, the values are returned as clones, and range scans as vectors.
+pub struct ConcurrentSkipList<K, V> {
+    list: RwLock<SkipList<K, V>>,
+}
+
+impl<K: Ord, V: Clone> ConcurrentSkipList<K, V> {
+    pub fn new() -> Self {
+        Self::from_list(SkipList::new())
+    }
+
+    /// Wraps an existing list, e.g. one with a chosen seed and probability.
+    pub fn from_list(list: SkipList<K, V>) -> Self {
+        ConcurrentSkipList {
+            list: RwLock::new(list),
+        }
+    }
+
+    /// Takes the list back.
+    pub fn into_inner(self) -> SkipList<K, V> {
+        self.list
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Runs `f` on the list with the read lock held. A panic of another thread in the middle of
+    /// an update cannot leave the list inconsistent, so a poisoned lock is used anyway.
+    pub fn read<R>(&self, f: impl FnOnce(&SkipList<K, V>) -> R) -> R {
+        f(&self.list.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn write<R>(&self, f: impl FnOnce(&mut SkipList<K, V>) -> R) -> R {
+        f(&mut self.list.write().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    pub fn len(&self) -> usize {
+        self.read(SkipList::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read(SkipList::is_empty)
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.read(|list| list.get(key).cloned())
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.read(|list| list.contains_key(key))
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.write(|list| list.insert(key, value))
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.write(|list| list.remove(key))
+    }
+
+    pub fn rank(&self, key: &K) -> usize {
+        self.read(|list| list.rank(key))
+    }
+
+    /// Returns a snapshot of the entries within `range`, in ascending order of keys.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Vec<(K, V)>
+    where
+        K: Clone,
+    {
+        self.read(|list| {
+            list.range(range)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+    }
+}
+
+impl<K: Ord, V: Clone> Default for ConcurrentSkipList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConcurrentSkipList, SkipList};
+    use quickcheck_macros::quickcheck;
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    #[test]
+    fn test_map_operations() {
+        let mut list = SkipList::with_seed(7);
+        assert!(list.is_empty());
+        for key in [5, 1, 9, 3, 7] {
+            assert_eq!(list.insert(key, key * 10), None);
+        }
+        assert_eq!(list.insert(3, 33), Some(30));
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.get(&3), Some(&33));
+        assert_eq!(list.get(&4), None);
+        *list.get_mut(&9).unwrap() += 1;
+        assert_eq!(list.get(&9), Some(&91));
+
+        let keys: Vec<i32> = list.iter().map(|(&k, _)| k).collect();
+        assert_eq!(keys, [1, 3, 5, 7, 9]);
+        let keys: Vec<i32> = list.range(3..9).map(|(&k, _)| k).collect();
+        assert_eq!(keys, [3, 5, 7]);
+        let keys: Vec<i32> = list
+            .range((Bound::Excluded(3), Bound::Included(9)))
+            .map(|(&k, _)| k)
+            .collect();
+        assert_eq!(keys, [5, 7, 9]);
+        assert_eq!(list.range(4..5).count(), 0);
+        assert_eq!(list.range(6..).len(), 2);
+
+        assert_eq!(list.rank(&5), 2);
+        assert_eq!(list.rank(&6), 3);
+        assert_eq!(list.select(3), Some((&7, &70)));
+        assert_eq!(list.select(5), None);
+        assert_eq!(list.first(), Some((&1, &10)));
+        assert_eq!(list.last(), Some((&9, &91)));
+
+        assert_eq!(list.remove(&5), Some(50));
+        assert_eq!(list.remove(&5), None);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.rank(&9), 3);
+        assert_eq!(list.insert(4, 40), None);
+        let keys: Vec<i32> = list.iter().map(|(&k, _)| k).collect();
+        assert_eq!(keys, [1, 3, 4, 7, 9]);
+    }
+
+    #[test]
+    fn test_same_seed_same_shape() {
+        let build = || {
+            let mut list = SkipList::with_probability(0.25, 42);
+            for key in 0..1000 {
+                list.insert(key, ());
+            }
+            list.nodes
+                .iter()
+                .map(|node| node.links.len())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(build(), build());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_probability() {
+        SkipList::<u8, ()>::with_probability(1.0, 0);
+    }
+
+    #[test]
+    fn test_readers_alongside_writer() {
+        let list = ConcurrentSkipList::from_list(SkipList::with_seed(1));
+        for key in 0..100 {
+            list.insert(key, key);
+        }
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for key in 100..1000 {
+                    list.insert(key, key);
+                    list.remove(&(key - 100));
+                }
+            });
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..200 {
+                        // The writer keeps exactly 100 consecutive keys between its updates
+                        let snapshot = list.range(..);
+                        assert!(snapshot.len() == 100 || snapshot.len() == 101);
+                        assert!(snapshot.windows(2).all(|w| w[0].0 + 1 == w[1].0));
+                        assert!(snapshot.iter().all(|&(k, v)| k == v));
+                    }
+                });
+            }
+        });
+        assert_eq!(list.len(), 100);
+        assert_eq!(list.get(&950), Some(950));
+        assert!(!list.contains_key(&899));
+        assert_eq!(list.into_inner().first(), Some((&900, &900)));
+    }
+
+    /// Applies random insertions and removals to a list and a `BTreeMap`, and compares them.
+    #[quickcheck]
+    fn prop_matches_btree_map(ops: Vec<(bool, u8)>, seed: u64, range: (u8, u8)) -> bool {
+        let mut list = SkipList::with_seed(seed);
+        let mut model = BTreeMap::new();
+        for (insert, key) in ops {
+            let same = if insert {
+                list.insert(key, u32::from(key) * 2) == model.insert(key, u32::from(key) * 2)
+            } else {
+                list.remove(&key) == model.remove(&key)
+            };
+            if !same {
+                return false;
+            }
+        }
+
+        let (start, end) = (range.0.min(range.1), range.0.max(range.1));
+        list.len() == model.len()
+            && list.iter().eq(model.iter())
+            && list.range(start..end).eq(model.range(start..end))
+            && list.range(start..=end).eq(model.range(start..=end))
+            && (0..=255).all(|key| list.rank(&key) == model.range(..key).count())
+            && (0..=model.len()).all(|i| list.select(i) == model.iter().nth(i))
+    }
+}
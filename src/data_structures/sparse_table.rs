@@ -0,0 +1,194 @@
+/*
+    A SparseTable answers range queries over a static array for any idempotent operation
+    (one where combining a value with itself changes nothing), such as min, max, gcd, and
+    bitwise and/or. Row k of the table holds the result for every range of length 2^k, and
+    any range is covered by two, possibly overlapping, such ranges.
+
+    A DisjointSparseTable drops the idempotence requirement, so it also works for sums,
+    products or matrix products: each level splits the array into blocks around midpoints
+    and stores results for ranges that end at a midpoint, so every range is covered by
+    exactly two stored results that do not overlap.
+
+    Precomputation complexity: O(n log(n))
+    Query complexity: O(1)
+
+    Wikipedia: <https://en.wikipedia.org/wiki/Range_minimum_query#Solution_using_constant_time_and_linearithmic_space>
+*/
+
+use super::RangeError;
+
+pub struct SparseTable<T, Op>
+where
+    T: Clone,
+    Op: Fn(&T, &T) -> T,
+{
+    table: Vec<Vec<T>>,
+    op: Op,
+}
+
+impl<T, Op> SparseTable<T, Op>
+where
+    T: Clone,
+    Op: Fn(&T, &T) -> T,
+{
+    /// Builds the table for `input`. `op` must be associative and idempotent.
+    pub fn new(input: &[T], op: Op) -> Self {
+        let mut table = vec![input.to_vec()];
+        for k in 1..=input.len().checked_ilog2().unwrap_or(0) {
+            let half = 1 << (k - 1);
+            let previous = &table[table.len() - 1];
+            let row = (0..=input.len() - (1 << k))
+                .map(|i| op(&previous[i], &previous[i + half]))
+                .collect();
+            table.push(row);
+        }
+        SparseTable { table, op }
+    }
+
+    /// Combines the elements in `start..end`.
+    pub fn query(&self, start: usize, end: usize) -> Result<T, RangeError> {
+        if start >= end || end > self.table[0].len() {
+            return Err(RangeError);
+        }
+        let k = (end - start).ilog2() as usize;
+        Ok((self.op)(
+            &self.table[k][start],
+            &self.table[k][end - (1 << k)],
+        ))
+    }
+}
+
+pub struct DisjointSparseTable<T, Op>
+where
+    T: Clone,
+    Op: Fn(&T, &T) -> T,
+{
+    /// `table[k][i]` combines the elements from `i` to the nearest midpoint of level `k`,
+    /// which is an odd multiple of `2^k`: up to the midpoint (exclusive) on its left side,
+    /// and from the midpoint (inclusive) on its right side.
+    table: Vec<Vec<T>>,
+    op: Op,
+}
+
+impl<T, Op> DisjointSparseTable<T, Op>
+where
+    T: Clone,
+    Op: Fn(&T, &T) -> T,
+{
+    /// Builds the table for `input`. `op` must be associative.
+    pub fn new(input: &[T], op: Op) -> Self {
+        let len = input.len();
+        let levels = len
+            .saturating_sub(1)
+            .checked_ilog2()
+            .map_or(1, |log| log + 1);
+        let mut table = Vec::new();
+        for k in 0..levels {
+            let half = 1 << k;
+            let mut row = input.to_vec();
+            for mid in (half..len).step_by(2 * half) {
+                for i in (mid - half..mid - 1).rev() {
+                    row[i] = op(&input[i], &row[i + 1]);
+                }
+                for i in mid + 1..len.min(mid + half) {
+                    row[i] = op(&row[i - 1], &input[i]);
+                }
+            }
+            table.push(row);
+        }
+        DisjointSparseTable { table, op }
+    }
+
+    /// Combines the elements in `start..end`, in order.
+    pub fn query(&self, start: usize, end: usize) -> Result<T, RangeError> {
+        if start >= end || end > self.table[0].len() {
+            return Err(RangeError);
+        }
+        let last = end - 1;
+        if start == last {
+            return Ok(self.table[0][start].clone());
+        }
+        // The highest differing bit picks the level whose midpoint lies between the two.
+        let k = (start ^ last).ilog2() as usize;
+        Ok((self.op)(&self.table[k][start], &self.table[k][last]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DisjointSparseTable, SparseTable};
+    use crate::data_structures::RangeError;
+    use quickcheck_macros::quickcheck;
+
+    fn gcd(a: &u32, b: &u32) -> u32 {
+        if *b == 0 {
+            *a
+        } else {
+            gcd(b, &(a % b))
+        }
+    }
+
+    #[test]
+    fn idempotent_operations() {
+        let values = [12, 18, 6, 9, 27, 36];
+        let gcds = SparseTable::new(&values, gcd);
+        assert_eq!(gcds.query(0, 2), Ok(6));
+        assert_eq!(gcds.query(3, 5), Ok(9));
+        assert_eq!(gcds.query(4, 6), Ok(9));
+        assert_eq!(gcds.query(2, 2), Err(RangeError));
+        assert_eq!(gcds.query(0, 7), Err(RangeError));
+
+        let ors = SparseTable::new(&[0b0001u8, 0b0100, 0b0010, 0b1000], |a, b| a | b);
+        assert_eq!(ors.query(0, 3), Ok(0b0111));
+        let ands = SparseTable::new(&[0b1110u8, 0b0111, 0b1111], |a, b| a & b);
+        assert_eq!(ands.query(0, 3), Ok(0b0110));
+        let maxes = SparseTable::new(&[1.5, -2.0, 7.25], |a: &f64, b: &f64| a.max(*b));
+        assert_eq!(maxes.query(0, 2), Ok(1.5));
+        assert_eq!(
+            SparseTable::new(&[], |a: &i32, b: &i32| *a.min(b)).query(0, 0),
+            Err(RangeError)
+        );
+    }
+
+    #[test]
+    fn matrix_products() {
+        type Matrix = [[u64; 2]; 2];
+        let multiply = |a: &Matrix, b: &Matrix| {
+            let mut product = [[0; 2]; 2];
+            for (i, row) in product.iter_mut().enumerate() {
+                for (j, cell) in row.iter_mut().enumerate() {
+                    *cell = a[i][0] * b[0][j] + a[i][1] * b[1][j];
+                }
+            }
+            product
+        };
+        // Products of the Fibonacci matrix and a shear that does not commute with it.
+        let fibonacci = [[1, 1], [1, 0]];
+        let shear = [[1, 0], [1, 1]];
+        let matrices = [fibonacci, shear, fibonacci, fibonacci, shear];
+        let table = DisjointSparseTable::new(&matrices, multiply);
+        for start in 0..matrices.len() {
+            for end in start + 1..=matrices.len() {
+                let expected = matrices[start + 1..end]
+                    .iter()
+                    .fold(matrices[start], |acc, m| multiply(&acc, m));
+                assert_eq!(table.query(start, end), Ok(expected));
+            }
+        }
+        assert_eq!(table.query(3, 2), Err(RangeError));
+    }
+
+    #[quickcheck]
+    fn tables_match_naive(values: Vec<i32>) -> bool {
+        let mins = SparseTable::new(&values, |a, b| *a.min(b));
+        let sums = DisjointSparseTable::new(&values, |a: &i32, b: &i32| a.wrapping_add(*b));
+        (0..values.len()).all(|start| {
+            (start + 1..=values.len()).all(|end| {
+                let range = &values[start..end];
+                mins.query(start, end) == Ok(*range.iter().min().unwrap())
+                    && sums.query(start, end)
+                        == Ok(range.iter().fold(0, |acc: i32, v| acc.wrapping_add(*v)))
+            })
+        })
+    }
+}
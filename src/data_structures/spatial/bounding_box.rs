@@ -0,0 +1,101 @@
+/// An axis-aligned box in `D` dimensions, closed on all sides.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox<const D: usize> {
+    pub min: [f64; D],
+    pub max: [f64; D],
+}
+
+impl<const D: usize> BoundingBox<D> {
+    /// Creates the box between two opposite corners, given in any order.
+    pub fn new(a: [f64; D], b: [f64; D]) -> Self {
+        let mut min = a;
+        let mut max = b;
+        for i in 0..D {
+            if min[i] > max[i] {
+                std::mem::swap(&mut min[i], &mut max[i]);
+            }
+        }
+        BoundingBox { min, max }
+    }
+
+    /// Creates the degenerate box containing a single point.
+    pub fn point(point: [f64; D]) -> Self {
+        BoundingBox {
+            min: point,
+            max: point,
+        }
+    }
+
+    pub fn contains_point(&self, point: &[f64; D]) -> bool {
+        (0..D).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    pub fn contains(&self, other: &Self) -> bool {
+        (0..D).all(|i| self.min[i] <= other.min[i] && other.max[i] <= self.max[i])
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..D).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = *self;
+        for i in 0..D {
+            result.min[i] = result.min[i].min(other.min[i]);
+            result.max[i] = result.max[i].max(other.max[i]);
+        }
+        result
+    }
+
+    /// Returns the area of the box in 2D, its volume in 3D, and so on.
+    pub fn volume(&self) -> f64 {
+        (0..D).map(|i| self.max[i] - self.min[i]).product()
+    }
+
+    /// Returns the sum of the lengths of the sides of the box.
+    pub fn margin(&self) -> f64 {
+        (0..D).map(|i| self.max[i] - self.min[i]).sum()
+    }
+
+    pub fn center(&self) -> [f64; D] {
+        let mut center = self.min;
+        for (c, max) in center.iter_mut().zip(&self.max) {
+            *c = (*c + max) / 2.0;
+        }
+        center
+    }
+
+    /// Returns how much the volume of the box grows to also contain `other`.
+    pub fn enlargement(&self, other: &Self) -> f64 {
+        self.union(other).volume() - self.volume()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundingBox;
+
+    #[test]
+    fn test_box_operations() {
+        let a = BoundingBox::new([2.0, 0.0], [0.0, 1.0]);
+        assert_eq!(a.min, [0.0, 0.0]);
+        assert_eq!(a.max, [2.0, 1.0]);
+        assert_eq!(a.volume(), 2.0);
+        assert_eq!(a.margin(), 3.0);
+        assert_eq!(a.center(), [1.0, 0.5]);
+
+        let b = BoundingBox::new([1.0, 1.0], [3.0, 3.0]);
+        assert!(a.intersects(&b));
+        assert!(!a.contains(&b));
+        assert_eq!(a.union(&b), BoundingBox::new([0.0, 0.0], [3.0, 3.0]));
+        assert_eq!(a.enlargement(&b), 7.0);
+        assert!(a.union(&b).contains(&b));
+
+        let p = BoundingBox::point([4.0, 0.5]);
+        assert!(!a.intersects(&p));
+        assert!(!a.contains_point(&[4.0, 0.5]));
+        assert!(a.contains_point(&[2.0, 1.0]));
+        assert_eq!(p.volume(), 0.0);
+    }
+}
@@ -0,0 +1,321 @@
+//! A k-d tree is a binary tree over points in `D` dimensions: each node splits the space in two
+//! halves along one coordinate, cycling through the coordinates with the depth.
+//!
+//! This tree is built once from all the points, splitting each subtree at the median, so it is
+//! balanced and can be stored implicitly: the points are reordered in place so that the root
+//! of every subtree is the middle element of its range. Building takes `O(n * log(n))` time.
+//!
+//! A nearest-neighbor search descends towards the query point first, and only visits the other
+//! side of a split when the splitting plane is closer than the worst neighbor found so far.
+//! On well-spread points, it takes `O(log(n))` time for a fixed `k`, but degrades towards
+//! `O(n)` in high dimensions.
+//!
+//! # Sources
+//! <https://en.wikipedia.org/wiki/K-d_tree>
+
+use super::squared_distance;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A point found by a query, with the value attached to it and its distance to the query point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor<'a, const D: usize, T> {
+    pub point: &'a [f64; D],
+    pub value: &'a T,
+    pub distance: f64,
+}
+
+/// A static k-d tree over points in `D` dimensions, each carrying a value of type `T`.
+#[derive(Debug, Clone)]
+pub struct KdTree<const D: usize, T> {
+    points: Vec<([f64; D], T)>,
+}
+
+/// A candidate of a k-nearest search, ordered by squared distance so that the heap of
+/// candidates gives the farthest one first.
+struct Candidate {
+    squared_distance: f64,
+    index: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.squared_distance
+            .total_cmp(&other.squared_distance)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl<const D: usize, T> KdTree<D, T> {
+    /// Builds a tree over the given points.
+    ///
+    /// # Panics
+    /// Panics if `D` is 0.
+    pub fn new(mut points: Vec<([f64; D], T)>) -> Self {
+        assert!(D > 0, "a k-d tree needs at least one dimension");
+        Self::build(&mut points, 0);
+        KdTree { points }
+    }
+
+    /// Places the median point of `points` along the axis of `depth` in the middle, with the
+    /// smaller points before it and the larger ones after it, then recurses on both halves.
+    fn build(points: &mut [([f64; D], T)], depth: usize) {
+        if points.len() <= 1 {
+            return;
+        }
+        let axis = depth % D;
+        let mid = points.len() / 2;
+        points.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+        let (left, right) = points.split_at_mut(mid);
+        Self::build(left, depth + 1);
+        Self::build(&mut right[1..], depth + 1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns all the points with their values, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[f64; D], &T)> {
+        self.points.iter().map(|(point, value)| (point, value))
+    }
+
+    fn neighbor(&self, index: usize, squared_distance: f64) -> Neighbor<'_, D, T> {
+        let (point, value) = &self.points[index];
+        Neighbor {
+            point,
+            value,
+            distance: squared_distance.sqrt(),
+        }
+    }
+
+    /// Returns the point closest to `query`, or `None` if the tree is empty.
+    pub fn nearest(&self, query: &[f64; D]) -> Option<Neighbor<'_, D, T>> {
+        self.k_nearest(query, 1).pop()
+    }
+
+    /// Returns the `k` points closest to `query` (or all of them if there are fewer than `k`),
+    /// from the closest to the farthest.
+    pub fn k_nearest(&self, query: &[f64; D], k: usize) -> Vec<Neighbor<'_, D, T>> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut candidates = BinaryHeap::with_capacity(k + 1);
+        self.search_k_nearest(0, self.points.len(), 0, query, k, &mut candidates);
+        candidates
+            .into_sorted_vec()
+            .into_iter()
+            .map(|c| self.neighbor(c.index, c.squared_distance))
+            .collect()
+    }
+
+    fn search_k_nearest(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        query: &[f64; D],
+        k: usize,
+        candidates: &mut BinaryHeap<Candidate>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let point = &self.points[mid].0;
+        let candidate = Candidate {
+            squared_distance: squared_distance(point, query),
+            index: mid,
+        };
+        if candidates.len() < k {
+            candidates.push(candidate);
+        } else if candidates.peek().is_some_and(|worst| candidate < *worst) {
+            candidates.pop();
+            candidates.push(candidate);
+        }
+
+        let axis = depth % D;
+        let diff = query[axis] - point[axis];
+        let (near, far) = if diff < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+        self.search_k_nearest(near.0, near.1, depth + 1, query, k, candidates);
+        if candidates.len() < k
+            || candidates
+                .peek()
+                .is_some_and(|worst| diff * diff < worst.squared_distance)
+        {
+            self.search_k_nearest(far.0, far.1, depth + 1, query, k, candidates);
+        }
+    }
+
+    /// Returns all the points at a distance of at most `radius` from `query`, in no particular
+    /// order.
+    pub fn within_radius(&self, query: &[f64; D], radius: f64) -> Vec<Neighbor<'_, D, T>> {
+        let mut result = Vec::new();
+        self.search_radius(0, self.points.len(), 0, query, radius, &mut result);
+        result
+    }
+
+    fn search_radius<'a>(
+        &'a self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        query: &[f64; D],
+        radius: f64,
+        result: &mut Vec<Neighbor<'a, D, T>>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let point = &self.points[mid].0;
+        let distance = squared_distance(point, query);
+        if distance <= radius * radius {
+            result.push(self.neighbor(mid, distance));
+        }
+
+        let axis = depth % D;
+        if query[axis] - radius <= point[axis] {
+            self.search_radius(start, mid, depth + 1, query, radius, result);
+        }
+        if query[axis] + radius >= point[axis] {
+            self.search_radius(mid + 1, end, depth + 1, query, radius, result);
+        }
+    }
+}
+
+impl<const D: usize, T> FromIterator<([f64; D], T)> for KdTree<D, T> {
+    fn from_iter<I: IntoIterator<Item = ([f64; D], T)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::squared_distance;
+    use super::KdTree;
+    use crate::geometry::Point;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_nearest_neighbors() {
+        let tree: KdTree<2, &str> = [
+            ([2.0, 3.0], "a"),
+            ([5.0, 4.0], "b"),
+            ([9.0, 6.0], "c"),
+            ([4.0, 7.0], "d"),
+            ([8.0, 1.0], "e"),
+            ([7.0, 2.0], "f"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(tree.len(), 6);
+
+        let nearest = tree.nearest(&[9.0, 2.0]).unwrap();
+        assert_eq!(*nearest.value, "e");
+        assert_eq!(nearest.point, &[8.0, 1.0]);
+        assert!((nearest.distance - 2.0_f64.sqrt()).abs() < 1e-12);
+
+        let values: Vec<&str> = tree
+            .k_nearest(&[5.0, 5.0], 3)
+            .into_iter()
+            .map(|n| *n.value)
+            .collect();
+        assert_eq!(values, ["b", "d", "a"]);
+        assert_eq!(tree.k_nearest(&[0.0, 0.0], 10).len(), 6);
+        assert!(tree.k_nearest(&[0.0, 0.0], 0).is_empty());
+
+        let mut values: Vec<&str> = tree
+            .within_radius(&[6.0, 3.0], 1.5)
+            .into_iter()
+            .map(|n| *n.value)
+            .collect();
+        values.sort_unstable();
+        assert_eq!(values, ["b", "f"]);
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree: KdTree<3, ()> = KdTree::new(Vec::new());
+        assert!(tree.is_empty());
+        assert!(tree.nearest(&[0.0; 3]).is_none());
+        assert!(tree.within_radius(&[0.0; 3], 10.0).is_empty());
+    }
+
+    #[test]
+    fn test_geometry_points() {
+        let points = [
+            Point::new(0.0, 0.0),
+            Point::new(3.0, 4.0),
+            Point::new(1.0, 1.0),
+        ];
+        let tree: KdTree<2, usize> = points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.into(), i))
+            .collect();
+        let query = Point::new(2.5, 3.0);
+        let nearest = tree.nearest(&(&query).into()).unwrap();
+        assert_eq!(Point::from(*nearest.point), points[1]);
+        assert_eq!(nearest.distance, query.euclidean_distance(&points[1]));
+    }
+
+    /// Compares the queries with a linear scan over all the points.
+    #[quickcheck]
+    fn prop_matches_linear_scan(points: Vec<(i8, i8, i8)>, query: (i8, i8, i8), k: u8) -> bool {
+        let to_point = |(x, y, z): (i8, i8, i8)| [f64::from(x), f64::from(y), f64::from(z)];
+        let points: Vec<[f64; 3]> = points.into_iter().map(to_point).collect();
+        let query = to_point(query);
+        let k = usize::from(k % 10);
+        let tree: KdTree<3, usize> = points.iter().copied().zip(0..).collect();
+
+        let mut distances: Vec<f64> = points
+            .iter()
+            .map(|p| squared_distance(p, &query).sqrt())
+            .collect();
+        distances.sort_by(f64::total_cmp);
+        let found: Vec<f64> = tree
+            .k_nearest(&query, k)
+            .iter()
+            .map(|n| n.distance)
+            .collect();
+        if found[..] != distances[..k.min(distances.len())] {
+            return false;
+        }
+
+        let radius = 40.0;
+        let mut expected: Vec<usize> = (0..points.len())
+            .filter(|&i| squared_distance(&points[i], &query) <= radius * radius)
+            .collect();
+        let mut found: Vec<usize> = tree
+            .within_radius(&query, radius)
+            .iter()
+            .map(|n| *n.value)
+            .collect();
+        expected.sort_unstable();
+        found.sort_unstable();
+        found == expected
+    }
+}
@@ -0,0 +1,28 @@
+//! Spatial indexes answer repeated geometric queries (nearest neighbors, points within a
+//! distance, objects overlapping a box) over a set of points or boxes, without scanning the
+//! whole set for every query.
+//!
+//! Points are `[f64; D]` arrays and boxes are `BoundingBox<D>`, so the same structures work in
+//! any dimension: `geometry::Point` converts to and from `[f64; 2]`.
+//!
+//! - `KdTree` is built once from a set of points, and answers nearest-neighbor and radius
+//!   queries.
+//! - `RTree` indexes boxes, supports insertions and deletions as well as bulk loading, and
+//!   answers window queries.
+//! - `PointQuadtree` splits the space in `2^D` orthants around each inserted point, and
+//!   answers window queries.
+
+mod bounding_box;
+mod kd_tree;
+mod quadtree;
+mod r_tree;
+
+pub use self::bounding_box::BoundingBox;
+pub use self::kd_tree::{KdTree, Neighbor};
+pub use self::quadtree::PointQuadtree;
+pub use self::r_tree::RTree;
+
+/// Returns the squared euclidean distance between two points.
+fn squared_distance<const D: usize>(a: &[f64; D], b: &[f64; D]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
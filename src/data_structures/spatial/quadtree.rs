@@ -0,0 +1,204 @@
+//! A point quadtree stores one point in each node, and splits the space around that point into
+//! `2^D` orthants, one per child: 4 quadrants in 2D (a quadtree), 8 octants in 3D (an octree).
+//!
+//! Unlike a k-d tree, it is built incrementally, but it is not rebalanced: inserting the points
+//! in a random order gives an expected depth of `O(log(n))`, while sorted points degenerate
+//! into a list.
+//!
+//! # Sources
+//! <https://en.wikipedia.org/wiki/Quadtree#Point_quadtree>
+
+use super::BoundingBox;
+
+struct Node<const D: usize, T> {
+    point: [f64; D],
+    value: T,
+    /// The `2^D` children: bit `i` of the index is set for the points whose coordinate `i` is
+    /// at least the one of `point`.
+    children: Vec<Option<Box<Node<D, T>>>>,
+}
+
+impl<const D: usize, T> Node<D, T> {
+    fn new(point: [f64; D], value: T) -> Self {
+        Node {
+            point,
+            value,
+            children: std::iter::repeat_with(|| None).take(1 << D).collect(),
+        }
+    }
+
+    fn orthant(&self, point: &[f64; D]) -> usize {
+        (0..D)
+            .filter(|&i| point[i] >= self.point[i])
+            .map(|i| 1 << i)
+            .sum()
+    }
+}
+
+/// A point quadtree in `D` dimensions, mapping distinct points to values of type `T`.
+pub struct PointQuadtree<const D: usize, T> {
+    root: Option<Box<Node<D, T>>>,
+    len: usize,
+}
+
+impl<const D: usize, T> PointQuadtree<D, T> {
+    pub fn new() -> Self {
+        PointQuadtree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a point with its value, returning the previous value if the point was already
+    /// present.
+    pub fn insert(&mut self, point: [f64; D], value: T) -> Option<T> {
+        let mut link = &mut self.root;
+        while let Some(node) = link {
+            if node.point == point {
+                return Some(std::mem::replace(&mut node.value, value));
+            }
+            let orthant = node.orthant(&point);
+            link = &mut node.children[orthant];
+        }
+        *link = Some(Box::new(Node::new(point, value)));
+        self.len += 1;
+        None
+    }
+
+    pub fn get(&self, point: &[f64; D]) -> Option<&T> {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if node.point == *point {
+                return Some(&node.value);
+            }
+            link = &node.children[node.orthant(point)];
+        }
+        None
+    }
+
+    pub fn contains(&self, point: &[f64; D]) -> bool {
+        self.get(point).is_some()
+    }
+
+    /// Returns all the points inside `window` (borders included) with their values, in no
+    /// particular order.
+    pub fn query(&self, window: &BoundingBox<D>) -> Vec<(&[f64; D], &T)> {
+        let mut result = Vec::new();
+        let mut stack: Vec<&Node<D, T>> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if window.contains_point(&node.point) {
+                result.push((&node.point, &node.value));
+            }
+            for (orthant, child) in node.children.iter().enumerate() {
+                let Some(child) = child else { continue };
+                // the orthant holds the points at least (or below) the node on every axis
+                let overlaps = (0..D).all(|i| {
+                    if orthant & (1 << i) != 0 {
+                        window.max[i] >= node.point[i]
+                    } else {
+                        window.min[i] < node.point[i]
+                    }
+                });
+                if overlaps {
+                    stack.push(child);
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<const D: usize, T> Default for PointQuadtree<D, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const D: usize, T> FromIterator<([f64; D], T)> for PointQuadtree<D, T> {
+    fn from_iter<I: IntoIterator<Item = ([f64; D], T)>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        for (point, value) in iter {
+            tree.insert(point, value);
+        }
+        tree
+    }
+}
+
+impl<const D: usize, T> Drop for PointQuadtree<D, T> {
+    // Drops the nodes iteratively, so that a degenerate (list-like) tree does not overflow the
+    // stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<D, T>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.children.iter_mut().filter_map(Option::take));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundingBox, PointQuadtree};
+    use quickcheck_macros::quickcheck;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_insert_and_query() {
+        let mut tree = PointQuadtree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.insert([5.0, 5.0], 'a'), None);
+        assert_eq!(tree.insert([1.0, 8.0], 'b'), None);
+        assert_eq!(tree.insert([7.0, 2.0], 'c'), None);
+        assert_eq!(tree.insert([5.0, 1.0], 'd'), None);
+        assert_eq!(tree.insert([7.0, 2.0], 'e'), Some('c'));
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.get(&[7.0, 2.0]), Some(&'e'));
+        assert!(tree.contains(&[5.0, 1.0]));
+        assert!(!tree.contains(&[5.0, 2.0]));
+
+        let mut found: Vec<char> = tree
+            .query(&BoundingBox::new([4.0, 0.0], [7.0, 5.0]))
+            .into_iter()
+            .map(|(_, &v)| v)
+            .collect();
+        found.sort_unstable();
+        assert_eq!(found, ['a', 'd', 'e']);
+        assert!(tree
+            .query(&BoundingBox::new([0.0, 0.0], [1.0, 1.0]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_sorted_points_in_3d() {
+        let tree: PointQuadtree<3, usize> = (0..10_000).map(|i| ([i as f64; 3], i)).collect();
+        assert_eq!(tree.len(), 10_000);
+        assert_eq!(tree.get(&[5.0; 3]), Some(&5));
+    }
+
+    /// Compares the window queries with a linear scan over all the points.
+    #[quickcheck]
+    fn prop_matches_linear_scan(points: Vec<(i8, i8)>, corners: ((i8, i8), (i8, i8))) -> bool {
+        let to_point = |(x, y): (i8, i8)| [f64::from(x), f64::from(y)];
+        let mut tree = PointQuadtree::new();
+        let mut expected = BTreeMap::new();
+        for (i, &p) in points.iter().enumerate() {
+            tree.insert(to_point(p), i);
+            expected.insert(p, i);
+        }
+        if tree.len() != expected.len() {
+            return false;
+        }
+        let window = BoundingBox::new(to_point(corners.0), to_point(corners.1));
+        expected.retain(|&p, _| window.contains_point(&to_point(p)));
+
+        let mut found: Vec<usize> = tree.query(&window).into_iter().map(|(_, &i)| i).collect();
+        let mut expected: Vec<usize> = expected.into_values().collect();
+        found.sort_unstable();
+        expected.sort_unstable();
+        found == expected
+    }
+}
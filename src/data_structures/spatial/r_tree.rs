@@ -0,0 +1,537 @@
+//! An R-tree indexes boxes (or points, as degenerate boxes) in `D` dimensions. Each node covers
+//! the bounding box of its entries, and the boxes of siblings may overlap: a window query only
+//! descends into the children whose box intersects the window.
+//!
+//! Insertions descend into the child whose box grows the least, and split the nodes holding
+//! more than `MAX_ENTRIES` entries with Guttman's quadratic split. A deletion that leaves a
+//! node with fewer than `MIN_ENTRIES` entries removes the node, and inserts its entries again.
+//!
+//! `bulk_load` builds a tree at once with the Sort-Tile-Recursive algorithm: the entries are
+//! sorted into slabs along the first axis, each slab is sorted along the second axis, and so
+//! on, then packed into full nodes, level by level. The nodes barely overlap, which makes the
+//! queries faster than on a tree built by insertions.
+//!
+//! # Sources
+//! - Guttman, "R-trees: a dynamic index structure for spatial searching", 1984
+//! - Leutenegger, Lopez and Edgington, "STR: a simple and efficient algorithm for R-tree
+//!   packing", 1997
+
+use super::BoundingBox;
+
+const MAX_ENTRIES: usize = 8;
+const MIN_ENTRIES: usize = 3;
+
+enum Node<const D: usize, T> {
+    Leaf(Vec<(BoundingBox<D>, T)>),
+    Internal(Vec<(BoundingBox<D>, Node<D, T>)>),
+}
+
+impl<const D: usize, T> Node<D, T> {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(entries) => entries.len(),
+            Node::Internal(children) => children.len(),
+        }
+    }
+
+    /// Returns the bounding box of all the entries of the node, which must not be empty.
+    fn bounding_box(&self) -> BoundingBox<D> {
+        match self {
+            Node::Leaf(entries) => bounding_box(entries),
+            Node::Internal(children) => bounding_box(children),
+        }
+    }
+
+    /// Moves all the entries stored below this node into `entries`.
+    fn collect_entries(self, entries: &mut Vec<(BoundingBox<D>, T)>) {
+        match self {
+            Node::Leaf(mut leaf) => entries.append(&mut leaf),
+            Node::Internal(children) => {
+                for (_, child) in children {
+                    child.collect_entries(entries);
+                }
+            }
+        }
+    }
+
+    /// Inserts an entry below this node, and returns the new sibling of the node if it had to
+    /// be split.
+    fn insert(&mut self, bbox: BoundingBox<D>, value: T) -> Option<(BoundingBox<D>, Self)> {
+        match self {
+            Node::Leaf(entries) => {
+                entries.push((bbox, value));
+                if entries.len() <= MAX_ENTRIES {
+                    return None;
+                }
+                let sibling = quadratic_split(entries);
+                Some((bounding_box(&sibling), Node::Leaf(sibling)))
+            }
+            Node::Internal(children) => {
+                let chosen = choose_subtree(children, &bbox);
+                let (child_box, child) = &mut children[chosen];
+                match child.insert(bbox, value) {
+                    Some(sibling) => {
+                        *child_box = child.bounding_box();
+                        children.push(sibling);
+                    }
+                    None => *child_box = child_box.union(&bbox),
+                }
+                if children.len() <= MAX_ENTRIES {
+                    return None;
+                }
+                let sibling = quadratic_split(children);
+                Some((bounding_box(&sibling), Node::Internal(sibling)))
+            }
+        }
+    }
+
+    /// Removes an entry below this node, and moves the entries of the nodes left underfull
+    /// into `orphans`.
+    fn remove(
+        &mut self,
+        bbox: &BoundingBox<D>,
+        value: &T,
+        orphans: &mut Vec<(BoundingBox<D>, T)>,
+    ) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        match self {
+            Node::Leaf(entries) => {
+                let index = entries.iter().position(|(b, v)| b == bbox && v == value)?;
+                Some(entries.swap_remove(index).1)
+            }
+            Node::Internal(children) => {
+                for index in 0..children.len() {
+                    if !children[index].0.contains(bbox) {
+                        continue;
+                    }
+                    if let Some(removed) = children[index].1.remove(bbox, value, orphans) {
+                        if children[index].1.len() < MIN_ENTRIES {
+                            children.swap_remove(index).1.collect_entries(orphans);
+                        } else {
+                            children[index].0 = children[index].1.bounding_box();
+                        }
+                        return Some(removed);
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Returns the bounding box of a non-empty list of entries.
+fn bounding_box<const D: usize, X>(entries: &[(BoundingBox<D>, X)]) -> BoundingBox<D> {
+    entries
+        .iter()
+        .map(|(bbox, _)| *bbox)
+        .reduce(|a, b| a.union(&b))
+        .expect("a node is never empty")
+}
+
+/// Returns the index of the child whose box grows the least to contain `bbox`, breaking ties
+/// by the smallest box.
+fn choose_subtree<const D: usize, X>(
+    children: &[(BoundingBox<D>, X)],
+    bbox: &BoundingBox<D>,
+) -> usize {
+    (0..children.len())
+        .min_by(|&a, &b| {
+            let (box_a, box_b) = (&children[a].0, &children[b].0);
+            box_a
+                .enlargement(bbox)
+                .total_cmp(&box_b.enlargement(bbox))
+                .then(box_a.volume().total_cmp(&box_b.volume()))
+        })
+        .expect("a node is never empty")
+}
+
+/// Splits an overflowing node with Guttman's quadratic split: the two entries that would waste
+/// the most space together seed two groups, then the remaining entries go one by one to the
+/// group whose box grows the least, starting with the entry with the strongest preference.
+///
+/// Keeps the first group in `entries` and returns the second one.
+fn quadratic_split<const D: usize, X>(
+    entries: &mut Vec<(BoundingBox<D>, X)>,
+) -> Vec<(BoundingBox<D>, X)> {
+    let mut seeds = (0, 1);
+    let mut worst_waste = f64::NEG_INFINITY;
+    for i in 0..entries.len() {
+        for j in i + 1..entries.len() {
+            let (a, b) = (&entries[i].0, &entries[j].0);
+            let waste = a.union(b).volume() - a.volume() - b.volume();
+            if waste > worst_waste {
+                worst_waste = waste;
+                seeds = (i, j);
+            }
+        }
+    }
+
+    // `seeds.0 < seeds.1`, so removing the second seed first keeps the index of the first one
+    let mut remaining = std::mem::take(entries);
+    let second = remaining.swap_remove(seeds.1);
+    let first = remaining.swap_remove(seeds.0);
+    let (mut first_box, mut second_box) = (first.0, second.0);
+    let (mut first_group, mut second_group) = (vec![first], vec![second]);
+
+    while !remaining.is_empty() {
+        // Fill a group that needs all the remaining entries to reach the minimum size
+        if first_group.len() + remaining.len() == MIN_ENTRIES {
+            first_group.append(&mut remaining);
+            break;
+        }
+        if second_group.len() + remaining.len() == MIN_ENTRIES {
+            second_group.append(&mut remaining);
+            break;
+        }
+
+        let growth =
+            |bbox: &BoundingBox<D>| (first_box.enlargement(bbox), second_box.enlargement(bbox));
+        let next = (0..remaining.len())
+            .max_by(|&a, &b| {
+                let (a1, a2) = growth(&remaining[a].0);
+                let (b1, b2) = growth(&remaining[b].0);
+                (a1 - a2).abs().total_cmp(&(b1 - b2).abs())
+            })
+            .unwrap();
+        let entry = remaining.swap_remove(next);
+        let (first_growth, second_growth) = growth(&entry.0);
+        let to_first = first_growth
+            .total_cmp(&second_growth)
+            .then(first_box.volume().total_cmp(&second_box.volume()))
+            .then(first_group.len().cmp(&second_group.len()))
+            .is_le();
+        if to_first {
+            first_box = first_box.union(&entry.0);
+            first_group.push(entry);
+        } else {
+            second_box = second_box.union(&entry.0);
+            second_group.push(entry);
+        }
+    }
+
+    *entries = first_group;
+    second_group
+}
+
+/// Groups the entries into nodes of at most `MAX_ENTRIES` entries with the Sort-Tile-Recursive
+/// algorithm, starting at the axis `axis`.
+fn str_pack<const D: usize, X>(
+    mut entries: Vec<(BoundingBox<D>, X)>,
+    axis: usize,
+    nodes: &mut Vec<Vec<(BoundingBox<D>, X)>>,
+) {
+    entries.sort_by(|a, b| a.0.center()[axis].total_cmp(&b.0.center()[axis]));
+    if axis + 1 >= D {
+        while !entries.is_empty() {
+            let rest = entries.split_off(entries.len().min(MAX_ENTRIES));
+            nodes.push(std::mem::replace(&mut entries, rest));
+        }
+        return;
+    }
+
+    // Cut the entries into `pages^(1 / remaining axes)` slabs of whole nodes, and tile each
+    // slab along the next axes
+    let pages = entries.len().div_ceil(MAX_ENTRIES);
+    let slabs = (pages as f64).powf(1.0 / (D - axis) as f64).ceil() as usize;
+    let slab_size = MAX_ENTRIES * pages.div_ceil(slabs.max(1));
+    while !entries.is_empty() {
+        let rest = entries.split_off(entries.len().min(slab_size));
+        str_pack(std::mem::replace(&mut entries, rest), axis + 1, nodes);
+    }
+}
+
+/// An R-tree in `D` dimensions, mapping boxes to values of type `T`.
+///
+/// Several entries can have the same box, or even the same box and value.
+pub struct RTree<const D: usize, T> {
+    root: Node<D, T>,
+    len: usize,
+}
+
+impl<const D: usize, T> RTree<D, T> {
+    pub fn new() -> Self {
+        RTree {
+            root: Node::Leaf(Vec::new()),
+            len: 0,
+        }
+    }
+
+    /// Builds a tree holding all the entries at once, with the Sort-Tile-Recursive algorithm.
+    pub fn bulk_load(entries: Vec<(BoundingBox<D>, T)>) -> Self {
+        let len = entries.len();
+        let mut leaves = Vec::new();
+        str_pack(entries, 0, &mut leaves);
+        let mut level: Vec<(BoundingBox<D>, Node<D, T>)> = leaves
+            .into_iter()
+            .map(|leaf| (bounding_box(&leaf), Node::Leaf(leaf)))
+            .collect();
+        while level.len() > 1 {
+            let mut nodes = Vec::new();
+            str_pack(level, 0, &mut nodes);
+            level = nodes
+                .into_iter()
+                .map(|children| (bounding_box(&children), Node::Internal(children)))
+                .collect();
+        }
+        RTree {
+            root: level
+                .pop()
+                .map_or_else(|| Node::Leaf(Vec::new()), |(_, root)| root),
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of levels of nodes, leaves included.
+    pub fn height(&self) -> usize {
+        let mut height = 1;
+        let mut node = &self.root;
+        while let Node::Internal(children) = node {
+            node = &children[0].1;
+            height += 1;
+        }
+        height
+    }
+
+    pub fn insert(&mut self, bbox: BoundingBox<D>, value: T) {
+        if let Some(sibling) = self.root.insert(bbox, value) {
+            // The root was split: the tree grows by one level
+            let old_root = std::mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            self.root = Node::Internal(vec![(old_root.bounding_box(), old_root), sibling]);
+        }
+        self.len += 1;
+    }
+
+    /// Inserts a point, as a box reduced to that point.
+    pub fn insert_point(&mut self, point: [f64; D], value: T) {
+        self.insert(BoundingBox::point(point), value);
+    }
+
+    /// Removes one entry with the given box and value, and returns its value, or `None` if
+    /// there is no such entry.
+    pub fn remove(&mut self, bbox: &BoundingBox<D>, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let mut orphans = Vec::new();
+        let removed = self.root.remove(bbox, value, &mut orphans)?;
+        self.len -= 1;
+
+        // Shorten the tree while the root has a single child (or none, if all of its children
+        // became underfull)
+        while let Node::Internal(children) = &mut self.root {
+            match children.len() {
+                0 => self.root = Node::Leaf(Vec::new()),
+                1 => self.root = children.pop().unwrap().1,
+                _ => break,
+            }
+        }
+        for (bbox, value) in orphans {
+            self.len -= 1;
+            self.insert(bbox, value);
+        }
+        Some(removed)
+    }
+
+    /// Returns all the entries whose box intersects `window` (borders included), in no
+    /// particular order.
+    pub fn search(&self, window: &BoundingBox<D>) -> Vec<(&BoundingBox<D>, &T)> {
+        self.search_by(
+            |bbox| bbox.intersects(window),
+            |bbox| bbox.intersects(window),
+        )
+    }
+
+    /// Returns all the entries whose box lies entirely inside `window`, in no particular order.
+    pub fn search_within(&self, window: &BoundingBox<D>) -> Vec<(&BoundingBox<D>, &T)> {
+        self.search_by(|bbox| bbox.intersects(window), |bbox| window.contains(bbox))
+    }
+
+    /// Visits the nodes whose box satisfies `visit_node`, and returns their entries whose box
+    /// satisfies `keep_entry`.
+    fn search_by(
+        &self,
+        visit_node: impl Fn(&BoundingBox<D>) -> bool,
+        keep_entry: impl Fn(&BoundingBox<D>) -> bool,
+    ) -> Vec<(&BoundingBox<D>, &T)> {
+        let mut result = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf(entries) => result.extend(
+                    entries
+                        .iter()
+                        .filter(|(bbox, _)| keep_entry(bbox))
+                        .map(|(bbox, value)| (bbox, value)),
+                ),
+                Node::Internal(children) => stack.extend(
+                    children
+                        .iter()
+                        .filter(|(bbox, _)| visit_node(bbox))
+                        .map(|(_, child)| child),
+                ),
+            }
+        }
+        result
+    }
+}
+
+impl<const D: usize, T> Default for RTree<D, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundingBox, Node, RTree, MAX_ENTRIES, MIN_ENTRIES};
+    use quickcheck_macros::quickcheck;
+
+    /// Checks that every node has the right number of entries and the exact bounding box of
+    /// its children, and that all the leaves are at the same depth. Returns the height.
+    fn check_node<const D: usize, T>(node: &Node<D, T>, is_root: bool, packed: bool) -> usize {
+        let min = if is_root || packed { 1 } else { MIN_ENTRIES };
+        assert!((min..=MAX_ENTRIES).contains(&node.len()) || (is_root && node.len() == 0));
+        match node {
+            Node::Leaf(_) => 1,
+            Node::Internal(children) => {
+                let heights: Vec<usize> = children
+                    .iter()
+                    .map(|(bbox, child)| {
+                        assert_eq!(*bbox, child.bounding_box());
+                        check_node(child, false, packed)
+                    })
+                    .collect();
+                assert!(heights.iter().all(|&h| h == heights[0]));
+                heights[0] + 1
+            }
+        }
+    }
+
+    fn sorted_values<T: Ord + Copy>(entries: Vec<(&BoundingBox<2>, &T)>) -> Vec<T> {
+        let mut values: Vec<T> = entries.into_iter().map(|(_, &v)| v).collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn test_insert_search_remove() {
+        let mut tree = RTree::new();
+        for x in 0..20 {
+            for y in 0..20 {
+                tree.insert_point([f64::from(x), f64::from(y)], (x, y));
+            }
+        }
+        tree.insert(BoundingBox::new([2.5, 2.5], [30.0, 3.5]), (-1, -1));
+        assert_eq!(tree.len(), 401);
+        assert!(tree.height() > 2);
+        check_node(&tree.root, true, false);
+
+        let window = BoundingBox::new([1.0, 2.0], [3.0, 3.0]);
+        assert_eq!(
+            sorted_values(tree.search(&window)),
+            [(-1, -1), (1, 2), (1, 3), (2, 2), (2, 3), (3, 2), (3, 3)]
+        );
+        assert_eq!(sorted_values(tree.search_within(&window)).len(), 6);
+        assert_eq!(
+            sorted_values(tree.search(&BoundingBox::point([25.0, 3.0]))),
+            [(-1, -1)]
+        );
+
+        assert_eq!(tree.remove(&BoundingBox::point([2.0, 2.0]), &(2, 3)), None);
+        assert_eq!(
+            tree.remove(&BoundingBox::point([2.0, 2.0]), &(2, 2)),
+            Some((2, 2))
+        );
+        assert_eq!(sorted_values(tree.search(&window)).len(), 6);
+
+        for x in 0..20 {
+            for y in 0..20 {
+                let removed =
+                    tree.remove(&BoundingBox::point([f64::from(x), f64::from(y)]), &(x, y));
+                assert_eq!(removed.is_some(), (x, y) != (2, 2));
+                check_node(&tree.root, true, false);
+            }
+        }
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.height(), 1);
+        assert_eq!(sorted_values(tree.search(&window)), [(-1, -1)]);
+    }
+
+    #[test]
+    fn test_bulk_load() {
+        let entries: Vec<(BoundingBox<3>, usize)> = (0..1000)
+            .map(|i| {
+                let p = [(i % 10) as f64, (i / 10 % 10) as f64, (i / 100) as f64];
+                (BoundingBox::point(p), i)
+            })
+            .collect();
+        let mut tree = RTree::bulk_load(entries);
+        assert_eq!(tree.len(), 1000);
+        check_node(&tree.root, true, true);
+
+        let window = BoundingBox::new([2.0, 2.0, 2.0], [3.0, 3.0, 3.0]);
+        let mut found: Vec<usize> = tree.search(&window).into_iter().map(|(_, &i)| i).collect();
+        found.sort_unstable();
+        assert_eq!(found, [222, 223, 232, 233, 322, 323, 332, 333]);
+
+        tree.insert(BoundingBox::point([2.5; 3]), 1000);
+        assert_eq!(tree.remove(&BoundingBox::point([2.0; 3]), &222), Some(222));
+        let mut found: Vec<usize> = tree.search(&window).into_iter().map(|(_, &i)| i).collect();
+        found.sort_unstable();
+        assert_eq!(found, [223, 232, 233, 322, 323, 332, 333, 1000]);
+
+        assert!(RTree::<3, usize>::bulk_load(Vec::new()).is_empty());
+    }
+
+    /// Applies random insertions and removals to a tree and a list, and compares window
+    /// queries on both, on a tree built by insertions and on a bulk-loaded tree.
+    #[quickcheck]
+    fn prop_matches_linear_scan(
+        ops: Vec<(bool, (i8, i8), (u8, u8))>,
+        window: (i8, i8, i8, i8),
+    ) -> bool {
+        let mut tree = RTree::new();
+        let mut entries: Vec<(BoundingBox<2>, usize)> = Vec::new();
+        for (i, (insert, (x, y), (w, h))) in ops.into_iter().enumerate() {
+            if insert || entries.is_empty() {
+                let (x, y) = (f64::from(x), f64::from(y));
+                let bbox = BoundingBox::new([x, y], [x + f64::from(w % 16), y + f64::from(h % 16)]);
+                tree.insert(bbox, i);
+                entries.push((bbox, i));
+            } else {
+                let (bbox, value) = entries.swap_remove(usize::from(w) % entries.len());
+                if tree.remove(&bbox, &value) != Some(value) {
+                    return false;
+                }
+            }
+        }
+        check_node(&tree.root, true, false);
+
+        let window = BoundingBox::new(
+            [f64::from(window.0), f64::from(window.1)],
+            [f64::from(window.2), f64::from(window.3)],
+        );
+        let expected: Vec<usize> = sorted_values(
+            entries
+                .iter()
+                .filter(|(bbox, _)| bbox.intersects(&window))
+                .map(|(bbox, value)| (bbox, value))
+                .collect(),
+        );
+        let bulk = RTree::bulk_load(entries.clone());
+        check_node(&bulk.root, true, true);
+        tree.len() == entries.len()
+            && sorted_values(tree.search(&window)) == expected
+            && sorted_values(bulk.search(&window)) == expected
+    }
+}
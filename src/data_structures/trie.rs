@@ -2,36 +2,43 @@
 //! A Trie is a tree-like data structure that is commonly used to store sequences of keys
 //! (such as strings, integers, or other iterable types) where each node represents one element
 //! of the key, and values can be associated with full sequences.
+//!
+//! The children of every node are kept sorted, so the entries under any prefix can be
+//! iterated in lexicographic order of their keys. See `RadixTrie` for a compressed variant
+//! which stores chains of single-child nodes as one node.
 
-use std::collections::HashMap;
-use std::hash::Hash;
+use std::collections::btree_map;
+use std::collections::BTreeMap;
 
 /// A single node in the Trie structure, representing a key and an optional value.
-#[derive(Debug, Default)]
-struct Node<Key: Default, Type: Default> {
+#[derive(Debug)]
+struct Node<Key, Type> {
     /// A map of children nodes where each key maps to another `Node`.
-    children: HashMap<Key, Node<Key, Type>>,
+    children: BTreeMap<Key, Node<Key, Type>>,
     /// The value associated with this node, if any.
     value: Option<Type>,
 }
 
+impl<Key, Type> Default for Node<Key, Type> {
+    fn default() -> Self {
+        Self {
+            children: BTreeMap::new(),
+            value: None,
+        }
+    }
+}
+
 /// A generic Trie (prefix tree) data structure that allows insertion and lookup
 /// based on a sequence of keys.
-#[derive(Debug, Default)]
-pub struct Trie<Key, Type>
-where
-    Key: Default + Eq + Hash,
-    Type: Default,
-{
-    /// The root node of the Trie, which does not hold a value itself.
+#[derive(Debug)]
+pub struct Trie<Key, Type> {
+    /// The root node of the Trie, which holds the value of the empty sequence.
     root: Node<Key, Type>,
+    /// The number of values stored in the Trie.
+    len: usize,
 }
 
-impl<Key, Type> Trie<Key, Type>
-where
-    Key: Default + Eq + Hash,
-    Type: Default,
-{
+impl<Key: Ord, Type> Trie<Key, Type> {
     /// Creates a new, empty `Trie`.
     ///
     /// # Returns
@@ -39,23 +46,38 @@ where
     pub fn new() -> Self {
         Self {
             root: Node::default(),
+            len: 0,
         }
     }
 
+    /// Returns the number of values stored in the Trie.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the Trie holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     /// Inserts a value into the Trie, associating it with a sequence of keys.
     ///
     /// # Arguments
     /// - `key`: An iterable sequence of keys (e.g., characters in a string or integers in a vector).
     /// - `value`: The value to associate with the sequence of keys.
-    pub fn insert(&mut self, key: impl IntoIterator<Item = Key>, value: Type)
-    where
-        Key: Eq + Hash,
-    {
+    ///
+    /// # Returns
+    /// The value previously associated with the sequence of keys, if any.
+    pub fn insert(&mut self, key: impl IntoIterator<Item = Key>, value: Type) -> Option<Type> {
         let mut node = &mut self.root;
         for c in key {
             node = node.children.entry(c).or_default();
         }
-        node.value = Some(value);
+        let old = node.value.replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
     }
 
     /// Retrieves a reference to the value associated with a sequence of keys, if it exists.
@@ -66,15 +88,158 @@ where
     /// # Returns
     /// An `Option` containing a reference to the value if the sequence of keys exists in the Trie,
     /// or `None` if it does not.
-    pub fn get(&self, key: impl IntoIterator<Item = Key>) -> Option<&Type>
-    where
-        Key: Eq + Hash,
-    {
+    pub fn get(&self, key: impl IntoIterator<Item = Key>) -> Option<&Type> {
+        self.find(key)?.value.as_ref()
+    }
+
+    /// Retrieves a mutable reference to the value associated with a sequence of keys, if it exists.
+    pub fn get_mut(&mut self, key: impl IntoIterator<Item = Key>) -> Option<&mut Type> {
+        let mut node = &mut self.root;
+        for c in key {
+            node = node.children.get_mut(&c)?;
+        }
+        node.value.as_mut()
+    }
+
+    /// Removes the value associated with a sequence of keys, along with the nodes that no
+    /// longer lead to any value.
+    ///
+    /// # Returns
+    /// The removed value, or `None` if the sequence of keys wasn't in the Trie.
+    pub fn remove(&mut self, key: impl IntoIterator<Item = Key>) -> Option<Type> {
+        let key: Vec<Key> = key.into_iter().collect();
+        let removed = Self::remove_from(&mut self.root, &key);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    fn remove_from(node: &mut Node<Key, Type>, key: &[Key]) -> Option<Type> {
+        let Some((first, rest)) = key.split_first() else {
+            return node.value.take();
+        };
+        let child = node.children.get_mut(first)?;
+        let removed = Self::remove_from(child, rest)?;
+        if child.value.is_none() && child.children.is_empty() {
+            node.children.remove(first);
+        }
+        Some(removed)
+    }
+
+    /// Returns the length of the longest sequence stored in the Trie that is a prefix of `key`,
+    /// along with its value.
+    pub fn longest_prefix(&self, key: impl IntoIterator<Item = Key>) -> Option<(usize, &Type)> {
+        let mut node = &self.root;
+        let mut longest = node.value.as_ref().map(|value| (0, value));
+        for (depth, c) in key.into_iter().enumerate() {
+            let Some(child) = node.children.get(&c) else {
+                break;
+            };
+            node = child;
+            if let Some(value) = &node.value {
+                longest = Some((depth + 1, value));
+            }
+        }
+        longest
+    }
+
+    /// Returns the node reached by following a sequence of keys.
+    fn find(&self, key: impl IntoIterator<Item = Key>) -> Option<&Node<Key, Type>> {
         let mut node = &self.root;
         for c in key {
             node = node.children.get(&c)?;
         }
-        node.value.as_ref()
+        Some(node)
+    }
+}
+
+impl<Key: Ord + Clone, Type> Trie<Key, Type> {
+    /// Returns an iterator over all the sequences of keys and their values, in lexicographic
+    /// order.
+    pub fn iter(&self) -> Iter<'_, Key, Type> {
+        Iter::new(&self.root, Vec::new())
+    }
+
+    /// Returns an iterator over the sequences of keys starting with `prefix` and their values,
+    /// in lexicographic order.
+    pub fn iter_prefix(&self, prefix: impl IntoIterator<Item = Key>) -> Iter<'_, Key, Type> {
+        let prefix: Vec<Key> = prefix.into_iter().collect();
+        match self.find(prefix.iter().cloned()) {
+            Some(node) => Iter::new(node, prefix),
+            None => Iter {
+                stack: Vec::new(),
+                path: Vec::new(),
+                pending: None,
+            },
+        }
+    }
+}
+
+impl<Key: Ord, Type> Default for Trie<Key, Type> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key: Ord, Type, K: IntoIterator<Item = Key>> FromIterator<(K, Type)> for Trie<Key, Type> {
+    fn from_iter<I: IntoIterator<Item = (K, Type)>>(iter: I) -> Self {
+        let mut trie = Trie::new();
+        for (key, value) in iter {
+            trie.insert(key, value);
+        }
+        trie
+    }
+}
+
+/// The children of a node left to visit, and the length of the sequence of keys leading to the
+/// node.
+type Frame<'a, Key, Type> = (btree_map::Iter<'a, Key, Node<Key, Type>>, usize);
+
+/// An iterator over the entries of a `Trie` in lexicographic order, yielding each sequence of
+/// keys as a `Vec`.
+pub struct Iter<'a, Key, Type> {
+    /// A frame for every node on the path from the start node.
+    stack: Vec<Frame<'a, Key, Type>>,
+    /// The sequence of keys leading to the last visited node.
+    path: Vec<Key>,
+    /// The value of the start node, which comes before the values below it.
+    pending: Option<&'a Type>,
+}
+
+impl<'a, Key, Type> Iter<'a, Key, Type> {
+    fn new(start: &'a Node<Key, Type>, path: Vec<Key>) -> Self {
+        Self {
+            stack: vec![(start.children.iter(), path.len())],
+            path,
+            pending: start.value.as_ref(),
+        }
+    }
+}
+
+impl<'a, Key: Clone, Type> Iterator for Iter<'a, Key, Type> {
+    type Item = (Vec<Key>, &'a Type);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.pending.take() {
+            return Some((self.path.clone(), value));
+        }
+        loop {
+            let (children, len) = self.stack.last_mut()?;
+            match children.next() {
+                Some((c, child)) => {
+                    self.path.truncate(*len);
+                    self.path.push(c.clone());
+                    self.stack.push((child.children.iter(), self.path.len()));
+                    if let Some(value) = &child.value {
+                        return Some((self.path.clone(), value));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
     }
 }
 
@@ -152,4 +317,71 @@ mod tests {
         assert_eq!(trie.get("apple".chars()), Some(&10));
         assert_eq!(trie.get("applepie".chars()), None);
     }
+
+    #[test]
+    fn test_remove() {
+        let mut trie: Trie<char, i32> = [("car", 1), ("cart", 2), ("cat", 3)]
+            .into_iter()
+            .map(|(key, value)| (key.chars(), value))
+            .collect();
+        assert_eq!(trie.len(), 3);
+        assert_eq!(trie.insert("cart".chars(), 4), Some(2));
+        assert_eq!(trie.len(), 3);
+
+        assert_eq!(trie.remove("ca".chars()), None);
+        assert_eq!(trie.remove("cart".chars()), Some(4));
+        assert_eq!(trie.remove("cart".chars()), None);
+        assert_eq!(trie.get("car".chars()), Some(&1));
+        assert_eq!(trie.remove("car".chars()), Some(1));
+        assert_eq!(trie.remove("cat".chars()), Some(3));
+        assert!(trie.is_empty());
+        // every node has been pruned
+        assert!(trie.root.children.is_empty());
+    }
+
+    #[test]
+    fn test_iter_prefix_in_order() {
+        let mut trie = Trie::new();
+        for (i, word) in ["to", "tea", "ted", "ten", "i", "in", "inn", "t"]
+            .iter()
+            .enumerate()
+        {
+            trie.insert(word.chars(), i);
+        }
+        *trie.get_mut("ten".chars()).unwrap() += 10;
+
+        let words = |iter: Iter<'_, char, usize>| -> Vec<(String, usize)> {
+            iter.map(|(key, &value)| (key.into_iter().collect(), value))
+                .collect()
+        };
+        assert_eq!(
+            words(trie.iter_prefix("te".chars())),
+            [
+                ("tea".to_string(), 1),
+                ("ted".to_string(), 2),
+                ("ten".to_string(), 13)
+            ]
+        );
+        let all: Vec<String> = words(trie.iter()).into_iter().map(|(w, _)| w).collect();
+        assert_eq!(all, ["i", "in", "inn", "t", "tea", "ted", "ten", "to"]);
+        assert_eq!(words(trie.iter_prefix("inn".chars())).len(), 1);
+        assert_eq!(words(trie.iter_prefix("x".chars())), []);
+    }
+
+    #[test]
+    fn test_longest_prefix() {
+        let mut trie = Trie::new();
+        trie.insert(vec![192, 168], "lan");
+        trie.insert(vec![192, 168, 1], "office");
+        trie.insert(vec![10], "vpn");
+
+        assert_eq!(
+            trie.longest_prefix(vec![192, 168, 1, 20]),
+            Some((3, &"office"))
+        );
+        assert_eq!(trie.longest_prefix(vec![192, 168, 2, 1]), Some((2, &"lan")));
+        assert_eq!(trie.longest_prefix(vec![192, 1]), None);
+        trie.insert(vec![], "default");
+        assert_eq!(trie.longest_prefix(vec![172, 16]), Some((0, &"default")));
+    }
 }
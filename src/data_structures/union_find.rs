@@ -4,89 +4,229 @@
 //! disjoint (non-overlapping) sets.
 //! It provides near-constant-time operations to add new sets, to find the
 //! representative of a set, and to merge sets.
+//!
+//! `DisjointSetUnion` works on the indices `0..=n`, and `UnionFind` maps arbitrary
+//! values onto such indices. Both also answer the size of a set and iterate over its
+//! members, which are kept in a circular linked list: merging two sets splices their
+//! lists together in `O(1)`.
 
-use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 
+/// Represents a node in the Disjoint Set Union (DSU) structure  which
+/// keep track of the parent-child relationships in the disjoint sets.
 #[derive(Debug)]
-pub struct UnionFind<T: Debug + Eq + Hash> {
-    payloads: HashMap<T, usize>, // Maps values to their indices in the parent_links array.
-    parent_links: Vec<usize>,    // Holds the parent pointers; root elements are their own parents.
-    sizes: Vec<usize>,           // Holds the sizes of the sets.
-    count: usize,                // Number of disjoint sets.
+pub struct DSUNode {
+    /// The index of the node's parent, or itself if it's the root.
+    parent: usize,
+    /// The size of the set rooted at this node, used for union by size.
+    size: usize,
+    /// The next member of the set, in a circular list of all its members.
+    next: usize,
 }
 
-impl<T: Debug + Eq + Hash> UnionFind<T> {
-    /// Creates an empty Union-Find structure with a specified capacity.
-    pub fn with_capacity(capacity: usize) -> Self {
+/// Disjoint Set Union (Union-Find) data structure, particularly useful for
+/// managing dynamic connectivity problems such as determining
+/// if two elements are in the same subset or merging two subsets.
+#[derive(Debug)]
+pub struct DisjointSetUnion {
+    /// List of DSU nodes where each element's parent and size are tracked.
+    nodes: Vec<DSUNode>,
+    /// Number of disjoint sets.
+    count: usize,
+}
+
+impl DisjointSetUnion {
+    /// Initializes `n + 1` disjoint sets, each element is its own parent.
+    ///
+    /// # Parameters
+    ///
+    /// - `n`: The number of elements to manage (`0` to `n` inclusive).
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `DisjointSetUnion` with `n + 1` independent sets.
+    pub fn new(num_elements: usize) -> DisjointSetUnion {
+        let mut dsu = Self::with_capacity(num_elements + 1);
+        for _ in 0..=num_elements {
+            dsu.push();
+        }
+        dsu
+    }
+
+    /// Creates a structure without any element, with room for `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> DisjointSetUnion {
         Self {
-            parent_links: Vec::with_capacity(capacity),
-            sizes: Vec::with_capacity(capacity),
-            payloads: HashMap::with_capacity(capacity),
+            nodes: Vec::with_capacity(capacity),
             count: 0,
         }
     }
 
-    /// Inserts a new item (disjoint set) into the data structure.
-    pub fn insert(&mut self, item: T) {
-        let key = self.payloads.len();
-        self.parent_links.push(key);
-        self.sizes.push(1);
-        self.payloads.insert(item, key);
+    /// Adds a new element in a set of its own, and returns its index.
+    pub fn push(&mut self) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push(DSUNode {
+            parent: idx,
+            size: 1,
+            next: idx,
+        });
         self.count += 1;
+        idx
     }
 
-    /// Returns the root index of the set containing the given value, or `None` if it doesn't exist.
-    pub fn find(&mut self, value: &T) -> Option<usize> {
-        self.payloads
-            .get(value)
-            .copied()
-            .map(|key| self.find_by_key(key))
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
     }
 
-    /// Unites the sets containing the two given values. Returns:
-    /// - `None` if either value hasn't been inserted,
-    /// - `Some(true)` if two disjoint sets have been merged,
-    /// - `Some(false)` if both elements were already in the same set.
-    pub fn union(&mut self, first_item: &T, sec_item: &T) -> Option<bool> {
-        let (first_root, sec_root) = (self.find(first_item), self.find(sec_item));
-        match (first_root, sec_root) {
-            (Some(first_root), Some(sec_root)) => Some(self.union_by_key(first_root, sec_root)),
-            _ => None,
+    /// Checks if there are no elements at all.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the number of disjoint sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Finds the representative (root) of the set containing `element` with path compression.
+    ///
+    /// Path compression ensures that future queries are faster by directly linking
+    /// all nodes in the path to the root.
+    ///
+    /// # Parameters
+    ///
+    /// - `element`: The element whose set representative is being found.
+    ///
+    /// # Returns
+    ///
+    /// The root representative of the set containing `element`.
+    pub fn find_set(&mut self, element: usize) -> usize {
+        if element != self.nodes[element].parent {
+            self.nodes[element].parent = self.find_set(self.nodes[element].parent);
         }
+        self.nodes[element].parent
+    }
+
+    /// Checks if two elements belong to the same set.
+    pub fn is_same_set(&mut self, first_elem: usize, sec_elem: usize) -> bool {
+        self.find_set(first_elem) == self.find_set(sec_elem)
+    }
+
+    /// Returns the number of elements in the set containing `element`.
+    pub fn size(&mut self, element: usize) -> usize {
+        let root = self.find_set(element);
+        self.nodes[root].size
     }
 
-    /// Finds the root of the set containing the element with the given index.
-    fn find_by_key(&mut self, key: usize) -> usize {
-        if self.parent_links[key] != key {
-            self.parent_links[key] = self.find_by_key(self.parent_links[key]);
+    /// Returns an iterator over the members of the set containing `element`, starting with
+    /// `element` itself.
+    ///
+    /// The iteration takes `O(1)` time per member, without looking for the root of the set.
+    pub fn members(&self, element: usize) -> Members<'_> {
+        Members {
+            nodes: &self.nodes,
+            start: element,
+            next: Some(element),
         }
-        self.parent_links[key]
     }
 
-    /// Unites the sets containing the two elements identified by their indices.
-    fn union_by_key(&mut self, first_key: usize, sec_key: usize) -> bool {
-        let (first_root, sec_root) = (self.find_by_key(first_key), self.find_by_key(sec_key));
+    /// Merges the sets containing `first_elem` and `sec_elem` using union by size.
+    ///
+    /// The smaller set is always attached to the root of the larger set to ensure balanced trees.
+    ///
+    /// # Parameters
+    ///
+    /// - `first_elem`: The first element whose set is to be merged.
+    /// - `sec_elem`: The second element whose set is to be merged.
+    ///
+    /// # Returns
+    ///
+    /// The root of the merged set, or `usize::MAX` if both elements are already in the same set.
+    pub fn merge(&mut self, first_elem: usize, sec_elem: usize) -> usize {
+        let mut first_root = self.find_set(first_elem);
+        let mut sec_root = self.find_set(sec_elem);
 
         if first_root == sec_root {
-            return false;
+            // Already in the same set, no merge required
+            return usize::MAX;
         }
 
-        match self.sizes[first_root].cmp(&self.sizes[sec_root]) {
-            Ordering::Less => {
-                self.parent_links[first_root] = sec_root;
-                self.sizes[sec_root] += self.sizes[first_root];
-            }
-            _ => {
-                self.parent_links[sec_root] = first_root;
-                self.sizes[first_root] += self.sizes[sec_root];
-            }
+        // Union by size: attach the smaller tree under the larger tree
+        if self.nodes[first_root].size < self.nodes[sec_root].size {
+            std::mem::swap(&mut first_root, &mut sec_root);
         }
 
+        self.nodes[sec_root].parent = first_root;
+        self.nodes[first_root].size += self.nodes[sec_root].size;
+
+        // Swapping the successors of two members of different cycles joins the cycles
+        let first_next = self.nodes[first_root].next;
+        self.nodes[first_root].next = self.nodes[sec_root].next;
+        self.nodes[sec_root].next = first_next;
+
         self.count -= 1;
-        true
+        first_root
+    }
+}
+
+/// An iterator over the members of a set of a `DisjointSetUnion`.
+pub struct Members<'a> {
+    nodes: &'a [DSUNode],
+    start: usize,
+    next: Option<usize>,
+}
+
+impl Iterator for Members<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let current = self.next?;
+        let next = self.nodes[current].next;
+        self.next = (next != self.start).then_some(next);
+        Some(current)
+    }
+}
+
+#[derive(Debug)]
+pub struct UnionFind<T: Debug + Eq + Hash> {
+    payloads: HashMap<T, usize>, // Maps values to their indices in the DSU.
+    dsu: DisjointSetUnion,       // Holds the sets of indices.
+}
+
+impl<T: Debug + Eq + Hash> UnionFind<T> {
+    /// Creates an empty Union-Find structure with a specified capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            payloads: HashMap::with_capacity(capacity),
+            dsu: DisjointSetUnion::with_capacity(capacity),
+        }
+    }
+
+    /// Inserts a new item (disjoint set) into the data structure.
+    /// An item that has already been inserted is left in its set.
+    pub fn insert(&mut self, item: T) {
+        if let Entry::Vacant(entry) = self.payloads.entry(item) {
+            entry.insert(self.dsu.push());
+        }
+    }
+
+    /// Returns the root index of the set containing the given value, or `None` if it doesn't exist.
+    pub fn find(&mut self, value: &T) -> Option<usize> {
+        let key = *self.payloads.get(value)?;
+        Some(self.dsu.find_set(key))
+    }
+
+    /// Unites the sets containing the two given values. Returns:
+    /// - `None` if either value hasn't been inserted,
+    /// - `Some(true)` if two disjoint sets have been merged,
+    /// - `Some(false)` if both elements were already in the same set.
+    pub fn union(&mut self, first_item: &T, sec_item: &T) -> Option<bool> {
+        let first_key = *self.payloads.get(first_item)?;
+        let sec_key = *self.payloads.get(sec_item)?;
+        Some(self.dsu.merge(first_key, sec_key) != usize::MAX)
     }
 
     /// Checks if two items belong to the same set.
@@ -94,20 +234,35 @@ impl<T: Debug + Eq + Hash> UnionFind<T> {
         matches!((self.find(first_item), self.find(sec_item)), (Some(first_root), Some(sec_root)) if first_root == sec_root)
     }
 
+    /// Returns the number of items in the set containing `item`, or `None` if it doesn't exist.
+    pub fn size(&mut self, item: &T) -> Option<usize> {
+        let key = *self.payloads.get(item)?;
+        Some(self.dsu.size(key))
+    }
+
+    /// Returns an iterator over the items in the same set as `item` (including `item`),
+    /// or `None` if it doesn't exist.
+    ///
+    /// Items are only stored as keys of the lookup table, so this first maps every index back
+    /// to its item, in `O(n)` time.
+    pub fn members(&self, item: &T) -> Option<impl Iterator<Item = &T> + '_> {
+        let key = *self.payloads.get(item)?;
+        let mut items = vec![None; self.dsu.len()];
+        for (item, &key) in &self.payloads {
+            items[key] = Some(item);
+        }
+        Some(self.dsu.members(key).filter_map(move |key| items[key]))
+    }
+
     /// Returns the number of disjoint sets.
     pub fn count(&self) -> usize {
-        self.count
+        self.dsu.count()
     }
 }
 
 impl<T: Debug + Eq + Hash> Default for UnionFind<T> {
     fn default() -> Self {
-        Self {
-            parent_links: Vec::default(),
-            sizes: Vec::default(),
-            payloads: HashMap::default(),
-            count: 0,
-        }
+        Self::with_capacity(0)
     }
 }
 
@@ -225,4 +380,106 @@ mod tests {
 
         assert_eq!(None, uf.union(&0, &10));
     }
+
+    #[test]
+    fn test_size_and_members() {
+        let mut uf = UnionFind::from_iter(["A", "B", "C", "D", "E"]);
+        uf.insert("A");
+        assert_eq!(uf.count(), 5);
+        uf.union(&"A", &"C");
+        uf.union(&"E", &"C");
+
+        assert_eq!(uf.size(&"E"), Some(3));
+        assert_eq!(uf.size(&"B"), Some(1));
+        assert_eq!(uf.size(&"W"), None);
+        let mut members: Vec<_> = uf.members(&"C").unwrap().copied().collect();
+        members.sort_unstable();
+        assert_eq!(members, ["A", "C", "E"]);
+        assert_eq!(uf.members(&"D").unwrap().collect::<Vec<_>>(), [&"D"]);
+        assert!(uf.members(&"W").is_none());
+    }
+
+    #[test]
+    fn test_keys_need_not_be_clone() {
+        #[derive(Debug, PartialEq, Eq, Hash)]
+        struct Key(u32);
+
+        let mut uf: UnionFind<Key> = (0..4).map(Key).collect();
+        assert_eq!(uf.union(&Key(0), &Key(3)), Some(true));
+        assert!(uf.is_same_set(&Key(3), &Key(0)));
+        assert_eq!(uf.size(&Key(3)), Some(2));
+        assert_eq!(uf.members(&Key(1)).unwrap().collect::<Vec<_>>(), [&Key(1)]);
+        assert_eq!(uf.find(&Key(4)), None);
+    }
+
+    #[test]
+    fn test_disjoint_set_union() {
+        let mut dsu = DisjointSetUnion::new(10);
+
+        dsu.merge(1, 2);
+        dsu.merge(2, 3);
+        dsu.merge(1, 9);
+        dsu.merge(4, 5);
+        dsu.merge(7, 8);
+        dsu.merge(4, 8);
+        dsu.merge(6, 9);
+
+        assert_eq!(dsu.find_set(1), dsu.find_set(2));
+        assert_eq!(dsu.find_set(1), dsu.find_set(3));
+        assert_eq!(dsu.find_set(1), dsu.find_set(6));
+        assert_eq!(dsu.find_set(1), dsu.find_set(9));
+
+        assert_eq!(dsu.find_set(4), dsu.find_set(5));
+        assert_eq!(dsu.find_set(4), dsu.find_set(7));
+        assert_eq!(dsu.find_set(4), dsu.find_set(8));
+
+        assert_ne!(dsu.find_set(1), dsu.find_set(10));
+        assert_ne!(dsu.find_set(4), dsu.find_set(10));
+
+        dsu.merge(3, 4);
+
+        assert_eq!(dsu.find_set(1), dsu.find_set(2));
+        assert_eq!(dsu.find_set(1), dsu.find_set(3));
+        assert_eq!(dsu.find_set(1), dsu.find_set(6));
+        assert_eq!(dsu.find_set(1), dsu.find_set(9));
+        assert_eq!(dsu.find_set(1), dsu.find_set(4));
+        assert_eq!(dsu.find_set(1), dsu.find_set(5));
+        assert_eq!(dsu.find_set(1), dsu.find_set(7));
+        assert_eq!(dsu.find_set(1), dsu.find_set(8));
+
+        assert_ne!(dsu.find_set(1), dsu.find_set(10));
+
+        dsu.merge(10, 1);
+        assert_eq!(dsu.find_set(10), dsu.find_set(1));
+        assert_eq!(dsu.find_set(10), dsu.find_set(2));
+        assert_eq!(dsu.find_set(10), dsu.find_set(3));
+        assert_eq!(dsu.find_set(10), dsu.find_set(4));
+        assert_eq!(dsu.find_set(10), dsu.find_set(5));
+        assert_eq!(dsu.find_set(10), dsu.find_set(6));
+        assert_eq!(dsu.find_set(10), dsu.find_set(7));
+        assert_eq!(dsu.find_set(10), dsu.find_set(8));
+        assert_eq!(dsu.find_set(10), dsu.find_set(9));
+    }
+
+    #[test]
+    fn test_dsu_size_and_members() {
+        let mut dsu = DisjointSetUnion::new(7);
+        assert_eq!(dsu.len(), 8);
+        assert_eq!(dsu.count(), 8);
+        dsu.merge(0, 3);
+        dsu.merge(5, 6);
+        dsu.merge(6, 3);
+        assert_eq!(dsu.merge(0, 5), usize::MAX);
+        assert_eq!(dsu.count(), 5);
+        assert_eq!(dsu.size(6), 4);
+        assert!(dsu.is_same_set(0, 6));
+
+        for element in [0, 3, 5, 6] {
+            let mut members: Vec<usize> = dsu.members(element).collect();
+            assert_eq!(members[0], element);
+            members.sort_unstable();
+            assert_eq!(members, [0, 3, 5, 6]);
+        }
+        assert_eq!(dsu.members(7).collect::<Vec<_>>(), [7]);
+    }
 }
@@ -1,9 +1,15 @@
+use super::IntegerSet;
+
 // This struct implements Van Emde Boas tree (VEB tree). It stores integers in range [0, U), where
-// O is any integer that is a power of 2. It supports operations such as insert, search,
+// U is any integer that is a power of 2. It supports operations such as insert, remove, search,
 // predecessor, and successor in O(log(log(U))) time. The structure takes O(U) space.
 pub struct VebTree {
     size: u32,
-    child_size: u32, // Set to square root of size. Cache here to avoid recomputation.
+    // Set to the lower square root of size, 2^floor(log(size) / 2). Cache here to avoid
+    // recomputation.
+    child_size: u32,
+    // The number of values in the tree, including the min which is not stored in a cluster.
+    len: usize,
     min: u32,
     max: u32,
     summary: Option<Box<VebTree>>,
@@ -12,14 +18,17 @@ pub struct VebTree {
 
 impl VebTree {
     /// Create a new, empty VEB tree. The tree will contain number of elements equal to size
-    /// rounded up to the nearest power of two.
+    /// rounded up to the nearest power of two, and at least 2.
     pub fn new(size: u32) -> VebTree {
-        let rounded_size = size.next_power_of_two();
-        let child_size = (size as f64).sqrt().ceil() as u32;
+        let rounded_size = size.next_power_of_two().max(2);
+        // A value is split into its high bits, the cluster, and its low bits, the offset within
+        // the cluster.
+        let child_size = 1 << (rounded_size.trailing_zeros() / 2);
+        let cluster_count = rounded_size / child_size;
 
         let mut cluster = Vec::new();
         if rounded_size > 2 {
-            for _ in 0..rounded_size {
+            for _ in 0..cluster_count {
                 cluster.push(VebTree::new(child_size));
             }
         }
@@ -27,13 +36,14 @@ impl VebTree {
         VebTree {
             size: rounded_size,
             child_size,
+            len: 0,
             min: u32::MAX,
             max: u32::MIN,
             cluster,
             summary: if rounded_size <= 2 {
                 None
             } else {
-                Some(Box::new(VebTree::new(child_size)))
+                Some(Box::new(VebTree::new(cluster_count)))
             },
         }
     }
@@ -50,12 +60,14 @@ impl VebTree {
         cluster * self.child_size + offset
     }
 
-    pub fn min(&self) -> u32 {
-        self.min
+    // Returns the smallest value in the tree, or `None` if the tree is empty.
+    pub fn min(&self) -> Option<u32> {
+        (!self.empty()).then_some(self.min)
     }
 
-    pub fn max(&self) -> u32 {
-        self.max
+    // Returns the largest value in the tree, or `None` if the tree is empty.
+    pub fn max(&self) -> Option<u32> {
+        (!self.empty()).then_some(self.max)
     }
 
     pub fn iter(&self) -> VebTreeIter {
@@ -83,12 +95,22 @@ impl VebTree {
         assert!(self.empty(), "tree should be empty");
         self.min = value;
         self.max = value;
+        self.len = 1;
     }
 
-    // Inserts value into the tree.
-    pub fn insert(&mut self, mut value: u32) {
+    // Inserts value into the tree. Returns false if it was already in the tree.
+    pub fn insert(&mut self, value: u32) -> bool {
         assert!(value < self.size);
+        if self.search(value) {
+            return false;
+        }
+        self.insert_new(value);
+        true
+    }
 
+    // Inserts a value that is not in the tree yet. Inserting it again would store it in a
+    // cluster as well as in the min.
+    fn insert_new(&mut self, mut value: u32) {
         if self.empty() {
             self.insert_empty(value);
             return;
@@ -109,18 +131,75 @@ impl VebTree {
                 // value and record that the cluster tree has an elements in the summary.
                 self.cluster[high as usize].insert_empty(low);
                 if let Some(summary) = self.summary.as_mut() {
-                    summary.insert(high);
+                    summary.insert_new(high);
                 }
             } else {
                 // If the cluster tree already has a value, the summary does not need to be
                 // updated. Recursively insert the value into the cluster tree.
-                self.cluster[high as usize].insert(low);
+                self.cluster[high as usize].insert_new(low);
             }
         }
 
         if value > self.max {
             self.max = value;
         }
+        self.len += 1;
+    }
+
+    // Removes value from the tree. Returns false if it was not in the tree.
+    pub fn remove(&mut self, value: u32) -> bool {
+        if !self.search(value) {
+            return false;
+        }
+        self.delete(value);
+        true
+    }
+
+    // Removes a value that is in the tree.
+    fn delete(&mut self, mut value: u32) {
+        self.len -= 1;
+        if self.min == self.max {
+            // The value is the only one in the tree.
+            self.min = u32::MAX;
+            self.max = u32::MIN;
+            return;
+        }
+
+        if self.size == 2 {
+            // Base case. Both 0 and 1 are in the tree, and the other one remains.
+            self.min = 1 - value;
+            self.max = self.min;
+            return;
+        }
+
+        if value == self.min {
+            // The min is not stored in a cluster. Replace it with the smallest value of the
+            // clusters, which is then deleted from its cluster instead.
+            let first_cluster = self.summary.as_ref().unwrap().min;
+            value = self.index(first_cluster, self.cluster[first_cluster as usize].min);
+            self.min = value;
+        }
+
+        let high = self.high(value);
+        let low = self.low(value);
+        self.cluster[high as usize].delete(low);
+
+        if self.cluster[high as usize].empty() {
+            // The cluster became empty, so it leaves the summary. If it held the max, the new max
+            // is the max of the last nonempty cluster, or the min if there is none.
+            let summary = self.summary.as_mut().unwrap();
+            summary.delete(high);
+            if value == self.max {
+                self.max = match summary.max() {
+                    Some(last_cluster) => {
+                        self.index(last_cluster, self.cluster[last_cluster as usize].max)
+                    }
+                    None => self.min,
+                };
+            }
+        } else if value == self.max {
+            self.max = self.index(high, self.cluster[high as usize].max);
+        }
     }
 
     // Returns the next greatest value(successor) in the tree after pred. Returns
@@ -130,19 +209,17 @@ impl VebTree {
             return None;
         }
 
-        if self.size == 2 {
-            // Base case. If pred is 0, and 1 exists in the tree (max is set to 1), the successor
-            // is 1.
-            return if pred == 0 && self.max == 1 {
-                Some(1)
-            } else {
-                None
-            };
-        }
-
         if pred < self.min {
             // If the predecessor is less than the minimum of this tree, the successor is the min.
             return Some(self.min);
+        } else if pred >= self.max {
+            return None;
+        }
+
+        if self.size == 2 {
+            // Base case. pred is 0, and 1 exists in the tree (max is set to 1), so the successor
+            // is 1.
+            return Some(1);
         }
 
         let low = self.low(pred);
@@ -169,17 +246,15 @@ impl VebTree {
             return None;
         }
 
-        // base case.
-        if self.size == 2 {
-            return if succ == 1 && self.min == 0 {
-                Some(0)
-            } else {
-                None
-            };
-        }
-
         if succ > self.max {
             return Some(self.max);
+        } else if succ <= self.min {
+            return None;
+        }
+
+        // base case. succ is 1, and 0 exists in the tree.
+        if self.size == 2 {
+            return Some(0);
         }
 
         let low = self.low(succ);
@@ -209,6 +284,44 @@ impl VebTree {
     }
 }
 
+impl IntegerSet for VebTree {
+    fn new(universe: u32) -> Self {
+        VebTree::new(universe)
+    }
+
+    fn insert(&mut self, value: u32) -> bool {
+        VebTree::insert(self, value)
+    }
+
+    fn remove(&mut self, value: u32) -> bool {
+        VebTree::remove(self, value)
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.search(value)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn min(&self) -> Option<u32> {
+        VebTree::min(self)
+    }
+
+    fn max(&self) -> Option<u32> {
+        VebTree::max(self)
+    }
+
+    fn succ(&self, value: u32) -> Option<u32> {
+        VebTree::succ(self, value)
+    }
+
+    fn pred(&self, value: u32) -> Option<u32> {
+        VebTree::pred(self, value)
+    }
+}
+
 pub struct VebTreeIter<'a> {
     tree: &'a VebTree,
     curr: Option<u32>,
@@ -234,6 +347,7 @@ impl<'a> Iterator for VebTreeIter<'a> {
 
 #[cfg(test)]
 mod test {
+    use super::super::IntegerSet;
     use super::VebTree;
     use rand::{rngs::StdRng, Rng, SeedableRng};
 
@@ -339,4 +453,34 @@ mod test {
         let elements: Vec<u32> = (0..100).map(|_| rng.gen_range(0..255)).collect();
         test_veb_tree(300, elements, Vec::new());
     }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = VebTree::new(64);
+        assert_eq!((tree.min(), tree.max()), (None, None));
+        for element in [3, 17, 18, 40, 63] {
+            assert!(tree.insert(element));
+        }
+        assert!(!tree.insert(40));
+        assert_eq!(tree.len(), 5);
+
+        // Removing the min pulls the next value out of its cluster, and removing the max
+        // falls back on the last nonempty cluster
+        assert!(tree.remove(3));
+        assert!(tree.remove(63));
+        assert!(!tree.remove(63));
+        assert_eq!((tree.min(), tree.max()), (Some(17), Some(40)));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), [17, 18, 40]);
+        assert_eq!(tree.pred(17), None);
+        assert_eq!(tree.succ(18), Some(40));
+
+        assert!(tree.remove(40));
+        assert!(tree.remove(17));
+        assert_eq!(tree.succ(0), Some(18));
+        assert!(tree.remove(18));
+        assert!(tree.empty());
+        assert_eq!(tree.len(), 0);
+        assert!(tree.insert(0));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), [0]);
+    }
 }
@@ -0,0 +1,203 @@
+//! A weighted (or "potential") Union-Find structure.
+//!
+//! Every element `x` has an unknown potential `p(x)`, and each union states a difference
+//! `p(b) - p(a) = w` between two elements. The structure tracks the potential of every
+//! element relative to the root of its set, so the difference between any two elements
+//! of the same set can be answered, and a union contradicting the known differences is
+//! detected.
+//!
+//! Each node stores its potential relative to its parent; path compression adds up the
+//! potentials along the path, so `find` still runs in near-constant amortized time.
+//!
+//! Typical uses are checking the consistency of relative measurements (`b` weighs 3 more
+//! than `a`...), or parity constraints with the potentials taken modulo 2.
+
+use std::ops::{Add, Neg, Sub};
+
+/// Enum representing the possible errors that can occur when adding a relation.
+#[derive(Debug, PartialEq, Eq)]
+pub enum WeightedUnionFindError {
+    /// Both elements are already in the same set, with a different difference.
+    Contradiction,
+}
+
+/// A weighted Union-Find structure over the elements `0..n`, with potentials of type `W`.
+///
+/// `W::default()` is the zero potential. Differences between potentials are negative as often
+/// as positive, so `W` must be a signed type. Unsigned types are rejected at compile time:
+///
+/// ```compile_fail
+/// use the_algorithms_rust::data_structures::WeightedUnionFind;
+///
+/// let uf = WeightedUnionFind::<u32>::new(4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WeightedUnionFind<W> {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    /// The potential of each element minus the potential of its parent.
+    potential: Vec<W>,
+    count: usize,
+}
+
+impl<W> WeightedUnionFind<W>
+where
+    W: Copy + Default + PartialEq + Add<Output = W> + Sub<Output = W> + Neg<Output = W>,
+{
+    /// Creates `n` disjoint sets, one for each element of `0..n`.
+    pub fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            potential: vec![W::default(); n],
+            count: n,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// Checks if there are no elements at all.
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Returns the number of disjoint sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the root of the set containing `element`, compressing the path to it.
+    pub fn find(&mut self, element: usize) -> usize {
+        let parent = self.parent[element];
+        if parent == element {
+            return element;
+        }
+        let root = self.find(parent);
+        // The parent is now a child of the root, with its potential relative to the root
+        self.potential[element] = self.potential[element] + self.potential[parent];
+        self.parent[element] = root;
+        root
+    }
+
+    /// Returns the potential of `element` relative to the root of its set.
+    fn potential(&mut self, element: usize) -> W {
+        self.find(element);
+        self.potential[element]
+    }
+
+    /// Checks if two elements belong to the same set.
+    pub fn is_same_set(&mut self, first_elem: usize, sec_elem: usize) -> bool {
+        self.find(first_elem) == self.find(sec_elem)
+    }
+
+    /// Returns the number of elements in the set containing `element`.
+    pub fn size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    /// Returns `p(sec_elem) - p(first_elem)`, or `None` if the elements are in different sets
+    /// and their difference is unknown.
+    pub fn diff(&mut self, first_elem: usize, sec_elem: usize) -> Option<W> {
+        if !self.is_same_set(first_elem, sec_elem) {
+            return None;
+        }
+        Some(self.potential(sec_elem) - self.potential(first_elem))
+    }
+
+    /// Records that `p(sec_elem) - p(first_elem) = weight`, merging the sets of both elements.
+    ///
+    /// # Returns
+    /// `Ok(true)` if two disjoint sets have been merged, `Ok(false)` if the relation was
+    /// already known, or `Err(WeightedUnionFindError::Contradiction)` if it contradicts the
+    /// known difference, in which case nothing changes.
+    pub fn union(
+        &mut self,
+        first_elem: usize,
+        sec_elem: usize,
+        weight: W,
+    ) -> Result<bool, WeightedUnionFindError> {
+        let (first_root, sec_root) = (self.find(first_elem), self.find(sec_elem));
+        let (first_potential, sec_potential) =
+            (self.potential[first_elem], self.potential[sec_elem]);
+        if first_root == sec_root {
+            return if sec_potential - first_potential == weight {
+                Ok(false)
+            } else {
+                Err(WeightedUnionFindError::Contradiction)
+            };
+        }
+
+        // Union by size, with the potential of the attached root chosen to satisfy the relation
+        if self.size[first_root] < self.size[sec_root] {
+            self.parent[first_root] = sec_root;
+            self.size[sec_root] += self.size[first_root];
+            self.potential[first_root] = sec_potential - weight - first_potential;
+        } else {
+            self.parent[sec_root] = first_root;
+            self.size[first_root] += self.size[sec_root];
+            self.potential[sec_root] = first_potential + weight - sec_potential;
+        }
+        self.count -= 1;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WeightedUnionFind, WeightedUnionFindError};
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_relative_weights() {
+        let mut uf = WeightedUnionFind::new(5);
+        assert_eq!(uf.union(0, 1, 3), Ok(true));
+        assert_eq!(uf.union(2, 1, -2), Ok(true));
+        assert_eq!(uf.union(3, 4, 10), Ok(true));
+        assert_eq!(uf.diff(0, 2), Some(5));
+        assert_eq!(uf.diff(2, 0), Some(-5));
+        assert_eq!(uf.diff(0, 3), None);
+
+        assert_eq!(uf.union(2, 3, 1), Ok(true));
+        assert_eq!(uf.diff(0, 4), Some(16));
+        assert_eq!(uf.size(4), 5);
+        assert_eq!(uf.count(), 1);
+
+        assert_eq!(uf.union(0, 4, 16), Ok(false));
+        assert_eq!(
+            uf.union(4, 0, 16),
+            Err(WeightedUnionFindError::Contradiction)
+        );
+        assert_eq!(uf.diff(0, 4), Some(16));
+    }
+
+    /// Relations derived from hidden potentials are never contradictory, and the differences
+    /// between connected elements match the hidden ones.
+    #[quickcheck]
+    fn prop_recovers_hidden_potentials(potentials: Vec<i32>, pairs: Vec<(usize, usize)>) -> bool {
+        if potentials.is_empty() {
+            return true;
+        }
+        let n = potentials.len();
+        let potentials: Vec<i64> = potentials.into_iter().map(i64::from).collect();
+        let mut uf = WeightedUnionFind::new(n);
+        for (a, b) in pairs {
+            let (a, b) = (a % n, b % n);
+            if uf.union(a, b, potentials[b] - potentials[a]).is_err() {
+                return false;
+            }
+            if uf.union(a, b, potentials[b] - potentials[a] + 1).is_ok() {
+                return false;
+            }
+        }
+        (0..n).all(|a| {
+            (0..n).all(|b| {
+                uf.diff(a, b)
+                    .is_none_or(|diff| diff == potentials[b] - potentials[a])
+            })
+        })
+    }
+}
@@ -0,0 +1,374 @@
+use super::IntegerSet;
+use std::collections::{BTreeSet, HashMap};
+use std::iter;
+use std::ops::Bound::{Excluded, Unbounded};
+
+/// The binary trie of a set of `bits`-bit values, with one hash map of prefixes per level, so
+/// that the longest prefix of a value in the trie is found by binary search over the levels.
+struct XFastTrie {
+    bits: u32,
+    /// `levels[l]` maps the prefixes of `l` bits to the smallest and largest value under them.
+    levels: Vec<HashMap<u32, (u32, u32)>>,
+    /// The previous and next value of each value.
+    links: HashMap<u32, (Option<u32>, Option<u32>)>,
+}
+
+impl XFastTrie {
+    fn new(bits: u32) -> Self {
+        XFastTrie {
+            bits,
+            levels: iter::repeat_with(HashMap::new)
+                .take(bits as usize + 1)
+                .collect(),
+            links: HashMap::new(),
+        }
+    }
+
+    fn prefix(&self, value: u32, len: u32) -> u32 {
+        if len == 0 {
+            0
+        } else {
+            value >> (self.bits - len)
+        }
+    }
+
+    fn first(&self) -> Option<u32> {
+        self.levels[0].get(&0).map(|&(min, _)| min)
+    }
+
+    fn last(&self) -> Option<u32> {
+        self.levels[0].get(&0).map(|&(_, max)| max)
+    }
+
+    fn prev(&self, value: u32) -> Option<u32> {
+        self.links[&value].0
+    }
+
+    fn next(&self, value: u32) -> Option<u32> {
+        self.links[&value].1
+    }
+
+    /// Returns the largest value at most `value`, in `O(log bits)`.
+    fn pred_or_eq(&self, value: u32) -> Option<u32> {
+        if self.links.contains_key(&value) {
+            return Some(value);
+        }
+        if self.links.is_empty() {
+            return None;
+        }
+        // The root matches, and the leaf level does not since value is not in the trie
+        let (mut low, mut high) = (0, self.bits - 1);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if self.levels[mid as usize].contains_key(&self.prefix(value, mid)) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        // The node has a single child, on the other side of value's next bit
+        let (min, max) = self.levels[low as usize][&self.prefix(value, low)];
+        if (value >> (self.bits - low - 1)) & 1 == 1 {
+            Some(max)
+        } else {
+            self.prev(min)
+        }
+    }
+
+    /// Inserts a value that is not in the trie, in `O(bits)`.
+    fn insert(&mut self, value: u32) {
+        let prev = self.pred_or_eq(value);
+        let next = match prev {
+            Some(prev) => self.next(prev),
+            None => self.first(),
+        };
+        self.links.insert(value, (prev, next));
+        if let Some(prev) = prev {
+            self.links.get_mut(&prev).unwrap().1 = Some(value);
+        }
+        if let Some(next) = next {
+            self.links.get_mut(&next).unwrap().0 = Some(value);
+        }
+        for len in 0..=self.bits {
+            let prefix = self.prefix(value, len);
+            self.levels[len as usize]
+                .entry(prefix)
+                .and_modify(|(min, max)| {
+                    *min = value.min(*min);
+                    *max = value.max(*max);
+                })
+                .or_insert((value, value));
+        }
+    }
+
+    /// Removes a value of the trie, in `O(bits)`.
+    fn remove(&mut self, value: u32) {
+        let (prev, next) = self.links.remove(&value).unwrap();
+        if let Some(prev) = prev {
+            self.links.get_mut(&prev).unwrap().1 = next;
+        }
+        if let Some(next) = next {
+            self.links.get_mut(&next).unwrap().0 = prev;
+        }
+        for len in 0..=self.bits {
+            let prefix = self.prefix(value, len);
+            let level = &mut self.levels[len as usize];
+            let bounds = level.get_mut(&prefix).unwrap();
+            // The values under a prefix are consecutive, so the neighbours replace the bounds
+            match *bounds {
+                (min, max) if min == value && max == value => {
+                    level.remove(&prefix);
+                }
+                (min, _) if min == value => bounds.0 = next.unwrap(),
+                (_, max) if max == value => bounds.1 = prev.unwrap(),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A set of integers of `0..universe` taking space proportional to its number of values.
+///
+/// The sorted values are split into buckets of about `w` consecutive values, `w` being the
+/// number of bits of the universe, each stored in a balanced search tree. The smallest value
+/// of each bucket represents it in an x-fast trie, which finds the bucket of any value in
+/// `O(log w)`. The trie takes `O(w)` time per update, but it only changes when a bucket is
+/// split or merged, once every `Θ(w)` updates, so all operations take `O(log w)` amortized.
+///
+/// # Sources
+/// Willard, "Log-logarithmic worst-case range queries are possible in space Θ(N)", 1983
+pub struct YFastTrie {
+    universe: u32,
+    /// The smallest value of each bucket.
+    representatives: XFastTrie,
+    buckets: HashMap<u32, BTreeSet<u32>>,
+    len: usize,
+}
+
+impl YFastTrie {
+    /// Creates an empty set that can hold the integers of `0..universe`.
+    pub fn new(universe: u32) -> Self {
+        let bits = u32::BITS - universe.saturating_sub(1).leading_zeros();
+        YFastTrie {
+            universe,
+            representatives: XFastTrie::new(bits),
+            buckets: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn universe(&self) -> u32 {
+        self.universe
+    }
+
+    /// Returns the number of values a bucket holds between splits and merges.
+    fn bucket_size(&self) -> usize {
+        self.representatives.bits.max(1) as usize
+    }
+
+    /// Moves a bucket to a new representative.
+    fn rekey(&mut self, old: u32, new: u32) {
+        let bucket = self.buckets.remove(&old).unwrap();
+        self.representatives.remove(old);
+        self.representatives.insert(new);
+        self.buckets.insert(new, bucket);
+    }
+
+    /// Splits a bucket in two halves if it grew too large.
+    fn split_if_large(&mut self, representative: u32) {
+        let limit = 2 * self.bucket_size();
+        let bucket = self.buckets.get_mut(&representative).unwrap();
+        if bucket.len() <= limit {
+            return;
+        }
+        let middle = *bucket.iter().nth(bucket.len() / 2).unwrap();
+        let upper = bucket.split_off(&middle);
+        self.representatives.insert(middle);
+        self.buckets.insert(middle, upper);
+    }
+
+    /// Merges a bucket with a neighbour if it shrank too much, splitting the result again if
+    /// it is then too large.
+    fn merge_if_small(&mut self, representative: u32) {
+        if self.buckets[&representative].len() >= self.bucket_size() / 2 {
+            return;
+        }
+        let (first, second) = match (
+            self.representatives.prev(representative),
+            self.representatives.next(representative),
+        ) {
+            (_, Some(next)) => (representative, next),
+            (Some(prev), None) => (prev, representative),
+            (None, None) => return,
+        };
+        let mut merged = self.buckets.remove(&second).unwrap();
+        self.representatives.remove(second);
+        let bucket = self.buckets.get_mut(&first).unwrap();
+        bucket.append(&mut merged);
+        self.split_if_large(first);
+    }
+}
+
+impl IntegerSet for YFastTrie {
+    fn new(universe: u32) -> Self {
+        YFastTrie::new(universe)
+    }
+
+    fn insert(&mut self, value: u32) -> bool {
+        assert!(value < self.universe, "value outside the universe");
+        let representative = match self.representatives.pred_or_eq(value) {
+            Some(representative) => representative,
+            // The value is smaller than all the others, so it joins the first bucket and
+            // becomes its representative
+            None => match self.representatives.first() {
+                Some(first) => {
+                    self.rekey(first, value);
+                    value
+                }
+                None => {
+                    self.representatives.insert(value);
+                    self.buckets.insert(value, BTreeSet::new());
+                    value
+                }
+            },
+        };
+        if !self.buckets.get_mut(&representative).unwrap().insert(value) {
+            return false;
+        }
+        self.len += 1;
+        self.split_if_large(representative);
+        true
+    }
+
+    fn remove(&mut self, value: u32) -> bool {
+        if value >= self.universe {
+            return false;
+        }
+        let Some(mut representative) = self.representatives.pred_or_eq(value) else {
+            return false;
+        };
+        let bucket = self.buckets.get_mut(&representative).unwrap();
+        if !bucket.remove(&value) {
+            return false;
+        }
+        self.len -= 1;
+        match bucket.first() {
+            None => {
+                self.buckets.remove(&representative);
+                self.representatives.remove(representative);
+                return true;
+            }
+            Some(&first) if value == representative => {
+                self.rekey(representative, first);
+                representative = first;
+            }
+            Some(_) => {}
+        }
+        self.merge_if_small(representative);
+        true
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        value < self.universe
+            && self
+                .representatives
+                .pred_or_eq(value)
+                .is_some_and(|representative| self.buckets[&representative].contains(&value))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn min(&self) -> Option<u32> {
+        self.representatives.first()
+    }
+
+    fn max(&self) -> Option<u32> {
+        let last = self.representatives.last()?;
+        self.buckets[&last].last().copied()
+    }
+
+    fn succ(&self, value: u32) -> Option<u32> {
+        if value >= self.universe {
+            return None;
+        }
+        match self.representatives.pred_or_eq(value) {
+            Some(representative) => self.buckets[&representative]
+                .range((Excluded(value), Unbounded))
+                .next()
+                .copied()
+                .or_else(|| self.representatives.next(representative)),
+            None => self.representatives.first(),
+        }
+    }
+
+    fn pred(&self, value: u32) -> Option<u32> {
+        if value >= self.universe {
+            return self.max();
+        }
+        // The bucket of the predecessor is the last one whose representative is smaller
+        let mut representative = self.representatives.pred_or_eq(value)?;
+        if representative == value {
+            representative = self.representatives.prev(representative)?;
+        }
+        self.buckets[&representative]
+            .range(..value)
+            .next_back()
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::IntegerSet;
+    use super::YFastTrie;
+
+    #[test]
+    fn test_buckets_split_and_merge() {
+        // 16 bits, so buckets hold between 8 and 32 values
+        let mut set = YFastTrie::new(1 << 16);
+        for value in (0..1000).map(|i| i * 61) {
+            assert!(set.insert(value));
+        }
+        assert_eq!(set.len(), 1000);
+        assert!(set.buckets.len() >= 1000 / 32);
+        assert!(set.buckets.values().all(|bucket| bucket.len() <= 32));
+        assert_eq!(set.succ(61 * 500), Some(61 * 501));
+        assert_eq!(set.pred(61 * 500), Some(61 * 499));
+        assert_eq!(set.pred(61 * 500 + 1), Some(61 * 500));
+        assert_eq!((set.min(), set.max()), (Some(0), Some(61 * 999)));
+
+        for value in (0..1000).filter(|i| i % 10 != 0).map(|i| i * 61) {
+            assert!(set.remove(value));
+        }
+        assert_eq!(set.len(), 100);
+        assert!(set.buckets.len() <= 100 / 8 + 1);
+        assert!(set
+            .buckets
+            .iter()
+            .all(|(&rep, bucket)| bucket.first() == Some(&rep)));
+        assert_eq!(set.succ(61), Some(610));
+        assert_eq!(set.pred(610), Some(0));
+        assert!(!set.contains(61));
+        assert!(set.contains(610));
+        assert_eq!(set.universe(), 1 << 16);
+    }
+
+    #[test]
+    fn test_extreme_universes() {
+        let mut set = YFastTrie::new(u32::MAX);
+        assert!(set.insert(u32::MAX - 1));
+        assert!(set.insert(0));
+        assert_eq!(set.succ(0), Some(u32::MAX - 1));
+        assert_eq!(set.pred(u32::MAX), Some(u32::MAX - 1));
+        assert_eq!(set.succ(u32::MAX - 1), None);
+
+        let mut single = YFastTrie::new(1);
+        assert!(single.insert(0));
+        assert!(!single.insert(0));
+        assert_eq!((single.succ(0), single.pred(1)), (None, Some(0)));
+        assert!(single.remove(0));
+        assert!(single.is_empty());
+    }
+}
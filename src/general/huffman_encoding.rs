@@ -1,3 +1,4 @@
+use crate::compression::{Compressor, Decompressor};
 use std::{
     cmp::Ordering,
     collections::{BTreeMap, BinaryHeap},
@@ -167,6 +168,198 @@ impl HuffmanEncoding {
     }
 }
 
+/// Length of the symbol count that ends a stream written by `HuffmanCompressor`.
+const TRAILER_LEN: usize = 8;
+
+/// Errors that can occur while encoding or decoding a stream written by `HuffmanCompressor`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum HuffmanStreamError {
+    /// The input contains a byte that is missing from the dictionary.
+    UnknownSymbol(u8),
+    /// The stream is too short to hold its trailer.
+    Truncated,
+    /// A bit sequence does not lead to any symbol of the dictionary.
+    InvalidCode,
+    /// The number of decoded symbols differs from the one recorded in the trailer.
+    SymbolCountMismatch,
+    /// The decoded data would exceed the size limit given to the decompressor.
+    OutputTooLarge,
+}
+
+/// Incremental byte compressor over a fixed dictionary.
+///
+/// Codes are packed least significant bit first, in the same order as `HuffmanEncoding`.
+/// Since the last byte may be padded, the stream ends with the number of encoded
+/// symbols as a little-endian `u64`.
+///
+/// A byte that is missing from the dictionary is reported as `UnknownSymbol`, after
+/// which the stream is incomplete and the compressor should be discarded.
+pub struct HuffmanCompressor<'a> {
+    dictionary: &'a HuffmanDictionary<u8>,
+    bits: u128,
+    num_bits: u32,
+    count: u64,
+}
+
+impl<'a> HuffmanCompressor<'a> {
+    pub fn new(dictionary: &'a HuffmanDictionary<u8>) -> Self {
+        HuffmanCompressor {
+            dictionary,
+            bits: 0,
+            num_bits: 0,
+            count: 0,
+        }
+    }
+}
+
+impl Compressor for HuffmanCompressor<'_> {
+    type Error = HuffmanStreamError;
+
+    fn compress_chunk(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), HuffmanStreamError> {
+        for &byte in input {
+            let code = self
+                .dictionary
+                .alphabet
+                .get(&byte)
+                .ok_or(HuffmanStreamError::UnknownSymbol(byte))?;
+            self.bits |= (code.value as u128) << self.num_bits;
+            self.num_bits += code.bits;
+            while self.num_bits >= 8 {
+                output.push(self.bits as u8);
+                self.bits >>= 8;
+                self.num_bits -= 8;
+            }
+            self.count += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), HuffmanStreamError> {
+        if self.num_bits > 0 {
+            output.push(self.bits as u8);
+        }
+        output.extend_from_slice(&self.count.to_le_bytes());
+        Ok(())
+    }
+}
+
+/// Incremental decompressor for streams written by `HuffmanCompressor`.
+///
+/// The symbol count in the trailer is untrusted: with a single-symbol dictionary it is
+/// the only thing the output size depends on, so the decompressor takes a size limit.
+pub struct HuffmanDecompressor<'a> {
+    dictionary: &'a HuffmanDictionary<u8>,
+    /// Position in the tree reached by the bits read so far.
+    node: &'a HuffmanNode<u8>,
+    /// Number of bits read since the last complete code.
+    code_bits: u32,
+    /// The last bytes seen, which may be the padded final byte and the trailer.
+    tail: Vec<u8>,
+    count: u64,
+    max_len: u64,
+}
+
+impl<'a> HuffmanDecompressor<'a> {
+    /// Creates a decompressor that produces at most `max_len` bytes.
+    pub fn new(dictionary: &'a HuffmanDictionary<u8>, max_len: usize) -> Self {
+        HuffmanDecompressor {
+            dictionary,
+            node: &dictionary.root,
+            code_bits: 0,
+            tail: Vec::new(),
+            count: 0,
+            max_len: max_len as u64,
+        }
+    }
+
+    /// Walks the tree along the `len` lowest bits of `byte`.
+    fn decode_bits(
+        &mut self,
+        byte: u8,
+        len: usize,
+        output: &mut Vec<u8>,
+    ) -> Result<(), HuffmanStreamError> {
+        for i in 0..len {
+            let child = if (byte >> i) & 1 == 0 {
+                &self.node.left
+            } else {
+                &self.node.right
+            };
+            self.node = child.as_deref().ok_or(HuffmanStreamError::InvalidCode)?;
+            self.code_bits += 1;
+            if let Some(symbol) = self.node.symbol {
+                if self.count == self.max_len {
+                    return Err(HuffmanStreamError::OutputTooLarge);
+                }
+                output.push(symbol);
+                self.count += 1;
+                self.node = &self.dictionary.root;
+                self.code_bits = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Decompressor for HuffmanDecompressor<'_> {
+    type Error = HuffmanStreamError;
+
+    fn decompress_chunk(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), HuffmanStreamError> {
+        self.tail.extend_from_slice(input);
+        // Everything before the final byte and the trailer holds whole bytes of codes.
+        if self.tail.len() > TRAILER_LEN + 1 {
+            let ready = self.tail.len() - TRAILER_LEN - 1;
+            let tail = std::mem::take(&mut self.tail);
+            for &byte in &tail[..ready] {
+                self.decode_bits(byte, 8, output)?;
+            }
+            self.tail = tail[ready..].to_vec();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), HuffmanStreamError> {
+        let Some(split) = self.tail.len().checked_sub(TRAILER_LEN) else {
+            return Err(HuffmanStreamError::Truncated);
+        };
+        let expected = u64::from_le_bytes(self.tail[split..].try_into().unwrap());
+
+        // A dictionary with a single symbol uses zero-bit codes.
+        if let Some(symbol) = self.dictionary.root.symbol {
+            if split != 0 {
+                return Err(HuffmanStreamError::InvalidCode);
+            }
+            if expected > self.max_len {
+                return Err(HuffmanStreamError::OutputTooLarge);
+            }
+            output.extend(std::iter::repeat_n(symbol, expected as usize));
+            return Ok(());
+        }
+
+        if let Some(&last) = self.tail[..split].first() {
+            // Stop at the expected count: the remaining bits are padding.
+            for i in 0..8 {
+                if self.count == expected {
+                    break;
+                }
+                self.decode_bits(last >> i, 1, output)?;
+            }
+        }
+        if self.count != expected || self.code_bits != 0 {
+            return Err(HuffmanStreamError::SymbolCountMismatch);
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -220,4 +413,118 @@ mod tests {
         let decoded = encoded.decode(&dict).unwrap();
         assert_eq!(decoded, bytes);
     }
+    #[test]
+    fn streaming_matches_one_shot() {
+        use crate::compression::{CompressWriter, DecompressReader};
+        use std::io::{Read, Write};
+
+        let text = "A stream of text, encoded a few bytes at a time.".repeat(20);
+        let bytes = text.as_bytes();
+        let dict = HuffmanDictionary::new(&get_frequency(bytes));
+        let encoding = dict.encode(bytes);
+
+        let mut writer = CompressWriter::new(Vec::new(), HuffmanCompressor::new(&dict));
+        for chunk in bytes.chunks(5) {
+            writer.write_all(chunk).unwrap();
+        }
+        let compressed = writer.finish().unwrap();
+
+        let code_len = encoding.num_bits.div_ceil(8) as usize;
+        let one_shot: Vec<u8> = encoding.data.iter().flat_map(|x| x.to_le_bytes()).collect();
+        assert_eq!(compressed[..code_len], one_shot[..code_len]);
+        assert_eq!(compressed[code_len..], (bytes.len() as u64).to_le_bytes());
+
+        let mut decompressed = Vec::new();
+        DecompressReader::new(
+            &compressed[..],
+            HuffmanDecompressor::new(&dict, bytes.len()),
+        )
+        .read_to_end(&mut decompressed)
+        .unwrap();
+        assert_eq!(decompressed, bytes);
+    }
+    #[test]
+    fn streaming_edge_cases() {
+        let dict = HuffmanDictionary::new(&[(b'a', 3), (b'b', 1)]);
+        let mut compressed = Vec::new();
+        HuffmanCompressor::new(&dict)
+            .finish(&mut compressed)
+            .unwrap();
+        assert_eq!(compressed, 0u64.to_le_bytes());
+
+        let mut decompressor = HuffmanDecompressor::new(&dict, 16);
+        let mut decoded = Vec::new();
+        decompressor
+            .decompress_chunk(&compressed, &mut decoded)
+            .unwrap();
+        decompressor.finish(&mut decoded).unwrap();
+        assert!(decoded.is_empty());
+
+        let mut decompressor = HuffmanDecompressor::new(&dict, 16);
+        decompressor
+            .decompress_chunk(&[0; 5], &mut decoded)
+            .unwrap();
+        assert_eq!(
+            decompressor.finish(&mut decoded),
+            Err(HuffmanStreamError::Truncated)
+        );
+
+        let mut decompressor = HuffmanDecompressor::new(&dict, 16);
+        let mut stream = vec![0b0000_0110];
+        stream.extend(9u64.to_le_bytes());
+        decompressor
+            .decompress_chunk(&stream, &mut decoded)
+            .unwrap();
+        assert_eq!(
+            decompressor.finish(&mut decoded),
+            Err(HuffmanStreamError::SymbolCountMismatch)
+        );
+
+        let single = HuffmanDictionary::new(&[(b'z', 10)]);
+        let mut compressor = HuffmanCompressor::new(&single);
+        let mut compressed = Vec::new();
+        compressor.compress_chunk(b"zzzz", &mut compressed).unwrap();
+        compressor.finish(&mut compressed).unwrap();
+        let mut decompressor = HuffmanDecompressor::new(&single, 4);
+        let mut decoded = Vec::new();
+        decompressor
+            .decompress_chunk(&compressed, &mut decoded)
+            .unwrap();
+        decompressor.finish(&mut decoded).unwrap();
+        assert_eq!(decoded, b"zzzz");
+    }
+    #[test]
+    fn untrusted_input() {
+        let dict = HuffmanDictionary::new(&[(b'a', 3), (b'b', 1)]);
+        let mut compressed = Vec::new();
+        assert_eq!(
+            HuffmanCompressor::new(&dict).compress_chunk(b"abc", &mut compressed),
+            Err(HuffmanStreamError::UnknownSymbol(b'c'))
+        );
+
+        // A huge symbol count in the trailer of a single-symbol stream
+        let single = HuffmanDictionary::new(&[(b'z', 10)]);
+        let mut decompressor = HuffmanDecompressor::new(&single, 1 << 20);
+        let mut decoded = Vec::new();
+        decompressor
+            .decompress_chunk(&u64::MAX.to_le_bytes(), &mut decoded)
+            .unwrap();
+        assert_eq!(
+            decompressor.finish(&mut decoded),
+            Err(HuffmanStreamError::OutputTooLarge)
+        );
+
+        let mut compressed = Vec::new();
+        let mut compressor = HuffmanCompressor::new(&dict);
+        compressor
+            .compress_chunk(b"aabab", &mut compressed)
+            .unwrap();
+        compressor.finish(&mut compressed).unwrap();
+        let mut decompressor = HuffmanDecompressor::new(&dict, 4);
+        let mut decoded = Vec::new();
+        let result = decompressor
+            .decompress_chunk(&compressed, &mut decoded)
+            .and_then(|()| decompressor.finish(&mut decoded));
+        assert_eq!(result, Err(HuffmanStreamError::OutputTooLarge));
+    }
 }
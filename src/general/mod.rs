@@ -13,7 +13,9 @@ pub use self::convex_hull::convex_hull_graham;
 pub use self::fisher_yates_shuffle::fisher_yates_shuffle;
 pub use self::genetic::GeneticAlgorithm;
 pub use self::hanoi::hanoi;
-pub use self::huffman_encoding::{HuffmanDictionary, HuffmanEncoding};
+pub use self::huffman_encoding::{
+    HuffmanCompressor, HuffmanDecompressor, HuffmanDictionary, HuffmanEncoding, HuffmanStreamError,
+};
 pub use self::kadane_algorithm::max_sub_array;
 pub use self::kmeans::f32::kmeans as kmeans_f32;
 pub use self::kmeans::f64::kmeans as kmeans_f64;
@@ -27,6 +27,19 @@ impl Point {
     }
 }
 
+// Conversions to the coordinate arrays used by the spatial indexes of `data_structures::spatial`.
+impl From<&Point> for [f64; 2] {
+    fn from(point: &Point) -> Self {
+        [point.x, point.y]
+    }
+}
+
+impl From<[f64; 2]> for Point {
+    fn from([x, y]: [f64; 2]) -> Self {
+        Point::new(x, y)
+    }
+}
+
 impl Sub for &Point {
     type Output = Point;
 
@@ -1,3 +1,4 @@
+use crate::data_structures::{DecreaseKeyHeap, IndexedHeap};
 use std::collections::BTreeMap;
 use std::ops::Add;
 
@@ -10,24 +11,40 @@ type Graph<V, E> = BTreeMap<V, BTreeMap<V, E>>;
 // since the start has no predecessor but is reachable, map[start] will be None
 //
 // Time: O(E * logV). For each vertex, we traverse each edge, resulting in O(E). For each edge, we
-// insert a new shortest path for a vertex into the tree, resulting in O(E * logV).
-// Space: O(V). The tree holds up to V vertices.
+// lower the distance of a vertex in the indexed heap, resulting in O(E * logV).
+// Space: O(V). The heap holds up to V vertices.
 pub fn dijkstra<V: Ord + Copy, E: Ord + Copy + Add<Output = E>>(
     graph: &Graph<V, E>,
     start: V,
 ) -> BTreeMap<V, Option<(V, E)>> {
+    dijkstra_with_heap::<V, E, IndexedHeap<V, E>>(graph, start)
+}
+
+// same as dijkstra, using the given kind of heap as the priority queue
+// vertices are popped by increasing distance, and the distance of a vertex still in the heap is
+// lowered with decrease_key when a shorter path to it is found
+//
+// Time: O(E + V * logV) with a Fibonacci heap, since decrease_key takes O(1) amortized time
+pub fn dijkstra_with_heap<V, E, H>(graph: &Graph<V, E>, start: V) -> BTreeMap<V, Option<(V, E)>>
+where
+    V: Ord + Copy,
+    E: Ord + Copy + Add<Output = E>,
+    H: DecreaseKeyHeap<V, E> + Default,
+{
     let mut ans = BTreeMap::new();
-    let mut prio = BTreeMap::new();
+    let mut prio = H::default();
+    // the handle of every vertex pushed into prio
+    let mut handles = BTreeMap::new();
 
     // start is the special case that doesn't have a predecessor
     ans.insert(start, None);
 
     for (new, weight) in &graph[&start] {
         ans.insert(*new, Some((start, *weight)));
-        prio.insert(*new, *weight);
+        handles.insert(*new, prio.push(*new, *weight));
     }
 
-    while let Some((vertex, path_weight)) = prio.pop_first() {
+    while let Some((vertex, path_weight)) = prio.pop() {
         for (next, weight) in &graph[&vertex] {
             let new_weight = path_weight + *weight;
             match ans.get(next) {
@@ -38,7 +55,14 @@ pub fn dijkstra<V: Ord + Copy, E: Ord + Copy + Add<Output = E>>(
                 // the new path is shorter, either new was not in ans or it was farther
                 _ => {
                     ans.insert(*next, Some((vertex, new_weight)));
-                    prio.insert(*next, new_weight);
+                    match handles.get(next) {
+                        Some(handle) => {
+                            prio.decrease_key(handle, new_weight);
+                        }
+                        None => {
+                            handles.insert(*next, prio.push(*next, new_weight));
+                        }
+                    }
                 }
             }
         }
@@ -49,7 +73,8 @@ pub fn dijkstra<V: Ord + Copy, E: Ord + Copy + Add<Output = E>>(
 
 #[cfg(test)]
 mod tests {
-    use super::{dijkstra, Graph};
+    use super::{dijkstra, dijkstra_with_heap, Graph};
+    use crate::data_structures::{FibonacciHeap, IndexedHeap, PairingHeap};
     use std::collections::BTreeMap;
 
     fn add_edge<V: Ord + Copy, E: Ord>(graph: &mut Graph<V, E>, v1: V, v2: V, c: E) {
@@ -152,4 +177,34 @@ mod tests {
         dists_e.insert('b', Some(('c', 39)));
         assert_eq!(dijkstra(&graph, 'e'), dists_e);
     }
+
+    #[test]
+    fn same_distances_with_every_heap() {
+        // a grid with irregular weights, so that many distances get lowered before being final
+        let mut graph = BTreeMap::new();
+        for x in 0..20_u32 {
+            for y in 0..20_u32 {
+                let v = x * 20 + y;
+                if x + 1 < 20 {
+                    add_edge(&mut graph, v, v + 20, (x * 7 + y * 13) % 17 + 1);
+                    add_edge(&mut graph, v + 20, v, (x * 11 + y * 3) % 19 + 1);
+                }
+                if y + 1 < 20 {
+                    add_edge(&mut graph, v, v + 1, (x * 5 + y * 17) % 23 + 1);
+                    add_edge(&mut graph, v + 1, v, (x * 13 + y * 7) % 11 + 1);
+                }
+            }
+        }
+
+        let dists = dijkstra_with_heap::<_, _, IndexedHeap<_, _>>(&graph, 0);
+        assert_eq!(dists.len(), 400);
+        let distance = |dists: &BTreeMap<u32, Option<(u32, u32)>>, v| dists[&v].map(|(_, d)| d);
+        let pairing = dijkstra_with_heap::<_, _, PairingHeap<_, _>>(&graph, 0);
+        let fibonacci = dijkstra_with_heap::<_, _, FibonacciHeap<_, _>>(&graph, 0);
+        for v in 0..400 {
+            assert_eq!(distance(&pairing, v), distance(&dists, v));
+            assert_eq!(distance(&fibonacci, v), distance(&dists, v));
+        }
+        assert_eq!(dijkstra(&graph, 0), dists);
+    }
 }
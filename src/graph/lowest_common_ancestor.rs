@@ -15,7 +15,7 @@ memory usage is O(n + q), but time complexity can be considered to be O(n + q),
 because alpha(n) < 5 for n < 10 ^ 600
  */
 
-use super::DisjointSetUnion;
+use crate::data_structures::DisjointSetUnion;
 pub struct LowestCommonAncestorOnline {
     // Make members public to allow the user to fill them themself.
     pub parents_sparse_table: Vec<Vec<usize>>,
@@ -1,7 +1,7 @@
 //! This module implements Kruskal's algorithm to find the Minimum Spanning Tree (MST)
 //! of an undirected, weighted graph using a Disjoint Set Union (DSU) for cycle detection.
 
-use crate::graph::DisjointSetUnion;
+use crate::data_structures::DisjointSetUnion;
 
 /// Represents an edge in the graph with a source, destination, and associated cost.
 #[derive(Debug, PartialEq, Eq)]
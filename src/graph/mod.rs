@@ -9,7 +9,6 @@ mod depth_first_search_tic_tac_toe;
 mod detect_cycle;
 mod dijkstra;
 mod dinic_maxflow;
-mod disjoint_set_union;
 mod eulerian_path;
 mod floyd_warshall;
 mod ford_fulkerson;
@@ -19,6 +18,7 @@ mod kosaraju;
 mod lee_breadth_first_search;
 mod lowest_common_ancestor;
 mod minimum_spanning_tree;
+mod offline_dynamic_connectivity;
 mod prim;
 mod prufer_code;
 mod strongly_connected_components;
@@ -35,9 +35,8 @@ pub use self::decremental_connectivity::DecrementalConnectivity;
 pub use self::depth_first_search::depth_first_search;
 pub use self::depth_first_search_tic_tac_toe::minimax;
 pub use self::detect_cycle::DetectCycle;
-pub use self::dijkstra::dijkstra;
+pub use self::dijkstra::{dijkstra, dijkstra_with_heap};
 pub use self::dinic_maxflow::DinicMaxFlow;
-pub use self::disjoint_set_union::DisjointSetUnion;
 pub use self::eulerian_path::find_eulerian_path;
 pub use self::floyd_warshall::floyd_warshall;
 pub use self::ford_fulkerson::ford_fulkerson;
@@ -47,9 +46,15 @@ pub use self::kosaraju::kosaraju;
 pub use self::lee_breadth_first_search::lee;
 pub use self::lowest_common_ancestor::{LowestCommonAncestorOffline, LowestCommonAncestorOnline};
 pub use self::minimum_spanning_tree::kruskal;
-pub use self::prim::{prim, prim_with_start};
+pub use self::offline_dynamic_connectivity::{
+    offline_dynamic_connectivity, ConnectivityAnswer, ConnectivityQuery,
+};
+pub use self::prim::{prim, prim_with_heap, prim_with_start};
 pub use self::prufer_code::{prufer_decode, prufer_encode};
 pub use self::strongly_connected_components::StronglyConnectedComponents;
 pub use self::tarjans_ssc::tarjan_scc;
 pub use self::topological_sort::topological_sort;
 pub use self::two_satisfiability::solve_two_satisfiability;
+
+// The DSU lives with the other union-find structures, it is re-exported at its former path
+pub use crate::data_structures::DisjointSetUnion;
@@ -0,0 +1,212 @@
+//! Offline dynamic connectivity: answers connectivity queries on a graph whose edges are added
+//! and removed over time, knowing all the operations in advance.
+//!
+//! Every edge is present during an interval of time (from its addition to its removal). The
+//! intervals are split over the nodes of a segment tree built on the timeline, as in a range
+//! update, so each one lands on `O(log(q))` nodes. A depth-first traversal of the tree then
+//! adds the edges of a node to a `RollbackUnionFind` on the way down and undoes them on the way
+//! up: at each leaf, the structure holds exactly the edges present at that time.
+//!
+//! # Complexity
+//! `O(q * log(q) * log(n))` time for `q` operations on `n` vertices, and `O(n + q * log(q))`
+//! space.
+//!
+//! # Sources
+//! <https://cp-algorithms.com/data_structures/deleting_in_log_n.html>
+
+use crate::data_structures::RollbackUnionFind;
+use std::collections::HashMap;
+
+/// An operation on an undirected graph, applied in order by `offline_dynamic_connectivity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectivityQuery {
+    /// Adds an edge between two vertices. The same edge can be added several times.
+    AddEdge(usize, usize),
+    /// Removes one copy of an edge between two vertices, if there is one.
+    RemoveEdge(usize, usize),
+    /// Asks whether two vertices are connected.
+    Connected(usize, usize),
+    /// Asks for the number of connected components.
+    ComponentCount,
+}
+
+/// The answer to a `Connected` or `ComponentCount` query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectivityAnswer {
+    Connected(bool),
+    ComponentCount(usize),
+}
+
+/// Applies the queries in order to a graph with `num_vertices` vertices and no edges, and
+/// returns the answers to the `Connected` and `ComponentCount` queries, in order.
+pub fn offline_dynamic_connectivity(
+    num_vertices: usize,
+    queries: &[ConnectivityQuery],
+) -> Vec<ConnectivityAnswer> {
+    if queries.is_empty() {
+        return Vec::new();
+    }
+
+    // The times at which each copy of each edge still present was added
+    let mut added: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    let mut tree = vec![Vec::new(); 4 * queries.len()];
+    for (time, query) in queries.iter().enumerate() {
+        match *query {
+            ConnectivityQuery::AddEdge(u, v) => {
+                added.entry((u.min(v), u.max(v))).or_default().push(time);
+            }
+            ConnectivityQuery::RemoveEdge(u, v) => {
+                let edge = (u.min(v), u.max(v));
+                if let Some(start) = added.get_mut(&edge).and_then(Vec::pop) {
+                    add_interval(&mut tree, 1, 0, queries.len(), start, time, edge);
+                }
+            }
+            _ => {}
+        }
+    }
+    for (edge, starts) in added {
+        for start in starts {
+            add_interval(&mut tree, 1, 0, queries.len(), start, queries.len(), edge);
+        }
+    }
+
+    let mut answers = vec![None; queries.len()];
+    let mut uf = RollbackUnionFind::new(num_vertices);
+    answer_queries(&tree, 1, 0, queries.len(), queries, &mut uf, &mut answers);
+    answers.into_iter().flatten().collect()
+}
+
+/// Adds an edge to the nodes of the segment tree covering the times `[start, end)`.
+fn add_interval(
+    tree: &mut [Vec<(usize, usize)>],
+    node: usize,
+    node_start: usize,
+    node_end: usize,
+    start: usize,
+    end: usize,
+    edge: (usize, usize),
+) {
+    if end <= node_start || node_end <= start {
+        return;
+    }
+    if start <= node_start && node_end <= end {
+        tree[node].push(edge);
+        return;
+    }
+    let mid = node_start + (node_end - node_start) / 2;
+    add_interval(tree, 2 * node, node_start, mid, start, end, edge);
+    add_interval(tree, 2 * node + 1, mid, node_end, start, end, edge);
+}
+
+/// Answers the queries of the times `[node_start, node_end)`, with the edges of the ancestors
+/// of the node already in `uf`.
+fn answer_queries(
+    tree: &[Vec<(usize, usize)>],
+    node: usize,
+    node_start: usize,
+    node_end: usize,
+    queries: &[ConnectivityQuery],
+    uf: &mut RollbackUnionFind,
+    answers: &mut [Option<ConnectivityAnswer>],
+) {
+    let snapshot = uf.snapshot();
+    for &(u, v) in &tree[node] {
+        uf.union(u, v);
+    }
+    if node_end - node_start == 1 {
+        answers[node_start] = match queries[node_start] {
+            ConnectivityQuery::Connected(u, v) => {
+                Some(ConnectivityAnswer::Connected(uf.is_same_set(u, v)))
+            }
+            ConnectivityQuery::ComponentCount => {
+                Some(ConnectivityAnswer::ComponentCount(uf.count()))
+            }
+            _ => None,
+        };
+    } else {
+        let mid = node_start + (node_end - node_start) / 2;
+        answer_queries(tree, 2 * node, node_start, mid, queries, uf, answers);
+        answer_queries(tree, 2 * node + 1, mid, node_end, queries, uf, answers);
+    }
+    uf.rollback(snapshot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConnectivityAnswer::{ComponentCount, Connected};
+    use super::{offline_dynamic_connectivity, ConnectivityAnswer, ConnectivityQuery};
+    use crate::data_structures::DisjointSetUnion;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_add_and_remove_edges() {
+        let queries = [
+            ConnectivityQuery::AddEdge(0, 1),
+            ConnectivityQuery::AddEdge(1, 2),
+            ConnectivityQuery::Connected(0, 2),
+            ConnectivityQuery::ComponentCount,
+            ConnectivityQuery::RemoveEdge(2, 1),
+            ConnectivityQuery::Connected(0, 2),
+            ConnectivityQuery::AddEdge(0, 2),
+            ConnectivityQuery::AddEdge(0, 2),
+            ConnectivityQuery::RemoveEdge(0, 2),
+            ConnectivityQuery::Connected(1, 2),
+            ConnectivityQuery::RemoveEdge(3, 4),
+            ConnectivityQuery::ComponentCount,
+        ];
+        assert_eq!(
+            offline_dynamic_connectivity(5, &queries),
+            [
+                Connected(true),
+                ComponentCount(3),
+                Connected(false),
+                Connected(true),
+                ComponentCount(3),
+            ]
+        );
+        assert!(offline_dynamic_connectivity(3, &[]).is_empty());
+    }
+
+    /// Rebuilds the graph from scratch for every query.
+    #[quickcheck]
+    fn prop_matches_recomputation(ops: Vec<(u8, u8, u8)>) -> bool {
+        let n = 8;
+        let queries: Vec<ConnectivityQuery> = ops
+            .into_iter()
+            .map(|(op, u, v)| {
+                let (u, v) = (usize::from(u) % n, usize::from(v) % n);
+                match op % 4 {
+                    0 => ConnectivityQuery::AddEdge(u, v),
+                    1 => ConnectivityQuery::RemoveEdge(u, v),
+                    2 => ConnectivityQuery::Connected(u, v),
+                    _ => ConnectivityQuery::ComponentCount,
+                }
+            })
+            .collect();
+
+        let mut edges = Vec::new();
+        let mut expected: Vec<ConnectivityAnswer> = Vec::new();
+        for query in &queries {
+            let mut dsu = DisjointSetUnion::with_capacity(n);
+            for _ in 0..n {
+                dsu.push();
+            }
+            for &(u, v) in &edges {
+                dsu.merge(u, v);
+            }
+            match *query {
+                ConnectivityQuery::AddEdge(u, v) => edges.push((u.min(v), u.max(v))),
+                ConnectivityQuery::RemoveEdge(u, v) => {
+                    if let Some(idx) = edges.iter().position(|&e| e == (u.min(v), u.max(v))) {
+                        edges.swap_remove(idx);
+                    }
+                }
+                ConnectivityQuery::Connected(u, v) => {
+                    expected.push(Connected(dsu.is_same_set(u, v)));
+                }
+                ConnectivityQuery::ComponentCount => expected.push(ComponentCount(dsu.count())),
+            }
+        }
+        offline_dynamic_connectivity(n, &queries) == expected
+    }
+}
@@ -1,3 +1,4 @@
+use crate::data_structures::DecreaseKeyHeap;
 use std::cmp::Reverse;
 use std::collections::{BTreeMap, BinaryHeap};
 use std::ops::Add;
@@ -57,11 +58,63 @@ pub fn prim_with_start<V: Ord + Copy, E: Ord + Add + Copy>(
     mst
 }
 
+// same as prim_with_start, using the given kind of heap as the priority queue
+// the heap holds every vertex adjacent to the MST with its cheapest edge to it (cost and source),
+// which is lowered with decrease_key when a cheaper edge is found, instead of pushing the edge
+//
+// Time: O(E + V * logV) with a Fibonacci heap, since decrease_key takes O(1) amortized time
+pub fn prim_with_heap<V, E, H>(graph: &Graph<V, E>, start: V) -> Graph<V, E>
+where
+    V: Ord + Copy,
+    E: Ord + Add + Copy,
+    H: DecreaseKeyHeap<V, (E, V)> + Default,
+{
+    // will contain the MST
+    let mut mst: Graph<V, E> = Graph::new();
+    let mut prio = H::default();
+    // the handle of every vertex pushed into prio
+    let mut handles = BTreeMap::new();
+
+    mst.insert(start, BTreeMap::new());
+
+    let mut vertex = start;
+    loop {
+        for (v, c) in &graph[&vertex] {
+            // the destination of the edge has already been seen
+            if mst.contains_key(v) {
+                continue;
+            }
+            match handles.get(v) {
+                Some(handle) => {
+                    prio.decrease_key(handle, (*c, vertex));
+                }
+                None => {
+                    handles.insert(*v, prio.push(*v, (*c, vertex)));
+                }
+            }
+        }
+
+        let Some((next, (dist, prev))) = prio.pop() else {
+            break;
+        };
+        add_edge(&mut mst, prev, next, dist);
+        vertex = next;
+    }
+
+    mst
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{add_edge, prim, Graph};
+    use super::{add_edge, prim, prim_with_heap, Graph};
+    use crate::data_structures::{FibonacciHeap, IndexedHeap, PairingHeap};
     use std::collections::BTreeMap;
 
+    fn total_weight(graph: &Graph<u32, i32>) -> i32 {
+        // every edge is stored in both directions
+        graph.values().flat_map(BTreeMap::values).sum::<i32>() / 2
+    }
+
     #[test]
     fn empty() {
         assert_eq!(prim::<usize, usize>(&BTreeMap::new()), BTreeMap::new());
@@ -196,4 +249,28 @@ mod tests {
 
         assert_eq!(prim(&graph), ans);
     }
+
+    #[test]
+    fn same_weight_with_every_heap() {
+        let mut graph = BTreeMap::new();
+        for i in 0..200_u32 {
+            for j in [i + 1, i * 3 + 7, i * 11 % 199] {
+                if j != i && j < 200 {
+                    add_edge(&mut graph, i, j, ((i * 31 + j * 17) % 50) as i32 - 10);
+                }
+            }
+        }
+
+        let mst = prim(&graph);
+        assert_eq!(mst.len(), 200);
+        let weight = total_weight(&mst);
+        for mst in [
+            prim_with_heap::<_, _, IndexedHeap<_, _>>(&graph, 0),
+            prim_with_heap::<_, _, PairingHeap<_, _>>(&graph, 0),
+            prim_with_heap::<_, _, FibonacciHeap<_, _>>(&graph, 0),
+        ] {
+            assert_eq!(mst.len(), 200);
+            assert_eq!(total_weight(&mst), weight);
+        }
+    }
 }
@@ -0,0 +1,87 @@
+use crate::data_structures::spatial::KdTree;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A k-nearest neighbors classifier: a point gets the most common label among the `k` closest
+/// training points.
+///
+/// The training points are stored in a `KdTree`, so a prediction does not scan all of them.
+pub struct KNearestNeighbors<const D: usize, L> {
+    tree: KdTree<D, L>,
+    k: usize,
+}
+
+impl<const D: usize, L: Clone + Eq + Hash> KNearestNeighbors<D, L> {
+    /// Returns `None` if `k` is 0 or if there is no training data.
+    ///
+    /// # Panics
+    /// Panics if `D` is 0: points without coordinates have no distance to each other.
+    pub fn new(training_data: Vec<([f64; D], L)>, k: usize) -> Option<Self> {
+        assert!(D > 0, "k-nearest neighbors needs at least one dimension");
+        if k == 0 || training_data.is_empty() {
+            return None;
+        }
+        Some(KNearestNeighbors {
+            tree: KdTree::new(training_data),
+            k,
+        })
+    }
+
+    /// Returns the most common label among the `k` training points closest to `point`. On a
+    /// tie, the label whose closest point is the nearest wins.
+    pub fn predict(&self, point: &[f64; D]) -> L {
+        let neighbors = self.tree.k_nearest(point, self.k);
+        let mut votes: HashMap<&L, usize> = HashMap::new();
+        for neighbor in &neighbors {
+            *votes.entry(neighbor.value).or_insert(0) += 1;
+        }
+        let best = votes.values().copied().max().unwrap_or(0);
+        // the neighbors come from the closest to the farthest
+        neighbors
+            .iter()
+            .find(|neighbor| votes[neighbor.value] == best)
+            .map(|neighbor| neighbor.value.clone())
+            .expect("the tree is not empty")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KNearestNeighbors;
+
+    #[test]
+    fn test_two_clusters() {
+        let training_data = vec![
+            ([1.0, 1.0], "red"),
+            ([1.5, 2.0], "red"),
+            ([2.0, 1.0], "red"),
+            ([8.0, 8.0], "blue"),
+            ([8.5, 9.0], "blue"),
+            ([9.0, 8.0], "blue"),
+        ];
+        let classifier = KNearestNeighbors::new(training_data, 3).unwrap();
+        assert_eq!(classifier.predict(&[0.0, 0.0]), "red");
+        assert_eq!(classifier.predict(&[7.0, 7.5]), "blue");
+        assert_eq!(classifier.predict(&[4.0, 4.0]), "red");
+    }
+
+    #[test]
+    fn test_ties_and_invalid_input() {
+        let training_data = vec![([0.0], 'a'), ([3.0], 'b'), ([10.0], 'c')];
+        let classifier = KNearestNeighbors::new(training_data.clone(), 2).unwrap();
+        assert_eq!(classifier.predict(&[1.0]), 'a');
+        assert_eq!(classifier.predict(&[2.0]), 'b');
+
+        let classifier = KNearestNeighbors::new(training_data.clone(), 10).unwrap();
+        assert_eq!(classifier.predict(&[9.0]), 'c');
+
+        assert!(KNearestNeighbors::new(training_data, 0).is_none());
+        assert!(KNearestNeighbors::<2, u8>::new(Vec::new(), 1).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one dimension")]
+    fn test_zero_dimensions() {
+        KNearestNeighbors::<0, u8>::new(Vec::new(), 1);
+    }
+}
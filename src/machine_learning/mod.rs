@@ -1,5 +1,6 @@
 mod cholesky;
 mod k_means;
+mod k_nearest_neighbors;
 mod linear_regression;
 mod logistic_regression;
 mod loss_function;
@@ -7,6 +8,7 @@ mod optimization;
 
 pub use self::cholesky::cholesky;
 pub use self::k_means::k_means;
+pub use self::k_nearest_neighbors::KNearestNeighbors;
 pub use self::linear_regression::linear_regression;
 pub use self::logistic_regression::logistic_regression;
 pub use self::loss_function::average_margin_ranking_loss;
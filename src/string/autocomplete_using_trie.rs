@@ -4,86 +4,69 @@
     word List => ["apple", "orange", "oregano"]
     prefix => "or"
     matches => ["orange", "oregano"]
-*/
 
-use std::collections::HashMap;
+    Every word counts how many times it has been added, and the suggestions are ranked
+    from the most to the least frequent word (alphabetically among equally frequent ones).
+    The words are stored in a `RadixTrie`, so that listing the words with a given prefix
+    only visits the subtree below that prefix.
+*/
 
-const END: char = '#';
+use crate::data_structures::RadixTrie;
+use std::cmp::Reverse;
 
-#[derive(Debug)]
-struct Trie(HashMap<char, Box<Trie>>);
+pub struct Autocomplete {
+    /// The number of times each word has been added.
+    trie: RadixTrie<char, u64>,
+}
 
-impl Trie {
+impl Autocomplete {
     fn new() -> Self {
-        Trie(HashMap::new())
-    }
-
-    fn insert(&mut self, text: &str) {
-        let mut trie = self;
-
-        for c in text.chars() {
-            trie = trie.0.entry(c).or_insert_with(|| Box::new(Trie::new()));
+        Self {
+            trie: RadixTrie::new(),
         }
-
-        trie.0.insert(END, Box::new(Trie::new()));
     }
 
-    fn find(&self, prefix: &str) -> Vec<String> {
-        let mut trie = self;
-
-        for c in prefix.chars() {
-            let char_trie = trie.0.get(&c);
-            if let Some(char_trie) = char_trie {
-                trie = char_trie;
-            } else {
-                return vec![];
-            }
+    /// Adds every word once: a word added several times is suggested before rarer ones.
+    pub fn insert_words<T: AsRef<str>>(&mut self, words: &[T]) {
+        for word in words {
+            self.insert_with_frequency(word.as_ref(), 1);
         }
-
-        Self::_elements(trie)
-            .iter()
-            .map(|s| prefix.to_owned() + s)
-            .collect()
     }
 
-    fn _elements(map: &Trie) -> Vec<String> {
-        let mut results = vec![];
-
-        for (c, v) in map.0.iter() {
-            let mut sub_result = vec![];
-            if c == &END {
-                sub_result.push("".to_owned())
-            } else {
-                Self::_elements(v)
-                    .iter()
-                    .map(|s| sub_result.push(c.to_string() + s))
-                    .collect()
+    /// Adds a word as if it had been added `count` times.
+    pub fn insert_with_frequency(&mut self, word: &str, count: u64) {
+        match self.trie.get_mut(word.chars()) {
+            Some(frequency) => *frequency += count,
+            None => {
+                self.trie.insert(word.chars(), count);
             }
-
-            results.extend(sub_result)
         }
-
-        results
     }
-}
-
-pub struct Autocomplete {
-    trie: Trie,
-}
 
-impl Autocomplete {
-    fn new() -> Self {
-        Self { trie: Trie::new() }
+    /// Returns how many times a word has been added.
+    pub fn frequency(&self, word: &str) -> u64 {
+        self.trie.get(word.chars()).copied().unwrap_or(0)
     }
 
-    pub fn insert_words<T: AsRef<str>>(&mut self, words: &[T]) {
-        for word in words {
-            self.trie.insert(word.as_ref());
-        }
+    /// Returns all the words starting with `prefix`, the most frequent first.
+    pub fn find_words(&self, prefix: &str) -> Vec<String> {
+        self.suggest(prefix, usize::MAX)
     }
 
-    pub fn find_words(&self, prefix: &str) -> Vec<String> {
-        self.trie.find(prefix)
+    /// Returns the `limit` most frequent words starting with `prefix`, the most frequent first.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let mut matches: Vec<(String, u64)> = self
+            .trie
+            .iter_prefix(prefix.chars())
+            .map(|(word, &frequency)| (word.into_iter().collect(), frequency))
+            .collect();
+        // the words come in alphabetical order, which the stable sort keeps for equal frequencies
+        matches.sort_by_key(|&(_, frequency)| Reverse(frequency));
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(word, _)| word)
+            .collect()
     }
 }
 
@@ -122,4 +105,22 @@ mod tests {
         auto_completed_words.sort();
         assert_eq!(auto_completed_words, prefix_or);
     }
+
+    #[test]
+    fn test_ranked_by_frequency() {
+        let mut auto_complete = Autocomplete::default();
+        auto_complete.insert_words(&["car", "cart", "care", "cat", "cart", "dog"]);
+        auto_complete.insert_with_frequency("cat", 5);
+
+        assert_eq!(auto_complete.frequency("cat"), 6);
+        assert_eq!(auto_complete.frequency("cart"), 2);
+        assert_eq!(auto_complete.frequency("ca"), 0);
+        assert_eq!(
+            auto_complete.find_words("ca"),
+            ["cat", "cart", "car", "care"]
+        );
+        assert_eq!(auto_complete.suggest("car", 2), ["cart", "car"]);
+        assert_eq!(auto_complete.suggest("", 1), ["cat"]);
+        assert!(auto_complete.find_words("x").is_empty());
+    }
 }